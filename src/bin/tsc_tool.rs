@@ -0,0 +1,157 @@
+//! Offline TSC script tool, lets modders encrypt, compile and decompile scripts without running the engine.
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use clap::{Parser, Subcommand};
+
+use doukutsu_rs::game::scripting::tsc::encryption::{decrypt_tsc, encrypt_tsc};
+use doukutsu_rs::game::scripting::tsc::text_script::{TextScript, TextScriptEncoding};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Encrypts, compiles and decompiles TSC scripts.", long_about = None)]
+struct Options {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Decrypts an encrypted .tsc file into plain text.
+    Decrypt {
+        input: PathBuf,
+
+        #[arg(short, long)]
+        /// Output file, defaults to the input with .txt extension.
+        output: Option<PathBuf>,
+    },
+    /// Encrypts a plain text script into a .tsc file.
+    Encrypt {
+        input: PathBuf,
+
+        #[arg(short, long)]
+        /// Output file, defaults to the input with .tsc extension.
+        output: Option<PathBuf>,
+    },
+    /// Compiles a script to engine bytecode, reporting errors with their line and column.
+    Compile {
+        input: PathBuf,
+
+        #[arg(short, long)]
+        /// Output file, defaults to the input with .tscb extension.
+        output: Option<PathBuf>,
+
+        #[arg(long)]
+        /// Treat the input as plain text even if it has .tsc extension.
+        plain: bool,
+
+        #[arg(long)]
+        /// Fail on quirks that the engine tolerates in vanilla and CS+ scripts.
+        strict: bool,
+
+        #[arg(long, default_value = "shift-jis")]
+        /// Text encoding of the script, uses the same names as the engine's data files.
+        encoding: String,
+    },
+    /// Decompiles engine bytecode back to a TSC script.
+    Decompile {
+        input: PathBuf,
+
+        #[arg(short, long)]
+        /// Output file, defaults to the input with .txt extension. A .tsc output is encrypted.
+        output: Option<PathBuf>,
+
+        #[arg(long)]
+        /// Print a listing of the bytecode instructions instead of TSC source.
+        listing: bool,
+
+        #[arg(long, default_value = "shift-jis")]
+        /// Text encoding of the generated script.
+        encoding: String,
+    },
+}
+
+fn is_tsc(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("tsc"))
+}
+
+/// Returns the output file, refusing to default to the input itself so it isn't overwritten by accident.
+fn output_path(input: &Path, output: Option<PathBuf>, extension: &str) -> Result<PathBuf, Box<dyn Error>> {
+    if let Some(output) = output {
+        return Ok(output);
+    }
+
+    let output = input.with_extension(extension);
+    if output.as_os_str().eq_ignore_ascii_case(input.as_os_str()) {
+        return Err(format!("{}: output would overwrite the input, pass it with --output.", input.display()).into());
+    }
+
+    Ok(output)
+}
+
+fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Decrypt { input, output } => {
+            let mut data = fs::read(&input)?;
+            decrypt_tsc(&mut data);
+            fs::write(output_path(&input, output, "txt")?, data)?;
+        }
+        Command::Encrypt { input, output } => {
+            let mut data = fs::read(&input)?;
+            encrypt_tsc(&mut data);
+            fs::write(output_path(&input, output, "tsc")?, data)?;
+        }
+        Command::Compile { input, output, plain, strict, encoding } => {
+            let mut data = fs::read(&input)?;
+            if is_tsc(&input) && !plain {
+                decrypt_tsc(&mut data);
+            }
+
+            let script = TextScript::compile(&data, strict, TextScriptEncoding::from(encoding.as_str()))
+                .map_err(|e| format!("{}: {}", input.display(), e))?;
+
+            let mut bytecode = Vec::new();
+            script.write_bytecode(&mut bytecode)?;
+            fs::write(output_path(&input, output, "tscb")?, bytecode)?;
+
+            println!("Compiled {} events.", script.get_event_ids().len());
+        }
+        Command::Decompile { input, output, listing, encoding } => {
+            let script = TextScript::read_bytecode(fs::read(&input)?.as_slice())?;
+
+            if listing {
+                let mut result = String::new();
+                for id in script.get_event_ids() {
+                    result.push_str(&format!("#{:04}\n", id));
+                    result.push_str(&script.decompile_event(id)?);
+                }
+
+                match output {
+                    Some(path) => fs::write(path, result)?,
+                    None => print!("{}", result),
+                }
+            } else {
+                let output = output_path(&input, output, "txt")?;
+                let mut data = script.decompile_to_tsc(TextScriptEncoding::from(encoding.as_str()))?;
+                if is_tsc(&output) {
+                    encrypt_tsc(&mut data);
+                }
+
+                fs::write(output, data)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let options = Options::parse();
+
+    if let Err(e) = run(options.command) {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
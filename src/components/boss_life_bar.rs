@@ -7,7 +7,7 @@ use crate::game::shared_game_state::SharedGameState;
 use crate::game::npc::boss::BossNPC;
 use crate::game::npc::list::{NPCAccessToken, NPCList};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[repr(u8)]
 enum BossLifeTarget {
     None,
@@ -15,6 +15,7 @@ enum BossLifeTarget {
     Boss,
 }
 
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct BossLifeBar {
     target: BossLifeTarget,
     life: u16,
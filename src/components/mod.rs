@@ -12,6 +12,7 @@ pub mod map_system;
 pub mod nikumaru;
 pub mod number_popup;
 pub mod replay;
+pub mod split_timer;
 pub mod stage_select;
 pub mod text_boxes;
 pub mod tilemap;
@@ -13,7 +13,7 @@ use crate::game::player::Player;
 use crate::game::scripting::tsc::text_script::TextScriptExecutionState;
 use crate::util::rng::RNG;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct NikumaruCounter {
     pub tick: usize,
     pub shown: bool,
@@ -5,7 +5,7 @@ use crate::framework::error::GameResult;
 use crate::game::frame::Frame;
 use crate::game::shared_game_state::SharedGameState;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub struct NumberPopup {
     pub value: i16,
     pub x: i32,
@@ -1,48 +1,743 @@
-use std::io::{Cursor, Read};
+use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 
 use byteorder::{LE, ReadBytesExt, WriteBytesExt};
 
+use crate::common::{Direction, Rect};
 use crate::entity::GameEntity;
 use crate::framework::context::Context;
+use crate::framework::error::GameError::{InvalidValue, ResourceLoadError};
 use crate::framework::error::GameResult;
 use crate::framework::filesystem;
 use crate::framework::keyboard::ScanCode;
-use crate::framework::vfs::OpenOptions;
 use crate::game::frame::Frame;
-use crate::game::shared_game_state::{ReplayKind, ReplayState, SharedGameState};
-use crate::input::replay_player_controller::{KeyState, ReplayController};
 use crate::game::player::Player;
+use crate::game::replay_library;
+use crate::game::settings::Settings;
+use crate::game::shared_game_state::{
+    CutsceneSkipMode, GameDifficulty, ReplayKind, ReplayState, SharedGameState, TimingMode,
+};
 use crate::graphics::font::Font;
+use crate::input::replay_player_controller::{KeyState, ReplayController};
+
+/// Version of the replay container written by this build.
+///
+/// Version 0 is the legacy format: a `u16` version placeholder, the RNG seed and one `u16` per tick.
+/// Version 2 adds the ghost track, version 3 adds keyframes.
+pub const REPLAY_VERSION: u16 = 3;
+
+/// Number of ticks between two state checksums stored in a replay.
+pub const REPLAY_CHECKSUM_INTERVAL: u32 = 50;
+
+/// Number of ticks Page Down skips during playback, 10 seconds at 50 ticks per second.
+pub const REPLAY_SEEK_STEP: usize = 500;
+
+/// Number of ticks between two keyframes stored in a replay, about a minute of gameplay.
+pub const REPLAY_KEYFRAME_INTERVAL: u32 = 3000;
+
+/// Longest replay that is loaded, a day of gameplay at 60 ticks per second.
+const MAX_REPLAY_TICKS: usize = 60 * 60 * 60 * 24;
+
+/// Largest header or keyframe of a replay that is loaded.
+const MAX_REPLAY_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Subset of `Settings` that influences the simulation and has to match during playback.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ReplaySettings {
+    pub timing_mode: TimingMode,
+    pub cutscene_skip_mode: CutsceneSkipMode,
+    pub allow_strafe: bool,
+    pub original_textures: bool,
+    pub god_mode: bool,
+    pub infinite_booster: bool,
+    pub noclip: bool,
+}
+
+impl Default for ReplaySettings {
+    fn default() -> Self {
+        ReplaySettings {
+            timing_mode: TimingMode::_50Hz,
+            cutscene_skip_mode: CutsceneSkipMode::Hold,
+            allow_strafe: true,
+            original_textures: false,
+            god_mode: false,
+            infinite_booster: false,
+            noclip: false,
+        }
+    }
+}
+
+impl ReplaySettings {
+    pub fn capture(settings: &Settings) -> ReplaySettings {
+        ReplaySettings {
+            timing_mode: settings.timing_mode,
+            cutscene_skip_mode: settings.cutscene_skip_mode,
+            allow_strafe: settings.allow_strafe,
+            original_textures: settings.original_textures,
+            god_mode: settings.god_mode,
+            infinite_booster: settings.infinite_booster,
+            noclip: settings.noclip,
+        }
+    }
+
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.timing_mode = self.timing_mode;
+        settings.cutscene_skip_mode = self.cutscene_skip_mode;
+        settings.allow_strafe = self.allow_strafe;
+        settings.original_textures = self.original_textures;
+        settings.god_mode = self.god_mode;
+        settings.infinite_booster = self.infinite_booster;
+        settings.noclip = self.noclip;
+    }
+
+    fn write_to<W: Write>(&self, data: &mut W) -> GameResult {
+        data.write_u8(match self.timing_mode {
+            TimingMode::_50Hz => 0,
+            TimingMode::_60Hz => 1,
+            TimingMode::FrameSynchronized => 2,
+        })?;
+        data.write_u8(match self.cutscene_skip_mode {
+            CutsceneSkipMode::Hold => 0,
+            CutsceneSkipMode::FastForward => 1,
+            CutsceneSkipMode::Auto => 2,
+        })?;
+        data.write_u8(
+            self.allow_strafe as u8
+                | (self.original_textures as u8) << 1
+                | (self.god_mode as u8) << 2
+                | (self.infinite_booster as u8) << 3
+                | (self.noclip as u8) << 4,
+        )?;
+
+        Ok(())
+    }
+
+    fn read_from<R: Read>(data: &mut R) -> GameResult<ReplaySettings> {
+        let timing_mode = match data.read_u8()? {
+            1 => TimingMode::_60Hz,
+            2 => TimingMode::FrameSynchronized,
+            _ => TimingMode::_50Hz,
+        };
+        let cutscene_skip_mode = match data.read_u8()? {
+            1 => CutsceneSkipMode::FastForward,
+            2 => CutsceneSkipMode::Auto,
+            _ => CutsceneSkipMode::Hold,
+        };
+        let flags = data.read_u8()?;
+
+        Ok(ReplaySettings {
+            timing_mode,
+            cutscene_skip_mode,
+            allow_strafe: flags & 0b00001 != 0,
+            original_textures: flags & 0b00010 != 0,
+            god_mode: flags & 0b00100 != 0,
+            infinite_booster: flags & 0b01000 != 0,
+            noclip: flags & 0b10000 != 0,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct ReplayHeader {
+    pub rng_seed: u64,
+    pub engine_version: String,
+    pub mod_id: String,
+    pub data_root: String,
+    pub stage_id: u16,
+    pub difficulty: GameDifficulty,
+    pub settings: ReplaySettings,
+    /// Unix timestamp of the start of the recording, 0 if unknown.
+    pub recorded_at: u64,
+}
+
+impl ReplayHeader {
+    pub fn new() -> ReplayHeader {
+        ReplayHeader {
+            rng_seed: 0,
+            engine_version: String::new(),
+            mod_id: String::new(),
+            data_root: String::new(),
+            stage_id: 0,
+            difficulty: GameDifficulty::Normal,
+            settings: ReplaySettings::default(),
+            recorded_at: 0,
+        }
+    }
+
+    pub fn capture(state: &SharedGameState, stage_id: usize) -> ReplayHeader {
+        let mod_id = state
+            .mod_path
+            .clone()
+            .and_then(|mod_path| state.mod_list.get_info_from_path(mod_path))
+            .map(|mod_info| mod_info.id.clone())
+            .unwrap_or_default();
+
+        ReplayHeader {
+            rng_seed: state.game_rng.dump_state(),
+            engine_version: env!("CARGO_PKG_VERSION").to_owned(),
+            mod_id,
+            data_root: state.constants.active_root.path.clone(),
+            stage_id: stage_id as u16,
+            difficulty: state.difficulty,
+            settings: ReplaySettings::capture(&state.settings),
+            recorded_at: chrono::Local::now().timestamp().max(0) as u64,
+        }
+    }
+
+    fn write_to<W: Write>(&self, data: &mut W) -> GameResult {
+        data.write_u64::<LE>(self.rng_seed)?;
+        write_string(data, &self.engine_version)?;
+        write_string(data, &self.mod_id)?;
+        write_string(data, &self.data_root)?;
+        data.write_u16::<LE>(self.stage_id)?;
+        data.write_u8(self.difficulty as u8)?;
+        self.settings.write_to(data)?;
+        data.write_u64::<LE>(self.recorded_at)?;
+
+        Ok(())
+    }
+
+    fn read_from<R: Read>(data: &mut R) -> GameResult<ReplayHeader> {
+        Ok(ReplayHeader {
+            rng_seed: data.read_u64::<LE>()?,
+            engine_version: read_string(data)?,
+            mod_id: read_string(data)?,
+            data_root: read_string(data)?,
+            stage_id: data.read_u16::<LE>()?,
+            difficulty: GameDifficulty::from_primitive(data.read_u8()?),
+            settings: ReplaySettings::read_from(data)?,
+            // added after version 1 was released, older headers end before it
+            recorded_at: data.read_u64::<LE>().unwrap_or(0),
+        })
+    }
+}
+
+fn write_string<W: Write>(data: &mut W, value: &str) -> GameResult {
+    let len = u16::try_from(value.len())
+        .map_err(|_| InvalidValue(format!("String is too long for a replay header: {} bytes", value.len())))?;
+
+    data.write_u16::<LE>(len)?;
+    data.write_all(value.as_bytes())?;
+
+    Ok(())
+}
+
+fn read_string<R: Read>(data: &mut R) -> GameResult<String> {
+    let len = data.read_u16::<LE>()? as usize;
+    let mut buf = vec![0u8; len];
+    data.read_exact(&mut buf)?;
+
+    String::from_utf8(buf).map_err(|_| ResourceLoadError("Invalid string in replay header".to_owned()))
+}
+
+/// Reads a chunk of given length, which is checked against the data that's actually there before allocating.
+fn read_chunk<R: Read>(data: &mut R, len: usize) -> GameResult<Vec<u8>> {
+    if len > MAX_REPLAY_CHUNK_SIZE {
+        return Err(ResourceLoadError(format!("Replay chunk is too large: {} bytes", len)));
+    }
+
+    let mut buf = Vec::new();
+    data.by_ref().take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(ResourceLoadError("Replay unexpectedly ended.".to_owned()));
+    }
+
+    Ok(buf)
+}
+
+/// Player state recorded every tick, used to draw a recorded run as a ghost next to the live player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GhostFrame {
+    pub stage_id: u16,
+    pub x: i32,
+    pub y: i32,
+    pub direction: Direction,
+    pub visible: bool,
+    pub anim_rect: Rect<u16>,
+    /// 0 if the player has no weapon equipped.
+    pub current_weapon: u8,
+    pub weapon_offset_y: i8,
+    pub weapon_rect: Rect<u16>,
+}
+
+impl GhostFrame {
+    fn write_to<W: Write>(&self, data: &mut W) -> GameResult {
+        data.write_u16::<LE>(self.stage_id)?;
+        data.write_i32::<LE>(self.x)?;
+        data.write_i32::<LE>(self.y)?;
+        data.write_u8(self.direction as u8 | (self.visible as u8) << 7)?;
+        write_rect(data, &self.anim_rect)?;
+        data.write_u8(self.current_weapon)?;
+        data.write_i8(self.weapon_offset_y)?;
+        write_rect(data, &self.weapon_rect)?;
+
+        Ok(())
+    }
+
+    fn read_from<R: Read>(data: &mut R) -> GameResult<GhostFrame> {
+        let stage_id = data.read_u16::<LE>()?;
+        let x = data.read_i32::<LE>()?;
+        let y = data.read_i32::<LE>()?;
+        let flags = data.read_u8()?;
+
+        Ok(GhostFrame {
+            stage_id,
+            x,
+            y,
+            direction: Direction::from_int_facing((flags & 0x7f) as usize).unwrap_or(Direction::Left),
+            visible: flags & 0x80 != 0,
+            anim_rect: read_rect(data)?,
+            current_weapon: data.read_u8()?,
+            weapon_offset_y: data.read_i8()?,
+            weapon_rect: read_rect(data)?,
+        })
+    }
+}
+
+fn write_rect<W: Write>(data: &mut W, rect: &Rect<u16>) -> GameResult {
+    data.write_u16::<LE>(rect.left)?;
+    data.write_u16::<LE>(rect.top)?;
+    data.write_u16::<LE>(rect.right)?;
+    data.write_u16::<LE>(rect.bottom)?;
+
+    Ok(())
+}
+
+fn read_rect<R: Read>(data: &mut R) -> GameResult<Rect<u16>> {
+    Ok(Rect {
+        left: data.read_u16::<LE>()?,
+        top: data.read_u16::<LE>()?,
+        right: data.read_u16::<LE>()?,
+        bottom: data.read_u16::<LE>()?,
+    })
+}
+
+/// A snapshot of the simulation the playback can be started from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayKeyframe {
+    /// Number of inputs consumed before the snapshot was taken.
+    pub tick: u32,
+    /// Save state without the replay, compressed with `SaveState::write_compressed`.
+    pub state: Vec<u8>,
+}
+
+/// Header and length of a replay, read without loading the rest of it.
+pub struct ReplaySummary {
+    pub version: u16,
+    pub header: ReplayHeader,
+    pub ticks: usize,
+    pub has_ghost: bool,
+}
+
+/// On-disk representation of a replay.
+///
+/// Layout of version 3 (all values little endian):
+/// - `u16` version
+/// - `u32` header length, followed by the header itself, so readers can skip fields they don't know
+/// - `u32` tick count
+/// - `u32` run count, followed by `(u16 keys, u16 length)` runs of `KeyState` values
+/// - `u32` checksum interval, `u32` checksum count, followed by `u32` checksums
+/// - `u32` ghost frame count, `u32` run count, followed by `(u16 length, GhostFrame)` runs
+/// - `u32` keyframe interval, `u32` keyframe count, followed by `(u32 tick, u32 length, [u8])` keyframes
+///
+/// Version 1 is the same without the ghost track, version 2 without the keyframes.
+#[derive(Clone)]
+pub struct ReplayData {
+    pub version: u16,
+    pub header: ReplayHeader,
+    pub inputs: Vec<u16>,
+    pub checksum_interval: u32,
+    pub checksums: Vec<u32>,
+    /// Player state for every recorded tick, empty for replays recorded before version 2.
+    pub ghost: Vec<GhostFrame>,
+    pub keyframe_interval: u32,
+    /// Keyframes ordered by tick, empty for replays recorded before version 3.
+    pub keyframes: Vec<ReplayKeyframe>,
+}
+
+impl ReplayData {
+    pub fn new() -> ReplayData {
+        ReplayData {
+            version: REPLAY_VERSION,
+            header: ReplayHeader::new(),
+            inputs: Vec::new(),
+            checksum_interval: REPLAY_CHECKSUM_INTERVAL,
+            checksums: Vec::new(),
+            ghost: Vec::new(),
+            keyframe_interval: REPLAY_KEYFRAME_INTERVAL,
+            keyframes: Vec::new(),
+        }
+    }
+
+    /// Returns the `KeyState` bits recorded for given tick.
+    pub fn input_at(&self, tick: usize) -> u16 {
+        *self.inputs.get(tick).unwrap_or(&0)
+    }
+
+    /// Returns the checksum expected at given tick, if one was recorded for it.
+    pub fn checksum_at(&self, tick: usize) -> Option<u32> {
+        if self.checksum_interval == 0 || tick % self.checksum_interval as usize != 0 {
+            return None;
+        }
+
+        self.checksums.get(tick / self.checksum_interval as usize).copied()
+    }
+
+    /// Returns true if a keyframe should be recorded before the input of the next tick.
+    pub fn is_keyframe_due(&self) -> bool {
+        let tick = self.inputs.len();
+
+        self.keyframe_interval != 0
+            && tick != 0
+            && tick % self.keyframe_interval as usize == 0
+            && self.keyframes.last().map_or(true, |keyframe| keyframe.tick as usize != tick)
+    }
+
+    /// Returns the last keyframe at or before given tick.
+    pub fn keyframe_before(&self, tick: usize) -> Option<&ReplayKeyframe> {
+        let idx = self.keyframes.partition_point(|keyframe| keyframe.tick as usize <= tick);
+
+        idx.checked_sub(1).map(|idx| &self.keyframes[idx])
+    }
+
+    pub fn encode_inputs(inputs: &[u16]) -> Vec<(u16, u16)> {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+
+        for &input in inputs {
+            match runs.last_mut() {
+                Some((keys, len)) if *keys == input && *len < u16::MAX => *len += 1,
+                _ => runs.push((input, 1)),
+            }
+        }
+
+        runs
+    }
+
+    pub fn decode_inputs(runs: &[(u16, u16)]) -> Vec<u16> {
+        let mut inputs = Vec::with_capacity(runs.iter().map(|&(_, len)| len as usize).sum());
+
+        for &(keys, len) in runs {
+            inputs.extend(std::iter::repeat(keys).take(len as usize));
+        }
+
+        inputs
+    }
+
+    pub fn write_to<W: Write>(&self, data: W) -> GameResult {
+        self.write_with_keyframes(data, &self.keyframes)
+    }
+
+    /// Writes the replay without its keyframes, used when the replay is part of a save state.
+    pub fn write_without_keyframes<W: Write>(&self, data: W) -> GameResult {
+        self.write_with_keyframes(data, &[])
+    }
+
+    fn write_with_keyframes<W: Write>(&self, mut data: W, keyframes: &[ReplayKeyframe]) -> GameResult {
+        let mut header = Vec::new();
+        self.header.write_to(&mut header)?;
+
+        data.write_u16::<LE>(REPLAY_VERSION)?;
+        data.write_u32::<LE>(header.len() as u32)?;
+        data.write_all(&header)?;
+
+        let runs = ReplayData::encode_inputs(&self.inputs);
+        data.write_u32::<LE>(self.inputs.len() as u32)?;
+        data.write_u32::<LE>(runs.len() as u32)?;
+        for (keys, len) in runs {
+            data.write_u16::<LE>(keys)?;
+            data.write_u16::<LE>(len)?;
+        }
+
+        data.write_u32::<LE>(self.checksum_interval)?;
+        data.write_u32::<LE>(self.checksums.len() as u32)?;
+        for checksum in &self.checksums {
+            data.write_u32::<LE>(*checksum)?;
+        }
+
+        let mut ghost_runs: Vec<(GhostFrame, u16)> = Vec::new();
+        for &frame in &self.ghost {
+            match ghost_runs.last_mut() {
+                Some((last, len)) if *last == frame && *len < u16::MAX => *len += 1,
+                _ => ghost_runs.push((frame, 1)),
+            }
+        }
+
+        data.write_u32::<LE>(self.ghost.len() as u32)?;
+        data.write_u32::<LE>(ghost_runs.len() as u32)?;
+        for (frame, len) in ghost_runs {
+            data.write_u16::<LE>(len)?;
+            frame.write_to(&mut data)?;
+        }
+
+        data.write_u32::<LE>(self.keyframe_interval)?;
+        data.write_u32::<LE>(keyframes.len() as u32)?;
+        for keyframe in keyframes {
+            data.write_u32::<LE>(keyframe.tick)?;
+            data.write_u32::<LE>(keyframe.state.len() as u32)?;
+            data.write_all(&keyframe.state)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(mut data: R) -> GameResult<ReplayData> {
+        let version = data.read_u16::<LE>()?;
+
+        match version {
+            0 => {
+                let mut header = ReplayHeader::new();
+                header.rng_seed = data.read_u64::<LE>()?;
+
+                let mut buf = Vec::new();
+                data.read_to_end(&mut buf)?;
+
+                let mut f = Cursor::new(buf);
+                let mut inputs = Vec::new();
+                while let Ok(input) = f.read_u16::<LE>() {
+                    inputs.push(input);
+                }
+
+                Ok(ReplayData {
+                    version,
+                    header,
+                    inputs,
+                    checksum_interval: 0,
+                    checksums: Vec::new(),
+                    ghost: Vec::new(),
+                    keyframe_interval: 0,
+                    keyframes: Vec::new(),
+                })
+            }
+            1..=3 => {
+                let header_len = data.read_u32::<LE>()? as usize;
+                let header_buf = read_chunk(&mut data, header_len)?;
+                let header = ReplayHeader::read_from(&mut Cursor::new(header_buf))?;
+
+                let tick_count = data.read_u32::<LE>()? as usize;
+                if tick_count > MAX_REPLAY_TICKS {
+                    return Err(ResourceLoadError(format!("Replay is too long: {} ticks", tick_count)));
+                }
+
+                // every count is checked against the tick count, so a corrupted one can't cause a huge allocation
+                let corrupted = |what: &str| ResourceLoadError(format!("Replay {} is corrupted.", what));
+
+                let run_count = data.read_u32::<LE>()? as usize;
+                if run_count > tick_count {
+                    return Err(corrupted("input stream"));
+                }
+
+                let mut runs = Vec::with_capacity(run_count);
+                let mut run_ticks = 0;
+                for _ in 0..run_count {
+                    let keys = data.read_u16::<LE>()?;
+                    let len = data.read_u16::<LE>()?;
+
+                    run_ticks += len as usize;
+                    if run_ticks > tick_count {
+                        return Err(corrupted("input stream"));
+                    }
+                    runs.push((keys, len));
+                }
+
+                let inputs = ReplayData::decode_inputs(&runs);
+                if inputs.len() != tick_count {
+                    return Err(ResourceLoadError(format!(
+                        "Replay input stream is corrupted: expected {} ticks, got {}",
+                        tick_count,
+                        inputs.len()
+                    )));
+                }
+
+                let checksum_interval = data.read_u32::<LE>()?;
+                let checksum_count = data.read_u32::<LE>()? as usize;
+                if checksum_count > tick_count / checksum_interval.max(1) as usize + 1 {
+                    return Err(corrupted("checksum list"));
+                }
+
+                let mut checksums = Vec::with_capacity(checksum_count);
+                for _ in 0..checksum_count {
+                    checksums.push(data.read_u32::<LE>()?);
+                }
+
+                let mut ghost = Vec::new();
+                if version >= 2 {
+                    let frame_count = data.read_u32::<LE>()? as usize;
+                    let run_count = data.read_u32::<LE>()? as usize;
+                    if frame_count > tick_count || run_count > frame_count {
+                        return Err(corrupted("ghost track"));
+                    }
+
+                    for _ in 0..run_count {
+                        let len = data.read_u16::<LE>()? as usize;
+                        let frame = GhostFrame::read_from(&mut data)?;
+
+                        if ghost.len() + len > frame_count {
+                            return Err(corrupted("ghost track"));
+                        }
+                        ghost.extend(std::iter::repeat(frame).take(len));
+                    }
+
+                    if ghost.len() != frame_count {
+                        return Err(ResourceLoadError(format!(
+                            "Replay ghost track is corrupted: expected {} frames, got {}",
+                            frame_count,
+                            ghost.len()
+                        )));
+                    }
+                }
+
+                let mut keyframe_interval = 0;
+                let mut keyframes = Vec::new();
+                if version >= 3 {
+                    keyframe_interval = data.read_u32::<LE>()?;
+                    let keyframe_count = data.read_u32::<LE>()? as usize;
+                    if keyframe_count > tick_count / keyframe_interval.max(1) as usize + 1 {
+                        return Err(corrupted("keyframe list"));
+                    }
+
+                    for _ in 0..keyframe_count {
+                        let tick = data.read_u32::<LE>()?;
+                        let len = data.read_u32::<LE>()? as usize;
+                        let state = read_chunk(&mut data, len)?;
+
+                        if keyframes.last().map_or(false, |last: &ReplayKeyframe| last.tick >= tick) {
+                            return Err(ResourceLoadError("Replay keyframes are out of order.".to_owned()));
+                        }
+                        keyframes.push(ReplayKeyframe { tick, state });
+                    }
+                }
+
+                Ok(ReplayData {
+                    version,
+                    header,
+                    inputs,
+                    checksum_interval,
+                    checksums,
+                    ghost,
+                    keyframe_interval,
+                    keyframes,
+                })
+            }
+            _ => Err(ResourceLoadError(format!("Unsupported replay version: {}", version))),
+        }
+    }
+
+    /// Reads the header and length of a replay, seeking over the inputs and checksums
+    /// and stopping before the ghost track and keyframes.
+    pub fn read_summary<R: Read + Seek>(mut data: R) -> GameResult<ReplaySummary> {
+        let version = data.read_u16::<LE>()?;
+
+        match version {
+            0 => {
+                let mut header = ReplayHeader::new();
+                header.rng_seed = data.read_u64::<LE>()?;
+
+                let start = data.stream_position()?;
+                let end = data.seek(SeekFrom::End(0))?;
+
+                Ok(ReplaySummary { version, header, ticks: (end - start) as usize / 2, has_ghost: false })
+            }
+            1..=3 => {
+                let header_len = data.read_u32::<LE>()? as usize;
+                let header_buf = read_chunk(&mut data, header_len)?;
+                let header = ReplayHeader::read_from(&mut Cursor::new(header_buf))?;
+
+                let ticks = data.read_u32::<LE>()? as usize;
+                if ticks > MAX_REPLAY_TICKS {
+                    return Err(ResourceLoadError(format!("Replay is too long: {} ticks", ticks)));
+                }
+
+                let mut has_ghost = false;
+                if version >= 2 {
+                    let run_count = data.read_u32::<LE>()?;
+                    data.seek(SeekFrom::Current(run_count as i64 * 4))?;
+
+                    let _checksum_interval = data.read_u32::<LE>()?;
+                    let checksum_count = data.read_u32::<LE>()?;
+                    data.seek(SeekFrom::Current(checksum_count as i64 * 4))?;
+
+                    has_ghost = data.read_u32::<LE>()? != 0;
+                }
+
+                Ok(ReplaySummary { version, header, ticks, has_ghost })
+            }
+            _ => Err(ResourceLoadError(format!("Unsupported replay version: {}", version))),
+        }
+    }
+}
+
+/// FNV-1a hasher used for replay state checksums, stable across platforms and builds.
+struct StateHasher(u32);
+
+impl StateHasher {
+    fn new() -> StateHasher {
+        StateHasher(0x811c9dc5)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u32;
+            self.0 = self.0.wrapping_mul(0x01000193);
+        }
+    }
+}
+
+/// Computes a checksum of the simulation state that is used to detect replay desyncs.
+pub fn state_checksum(state: &SharedGameState, player: &Player) -> u32 {
+    let mut hasher = StateHasher::new();
+
+    hasher.write(&state.game_rng.dump_state().to_le_bytes());
+    hasher.write(&player.x.to_le_bytes());
+    hasher.write(&player.y.to_le_bytes());
+    hasher.write(&player.vel_x.to_le_bytes());
+    hasher.write(&player.vel_y.to_le_bytes());
+    hasher.write(&player.life.to_le_bytes());
+
+    let mut flags = [0u8; 1000];
+    state.game_flags.copy_to_slice(&mut flags);
+    hasher.write(&flags);
+
+    hasher.0
+}
 
 #[derive(Clone)]
 pub struct Replay {
-    replay_version: u16,
-    keylist: Vec<u16>,
+    pub data: ReplayData,
     last_input: KeyState,
-    rng_seed: u64,
     pub controller: ReplayController,
     tick: usize,
     resume_tick: usize,
     is_active: bool,
+    /// Tick the playback fast-forwards to, see [Replay::seek].
+    seek_target: Option<usize>,
+    seek_key_held: bool,
+    /// The tick at which the state checksum first differed from the recorded one.
+    pub desync_tick: Option<usize>,
+    /// Nikumaru counter value at the point the played back run hit `<STC`.
+    pub finish_time: Option<usize>,
+    preloaded: bool,
 }
 
 impl Replay {
     pub fn new() -> Replay {
         Replay {
-            replay_version: 0,
-            keylist: Vec::new(),
+            data: ReplayData::new(),
             last_input: KeyState(0),
-            rng_seed: 0,
             controller: ReplayController::new(),
             tick: 0,
             resume_tick: 0,
             is_active: false,
+            seek_target: None,
+            seek_key_held: false,
+            desync_tick: None,
+            finish_time: None,
+            preloaded: false,
         }
     }
 
-    pub fn initialize_recording(&mut self, state: &mut SharedGameState) {
+    pub fn initialize_recording(&mut self, state: &mut SharedGameState, stage_id: usize) {
         if !self.is_active {
-            self.rng_seed = state.game_rng.dump_state();
+            self.data = ReplayData::new();
+            self.data.header = ReplayHeader::capture(state, stage_id);
             self.is_active = true;
         }
     }
@@ -61,9 +756,21 @@ impl Replay {
             self.write_replay(state, ctx, ReplayKind::Best)?;
         }
 
+        if let Err(err) = replay_library::add(ctx, &self.data) {
+            log::warn!("Failed to add the replay to the library: {}", err);
+        }
+        replay_library::prune(ctx);
+
         Ok(())
     }
 
+    /// Appends the state of the recorded player to the ghost track, called once per recorded tick.
+    pub fn record_ghost_frame(&mut self, player: &Player, stage_id: usize) {
+        if self.data.ghost.len() < self.data.inputs.len() {
+            self.data.ghost.push(player.ghost_frame(stage_id));
+        }
+    }
+
     pub fn initialize_playback(
         &mut self,
         state: &mut SharedGameState,
@@ -72,48 +779,126 @@ impl Replay {
     ) -> GameResult {
         if !self.is_active {
             state.replay_state = ReplayState::Playback(replay_kind);
-            self.read_replay(state, ctx, replay_kind)?;
-            state.game_rng.load_state(self.rng_seed);
-            self.is_active = true;
+            if !self.preloaded {
+                self.read_replay(state, ctx, replay_kind)?;
+            }
+            self.start_playback(state);
         }
         Ok(())
     }
 
-    fn write_replay(&mut self, state: &mut SharedGameState, ctx: &mut Context, replay_kind: ReplayKind) -> GameResult {
-        if let Ok(mut file) = filesystem::open_options(
-            ctx,
-            [state.get_rec_filename(), replay_kind.get_suffix()].join(""),
-            OpenOptions::new().write(true).create(true),
-        ) {
-            file.write_u16::<LE>(0)?; // Space for versioning replay files
-            file.write_u64::<LE>(self.rng_seed)?;
-            for input in &self.keylist {
-                file.write_u16::<LE>(*input)?;
+    /// Uses given replay data for the next playback instead of reading it from the user directory.
+    pub fn preload(&mut self, data: ReplayData) {
+        self.data = data;
+        self.preloaded = true;
+    }
+
+    /// Seeds the game with the loaded replay data and applies the settings it was recorded with.
+    pub fn start_playback(&mut self, state: &mut SharedGameState) {
+        let header = &self.data.header;
+
+        if self.data.version > 0 {
+            if header.engine_version != env!("CARGO_PKG_VERSION") {
+                log::warn!(
+                    "Replay was recorded with engine version {}, playback may desync.",
+                    header.engine_version
+                );
+            }
+
+            let current = ReplayHeader::capture(state, header.stage_id as usize);
+            if header.mod_id != current.mod_id || header.data_root != current.data_root {
+                log::warn!(
+                    "Replay was recorded for mod '{}' in '{}', but '{}' in '{}' is active, playback may desync.",
+                    header.mod_id,
+                    header.data_root,
+                    current.mod_id,
+                    current.data_root
+                );
+            }
+
+            if state.replay_settings_backup.is_none() {
+                state.replay_settings_backup = Some(ReplaySettings::capture(&state.settings));
             }
+            header.settings.apply(&mut state.settings);
+            state.difficulty = header.difficulty;
+        }
+
+        state.game_rng.load_state(header.rng_seed);
+        self.desync_tick = None;
+        self.finish_time = None;
+        self.is_active = true;
+    }
+
+    fn stop_playback(&self, state: &mut SharedGameState, player: &mut Player) {
+        state.replay_state = ReplayState::None;
+        player.controller = state.settings.create_player1_controller();
+
+        state.restore_replay_settings();
+    }
+
+    /// Returns true if the playback has consumed all recorded input.
+    pub fn is_finished(&self) -> bool {
+        self.is_active && self.tick >= self.data.inputs.len()
+    }
+
+    pub fn current_tick(&self) -> usize {
+        self.tick
+    }
+
+    /// Fast-forwards the playback to given tick, the game scene runs the ticks in between without drawing them.
+    /// The simulation can't run backwards, so ticks that were already played back are only reachable through
+    /// an earlier keyframe, and ignored without one.
+    pub fn seek(&mut self, tick: usize) {
+        if tick > self.tick || self.data.keyframe_before(tick).is_some() {
+            self.seek_target = Some(tick.min(self.data.inputs.len()));
+        }
+    }
+
+    /// Returns the tick of a pending seek and clears it.
+    pub fn take_seek_target(&mut self) -> Option<usize> {
+        self.seek_target.take()
+    }
+
+    /// Continues the playback from given tick, the state of the game has to be restored from a keyframe first.
+    pub fn resume_at(&mut self, tick: usize) {
+        self.tick = tick;
+        self.resume_tick = tick;
+        self.last_input = KeyState(tick.checked_sub(1).map_or(0, |tick| self.data.input_at(tick)));
+        self.desync_tick = None;
+    }
+
+    fn write_replay(&mut self, state: &mut SharedGameState, ctx: &mut Context, replay_kind: ReplayKind) -> GameResult {
+        if let Ok(file) = filesystem::user_create(ctx, [state.get_rec_filename(), replay_kind.get_suffix()].join("")) {
+            self.data.write_to(file)?;
         }
         Ok(())
     }
 
     fn read_replay(&mut self, state: &mut SharedGameState, ctx: &mut Context, replay_kind: ReplayKind) -> GameResult {
-        if let Ok(mut file) = filesystem::user_open(ctx, [state.get_rec_filename(), replay_kind.get_suffix()].join(""))
-        {
-            self.replay_version = file.read_u16::<LE>()?;
-            self.rng_seed = file.read_u64::<LE>()?;
+        if let Ok(file) = filesystem::user_open(ctx, [state.get_rec_filename(), replay_kind.get_suffix()].join("")) {
+            self.data = ReplayData::read_from(file)?;
+        }
+        Ok(())
+    }
 
-            let mut data = Vec::new();
-            file.read_to_end(&mut data)?;
+    fn check_desync(&mut self, state: &SharedGameState, player: &Player) {
+        if self.desync_tick.is_some() {
+            return;
+        }
 
-            let count = data.len() / 2;
-            let mut inputs = Vec::new();
-            let mut f = Cursor::new(data);
+        if let Some(expected) = self.data.checksum_at(self.tick) {
+            let actual = state_checksum(state, player);
 
-            for _ in 0..count {
-                inputs.push(f.read_u16::<LE>()?);
+            if actual != expected {
+                log::error!(
+                    "Replay desync detected at tick {} (expected checksum {:08x}, got {:08x}).",
+                    self.tick,
+                    expected,
+                    actual
+                );
+                self.desync_tick = Some(self.tick);
             }
-
-            self.keylist = inputs;
         }
-        Ok(())
     }
 }
 
@@ -136,12 +921,27 @@ impl GameEntity<(&mut Context, &mut Player)> for Replay {
                     + ((player.controller.skip() as u16) << 12)
                     + ((player.controller.strafe() as u16) << 13);
 
-                self.keylist.push(inputs);
+                let tick = self.data.inputs.len();
+                if self.data.checksum_interval > 0 && tick % self.data.checksum_interval as usize == 0 {
+                    self.data.checksums.push(state_checksum(state, player));
+                }
+
+                self.data.inputs.push(inputs);
             }
             ReplayState::Playback(_) => {
                 let pause = ctx.keyboard_context.is_key_pressed(ScanCode::Escape) && (self.tick - self.resume_tick > 3);
 
-                let next_input = if pause { 1 << 10 } else { *self.keylist.get(self.tick).unwrap_or(&0) };
+                let seek_key = ctx.keyboard_context.is_key_pressed(ScanCode::PageDown);
+                if seek_key && !self.seek_key_held {
+                    self.seek(self.tick + REPLAY_SEEK_STEP);
+                }
+                self.seek_key_held = seek_key;
+
+                if !pause {
+                    self.check_desync(state, player);
+                }
+
+                let next_input = if pause { 1 << 10 } else { self.data.input_at(self.tick) };
 
                 self.controller.state = KeyState(next_input);
                 self.controller.old_state = self.last_input;
@@ -154,9 +954,8 @@ impl GameEntity<(&mut Context, &mut Player)> for Replay {
                     self.resume_tick = self.tick;
                 };
 
-                if self.tick >= self.keylist.len() {
-                    state.replay_state = ReplayState::None;
-                    player.controller = state.settings.create_player1_controller();
+                if self.tick >= self.data.inputs.len() {
+                    self.stop_playback(state, player);
                 }
             }
             ReplayState::None => {}
@@ -172,9 +971,11 @@ impl GameEntity<(&mut Context, &mut Player)> for Replay {
         match state.replay_state {
             ReplayState::None => {}
             ReplayState::Playback(_) => {
+                let text = if self.desync_tick.is_some() { "DSYN" } else { "PLAY" };
+
                 state.font.builder()
                     .position(x, y)
-                    .draw("PLAY", ctx, &state.constants, &mut state.texture_set)?;
+                    .draw(text, ctx, &state.constants, &mut state.texture_set)?;
             }
             ReplayState::Recording => {
                 state.font.builder()
@@ -186,3 +987,181 @@ impl GameEntity<(&mut Context, &mut Player)> for Replay {
         Ok(())
     }
 }
+
+#[test]
+fn test_replay_roundtrip() {
+    let mut data = ReplayData::new();
+    data.header.rng_seed = 0x1234_5678_9abc_def0;
+    data.header.engine_version = "1.0.0".to_owned();
+    data.header.mod_id = "mod1".to_owned();
+    data.header.stage_id = 13;
+    data.header.difficulty = GameDifficulty::Hard;
+    data.header.settings.timing_mode = TimingMode::_60Hz;
+    data.header.settings.noclip = true;
+    data.inputs = vec![0, 0, 0, 1, 1, 64, 0, 0];
+    data.inputs.extend(std::iter::repeat(2).take(70000));
+    data.checksums = vec![1, 2, 3];
+    data.header.recorded_at = 1_700_000_000;
+
+    let frame = GhostFrame {
+        stage_id: 13,
+        x: 0x4000,
+        y: -0x200,
+        direction: Direction::Right,
+        visible: true,
+        anim_rect: Rect::new(16, 0, 32, 16),
+        current_weapon: 2,
+        weapon_offset_y: -4,
+        weapon_rect: Rect::new(24, 0, 48, 16),
+    };
+    data.ghost = vec![frame; 3];
+    data.ghost.push(GhostFrame { visible: false, direction: Direction::Left, ..frame });
+    data.keyframes = vec![
+        ReplayKeyframe { tick: REPLAY_KEYFRAME_INTERVAL, state: b"{}".to_vec() },
+        ReplayKeyframe { tick: REPLAY_KEYFRAME_INTERVAL * 2, state: Vec::new() },
+    ];
+
+    let mut buf = Vec::new();
+    data.write_to(&mut buf).unwrap();
+
+    let read = ReplayData::read_from(Cursor::new(buf)).unwrap();
+    assert_eq!(read.version, REPLAY_VERSION);
+    assert_eq!(read.header.rng_seed, data.header.rng_seed);
+    assert_eq!(read.header.mod_id, "mod1");
+    assert_eq!(read.header.stage_id, 13);
+    assert_eq!(read.header.difficulty, GameDifficulty::Hard);
+    assert!(read.header.settings == data.header.settings);
+    assert_eq!(read.inputs, data.inputs);
+    assert_eq!(read.checksums, data.checksums);
+    assert_eq!(read.header.recorded_at, 1_700_000_000);
+    assert_eq!(read.ghost, data.ghost);
+    assert_eq!(read.keyframe_interval, REPLAY_KEYFRAME_INTERVAL);
+    assert_eq!(read.keyframes, data.keyframes);
+    assert_eq!(read.checksum_at(0), Some(1));
+    assert_eq!(read.checksum_at(REPLAY_CHECKSUM_INTERVAL as usize), Some(2));
+    assert_eq!(read.checksum_at(1), None);
+
+    let mut buf = Vec::new();
+    data.write_to(&mut buf).unwrap();
+    let summary = ReplayData::read_summary(Cursor::new(buf)).unwrap();
+    assert_eq!(summary.version, REPLAY_VERSION);
+    assert_eq!(summary.header.mod_id, "mod1");
+    assert_eq!(summary.ticks, data.inputs.len());
+    assert!(summary.has_ghost);
+}
+
+#[test]
+fn test_replay_legacy() {
+    let mut buf = Vec::new();
+    buf.write_u16::<LE>(0).unwrap();
+    buf.write_u64::<LE>(42).unwrap();
+    for input in [0u16, 1, 2, 3] {
+        buf.write_u16::<LE>(input).unwrap();
+    }
+
+    let read = ReplayData::read_from(Cursor::new(&buf)).unwrap();
+    assert_eq!(read.version, 0);
+    assert_eq!(read.header.rng_seed, 42);
+    assert_eq!(read.inputs, vec![0, 1, 2, 3]);
+    assert_eq!(read.checksum_at(0), None);
+    assert!(read.keyframes.is_empty());
+
+    let summary = ReplayData::read_summary(Cursor::new(buf)).unwrap();
+    assert_eq!(summary.header.rng_seed, 42);
+    assert_eq!(summary.ticks, 4);
+    assert!(!summary.has_ghost);
+}
+
+#[test]
+fn test_replay_keyframes() {
+    let mut data = ReplayData::new();
+    data.keyframe_interval = 10;
+    assert!(!data.is_keyframe_due());
+
+    for tick in 1..=30 {
+        data.inputs.push(0);
+        assert_eq!(data.is_keyframe_due(), tick % 10 == 0);
+
+        if data.is_keyframe_due() {
+            data.keyframes.push(ReplayKeyframe { tick, state: Vec::new() });
+            assert!(!data.is_keyframe_due());
+        }
+    }
+
+    let keyframe_tick = |tick| data.keyframe_before(tick).map(|keyframe| keyframe.tick);
+    assert_eq!(keyframe_tick(0), None);
+    assert_eq!(keyframe_tick(9), None);
+    assert_eq!(keyframe_tick(10), Some(10));
+    assert_eq!(keyframe_tick(25), Some(20));
+    assert_eq!(keyframe_tick(1000), Some(30));
+
+    // seeking backwards needs a keyframe before the target
+    let mut replay = Replay::new();
+    replay.data = data.clone();
+    replay.tick = 25;
+    replay.seek(5);
+    assert_eq!(replay.take_seek_target(), None);
+    replay.seek(15);
+    assert_eq!(replay.take_seek_target(), Some(15));
+
+    // replays stored in save states leave the keyframes out
+    let mut buf = Vec::new();
+    data.write_without_keyframes(&mut buf).unwrap();
+    let stripped = ReplayData::read_from(Cursor::new(buf)).unwrap();
+    assert_eq!(stripped.inputs, data.inputs);
+    assert!(stripped.keyframes.is_empty());
+
+    let mut buf = Vec::new();
+    data.write_to(&mut buf).unwrap();
+    // moves the first keyframe, each is a tick and an empty state, after the last one
+    let first = buf.len() - 3 * 8;
+    buf[first..first + 4].copy_from_slice(&40u32.to_le_bytes());
+    assert!(ReplayData::read_from(Cursor::new(buf)).is_err());
+}
+
+#[test]
+fn test_replay_seek() {
+    let mut replay = Replay::new();
+    replay.data.inputs = vec![0; 100];
+
+    replay.seek(0);
+    assert_eq!(replay.take_seek_target(), None);
+
+    replay.seek(1000);
+    assert_eq!(replay.take_seek_target(), Some(100));
+    assert_eq!(replay.take_seek_target(), None);
+
+    replay.tick = 50;
+    replay.seek(40);
+    assert_eq!(replay.take_seek_target(), None);
+    replay.seek(60);
+    assert_eq!(replay.take_seek_target(), Some(60));
+}
+
+#[test]
+fn test_replay_bounded_lengths() {
+    let mut data = ReplayData::new();
+    data.inputs = vec![0; 20];
+    data.keyframe_interval = 10;
+    data.keyframes = vec![ReplayKeyframe { tick: 10, state: b"abc".to_vec() }];
+
+    let mut buf = Vec::new();
+    data.write_to(&mut buf).unwrap();
+    assert!(ReplayData::read_from(Cursor::new(&buf)).is_ok());
+
+    let mut corrupted = buf.clone();
+    corrupted[2..6].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(ReplayData::read_from(Cursor::new(corrupted)).is_err());
+
+    let mut corrupted = buf.clone();
+    let len = buf.len() - 3 - 4;
+    corrupted[len..len + 4].copy_from_slice(&0xffff_0000u32.to_le_bytes());
+    assert!(ReplayData::read_from(Cursor::new(corrupted)).is_err());
+
+    let mut corrupted = buf;
+    corrupted[len..len + 4].copy_from_slice(&4u32.to_le_bytes());
+    assert!(ReplayData::read_from(Cursor::new(corrupted)).is_err());
+
+    data.header.mod_id = "a".repeat(u16::MAX as usize + 1);
+    assert!(data.write_to(&mut Vec::new()).is_err());
+}
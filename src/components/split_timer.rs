@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::framework::context::Context;
+use crate::framework::error::GameResult;
+use crate::framework::filesystem;
+use crate::game::shared_game_state::{ReplayState, SharedGameState, TimingMode};
+
+/// Split definition in the user directory.
+///
+/// The format is `{"splits": [{"name": "First Cave", "trigger": {"stage": 12}}, ...]}`, see `SplitTrigger`
+/// for the available triggers.
+pub const SPLITS_FILE: &str = "/splits.json";
+
+/// Best segment times of the split definition, keyed by split name.
+pub const SPLIT_BESTS_FILE: &str = "/splits.best.json";
+
+/// Engine event ending a segment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitTrigger {
+    /// Entering the stage with given ID through `<TRA`.
+    Stage(usize),
+    /// Defeating the boss with given number, as used by `<BSL` and stage tables. 0 matches any boss.
+    Boss(u16),
+    /// Setting the game flag with given ID.
+    Flag(usize),
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Split {
+    pub name: String,
+    pub trigger: SplitTrigger,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SplitDefinition {
+    pub splits: Vec<Split>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SegmentTime {
+    /// In-game time in ticks.
+    pub igt: usize,
+    /// Real time in milliseconds.
+    pub rta: u64,
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct SplitBests {
+    pub segments: HashMap<String, SegmentTime>,
+    /// Cumulative times at each split of the fastest finished run.
+    #[serde(default)]
+    pub personal_best: Vec<SegmentTime>,
+}
+
+/// Real-time and in-game-time timer split automatically on engine events.
+///
+/// The in-game time counts game scene ticks outside of the pause menu, so it's the same for a replay
+/// and the run it was recorded from. The real time includes the pause menu and is measured with a clock,
+/// unless the game doesn't run at real-time speed, then it's derived from the tick count instead.
+/// Best segment times are only updated outside of replay playback.
+pub struct SplitTimer {
+    pub definition: Option<SplitDefinition>,
+    pub bests: SplitBests,
+    /// Index of the current split, equal to the split count once the run is finished.
+    pub current: usize,
+    /// Cumulative times at each finished split.
+    pub times: Vec<SegmentTime>,
+    pub igt: usize,
+    /// Game scene ticks since the start of the run, including the pause menu.
+    ticks: usize,
+    timing_mode: TimingMode,
+    real_time: bool,
+    started_at: Option<Instant>,
+    finished_rta: Option<Duration>,
+    bests_dirty: bool,
+}
+
+impl SplitTimer {
+    pub fn new() -> SplitTimer {
+        SplitTimer {
+            definition: None,
+            bests: SplitBests::default(),
+            current: 0,
+            times: Vec::new(),
+            igt: 0,
+            ticks: 0,
+            timing_mode: TimingMode::_50Hz,
+            real_time: true,
+            started_at: None,
+            finished_rta: None,
+            bests_dirty: false,
+        }
+    }
+
+    /// Loads the split definition and best times from the user directory, the timer stays disabled
+    /// if there's no definition.
+    pub fn load(&mut self, ctx: &Context) {
+        self.definition = None;
+        self.bests = SplitBests::default();
+
+        if let Ok(file) = filesystem::user_open(ctx, SPLITS_FILE) {
+            match serde_json::from_reader::<_, SplitDefinition>(file) {
+                Ok(definition) => self.definition = Some(definition),
+                Err(err) => log::warn!("Failed to load split definition: {}", err),
+            }
+        }
+
+        if let Ok(file) = filesystem::user_open(ctx, SPLIT_BESTS_FILE) {
+            match serde_json::from_reader::<_, SplitBests>(file) {
+                Ok(bests) => self.bests = bests,
+                Err(err) => log::warn!("Failed to load best split times: {}", err),
+            }
+        }
+    }
+
+    /// Restarts the timer at the beginning of a run, `timing_mode` determines the length of a tick.
+    pub fn start(&mut self, timing_mode: TimingMode) {
+        self.current = 0;
+        self.times.clear();
+        self.igt = 0;
+        self.ticks = 0;
+        self.timing_mode = timing_mode;
+        self.real_time = true;
+        self.started_at = Some(Instant::now());
+        self.finished_rta = None;
+    }
+
+    /// Derives the real time from the tick count for the rest of the run, for when the ticks don't run
+    /// at real-time speed, eg. headless replay playback, frame capture or replay seeking.
+    pub fn disable_real_time(&mut self) {
+        self.real_time = false;
+    }
+
+    /// Stops and hides the timer until the next run starts.
+    pub fn stop(&mut self) {
+        self.started_at = None;
+        self.finished_rta = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some() && self.finished_rta.is_none()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished_rta.is_some()
+    }
+
+    pub fn split_count(&self) -> usize {
+        self.definition.as_ref().map_or(0, |definition| definition.splits.len())
+    }
+
+    pub fn current_split(&self) -> Option<&Split> {
+        self.definition.as_ref().and_then(|definition| definition.splits.get(self.current))
+    }
+
+    pub fn rta(&self) -> Duration {
+        match (self.finished_rta, self.started_at) {
+            (Some(rta), _) => rta,
+            (None, Some(_)) if !self.real_time => Duration::from_millis(ticks_to_millis(self.ticks, self.timing_mode)),
+            (None, Some(started_at)) => started_at.elapsed(),
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    /// Time of the current segment so far, or of the last one if the run is finished.
+    pub fn segment_time(&self) -> SegmentTime {
+        let now = match self.finished_rta {
+            Some(_) => self.times.last().copied().unwrap_or(SegmentTime { igt: 0, rta: 0 }),
+            None => SegmentTime { igt: self.igt, rta: self.rta().as_millis() as u64 },
+        };
+        let start = match self.finished_rta {
+            Some(_) if self.times.len() >= 2 => self.times[self.times.len() - 2],
+            Some(_) => SegmentTime { igt: 0, rta: 0 },
+            None => self.times.last().copied().unwrap_or(SegmentTime { igt: 0, rta: 0 }),
+        };
+
+        SegmentTime { igt: now.igt - start.igt, rta: now.rta.saturating_sub(start.rta) }
+    }
+
+    pub fn best_segment(&self, name: &str) -> Option<SegmentTime> {
+        self.bests.segments.get(name).copied()
+    }
+
+    /// Cumulative time of the personal best at given split, if the personal best was made with the same splits.
+    pub fn personal_best_at(&self, split: usize) -> Option<SegmentTime> {
+        if self.bests.personal_best.len() != self.split_count() {
+            return None;
+        }
+
+        self.bests.personal_best.get(split).copied()
+    }
+
+    /// Advances the timer by one game scene tick, the in-game time only advances outside of the pause menu.
+    pub fn tick(&mut self, paused: bool) {
+        if self.is_running() && self.definition.is_some() {
+            self.ticks += 1;
+
+            if !paused {
+                self.igt += 1;
+            }
+        }
+    }
+
+    /// Writes updated best times to the user directory.
+    pub fn save_bests(&mut self, ctx: &Context) -> GameResult {
+        if !self.bests_dirty {
+            return Ok(());
+        }
+
+        self.bests_dirty = false;
+
+        let file = filesystem::user_create(ctx, SPLIT_BESTS_FILE)?;
+        serde_json::to_writer_pretty(file, &self.bests)?;
+
+        Ok(())
+    }
+
+    pub fn on_stage_change(&mut self, stage_id: usize, replay_state: ReplayState) {
+        self.trigger(SplitTrigger::Stage(stage_id), replay_state);
+    }
+
+    pub fn on_boss_defeated(&mut self, boss_type: u16, replay_state: ReplayState) {
+        self.trigger(SplitTrigger::Boss(boss_type), replay_state);
+    }
+
+    pub fn on_flag_set(&mut self, id: usize, replay_state: ReplayState) {
+        self.trigger(SplitTrigger::Flag(id), replay_state);
+    }
+
+    fn trigger(&mut self, event: SplitTrigger, replay_state: ReplayState) {
+        if !self.is_running() {
+            return;
+        }
+
+        let matches = match (self.current_split().map(|split| split.trigger), event) {
+            (Some(SplitTrigger::Boss(0)), SplitTrigger::Boss(_)) => true,
+            (Some(trigger), event) => trigger == event,
+            (None, _) => false,
+        };
+
+        if matches {
+            self.split(replay_state);
+        }
+    }
+
+    fn split(&mut self, replay_state: ReplayState) {
+        let segment = self.segment_time();
+        let name = match self.current_split() {
+            Some(split) => split.name.clone(),
+            None => return,
+        };
+
+        log::info!("Split '{}': {} ticks, {} ms", name, segment.igt, segment.rta);
+
+        if !matches!(replay_state, ReplayState::Playback(_)) {
+            let is_best = match self.bests.segments.get(&name) {
+                Some(best) => (segment.igt, segment.rta) < (best.igt, best.rta),
+                None => true,
+            };
+
+            if is_best {
+                self.bests.segments.insert(name, segment);
+                self.bests_dirty = true;
+            }
+        }
+
+        self.times.push(SegmentTime { igt: self.igt, rta: self.rta().as_millis() as u64 });
+        self.current += 1;
+
+        if self.current >= self.split_count() {
+            self.finished_rta = Some(self.rta());
+
+            let is_personal_best = match self.bests.personal_best.last() {
+                Some(best) if self.bests.personal_best.len() == self.times.len() => {
+                    (self.igt, self.times[self.times.len() - 1].rta) < (best.igt, best.rta)
+                }
+                _ => true,
+            };
+
+            if is_personal_best && !matches!(replay_state, ReplayState::Playback(_)) {
+                self.bests.personal_best = self.times.clone();
+                self.bests_dirty = true;
+            }
+        }
+    }
+}
+
+/// Formats a time as `m:ss.t`.
+fn format_time(millis: u64) -> String {
+    format!("{}:{:02}.{}", millis / 60000, (millis / 1000) % 60, (millis / 100) % 10)
+}
+
+/// Formats the difference to a reference time as `+m:ss.t` or `-m:ss.t`.
+fn format_delta(millis: u64, reference: u64) -> String {
+    if millis >= reference {
+        format!("+{}", format_time(millis - reference))
+    } else {
+        format!("-{}", format_time(reference - millis))
+    }
+}
+
+fn ticks_to_millis(ticks: usize, timing_mode: TimingMode) -> u64 {
+    // frame synchronized timing has no fixed tick length, assume the display runs at 60Hz.
+    let tps = match timing_mode.get_tps() {
+        0 => 60,
+        tps => tps,
+    };
+
+    ticks as u64 * 1000 / tps as u64
+}
+
+/// Draws the split timer in the top right corner of the screen, if a split definition is loaded.
+pub fn draw_split_timer(state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
+    let timer = &state.split_timer;
+    if timer.definition.is_none() || timer.started_at.is_none() {
+        return Ok(());
+    }
+
+    let timing_mode = state.settings.timing_mode;
+    let segment = timer.segment_time();
+    let split_index = timer.current.min(timer.split_count().saturating_sub(1));
+    let split_name = timer
+        .definition
+        .as_ref()
+        .and_then(|definition| definition.splits.get(split_index))
+        .map_or("", |split| split.name.as_str());
+    let best = match timer.best_segment(split_name) {
+        Some(best) => format_time(ticks_to_millis(best.igt, timing_mode)),
+        None => "-".to_owned(),
+    };
+
+    let igt = ticks_to_millis(timer.igt, timing_mode);
+    // the run so far against the personal best at the split being run, or the final split once finished
+    let personal_best = match timer.personal_best_at(split_index) {
+        Some(pb) => {
+            let pb_igt = ticks_to_millis(pb.igt, timing_mode);
+            format!("PB {} {}", format_time(pb_igt), format_delta(igt, pb_igt))
+        }
+        None => "PB -".to_owned(),
+    };
+
+    let lines = [
+        format!("{} ({}/{})", split_name, split_index + 1, timer.split_count()),
+        format!("{} / {}", format_time(ticks_to_millis(segment.igt, timing_mode)), best),
+        format!("IGT {}", format_time(igt)),
+        format!("RTA {}", format_time(timer.rta().as_millis() as u64)),
+        personal_best,
+    ];
+
+    let mut y = 20.0 + if state.settings.fps_counter { 12.0 } else { 0.0 };
+    for line in lines.iter() {
+        let width = state.font.builder().compute_width(line);
+
+        state.font.builder().position(state.canvas_size.0 - width - 8.0, y).shadow(true).draw(
+            line,
+            ctx,
+            &state.constants,
+            &mut state.texture_set,
+        )?;
+
+        y += 12.0;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_split_timer() {
+    let mut timer = SplitTimer::new();
+    timer.definition = Some(
+        serde_json::from_str(
+            r#"{"splits": [
+                {"name": "First Cave", "trigger": {"stage": 12}},
+                {"name": "Balfrog", "trigger": {"boss": 0}},
+                {"name": "Sanctuary", "trigger": {"flag": 1500}}
+            ]}"#,
+        )
+        .unwrap(),
+    );
+    timer.start(TimingMode::_50Hz);
+
+    for _ in 0..100 {
+        timer.tick(false);
+    }
+    timer.on_flag_set(1500, ReplayState::None);
+    timer.on_stage_change(13, ReplayState::None);
+    assert_eq!(timer.current, 0);
+
+    timer.on_stage_change(12, ReplayState::None);
+    assert_eq!(timer.current, 1);
+    assert_eq!(timer.best_segment("First Cave").unwrap().igt, 100);
+
+    for _ in 0..50 {
+        timer.tick(false);
+    }
+    timer.on_boss_defeated(2, ReplayState::Playback(crate::game::shared_game_state::ReplayKind::Best));
+    assert_eq!(timer.current, 2);
+    assert_eq!(timer.segment_time().igt, 0);
+    assert!(timer.best_segment("Balfrog").is_none());
+
+    timer.tick(true);
+    timer.tick(false);
+    timer.on_flag_set(1500, ReplayState::None);
+    assert!(timer.is_finished());
+    assert_eq!(timer.igt, 151);
+    assert_eq!(timer.segment_time().igt, 1);
+
+    timer.tick(false);
+    assert_eq!(timer.igt, 151);
+
+    // the finished run is the first personal best
+    let pb: Vec<_> = timer.bests.personal_best.iter().map(|time| time.igt).collect();
+    assert_eq!(pb, [100, 150, 151]);
+    assert_eq!(timer.personal_best_at(1).unwrap().igt, 150);
+}
+
+#[test]
+fn test_split_timer_tick_time() {
+    let mut timer = SplitTimer::new();
+    timer.definition = Some(serde_json::from_str(r#"{"splits": [{"name": "Egg", "trigger": {"stage": 2}}]}"#).unwrap());
+    timer.start(TimingMode::_50Hz);
+    timer.disable_real_time();
+
+    // the pause menu counts towards the real time only
+    for tick in 0..100 {
+        timer.tick(tick >= 90);
+    }
+    assert_eq!(timer.igt, 90);
+    assert_eq!(timer.rta(), Duration::from_secs(2));
+
+    timer.on_stage_change(2, ReplayState::None);
+    assert!(timer.is_finished());
+    assert_eq!(timer.bests.personal_best, [SegmentTime { igt: 90, rta: 2000 }]);
+
+    // a slower run doesn't replace the personal best
+    timer.start(TimingMode::_50Hz);
+    timer.disable_real_time();
+    for _ in 0..120 {
+        timer.tick(false);
+    }
+    timer.on_stage_change(2, ReplayState::None);
+    assert_eq!(timer.bests.personal_best[0].igt, 90);
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(format_time(0), "0:00.0");
+    assert_eq!(format_time(83_456), "1:23.4");
+    assert_eq!(ticks_to_millis(150, TimingMode::_50Hz), 3000);
+    assert_eq!(format_delta(1500, 1000), "+0:00.5");
+    assert_eq!(format_delta(1000, 62_000), "-1:01.0");
+}
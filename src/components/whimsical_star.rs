@@ -7,6 +7,7 @@ use crate::game::shared_game_state::SharedGameState;
 use crate::game::player::{Player, TargetPlayer};
 use crate::game::weapon::bullet::{Bullet, BulletManager};
 
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct WhimsicalStar {
     pub star: [Star; 3],
     pub tex: String,
@@ -15,6 +16,7 @@ pub struct WhimsicalStar {
     pub active_star: u8,
 }
 
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Star {
     pub x: i32,
     pub y: i32,
@@ -31,6 +33,12 @@ impl Star {
     }
 }
 
+impl Default for WhimsicalStar {
+    fn default() -> Self {
+        WhimsicalStar::new()
+    }
+}
+
 impl WhimsicalStar {
     pub fn new() -> WhimsicalStar {
         WhimsicalStar {
@@ -42,6 +50,7 @@ impl WhimsicalStar {
         }
     }
 
+    /// Takes the texture and sprites of the stars from the skin of given player.
     pub fn init(&mut self, player: &Player) {
         self.tex = player.skin.get_skin_texture_name().to_string();
         for (iter, star) in &mut self.star.iter_mut().enumerate() {
@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::io::BufWriter;
 use std::ops::Deref;
 use std::rc::Rc;
 
@@ -8,10 +9,12 @@ use crate::common::{Color, Rect};
 use crate::components::background::Background;
 use crate::components::tilemap::{TileLayer, Tilemap};
 use crate::framework::context::Context;
-use crate::framework::error::GameResult;
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::filesystem;
 use crate::framework::graphics;
 use crate::game::shared_game_state::SharedGameState;
 use crate::game::frame::Frame;
+use crate::game::map::NPCData;
 use crate::game::stage::{Stage, StageTexturePaths};
 use crate::graphics::texture_set::I_MAG;
 
@@ -26,6 +29,7 @@ pub enum CurrentTool {
 pub struct EditorInstance {
     pub stage: Stage,
     pub stage_id: usize,
+    pub npc_list: Vec<NPCData>,
     pub frame: Frame,
     pub background: Background,
     pub stage_textures: Rc<RefCell<StageTexturePaths>>,
@@ -34,10 +38,14 @@ pub struct EditorInstance {
     pub current_tile: u8,
     pub mouse_pos: (f32, f32),
     pub want_capture_mouse: bool,
+    /// Tile where the rectangle tool started dragging.
+    pub rect_start: Option<(usize, usize)>,
+    /// Set when the map has been changed since it was loaded or saved.
+    pub modified: bool,
 }
 
 impl EditorInstance {
-    pub fn new(stage_id: usize, stage: Stage) -> EditorInstance {
+    pub fn new(stage_id: usize, stage: Stage, npc_list: Vec<NPCData>) -> EditorInstance {
         let stage_textures = {
             let mut textures = StageTexturePaths::new();
             textures.update(&stage);
@@ -50,6 +58,7 @@ impl EditorInstance {
         EditorInstance {
             stage,
             stage_id,
+            npc_list,
             frame,
             background: Background::new(),
             stage_textures,
@@ -58,9 +67,111 @@ impl EditorInstance {
             current_tile: 0,
             mouse_pos: (0.0, 0.0),
             want_capture_mouse: true,
+            rect_start: None,
+            modified: false,
         }
     }
 
+    /// Returns the foreground tile under the mouse cursor.
+    fn mouse_tile(&self) -> Option<(usize, usize)> {
+        let tile_size = self.stage.map.tile_size.as_int();
+        let halft = tile_size / 2;
+        let stage_mouse_x = (self.frame.x / 0x200) + halft + (self.mouse_pos.0 / self.zoom) as i32;
+        let stage_mouse_y = (self.frame.y / 0x200) + halft + (self.mouse_pos.1 / self.zoom) as i32;
+        let tile_x = stage_mouse_x.div_euclid(tile_size);
+        let tile_y = stage_mouse_y.div_euclid(tile_size);
+
+        if tile_x < 0 || tile_y < 0 || tile_x >= self.stage.map.width as i32 || tile_y >= self.stage.map.height as i32 {
+            return None;
+        }
+
+        Some((tile_x as usize, tile_y as usize))
+    }
+
+    /// Tiles covered by the rectangle tool, as inclusive (left, top, right, bottom) bounds.
+    fn rect_bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        let (start_x, start_y) = self.rect_start?;
+        let (end_x, end_y) = self.mouse_tile().unwrap_or((start_x, start_y));
+
+        Some((start_x.min(end_x), start_y.min(end_y), start_x.max(end_x), start_y.max(end_y)))
+    }
+
+    fn set_tile(&mut self, x: usize, y: usize) {
+        self.modified |= self.stage.change_tile(x, y, self.current_tile);
+    }
+
+    /// Replaces the area of same tiles connected to given one with the current tile.
+    pub fn flood_fill(&mut self, x: usize, y: usize) {
+        let target = self.stage.tile_at(x, y);
+        if target == self.current_tile {
+            return;
+        }
+
+        let (width, height) = (self.stage.map.width as usize, self.stage.map.height as usize);
+        let mut queue = vec![(x, y)];
+
+        while let Some((x, y)) = queue.pop() {
+            if self.stage.tile_at(x, y) != target {
+                continue;
+            }
+
+            self.set_tile(x, y);
+
+            if x > 0 {
+                queue.push((x - 1, y));
+            }
+            if x + 1 < width {
+                queue.push((x + 1, y));
+            }
+            if y > 0 {
+                queue.push((x, y - 1));
+            }
+            if y + 1 < height {
+                queue.push((x, y + 1));
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, left: usize, top: usize, right: usize, bottom: usize) {
+        for y in top..=bottom {
+            for x in left..=right {
+                self.set_tile(x, y);
+            }
+        }
+    }
+
+    /// Writes the map, tile attributes and NPCs of the stage back to the data root its map was loaded from.
+    pub fn save(&mut self, state: &SharedGameState, ctx: &mut Context) -> GameResult {
+        if self.stage.data.pxpack_data.is_some() {
+            return Err(GameError::InvalidValue("Saving PxPack maps is not supported.".to_owned()));
+        }
+
+        // same lookup order as Stage::load
+        let map_name = ["Stage/", &self.stage.data.map, ".pxm"].join("");
+        let roots = &state.constants.base_paths;
+        let root = roots.iter().find(|root| filesystem::exists(ctx, [root, &map_name].join(""))).ok_or_else(|| {
+            GameError::ResourceNotFound(format!("{} not found in any data root.", map_name), Vec::new())
+        })?;
+
+        let map_path = [root, &map_name].join("");
+        let attrib_path = [root, "Stage/", &self.stage.data.tileset.name, ".pxa"].join("");
+        let npc_path = [root, "Stage/", &self.stage.data.map, ".pxe"].join("");
+        log::info!("Saving stage {} to {}", self.stage.data.map, root);
+
+        let create = |path: &str| {
+            filesystem::overwrite(ctx, path)
+                .map(BufWriter::new)
+                .map_err(|err| GameError::FilesystemError(format!("Data root {} isn't writable: {}", root, err)))
+        };
+
+        self.stage.map.save_pxm(create(&map_path)?, create(&attrib_path)?)?;
+        NPCData::save_to(&self.npc_list, create(&npc_path)?)?;
+
+        self.modified = false;
+
+        Ok(())
+    }
+
     pub fn process(&mut self, state: &mut SharedGameState, ctx: &mut Context, ui: &mut imgui::Ui, tool: CurrentTool) {
         self.frame.prev_x = self.frame.x;
         self.frame.prev_y = self.frame.y;
@@ -87,29 +198,47 @@ impl EditorInstance {
                 drag |= ui.is_mouse_down(MouseButton::Right);
 
                 if !drag && ui.is_mouse_down(MouseButton::Left) {
-                    let tile_size = self.stage.map.tile_size.as_int();
-                    let halft = tile_size / 2;
-                    let stage_mouse_x = (self.frame.x / 0x200) + halft + (self.mouse_pos.0 / self.zoom) as i32;
-                    let stage_mouse_y = (self.frame.y / 0x200) + halft + (self.mouse_pos.1 / self.zoom) as i32;
-                    let tile_x = stage_mouse_x / tile_size;
-                    let tile_y = stage_mouse_y / tile_size;
-
-                    if tile_x >= 0
-                        && tile_y >= 0
-                        && tile_x < self.stage.map.width as i32
-                        && tile_y < self.stage.map.height as i32
-                    {
-                        self.stage.change_tile(tile_x as usize, tile_y as usize, self.current_tile);
+                    if let Some((tile_x, tile_y)) = self.mouse_tile() {
+                        self.set_tile(tile_x, tile_y);
                     }
                 }
             }
             CurrentTool::Fill => {
                 self.palette_window(state, ctx, ui);
+
+                if ui.io().want_capture_mouse {
+                    return;
+                }
+
                 drag |= ui.is_mouse_down(MouseButton::Right);
+
+                if !drag && ui.is_mouse_clicked(MouseButton::Left) {
+                    if let Some((tile_x, tile_y)) = self.mouse_tile() {
+                        self.flood_fill(tile_x, tile_y);
+                    }
+                }
             }
             CurrentTool::Rectangle => {
                 self.palette_window(state, ctx, ui);
+
+                if ui.io().want_capture_mouse {
+                    self.rect_start = None;
+                    return;
+                }
+
                 drag |= ui.is_mouse_down(MouseButton::Right);
+
+                if !drag && ui.is_mouse_clicked(MouseButton::Left) {
+                    self.rect_start = self.mouse_tile();
+                }
+
+                if ui.is_mouse_released(MouseButton::Left) {
+                    if let Some((left, top, right, bottom)) = self.rect_bounds() {
+                        self.fill_rect(left, top, right, bottom);
+                    }
+
+                    self.rect_start = None;
+                }
             }
         }
 
@@ -128,16 +257,15 @@ impl EditorInstance {
 
         let tile_size = self.stage.map.tile_size.as_int();
         let halft = tile_size / 2;
-        let stage_mouse_x = (self.frame.x / 0x200) + halft + (self.mouse_pos.0 / self.zoom) as i32;
-        let stage_mouse_y = (self.frame.y / 0x200) + halft + (self.mouse_pos.1 / self.zoom) as i32;
-        let tile_x = stage_mouse_x / tile_size;
-        let tile_y = stage_mouse_y / tile_size;
         let frame_x = self.frame.x as f32 / 512.0;
         let frame_y = self.frame.y as f32 / 512.0;
 
-        if tile_x < 0 || tile_y < 0 || tile_x >= self.stage.map.width as i32 || tile_y >= self.stage.map.height as i32 {
-            return Ok(());
-        }
+        // the rectangle tool previews the whole area while dragging
+        let (left, top, right, bottom) = match (self.rect_bounds(), self.mouse_tile()) {
+            (Some(bounds), _) => bounds,
+            (None, Some((tile_x, tile_y))) => (tile_x, tile_y, tile_x, tile_y),
+            (None, None) => return Ok(()),
+        };
 
         let name = &self.stage_textures.deref().borrow().tileset_fg;
 
@@ -150,12 +278,16 @@ impl EditorInstance {
                 tile_size16,
             );
 
-            batch.add_rect_tinted(
-                (tile_x * tile_size - halft) as f32 - frame_x,
-                (tile_y * tile_size - halft) as f32 - frame_y,
-                (255, 255, 255, 192),
-                &rect,
-            );
+            for tile_y in top as i32..=bottom as i32 {
+                for tile_x in left as i32..=right as i32 {
+                    batch.add_rect_tinted(
+                        (tile_x * tile_size - halft) as f32 - frame_x,
+                        (tile_y * tile_size - halft) as f32 - frame_y,
+                        (255, 255, 255, 192),
+                        &rect,
+                    );
+                }
+            }
 
             batch.draw(ctx)?;
         }
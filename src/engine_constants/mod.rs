@@ -18,7 +18,7 @@ use crate::game::settings::Settings;
 use crate::game::shared_game_state::{FontData, Season};
 use crate::game::stage::StageData;
 use crate::graphics::texture_set::TextureSet;
-use crate::i18n::Locale;
+use crate::i18n::{Locale, FALLBACK_LOCALE};
 use crate::sound::pixtone::{Channel, Envelope, PixToneParameters, Waveform};
 use crate::sound::SoundManager;
 
@@ -169,13 +169,107 @@ pub struct AnimatedFace {
     pub anim_frames: Vec<(u16, u16)>,
 }
 
-#[derive(Debug, Clone)]
+/// Soundtrack that can be picked instead of the Organya one, declared in `soundtracks.json`.
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct ExtraSoundtrack {
     pub id: String,
+    /// Display name used if the locale doesn't have a `soundtrack.<id>` key.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Directory with the songs, relative paths are resolved against the data root the metadata was loaded from.
     pub path: String,
+    /// File name of the songs without the extension, `{name}` is replaced with the name from the music table
+    /// and `{id}` with the song number.
+    #[serde(default = "default_soundtrack_pattern")]
+    pub pattern: String,
+    /// Per-song overrides, keyed by the name from the music table.
+    #[serde(default)]
+    pub songs: HashMap<String, SoundtrackSong>,
+    /// Whether songs missing from the soundtrack are played from the Organya soundtrack instead of staying silent.
+    #[serde(default = "default_true")]
+    pub organya_fallback: bool,
+    #[serde(skip)]
     pub available: bool,
 }
 
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct SoundtrackSong {
+    /// Plays the Organya version of the song instead.
+    pub organya: bool,
+    /// File played once before the looped part, relative to the soundtrack directory.
+    pub intro: Option<String>,
+    /// Looped file, relative to the soundtrack directory.
+    #[serde(rename = "loop")]
+    pub loop_file: Option<String>,
+    /// Sample frame of the looped file playback jumps back to after reaching its end.
+    pub loop_start: u64,
+    /// Sample frame of the looped file that is treated as its end, the end of the file if not set.
+    pub loop_end: Option<u64>,
+}
+
+fn default_soundtrack_pattern() -> String {
+    "{name}".to_owned()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl ExtraSoundtrack {
+    pub fn new(id: &str, path: &str) -> ExtraSoundtrack {
+        ExtraSoundtrack {
+            id: id.to_owned(),
+            name: None,
+            path: path.to_owned(),
+            pattern: default_soundtrack_pattern(),
+            songs: HashMap::new(),
+            organya_fallback: true,
+            available: false,
+        }
+    }
+
+    /// Returns the file name of given song without the extension.
+    pub fn song_file_name(&self, song_id: usize, song_name: &str) -> String {
+        self.pattern.replace("{name}", song_name).replace("{id}", &song_id.to_string())
+    }
+
+    /// Drops the loop points of songs that end their loop before it starts, the playback would never get past them.
+    pub fn validate_loop_points(&mut self) {
+        for (name, song) in self.songs.iter_mut() {
+            if let Some(loop_end) = song.loop_end {
+                if loop_end <= song.loop_start {
+                    log::warn!(
+                        "Song {} of soundtrack {} has loop end {} not after loop start {}, ignoring its loop points.",
+                        name,
+                        self.id,
+                        loop_end,
+                        song.loop_start
+                    );
+                    song.loop_start = 0;
+                    song.loop_end = None;
+                }
+            }
+        }
+    }
+
+    pub fn localized_name(&self, loc: &Locale) -> String {
+        let key = format!("soundtrack.{}", self.id);
+        let translated = loc.t(&key);
+
+        if translated != key {
+            translated.to_owned()
+        } else {
+            self.name.clone().unwrap_or_else(|| self.id.clone())
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SoundtrackTable {
+    soundtracks: Vec<ExtraSoundtrack>,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct TextScriptConsts {
     pub encoding: TextScriptEncoding,
@@ -1709,12 +1803,7 @@ impl EngineConstants {
             intro_background_color: Color::from_rgb(0, 0, 0),
             font_path: "csfont.fnt".to_owned(),
             font_space_offset: 0.0,
-            soundtracks: vec![
-                ExtraSoundtrack { id: "remastered".to_owned(), path: "/base/Ogg11/".to_owned(), available: false },
-                ExtraSoundtrack { id: "new".to_owned(), path: "/base/Ogg/".to_owned(), available: false },
-                ExtraSoundtrack { id: "famitracks".to_owned(), path: "/base/ogg17/".to_owned(), available: false },
-                ExtraSoundtrack { id: "ridiculon".to_owned(), path: "/base/ogg_ridic/".to_owned(), available: false },
-            ],
+            soundtracks: EngineConstants::builtin_soundtracks(),
             music_table: vec![
                 "xxxx".to_owned(),
                 "wanpaku".to_owned(),
@@ -2087,6 +2176,7 @@ impl EngineConstants {
         self.locales.clear();
 
         let locale_files = filesystem::read_dir_find(ctx, &self.base_paths, "locale/");
+        let mut locales = Vec::new();
 
         for locale_file in locale_files.unwrap() {
             if locale_file.extension().unwrap() != "json" {
@@ -2099,8 +2189,16 @@ impl EngineConstants {
                 parts.next().unwrap().to_string()
             };
 
-            let mut locale = Locale::new(ctx, &self.base_paths, &locale_code);
+            match Locale::new(ctx, &self.base_paths, &locale_code) {
+                Ok(locale) => locales.push(locale),
+                Err(err) => log::warn!("Failed to load locale {}: {}", locale_code, err),
+            }
+        }
+
+        Locale::resolve_inheritance(&mut locales);
+        let reference = locales.iter().find(|locale| locale.code == FALLBACK_LOCALE).cloned();
 
+        for mut locale in locales {
             // Roots that support locales may have their own default locale, which differs from English
             if locale.code == self.base_locale {
                 locale.is_present = true;
@@ -2121,12 +2219,28 @@ impl EngineConstants {
                 }
             }
 
-            if locale_code == "jp" && filesystem::exists(ctx, "/base/credit_jp.tsc") {
+            if locale.code == "jp" && filesystem::exists(ctx, "/base/credit_jp.tsc") {
                 locale.set_font(FontData::new("csfontjp.fnt".to_owned(), 0.5, 0.0));
             }
 
-            self.locales.push(locale.clone());
-            log::info!("Loaded locale {} ({})", locale_code, locale.name.clone());
+            if let Some(reference) = &reference {
+                let missing = locale.missing_keys(reference);
+                if !missing.is_empty() {
+                    log::warn!(
+                        "Locale {} is missing {} strings, falling back to {}.",
+                        locale.code,
+                        missing.len(),
+                        locale.parent.as_deref().unwrap_or(FALLBACK_LOCALE)
+                    );
+
+                    for key in missing {
+                        log::debug!("Missing string in locale {}: {}", locale.code, key);
+                    }
+                }
+            }
+
+            log::info!("Loaded locale {} ({})", locale.code, locale.name);
+            self.locales.push(locale);
         }
 
         Ok(())
@@ -2134,10 +2248,53 @@ impl EngineConstants {
 
     pub fn apply_constant_json_files(&mut self) {}
 
-    // TODO: load soundtrack metadata from a json file
+    fn builtin_soundtracks() -> Vec<ExtraSoundtrack> {
+        vec![
+            ExtraSoundtrack::new("remastered", "/base/Ogg11/"),
+            ExtraSoundtrack::new("new", "/base/Ogg/"),
+            ExtraSoundtrack::new("famitracks", "/base/ogg17/"),
+            ExtraSoundtrack::new("ridiculon", "/base/ogg_ridic/"),
+        ]
+    }
+
+    /// Loads `soundtracks.json` from every data root, entries from higher priority roots replace
+    /// the built-in soundtracks and the ones from lower priority roots with the same id.
     pub fn load_soundtracks(&mut self, ctx: &mut Context) {
+        self.soundtracks = EngineConstants::builtin_soundtracks();
+
+        for root in self.base_paths.iter().rev() {
+            let Ok(file) = filesystem::open(ctx, format!("{}soundtracks.json", root)) else {
+                continue;
+            };
+
+            match serde_json::from_reader::<_, SoundtrackTable>(file) {
+                Ok(table) => {
+                    for mut soundtrack in table.soundtracks {
+                        if !soundtrack.path.starts_with('/') {
+                            soundtrack.path.insert_str(0, root);
+                        }
+
+                        if !soundtrack.path.ends_with('/') {
+                            soundtrack.path.push('/');
+                        }
+
+                        soundtrack.validate_loop_points();
+
+                        if let Some(existing) = self.soundtracks.iter_mut().find(|s| s.id == soundtrack.id) {
+                            *existing = soundtrack;
+                        } else {
+                            self.soundtracks.push(soundtrack);
+                        }
+                    }
+                }
+                Err(err) => log::warn!("Failed to deserialize soundtracks from {}: {}", root, err),
+            }
+        }
+
         for soundtrack in self.soundtracks.iter_mut() {
-            if filesystem::exists_find(ctx, &self.base_paths, &soundtrack.path) {
+            if filesystem::exists(ctx, &soundtrack.path)
+                || filesystem::exists_find(ctx, &self.base_paths, &soundtrack.path)
+            {
                 log::info!("Enabling soundtrack {} from {}.", soundtrack.id, soundtrack.path);
                 soundtrack.available = true;
             }
@@ -2261,3 +2418,111 @@ impl EngineConstants {
         Ok(())
     }
 }
+
+#[test]
+fn test_soundtrack_table() {
+    let json = r#"{
+        "soundtracks": [
+            { "id": "remastered", "path": "Ogg11" },
+            {
+                "id": "arranged",
+                "name": "Arranged",
+                "path": "/arranged/",
+                "pattern": "{id}_{name}",
+                "organya_fallback": false,
+                "songs": {
+                    "wanpaku": { "loop_start": 44100, "loop_end": 441000 },
+                    "ika": { "organya": true },
+                    "kaze": { "intro": "kaze_a.ogg", "loop": "kaze_b.ogg" }
+                }
+            }
+        ]
+    }"#;
+
+    let table: SoundtrackTable = serde_json::from_str(json).unwrap();
+    let remastered = &table.soundtracks[0];
+    assert_eq!(remastered.pattern, "{name}");
+    assert!(remastered.organya_fallback);
+    assert!(remastered.songs.is_empty());
+
+    let arranged = &table.soundtracks[1];
+    assert_eq!(arranged.name.as_deref(), Some("Arranged"));
+    assert!(!arranged.organya_fallback);
+    assert_eq!(arranged.song_file_name(1, "wanpaku"), "1_wanpaku");
+    assert_eq!(arranged.songs["wanpaku"].loop_start, 44100);
+    assert_eq!(arranged.songs["wanpaku"].loop_end, Some(441000));
+    assert!(arranged.songs["ika"].organya);
+    assert_eq!(arranged.songs["kaze"].intro.as_deref(), Some("kaze_a.ogg"));
+    assert_eq!(arranged.songs["kaze"].loop_file.as_deref(), Some("kaze_b.ogg"));
+}
+
+#[test]
+fn test_soundtrack_loop_points() {
+    let json = r#"{
+        "id": "arranged",
+        "path": "/arranged/",
+        "songs": {
+            "wanpaku": { "loop_start": 44100, "loop_end": 441000 },
+            "ika": { "loop_start": 44100, "loop_end": 44100 },
+            "kaze": { "loop_start": 88200, "loop_end": 100 },
+            "mura": { "loop_start": 44100 }
+        }
+    }"#;
+
+    let mut soundtrack: ExtraSoundtrack = serde_json::from_str(json).unwrap();
+    soundtrack.validate_loop_points();
+
+    assert_eq!(soundtrack.songs["wanpaku"].loop_start, 44100);
+    assert_eq!(soundtrack.songs["wanpaku"].loop_end, Some(441000));
+    for name in ["ika", "kaze"] {
+        assert_eq!(soundtrack.songs[name].loop_start, 0);
+        assert_eq!(soundtrack.songs[name].loop_end, None);
+    }
+    assert_eq!(soundtrack.songs["mura"].loop_start, 44100);
+    assert_eq!(soundtrack.songs["mura"].loop_end, None);
+}
+
+#[test]
+fn test_load_soundtracks() {
+    use crate::framework::vfs::PhysicalFS;
+
+    let dir = std::env::temp_dir().join(format!("doukutsu-rs-soundtracks-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("base/Ogg")).unwrap();
+    std::fs::create_dir_all(dir.join("mod/arranged")).unwrap();
+    std::fs::write(
+        dir.join("base/soundtracks.json"),
+        r#"{ "soundtracks": [{ "id": "new", "path": "Ogg", "pattern": "{id}" }, { "id": "arranged", "path": "missing" }] }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("mod/soundtracks.json"),
+        r#"{ "soundtracks": [{ "id": "arranged", "name": "Arranged", "path": "arranged" }] }"#,
+    )
+    .unwrap();
+
+    let mut ctx = Context::new();
+    filesystem::mount_vfs(&mut ctx, Box::new(PhysicalFS::new(&dir, true)));
+
+    let mut constants = EngineConstants::defaults();
+    constants.base_paths = vec!["/mod/".to_owned(), "/base/".to_owned()];
+    constants.load_soundtracks(&mut ctx);
+
+    let ids: Vec<_> = constants.soundtracks.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, vec!["remastered", "new", "famitracks", "ridiculon", "arranged"]);
+
+    // a data root replaces the built-in soundtrack with the same id
+    let new = &constants.soundtracks[1];
+    assert_eq!(new.path, "/base/Ogg/");
+    assert_eq!(new.pattern, "{id}");
+    assert!(new.available);
+
+    // and a higher priority root replaces the one of a lower priority root
+    let arranged = &constants.soundtracks[4];
+    assert_eq!(arranged.path, "/mod/arranged/");
+    assert_eq!(arranged.name.as_deref(), Some("Arranged"));
+    assert!(arranged.available);
+
+    assert!(!constants.soundtracks[0].available);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -5,7 +5,7 @@ use imgui::DrawData;
 
 use crate::common::{Color, Rect};
 use crate::framework::context::Context;
-use crate::framework::error::GameResult;
+use crate::framework::error::{GameError, GameResult};
 use crate::framework::graphics::{BlendMode, VSyncMode};
 use crate::game::shared_game_state::{SharedGameState, WindowMode};
 use crate::game::Game;
@@ -88,6 +88,16 @@ pub trait BackendRenderer {
         shader: BackendShader,
     ) -> GameResult;
 
+    /// Reads back the last rendered frame as tightly packed RGBA8 rows, top to bottom.
+    fn read_framebuffer(&mut self) -> GameResult<(u16, u16, Vec<u8>)> {
+        Err(GameError::RenderError("This renderer doesn't support reading back the framebuffer.".to_string()))
+    }
+
+    /// Reads back the contents of a render target as tightly packed RGBA8 rows, top to bottom.
+    fn read_texture(&mut self, _texture: &Box<dyn BackendTexture>) -> GameResult<(u16, u16, Vec<u8>)> {
+        Err(GameError::RenderError("This renderer doesn't support reading back textures.".to_string()))
+    }
+
     fn as_any(&self) -> &dyn Any;
 }
 
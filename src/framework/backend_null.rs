@@ -11,6 +11,7 @@ use crate::framework::backend::{
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::framework::graphics::BlendMode;
+use crate::framework::render_software::SoftwareRenderer;
 use crate::game::Game;
 
 pub struct NullBackend;
@@ -61,7 +62,12 @@ impl BackendEventLoop for NullEventLoop {
         }
     }
 
-    fn new_renderer(&self, _ctx: *mut Context) -> GameResult<Box<dyn BackendRenderer>> {
+    fn new_renderer(&self, ctx: *mut Context) -> GameResult<Box<dyn BackendRenderer>> {
+        let ctx = unsafe { &mut *ctx };
+        if ctx.software_rendering {
+            return Ok(Box::new(SoftwareRenderer::new(640, 480)?));
+        }
+
         let mut imgui = imgui::Context::create();
         imgui.io_mut().display_size = [640.0, 480.0];
         imgui.fonts().build_alpha8_texture();
@@ -11,6 +11,10 @@ use crate::game::Game;
 
 pub struct Context {
     pub headless: bool,
+    /// In headless mode, draw frames with the software renderer instead of discarding them.
+    pub software_rendering: bool,
+    /// In headless mode, mix the audio into memory instead of discarding it, see [crate::sound::capture].
+    pub audio_capture: bool,
     pub window: WindowParams,
     pub(crate) filesystem: Filesystem,
     pub(crate) renderer: Option<Box<dyn BackendRenderer>>,
@@ -26,6 +30,8 @@ impl Context {
     pub fn new() -> Context {
         Context {
             headless: false,
+            software_rendering: false,
+            audio_capture: false,
             window: WindowParams::default(),
             filesystem: Filesystem::new(),
             renderer: None,
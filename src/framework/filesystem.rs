@@ -108,6 +108,13 @@ impl Filesystem {
         self.user_vfs.create(path.as_ref()).map(|f| File::VfsFile(f))
     }
 
+    /// Opens a file in the game data directory to be written to, truncating it
+    /// if it already exists. The data directory stays mounted read-only,
+    /// only the file itself is opened for writing.
+    pub(crate) fn overwrite<P: AsRef<path::Path>>(&self, path: P) -> GameResult<File> {
+        self.vfs.overwrite(path.as_ref()).map(|f| File::VfsFile(f))
+    }
+
     /// Create an empty directory in the user dir
     /// with the given name.  Any parents to that directory
     /// that do not exist will be created.
@@ -254,7 +261,7 @@ pub fn open<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult<File> {
     ctx.filesystem.open(path)
 }
 
-pub fn open_find<P: AsRef<path::Path>>(ctx: &Context, roots: &Vec<String>, path: P) -> GameResult<File> {
+pub fn open_find<P: AsRef<path::Path>>(ctx: &Context, roots: &[String], path: P) -> GameResult<File> {
     let mut errors = Vec::new();
     for root in roots {
         let full_path = normilize_find_path(root, &path);
@@ -287,6 +294,13 @@ pub fn user_create<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult<F
     ctx.filesystem.user_create(path)
 }
 
+/// Opens a file in the game data directory to be written to, truncating it
+/// if it already exists. The data directory stays mounted read-only,
+/// only the file itself is opened for writing.
+pub fn overwrite<P: AsRef<path::Path>>(ctx: &Context, path: P) -> GameResult<File> {
+    ctx.filesystem.overwrite(path)
+}
+
 /// Create an empty directory in the user dir
 /// with the given name.  Any parents to that directory
 /// that do not exist will be created.
@@ -336,7 +350,7 @@ pub fn exists<P: AsRef<path::Path>>(ctx: &Context, path: P) -> bool {
     ctx.filesystem.exists(path.as_ref())
 }
 
-pub fn exists_find<P: AsRef<path::Path>>(ctx: &Context, roots: &Vec<String>, path: P) -> bool {
+pub fn exists_find<P: AsRef<path::Path>>(ctx: &Context, roots: &[String], path: P) -> bool {
     for root in roots {
         let full_path = normilize_find_path(root, &path);
         if ctx.filesystem.exists(full_path) {
@@ -81,6 +81,22 @@ pub fn create_texture(ctx: &mut Context, width: u16, height: u16, data: &[u8]) -
     Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
 }
 
+pub fn read_framebuffer(ctx: &mut Context) -> GameResult<(u16, u16, Vec<u8>)> {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.read_framebuffer();
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
+pub fn read_texture(ctx: &mut Context, texture: &Box<dyn BackendTexture>) -> GameResult<(u16, u16, Vec<u8>)> {
+    if let Some(renderer) = &mut ctx.renderer {
+        return renderer.read_texture(texture);
+    }
+
+    Err(GameError::RenderError("Rendering backend hasn't been initialized yet.".to_string()))
+}
+
 pub fn screen_size(ctx: &mut Context) -> (f32, f32) {
     ctx.screen_size
 }
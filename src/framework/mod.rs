@@ -18,6 +18,7 @@ pub mod graphics;
 pub mod keyboard;
 #[cfg(feature = "render-opengl")]
 pub mod render_opengl;
+pub mod render_software;
 pub mod ui;
 pub mod util;
 pub mod vfs;
@@ -1206,6 +1206,80 @@ impl BackendRenderer for OpenGLRenderer {
         self.draw_arrays(gl::TRIANGLES, vertices, texture, shader)
     }
 
+    fn read_framebuffer(&mut self) -> GameResult<(u16, u16, Vec<u8>)> {
+        if let Some((_, gl)) = self.get_context() {
+            let (width, height) = self.render_data.last_size;
+            let stride = width as usize * 4;
+            let mut pixels = vec![0u8; stride * height as usize];
+
+            unsafe {
+                let current_framebuffer = return_param(|x| gl.gl.GetIntegerv(gl::FRAMEBUFFER_BINDING, x)) as u32;
+
+                gl.gl.BindFramebuffer(gl::FRAMEBUFFER, self.render_data.surf_framebuffer);
+                gl.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+                gl.gl.ReadPixels(
+                    0,
+                    0,
+                    width as _,
+                    height as _,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as _,
+                );
+                gl.gl.BindFramebuffer(gl::FRAMEBUFFER, current_framebuffer);
+            }
+
+            // OpenGL returns the rows bottom to top.
+            let mut flipped = Vec::with_capacity(pixels.len());
+            for row in pixels.chunks_exact(stride).rev() {
+                flipped.extend_from_slice(row);
+            }
+
+            Ok((width as u16, height as u16, flipped))
+        } else {
+            Err(RenderError("No OpenGL context available!".to_string()))
+        }
+    }
+
+    fn read_texture(&mut self, texture: &Box<dyn BackendTexture>) -> GameResult<(u16, u16, Vec<u8>)> {
+        let gl_texture = texture
+            .as_any()
+            .downcast_ref::<OpenGLTexture>()
+            .ok_or_else(|| RenderError("This texture was not created by OpenGL backend.".to_string()))?;
+
+        if gl_texture.framebuffer_id == 0 {
+            return Err(RenderError("Only textures created as render targets can be read back.".to_string()));
+        }
+
+        if let Some((_, gl)) = self.get_context() {
+            let (width, height) = (gl_texture.width, gl_texture.height);
+            let mut pixels = vec![0u8; width as usize * height as usize * 4];
+
+            // Render targets are drawn with a flipped projection, so unlike the screen surface
+            // the rows are already stored top to bottom.
+            unsafe {
+                let current_framebuffer = return_param(|x| gl.gl.GetIntegerv(gl::FRAMEBUFFER_BINDING, x)) as u32;
+
+                gl.gl.BindFramebuffer(gl::FRAMEBUFFER, gl_texture.framebuffer_id);
+                gl.gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+                gl.gl.ReadPixels(
+                    0,
+                    0,
+                    width as _,
+                    height as _,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as _,
+                );
+                gl.gl.BindFramebuffer(gl::FRAMEBUFFER, current_framebuffer);
+            }
+
+            Ok((width, height, pixels))
+        } else {
+            Err(RenderError("No OpenGL context available!".to_string()))
+        }
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -0,0 +1,703 @@
+//! A renderer that rasterizes everything on the CPU into RGBA8 buffers.
+//!
+//! It doesn't need a GPU or a display, which makes it suitable for capturing frames in CI and comparing
+//! them against reference images. The output closely follows the OpenGL renderer (nearest filtering,
+//! same blend equations), except for the water distortion shader, which is drawn as a plain fill
+//! like on the Horizon backend.
+
+use std::any::Any;
+use std::cell::{RefCell, UnsafeCell};
+use std::collections::HashMap;
+use std::io::Read;
+use std::rc::{Rc, Weak};
+
+use imgui::{DrawCmd, DrawCmdParams, DrawData, TextureId, Ui};
+
+use crate::common::{Color, Rect};
+use crate::framework::backend::{BackendRenderer, BackendShader, BackendTexture, SpriteBatchCommand, VertexData};
+use crate::framework::error::GameError::RenderError;
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::graphics::BlendMode;
+use crate::framework::ui::init_imgui;
+
+/// Tightly packed RGBA8 pixels, rows top to bottom.
+struct Surface {
+    width: u16,
+    height: u16,
+    pixels: Vec<u8>,
+}
+
+impl Surface {
+    fn new(width: u16, height: u16) -> Surface {
+        Surface { width, height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    #[inline]
+    fn texel(&self, x: i32, y: i32) -> [u8; 4] {
+        let x = x.clamp(0, self.width as i32 - 1) as usize;
+        let y = y.clamp(0, self.height as i32 - 1) as usize;
+        let idx = (y * self.width as usize + x) * 4;
+
+        [self.pixels[idx], self.pixels[idx + 1], self.pixels[idx + 2], self.pixels[idx + 3]]
+    }
+
+    /// Nearest neighbour sampling with normalized coordinates, clamped to the edges.
+    #[inline]
+    fn sample(&self, u: f32, v: f32) -> [u8; 4] {
+        self.texel((u * self.width as f32).floor() as i32, (v * self.height as f32).floor() as i32)
+    }
+}
+
+/// Area of the render target that can be drawn to, as (left, top, right, bottom) with exclusive right and bottom.
+#[derive(Copy, Clone)]
+struct Bounds(i32, i32, i32, i32);
+
+/// State shared between the renderer and its textures, since sprite batches draw themselves.
+struct RenderState {
+    screen: Rc<RefCell<Surface>>,
+    target: Option<Rc<RefCell<Surface>>>,
+    blend: BlendMode,
+    clip: Option<Rect>,
+    textures: HashMap<usize, Weak<RefCell<Surface>>>,
+    next_texture_id: usize,
+}
+
+impl RenderState {
+    fn target(&self) -> Rc<RefCell<Surface>> {
+        self.target.clone().unwrap_or_else(|| self.screen.clone())
+    }
+
+    fn register_texture(&mut self, surface: &Rc<RefCell<Surface>>) -> usize {
+        self.textures.retain(|_, surface| surface.strong_count() > 0);
+
+        self.next_texture_id += 1;
+        self.textures.insert(self.next_texture_id, Rc::downgrade(surface));
+        self.next_texture_id
+    }
+
+    fn bounds(&self, target: &Surface) -> Bounds {
+        let mut bounds = Bounds(0, 0, target.width as i32, target.height as i32);
+
+        if let Some(clip) = &self.clip {
+            bounds =
+                intersect(bounds, Bounds(clip.left as i32, clip.top as i32, clip.right as i32, clip.bottom as i32));
+        }
+
+        bounds
+    }
+}
+
+fn intersect(a: Bounds, b: Bounds) -> Bounds {
+    Bounds(a.0.max(b.0), a.1.max(b.1), a.2.min(b.2), a.3.min(b.3))
+}
+
+#[inline]
+fn mul_u8(a: u8, b: u8) -> u8 {
+    ((a as u32 * b as u32 + 127) / 255) as u8
+}
+
+#[inline]
+fn modulate(texel: [u8; 4], color: [u8; 4]) -> [u8; 4] {
+    [mul_u8(texel[0], color[0]), mul_u8(texel[1], color[1]), mul_u8(texel[2], color[2]), mul_u8(texel[3], color[3])]
+}
+
+/// Blends the source color into the destination pixel using the same equations as the OpenGL renderer.
+#[inline]
+fn blend_pixel(dst: &mut [u8], src: [u8; 4], mode: BlendMode) {
+    match mode {
+        BlendMode::None => dst.copy_from_slice(&src),
+        BlendMode::Alpha => {
+            let alpha = src[3] as u32;
+            for (d, s) in dst.iter_mut().zip(src) {
+                *d = ((s as u32 * alpha + *d as u32 * (255 - alpha) + 127) / 255) as u8;
+            }
+        }
+        BlendMode::Add => {
+            for (d, s) in dst.iter_mut().zip(src) {
+                *d = d.saturating_add(s);
+            }
+        }
+        BlendMode::Multiply => {
+            for (d, s) in dst.iter_mut().zip(src) {
+                *d = mul_u8(*d, s);
+            }
+        }
+    }
+}
+
+fn fill_rect(target: &mut Surface, bounds: Bounds, rect: Bounds, color: [u8; 4], mode: BlendMode) {
+    let Bounds(left, top, right, bottom) = intersect(bounds, rect);
+    let stride = target.width as usize * 4;
+
+    for y in top.max(0)..bottom {
+        for x in left.max(0)..right {
+            let idx = y as usize * stride + x as usize * 4;
+            blend_pixel(&mut target.pixels[idx..idx + 4], color, mode);
+        }
+    }
+}
+
+/// Copies `src` (in texels) of the source to `dest` (in pixels) of the target, sampling at pixel centers.
+/// The source rectangle is flipped if its left side is greater than the right one, same for top and bottom.
+fn blit(
+    target: &mut Surface,
+    bounds: Bounds,
+    source: &Surface,
+    src: Rect<f32>,
+    dest: Rect<f32>,
+    color: [u8; 4],
+    mode: BlendMode,
+) {
+    let (dest_w, dest_h) = (dest.right - dest.left, dest.bottom - dest.top);
+    if dest_w <= 0.0 || dest_h <= 0.0 {
+        return;
+    }
+
+    let covered = Bounds(
+        (dest.left - 0.5).ceil() as i32,
+        (dest.top - 0.5).ceil() as i32,
+        (dest.right - 0.5).ceil() as i32,
+        (dest.bottom - 0.5).ceil() as i32,
+    );
+    let Bounds(left, top, right, bottom) = intersect(bounds, covered);
+    let stride = target.width as usize * 4;
+
+    for y in top.max(0)..bottom {
+        let v = src.top + (y as f32 + 0.5 - dest.top) / dest_h * (src.bottom - src.top);
+
+        for x in left.max(0)..right {
+            let u = src.left + (x as f32 + 0.5 - dest.left) / dest_w * (src.right - src.left);
+            let texel = source.texel(u.floor() as i32, v.floor() as i32);
+
+            let idx = y as usize * stride + x as usize * 4;
+            blend_pixel(&mut target.pixels[idx..idx + 4], modulate(texel, color), mode);
+        }
+    }
+}
+
+/// Returns true if pixels exactly on the edge from `a` to `b` belong to the triangle.
+///
+/// Edges shared by two triangles are walked in opposite directions, so this makes sure pixels on them
+/// are drawn exactly once.
+#[inline]
+fn owns_edge(a: (f32, f32), b: (f32, f32)) -> bool {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+#[inline]
+fn edge(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> f32 {
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0)
+}
+
+/// Rasterizes a single triangle, interpolating the vertex colors and texture coordinates.
+fn draw_triangle(
+    target: &mut Surface,
+    bounds: Bounds,
+    mut vertices: [VertexData; 3],
+    texture: Option<&Surface>,
+    mode: BlendMode,
+) {
+    let mut area = edge(vertices[0].position, vertices[1].position, vertices[2].position);
+    if area == 0.0 {
+        return;
+    }
+
+    if area < 0.0 {
+        vertices.swap(1, 2);
+        area = -area;
+    }
+
+    let [v0, v1, v2] = vertices;
+    let (p0, p1, p2) = (v0.position, v1.position, v2.position);
+    let owns = [owns_edge(p1, p2), owns_edge(p2, p0), owns_edge(p0, p1)];
+
+    let covered = Bounds(
+        p0.0.min(p1.0).min(p2.0).floor() as i32,
+        p0.1.min(p1.1).min(p2.1).floor() as i32,
+        p0.0.max(p1.0).max(p2.0).ceil() as i32 + 1,
+        p0.1.max(p1.1).max(p2.1).ceil() as i32 + 1,
+    );
+    let Bounds(left, top, right, bottom) = intersect(bounds, covered);
+    let stride = target.width as usize * 4;
+
+    let color_of = |v: &VertexData| [v.color.0 as f32, v.color.1 as f32, v.color.2 as f32, v.color.3 as f32];
+    let (c0, c1, c2) = (color_of(&v0), color_of(&v1), color_of(&v2));
+
+    for y in top.max(0)..bottom {
+        for x in left.max(0)..right {
+            let p = (x as f32 + 0.5, y as f32 + 0.5);
+            let w = [edge(p1, p2, p), edge(p2, p0, p), edge(p0, p1, p)];
+
+            if w.iter().zip(owns).any(|(&w, owns)| w < 0.0 || (w == 0.0 && !owns)) {
+                continue;
+            }
+
+            let (b0, b1, b2) = (w[0] / area, w[1] / area, w[2] / area);
+            let mut color: [u8; 4] =
+                std::array::from_fn(|i| (c0[i] * b0 + c1[i] * b1 + c2[i] * b2).round().clamp(0.0, 255.0) as u8);
+
+            if let Some(texture) = texture {
+                let u = v0.uv.0 * b0 + v1.uv.0 * b1 + v2.uv.0 * b2;
+                let v = v0.uv.1 * b0 + v1.uv.1 * b1 + v2.uv.1 * b2;
+                color = modulate(texture.sample(u, v), color);
+            }
+
+            let idx = y as usize * stride + x as usize * 4;
+            blend_pixel(&mut target.pixels[idx..idx + 4], color, mode);
+        }
+    }
+}
+
+fn draw_triangles(
+    target: &mut Surface,
+    bounds: Bounds,
+    vertices: &[VertexData],
+    texture: Option<&Surface>,
+    mode: BlendMode,
+) {
+    for triangle in vertices.chunks_exact(3) {
+        draw_triangle(target, bounds, [triangle[0], triangle[1], triangle[2]], texture, mode);
+    }
+}
+
+pub struct SoftwareTexture {
+    id: usize,
+    surface: Rc<RefCell<Surface>>,
+    commands: Vec<SpriteBatchCommand>,
+    state: Rc<RefCell<RenderState>>,
+}
+
+impl BackendTexture for SoftwareTexture {
+    fn dimensions(&self) -> (u16, u16) {
+        let surface = self.surface.borrow();
+        (surface.width, surface.height)
+    }
+
+    fn add(&mut self, command: SpriteBatchCommand) {
+        self.commands.push(command);
+    }
+
+    fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    fn draw(&mut self) -> GameResult {
+        let state = self.state.borrow();
+        let target = state.target();
+
+        if Rc::ptr_eq(&target, &self.surface) {
+            // drawing a texture onto itself reads the pixels from before the draw, like it would on a GPU
+            let surface = self.surface.borrow();
+            let copy = Surface { width: surface.width, height: surface.height, pixels: surface.pixels.clone() };
+            drop(surface);
+
+            self.draw_commands(&state, &target, &copy);
+        } else {
+            self.draw_commands(&state, &target, &self.surface.borrow());
+        }
+
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl SoftwareTexture {
+    fn draw_commands(&self, state: &RenderState, target: &RefCell<Surface>, source: &Surface) {
+        let mut target = target.borrow_mut();
+        let bounds = state.bounds(&target);
+
+        for command in self.commands.iter() {
+            let (mut src, dest, (flip_x, flip_y), color) = match *command {
+                SpriteBatchCommand::DrawRect(src, dest) => (src, dest, (false, false), [255; 4]),
+                SpriteBatchCommand::DrawRectFlip(src, dest, flip_x, flip_y) => (src, dest, (flip_x, flip_y), [255; 4]),
+                SpriteBatchCommand::DrawRectTinted(src, dest, color) => {
+                    let (r, g, b, a) = color.to_rgba();
+                    (src, dest, (false, false), [r, g, b, a])
+                }
+                SpriteBatchCommand::DrawRectFlipTinted(src, dest, flip_x, flip_y, color) => {
+                    let (r, g, b, a) = color.to_rgba();
+                    (src, dest, (flip_x, flip_y), [r, g, b, a])
+                }
+            };
+
+            if flip_x {
+                std::mem::swap(&mut src.left, &mut src.right);
+            }
+
+            if flip_y {
+                std::mem::swap(&mut src.top, &mut src.bottom);
+            }
+
+            blit(&mut target, bounds, source, src, dest, color, state.blend);
+        }
+    }
+}
+
+pub struct SoftwareRenderer {
+    state: Rc<RefCell<RenderState>>,
+    imgui: UnsafeCell<imgui::Context>,
+    /// imgui font atlas, textures are only referenced weakly by their ids.
+    _font: Rc<RefCell<Surface>>,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: u16, height: u16) -> GameResult<SoftwareRenderer> {
+        let screen = Rc::new(RefCell::new(Surface::new(width, height)));
+        let state = Rc::new(RefCell::new(RenderState {
+            screen,
+            target: None,
+            blend: BlendMode::Alpha,
+            clip: None,
+            textures: HashMap::new(),
+            next_texture_id: 0,
+        }));
+
+        let mut imgui = init_imgui()?;
+        imgui.io_mut().display_size = [width as f32, height as f32];
+
+        let font = {
+            let mut atlas = imgui.fonts();
+            let texture = atlas.build_rgba32_texture();
+            let font = Rc::new(RefCell::new(Surface {
+                width: texture.width as u16,
+                height: texture.height as u16,
+                pixels: texture.data.to_vec(),
+            }));
+
+            atlas.tex_id = TextureId::new(state.borrow_mut().register_texture(&font));
+            font
+        };
+
+        Ok(SoftwareRenderer { state, imgui: UnsafeCell::new(imgui), _font: font })
+    }
+
+    fn new_texture(&mut self, surface: Surface) -> Box<dyn BackendTexture> {
+        let surface = Rc::new(RefCell::new(surface));
+        let id = self.state.borrow_mut().register_texture(&surface);
+
+        Box::new(SoftwareTexture { id, surface, commands: Vec::new(), state: self.state.clone() })
+    }
+
+    fn downcast(texture: &dyn BackendTexture) -> GameResult<&SoftwareTexture> {
+        texture
+            .as_any()
+            .downcast_ref::<SoftwareTexture>()
+            .ok_or_else(|| RenderError("This texture was not created by the software renderer.".to_string()))
+    }
+}
+
+impl BackendRenderer for SoftwareRenderer {
+    fn renderer_name(&self) -> String {
+        "Software".to_owned()
+    }
+
+    fn clear(&mut self, color: Color) {
+        let state = self.state.borrow();
+        let target = state.target();
+        let mut target = target.borrow_mut();
+        let bounds = state.bounds(&target);
+        let (r, g, b, a) = color.to_rgba();
+
+        fill_rect(&mut target, bounds, bounds, [r, g, b, a], BlendMode::None);
+    }
+
+    fn present(&mut self) -> GameResult {
+        Ok(())
+    }
+
+    fn prepare_draw(&mut self, width: f32, height: f32) -> GameResult {
+        let mut state = self.state.borrow_mut();
+        let (width, height) = (width as u16, height as u16);
+
+        // like the OpenGL renderer, every frame starts with a cleared transparent surface
+        *state.screen.borrow_mut() = Surface::new(width, height);
+        state.target = None;
+        state.blend = BlendMode::Alpha;
+
+        Ok(())
+    }
+
+    fn create_texture_mutable(&mut self, width: u16, height: u16) -> GameResult<Box<dyn BackendTexture>> {
+        Ok(self.new_texture(Surface::new(width, height)))
+    }
+
+    fn create_texture(&mut self, width: u16, height: u16, data: &[u8]) -> GameResult<Box<dyn BackendTexture>> {
+        let size = width as usize * height as usize * 4;
+        if data.len() < size {
+            return Err(RenderError(format!(
+                "Texture data is too short: expected {} bytes for {}x{}, got {}.",
+                size,
+                width,
+                height,
+                data.len()
+            )));
+        }
+
+        Ok(self.new_texture(Surface { width, height, pixels: data[..size].to_vec() }))
+    }
+
+    fn set_blend_mode(&mut self, blend: BlendMode) -> GameResult {
+        self.state.borrow_mut().blend = blend;
+
+        Ok(())
+    }
+
+    fn set_render_target(&mut self, texture: Option<&Box<dyn BackendTexture>>) -> GameResult {
+        let target = match texture {
+            Some(texture) => Some(Self::downcast(texture.as_ref())?.surface.clone()),
+            None => None,
+        };
+        self.state.borrow_mut().target = target;
+
+        Ok(())
+    }
+
+    fn draw_rect(&mut self, rect: Rect<isize>, color: Color) -> GameResult {
+        let state = self.state.borrow();
+        let target = state.target();
+        let mut target = target.borrow_mut();
+        let bounds = state.bounds(&target);
+        let (r, g, b, a) = color.to_rgba();
+        let rect = Bounds(rect.left as i32, rect.top as i32, rect.right as i32, rect.bottom as i32);
+
+        fill_rect(&mut target, bounds, rect, [r, g, b, a], state.blend);
+
+        Ok(())
+    }
+
+    fn draw_outline_rect(&mut self, rect: Rect<isize>, line_width: usize, color: Color) -> GameResult {
+        let line_width = line_width as isize;
+        let Rect { left, top, right, bottom } = rect;
+
+        self.draw_rect(Rect::new(left, top, right, top + line_width), color)?;
+        self.draw_rect(Rect::new(left, bottom - line_width, right, bottom), color)?;
+        self.draw_rect(Rect::new(left, top + line_width, left + line_width, bottom - line_width), color)?;
+        self.draw_rect(Rect::new(right - line_width, top + line_width, right, bottom - line_width), color)
+    }
+
+    fn set_clip_rect(&mut self, rect: Option<Rect>) -> GameResult {
+        self.state.borrow_mut().clip = rect;
+
+        Ok(())
+    }
+
+    fn imgui(&self) -> GameResult<&mut imgui::Context> {
+        unsafe { Ok(&mut *self.imgui.get()) }
+    }
+
+    fn imgui_texture_id(&self, texture: &Box<dyn BackendTexture>) -> GameResult<TextureId> {
+        Ok(TextureId::new(Self::downcast(texture.as_ref())?.id))
+    }
+
+    fn prepare_imgui(&mut self, _ui: &Ui) -> GameResult {
+        Ok(())
+    }
+
+    fn render_imgui(&mut self, draw_data: &DrawData) -> GameResult {
+        let state = self.state.borrow();
+        let mut screen = state.screen.borrow_mut();
+        let screen_bounds = Bounds(0, 0, screen.width as i32, screen.height as i32);
+        let mut vertices = Vec::new();
+
+        for draw_list in draw_data.draw_lists() {
+            let vtx_buffer = draw_list.vtx_buffer();
+            let idx_buffer = draw_list.idx_buffer();
+
+            for cmd in draw_list.commands() {
+                if let DrawCmd::Elements {
+                    count,
+                    cmd_params: DrawCmdParams { clip_rect: [x, y, z, w], texture_id, idx_offset, .. },
+                } = cmd
+                {
+                    let texture = state.textures.get(&texture_id.id()).and_then(Weak::upgrade);
+                    let texture = texture.as_ref().map(|texture| texture.borrow());
+                    let bounds = intersect(screen_bounds, Bounds(x as i32, y as i32, z.ceil() as i32, w.ceil() as i32));
+
+                    vertices.clear();
+                    vertices.extend(idx_buffer[idx_offset..idx_offset + count].iter().map(|&idx| {
+                        let vert = &vtx_buffer[idx as usize];
+                        VertexData {
+                            position: (vert.pos[0], vert.pos[1]),
+                            color: (vert.col[0], vert.col[1], vert.col[2], vert.col[3]),
+                            uv: (vert.uv[0], vert.uv[1]),
+                        }
+                    }));
+
+                    draw_triangles(&mut screen, bounds, &vertices, texture.as_deref(), BlendMode::Alpha);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn supports_vertex_draw(&self) -> bool {
+        true
+    }
+
+    fn draw_triangle_list(
+        &mut self,
+        vertices: &[VertexData],
+        texture: Option<&Box<dyn BackendTexture>>,
+        shader: BackendShader,
+    ) -> GameResult {
+        let texture = match (shader, texture) {
+            (BackendShader::Texture, Some(texture)) => Some(Self::downcast(texture.as_ref())?.surface.clone()),
+            _ => None,
+        };
+
+        let state = self.state.borrow();
+        let target = state.target();
+        if texture.as_ref().map_or(false, |texture| Rc::ptr_eq(texture, &target)) {
+            return Err(RenderError("Cannot draw a texture onto itself.".to_string()));
+        }
+
+        let texture = texture.as_ref().map(|texture| texture.borrow());
+        let mut target = target.borrow_mut();
+        let bounds = state.bounds(&target);
+
+        draw_triangles(&mut target, bounds, vertices, texture.as_deref(), state.blend);
+
+        Ok(())
+    }
+
+    fn read_framebuffer(&mut self) -> GameResult<(u16, u16, Vec<u8>)> {
+        let state = self.state.borrow();
+        let screen = state.screen.borrow();
+
+        Ok((screen.width, screen.height, screen.pixels.clone()))
+    }
+
+    fn read_texture(&mut self, texture: &Box<dyn BackendTexture>) -> GameResult<(u16, u16, Vec<u8>)> {
+        let surface = Self::downcast(texture.as_ref())?.surface.borrow();
+
+        Ok((surface.width, surface.height, surface.pixels.clone()))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Result of comparing a frame against a reference image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageDiff {
+    /// Number of pixels with any channel differing by more than the tolerance.
+    pub mismatched_pixels: usize,
+    /// Largest difference of a single channel.
+    pub max_difference: u8,
+}
+
+impl ImageDiff {
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compares RGBA8 pixels, as returned by [BackendRenderer::read_framebuffer], against a reference PNG.
+pub fn compare_with_png<R: Read>(
+    width: u16,
+    height: u16,
+    pixels: &[u8],
+    mut reference: R,
+    tolerance: u8,
+) -> GameResult<ImageDiff> {
+    let mut data = Vec::new();
+    reference.read_to_end(&mut data)?;
+
+    let image = image::load_from_memory_with_format(&data, image::ImageFormat::Png)
+        .map_err(|e| GameError::ResourceLoadError(format!("Failed to load the reference image: {}", e)))?
+        .to_rgba8();
+
+    if image.dimensions() != (width as u32, height as u32) {
+        return Err(GameError::InvalidValue(format!(
+            "Reference image is {}x{}, but the frame is {}x{}.",
+            image.width(),
+            image.height(),
+            width,
+            height
+        )));
+    }
+
+    let mut diff = ImageDiff { mismatched_pixels: 0, max_difference: 0 };
+    for (actual, expected) in pixels.chunks_exact(4).zip(image.as_raw().chunks_exact(4)) {
+        let difference = actual.iter().zip(expected).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0);
+
+        diff.max_difference = diff.max_difference.max(difference);
+        if difference > tolerance {
+            diff.mismatched_pixels += 1;
+        }
+    }
+
+    Ok(diff)
+}
+
+#[cfg(test)]
+fn render_test_scene() -> (u16, u16, Vec<u8>) {
+    let mut renderer = SoftwareRenderer::new(4, 4).unwrap();
+    renderer.prepare_draw(4.0, 4.0).unwrap();
+    renderer.clear(Color::from_rgba(0, 0, 0, 255));
+
+    let red = [255, 0, 0, 255];
+    let green = [0, 255, 0, 255];
+    let mut texture = renderer.create_texture(2, 1, &[red, green].concat()).unwrap();
+
+    // flipped sprite scaled 2x into the top left corner
+    renderer.set_clip_rect(Some(Rect::new(0, 0, 4, 1))).unwrap();
+    texture.add(SpriteBatchCommand::DrawRectFlip(
+        Rect::new(0.0, 0.0, 2.0, 1.0),
+        Rect::new(0.0, 0.0, 4.0, 2.0),
+        true,
+        false,
+    ));
+    texture.draw().unwrap();
+    renderer.set_clip_rect(None).unwrap();
+
+    // two triangles sharing an edge, the diagonal must only be blended once
+    let color = (255, 255, 255, 128);
+    let quad = [(0.0, 2.0), (4.0, 2.0), (4.0, 4.0), (0.0, 2.0), (4.0, 4.0), (0.0, 4.0)].map(|position| VertexData {
+        position,
+        color,
+        uv: (0.0, 0.0),
+    });
+    renderer.draw_triangle_list(&quad, None, BackendShader::Fill).unwrap();
+
+    renderer.set_blend_mode(BlendMode::Add).unwrap();
+    renderer.draw_rect(Rect::new(3, 3, 4, 4), Color::from_rgba(0, 0, 255, 255)).unwrap();
+
+    renderer.read_framebuffer().unwrap()
+}
+
+#[test]
+fn test_software_renderer() {
+    let red = [255, 0, 0, 255];
+    let green = [0, 255, 0, 255];
+    let (width, height, pixels) = render_test_scene();
+    let pixel = |x: usize, y: usize| &pixels[(y * width as usize + x) * 4..][..4];
+
+    assert_eq!((width, height), (4, 4));
+    assert_eq!(pixel(0, 0), &green);
+    assert_eq!(pixel(3, 0), &red);
+    assert_eq!(pixel(0, 1), &[0, 0, 0, 255]);
+    assert!((2..4).all(|y| (0..4).all(|x| pixel(x, y)[0] == 128)));
+    assert_eq!(pixel(3, 3), &[128, 128, 255, 255]);
+}
+
+#[test]
+fn test_compare_with_png() {
+    let reference = include_bytes!("render_software_ref.png");
+    let (width, height, mut pixels) = render_test_scene();
+
+    let diff = compare_with_png(width, height, &pixels, &reference[..], 1).unwrap();
+    assert!(diff.is_match(), "{:?}", diff);
+
+    pixels[0..4].copy_from_slice(&[255, 255, 255, 255]);
+    let diff = compare_with_png(width, height, &pixels, &reference[..], 1).unwrap();
+    assert_eq!(diff, ImageDiff { mismatched_pixels: 1, max_difference: 255 });
+
+    assert!(compare_with_png(2, 2, &pixels[..16], &reference[..], 1).is_err());
+}
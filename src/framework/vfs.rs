@@ -12,12 +12,15 @@
 //! as a trait object, and its path abstraction is not the most
 //! convenient.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::ffi::OsStr;
 use std::fmt::{self, Debug};
 use std::fs;
-use std::io::{Read, Seek, Write};
+use std::io::{self, Cursor, Read, Seek, SeekFrom, Write};
 use std::path::{self, Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use zip::ZipArchive;
 
 use crate::framework::error::{GameError, GameResult};
 
@@ -115,6 +118,12 @@ pub trait VFS: Debug {
     fn append(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
         self.open_options(path, OpenOptions::new().write(true).create(true).append(true))
     }
+    /// Open the file at this path for writing, truncating it, even if the filesystem is mounted read-only.
+    /// Used by the editor to save the stage files, filesystems that can't be written to return an error.
+    fn overwrite(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
+        let msg = format!("Cannot overwrite file {:?} in root {:?}, filesystem can't be written to", path, self);
+        Err(GameError::FilesystemError(msg))
+    }
     /// Create a directory at the location by this path
     fn mkdir(&self, path: &Path) -> GameResult;
 
@@ -323,6 +332,13 @@ impl VFS for PhysicalFS {
         open_options.to_fs_openoptions().open(p).map(|x| Box::new(x) as Box<dyn VFile>).map_err(GameError::from)
     }
 
+    /// Open the file at this path for writing, only the file itself is written to and no directories are created
+    fn overwrite(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
+        let p = self.to_absolute(path)?;
+        let open_options = OpenOptions::new().write(true).create(true).truncate(true);
+        open_options.to_fs_openoptions().open(p).map(|x| Box::new(x) as Box<dyn VFile>).map_err(GameError::from)
+    }
+
     /// Create a directory at the location by this path
     fn mkdir(&self, path: &Path) -> GameResult {
         if self.readonly {
@@ -472,6 +488,24 @@ impl VFS for OverlayFS {
         Err(GameError::ResourceNotFound(errmessage, tried))
     }
 
+    /// Overwrite the file in the root it would be opened from, or in the first root that can write it
+    fn overwrite(&self, path: &Path) -> GameResult<Box<dyn VFile>> {
+        if let Some(vfs) = self.roots.iter().find(|vfs| vfs.exists(path)) {
+            return vfs.overwrite(path);
+        }
+
+        let mut tried: Vec<(PathBuf, GameError)> = vec![];
+
+        for vfs in &self.roots {
+            match vfs.overwrite(path) {
+                Err(e) => tried.push((vfs.to_path_buf().unwrap_or_else(|| PathBuf::from("<invalid path>")), e)),
+                f => return f,
+            }
+        }
+        let errmessage = String::from(convenient_path_to_str(path)?);
+        Err(GameError::ResourceNotFound(errmessage, tried))
+    }
+
     /// Create a directory at the location by this path
     fn mkdir(&self, path: &Path) -> GameResult {
         for vfs in &self.roots {
@@ -546,6 +580,228 @@ impl VFS for OverlayFS {
     }
 }
 
+/// Returns true if the path points to a zip archive that can be mounted with [ZipFS].
+pub fn is_zip_path(path: &Path) -> bool {
+    path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Reader the zip archive is read from.
+pub trait ZipSource: Read + Seek + Send {}
+
+impl<T> ZipSource for T where T: Read + Seek + Send {}
+
+#[derive(Debug, Clone)]
+enum ZipNode {
+    /// Index of the entry in the archive and its uncompressed size.
+    File(usize, u64),
+    Directory,
+}
+
+/// A read-only VFS backed by a zip archive.
+///
+/// The contents of the archive appear under `mount_point`, which lets mods shipped as `mods/example.zip`
+/// be accessed as if the archive was a directory. Lookups are case insensitive, like `PhysicalFS::new_lowercase`.
+pub struct ZipFS {
+    source: PathBuf,
+    mount_point: PathBuf,
+    archive: Mutex<ZipArchive<Box<dyn ZipSource>>>,
+    /// Lowercase paths of files and directories, relative to the archive root and separated with '/'.
+    nodes: HashMap<String, ZipNode>,
+    /// Lowercase directory path to the original names of its direct children.
+    children: HashMap<String, Vec<String>>,
+}
+
+/// Zip archive entry, extracted into memory when opened.
+#[derive(Debug)]
+pub struct ZipFSFile(Cursor<Vec<u8>>);
+
+impl Read for ZipFSFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Seek for ZipFSFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+impl Write for ZipFSFile {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "Zip file system is read-only."))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "Zip file system is read-only."))
+    }
+}
+
+struct ZipMetadata {
+    is_dir: bool,
+    size: u64,
+}
+
+impl VMetadata for ZipMetadata {
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    fn len(&self) -> u64 {
+        self.size
+    }
+}
+
+fn zip_error(source: &Path, err: zip::result::ZipError) -> GameError {
+    GameError::FilesystemError(format!("Error reading zip archive {:?}: {}", source, err))
+}
+
+impl ZipFS {
+    /// Opens a zip archive from the disk and mounts its contents at the VFS root.
+    pub fn new(path: &Path) -> GameResult<Self> {
+        let file = fs::File::open(path)?;
+        Self::from_reader(Box::new(file), path, Path::new("/"))
+    }
+
+    /// Mounts the contents of an archive at `mount_point`, `source` identifies the archive
+    /// in error messages and is used for unmounting.
+    pub fn from_reader(reader: Box<dyn ZipSource>, source: &Path, mount_point: &Path) -> GameResult<Self> {
+        let mut archive = ZipArchive::new(reader).map_err(|e| zip_error(source, e))?;
+        let mount_point = sanitize_path(mount_point)
+            .ok_or_else(|| GameError::FilesystemError(format!("Invalid zip archive mount point: {:?}", mount_point)))?;
+
+        let mut nodes = HashMap::new();
+        let mut children: HashMap<String, Vec<String>> = HashMap::new();
+        nodes.insert(String::new(), ZipNode::Directory);
+
+        for index in 0..archive.len() {
+            let entry = archive.by_index_raw(index).map_err(|e| zip_error(source, e))?;
+            let name = entry.name().replace('\\', "/");
+            let node = if entry.is_dir() { ZipNode::Directory } else { ZipNode::File(index, entry.size()) };
+
+            let components: Vec<&str> = name.split('/').filter(|c| !c.is_empty()).collect();
+            let mut parent = String::new();
+
+            for (depth, component) in components.iter().enumerate() {
+                let key = if parent.is_empty() {
+                    component.to_lowercase()
+                } else {
+                    format!("{}/{}", parent, component.to_lowercase())
+                };
+
+                if !nodes.contains_key(&key) {
+                    let is_last = depth == components.len() - 1;
+                    nodes.insert(key.clone(), if is_last { node.clone() } else { ZipNode::Directory });
+                    children.entry(parent).or_default().push(component.to_string());
+                }
+
+                parent = key;
+            }
+        }
+
+        Ok(ZipFS { source: source.to_path_buf(), mount_point, archive: Mutex::new(archive), nodes, children })
+    }
+
+    /// Converts a VFS path into the lowercase key of the entry inside of the archive.
+    fn to_key(&self, path: &Path) -> Option<String> {
+        let path = sanitize_path(path)?;
+        let relative = path.strip_prefix(&self.mount_point).ok()?;
+
+        let components: Vec<String> = relative
+            .components()
+            .filter_map(|c| if let Component::Normal(s) = c { Some(s.to_string_lossy().to_lowercase()) } else { None })
+            .collect();
+
+        Some(components.join("/"))
+    }
+
+    fn find(&self, path: &Path) -> GameResult<(String, &ZipNode)> {
+        self.to_key(path)
+            .and_then(|key| self.nodes.get(&key).map(|node| (key, node)))
+            .ok_or_else(|| GameError::FilesystemError(format!("File {:?} not found in {:?}", path, self.source)))
+    }
+
+    fn read_only_error(&self) -> GameResult {
+        Err(GameError::FilesystemError(format!("Zip archive {:?} is read-only.", self.source)))
+    }
+}
+
+impl Debug for ZipFS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "<ZipFS source: {}, mount point: /{}>", self.source.display(), self.mount_point.display())
+    }
+}
+
+impl VFS for ZipFS {
+    fn open_options(&self, path: &Path, open_options: OpenOptions) -> GameResult<Box<dyn VFile>> {
+        if open_options.write || open_options.create || open_options.append || open_options.truncate {
+            self.read_only_error()?;
+        }
+
+        match self.find(path)? {
+            (_, &ZipNode::File(index, size)) => {
+                let mut archive = self.archive.lock().unwrap();
+                let mut entry = archive.by_index(index).map_err(|e| zip_error(&self.source, e))?;
+
+                // the size comes from the archive, so it only limits the read instead of sizing the buffer
+                let mut buf = Vec::new();
+                entry.take(size).read_to_end(&mut buf)?;
+
+                Ok(Box::new(ZipFSFile(Cursor::new(buf))))
+            }
+            (_, ZipNode::Directory) => Err(GameError::FilesystemError(format!("{:?} is a directory.", path))),
+        }
+    }
+
+    fn mkdir(&self, _path: &Path) -> GameResult {
+        self.read_only_error()
+    }
+
+    fn rm(&self, _path: &Path) -> GameResult {
+        self.read_only_error()
+    }
+
+    fn rmrf(&self, _path: &Path) -> GameResult {
+        self.read_only_error()
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.find(path).is_ok()
+    }
+
+    fn metadata(&self, path: &Path) -> GameResult<Box<dyn VMetadata>> {
+        let metadata = match self.find(path)? {
+            (_, &ZipNode::File(_, size)) => ZipMetadata { is_dir: false, size },
+            (_, ZipNode::Directory) => ZipMetadata { is_dir: true, size: 0 },
+        };
+
+        Ok(Box::new(metadata))
+    }
+
+    fn read_dir(&self, path: &Path) -> GameResult<Box<dyn Iterator<Item = GameResult<PathBuf>>>> {
+        let (key, node) = self.find(path)?;
+        if let ZipNode::File(..) = node {
+            return Err(GameError::FilesystemError(format!("{:?} is not a directory.", path)));
+        }
+
+        let entries: Vec<GameResult<PathBuf>> = self
+            .children
+            .get(&key)
+            .map(|names| names.iter().map(|name| Ok(path.join(name))).collect())
+            .unwrap_or_default();
+
+        Ok(Box::new(entries.into_iter()))
+    }
+
+    fn to_path_buf(&self) -> Option<PathBuf> {
+        Some(self.source.clone())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::{self, BufRead};
@@ -689,5 +945,61 @@ mod tests {
         assert!(!fs.exists(testdir));
     }
 
+    #[test]
+    fn headless_test_physical_overwrite() {
+        let dir = std::env::temp_dir().join(format!("doukutsu-rs-overwrite-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Stage")).unwrap();
+        std::fs::write(dir.join("Stage/Pole.pxm"), b"old").unwrap();
+
+        let mut ofs = OverlayFS::new();
+        ofs.push_back(Box::new(PhysicalFS::new(&dir, true)));
+
+        // the mount stays read-only
+        assert!(ofs.create(Path::new("/Stage/Pole.pxm")).is_err());
+        assert!(ofs.mkdir(Path::new("/Stage/New")).is_err());
+
+        {
+            let mut f = ofs.overwrite(Path::new("/Stage/Pole.pxm")).unwrap();
+            f.write_all(b"new").unwrap();
+        }
+        assert_eq!(std::fs::read(dir.join("Stage/Pole.pxm")).unwrap(), b"new");
+
+        // no directories are created
+        assert!(ofs.overwrite(Path::new("/Missing/Pole.pxm")).is_err());
+        assert!(!dir.join("Missing").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn headless_test_zip() {
+        use zip::write::FileOptions;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        writer.start_file("Stage/Pole.TSC", options).unwrap();
+        writer.write_all(b"#0090").unwrap();
+        writer.start_file("mod.txt", options).unwrap();
+        let data = writer.finish().unwrap().into_inner();
+
+        let path = Path::new("/mods/test.zip");
+        let fs = ZipFS::from_reader(Box::new(Cursor::new(data)), path, path).unwrap();
+
+        assert!(fs.exists(Path::new("/mods/test.zip/mod.txt")));
+        assert!(fs.exists(Path::new("/mods/test.zip/stage/pole.tsc")));
+        assert!(fs.metadata(Path::new("/mods/test.zip/Stage")).unwrap().is_dir());
+        assert!(!fs.exists(Path::new("/mods/other.zip/mod.txt")));
+        assert!(!fs.exists(Path::new("/mod.txt")));
+        assert!(fs.create(Path::new("/mods/test.zip/save.dat")).is_err());
+
+        let mut buf = Vec::new();
+        fs.open(Path::new("/mods/test.zip/STAGE/Pole.tsc")).unwrap().read_to_end(&mut buf).unwrap();
+        assert_eq!(&buf[..], b"#0090");
+
+        let entries: Vec<PathBuf> =
+            fs.read_dir(Path::new("/mods/test.zip/stage")).unwrap().map(Result::unwrap).collect();
+        assert_eq!(entries, vec![PathBuf::from("/mods/test.zip/stage/Pole.TSC")]);
+    }
+
     // BUGGO: TODO: Make sure all functions are tested for OverlayFS and ZipFS!!
 }
@@ -2,7 +2,7 @@ use crate::common::{CDEG_RAD, Condition, Direction, Rect};
 use crate::engine_constants::EngineConstants;
 use crate::util::rng::RNG;
 
-#[derive(Debug, EnumIter, PartialEq, Eq, Hash, Copy, Clone)]
+#[derive(Debug, EnumIter, PartialEq, Eq, Hash, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum CaretType {
     None,
     Bubble,
@@ -50,6 +50,7 @@ impl CaretType {
     }
 }
 
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Caret {
     pub ctype: CaretType,
     pub x: i32,
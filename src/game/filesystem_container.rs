@@ -6,7 +6,7 @@ use crate::{
         context::Context,
         error::GameResult,
         filesystem::{mount_user_vfs, mount_vfs, unmount_user_vfs},
-        vfs::PhysicalFS,
+        vfs::{is_zip_path, PhysicalFS, ZipFS},
     },
 };
 
@@ -70,7 +70,11 @@ impl FilesystemContainer {
 
         #[cfg(not(any(target_os = "android", target_os = "horizon")))]
         {
-            mount_vfs(context, Box::new(PhysicalFS::new(&resource_dir, true)));
+            if is_zip_path(&resource_dir) {
+                mount_vfs(context, Box::new(ZipFS::new(&resource_dir)?));
+            } else {
+                mount_vfs(context, Box::new(PhysicalFS::new(&resource_dir, true)));
+            }
             self.game_path = resource_dir.clone();
         }
 
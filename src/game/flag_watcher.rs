@@ -0,0 +1,124 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::framework::context::Context;
+use crate::framework::filesystem;
+use crate::framework::filesystem::File;
+
+/// Optional per-mod file with human readable flag names, looked up in the same paths as other game data.
+pub const FLAG_NAMES_FILE: &str = "flag_names.json";
+
+const MAX_RECORDED_CHANGES: usize = 100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FlagKind {
+    Game,
+    Map,
+}
+
+/// Names shown next to the flag numbers in the debugger.
+///
+/// The file format is `{"flags": {"200": "Met Balrog"}, "map_flags": {"4": "Visited Egg Corridor"}}`,
+/// both maps are optional.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct FlagNames {
+    pub flags: HashMap<usize, String>,
+    pub map_flags: HashMap<usize, String>,
+}
+
+impl FlagNames {
+    pub fn load(ctx: &Context, roots: &[String]) -> FlagNames {
+        let file = match filesystem::open_find(ctx, roots, FLAG_NAMES_FILE) {
+            Ok(file) => file,
+            Err(_) => return FlagNames::default(),
+        };
+
+        match serde_json::from_reader::<File, FlagNames>(file) {
+            Ok(names) => names,
+            Err(err) => {
+                log::warn!("Failed to load {}: {}", FLAG_NAMES_FILE, err);
+                FlagNames::default()
+            }
+        }
+    }
+
+    pub fn get(&self, kind: FlagKind, id: usize) -> Option<&str> {
+        match kind {
+            FlagKind::Game => self.flags.get(&id),
+            FlagKind::Map => self.map_flags.get(&id),
+        }
+        .map(|name| name.as_str())
+    }
+}
+
+/// A recorded change of a watched flag.
+#[derive(Debug, Clone)]
+pub struct FlagChange {
+    pub kind: FlagKind,
+    pub id: usize,
+    pub value: bool,
+    /// Event and opcode that changed the flag, if it was changed by a script.
+    pub source: Option<(u16, &'static str)>,
+}
+
+/// Watchpoints on game and map flags, set from the live debugger.
+pub struct FlagWatcher {
+    pub game_watchpoints: BTreeSet<usize>,
+    pub map_watchpoints: BTreeSet<usize>,
+    /// Most recent changes of watched flags, oldest first.
+    pub changes: Vec<FlagChange>,
+    /// Set when a watched flag changes, the game scene doesn't tick until it's cleared.
+    pub paused: bool,
+}
+
+impl FlagWatcher {
+    pub fn new() -> FlagWatcher {
+        FlagWatcher {
+            game_watchpoints: BTreeSet::new(),
+            map_watchpoints: BTreeSet::new(),
+            changes: Vec::new(),
+            paused: false,
+        }
+    }
+
+    pub fn watchpoints_mut(&mut self, kind: FlagKind) -> &mut BTreeSet<usize> {
+        match kind {
+            FlagKind::Game => &mut self.game_watchpoints,
+            FlagKind::Map => &mut self.map_watchpoints,
+        }
+    }
+
+    pub fn is_watched(&self, kind: FlagKind, id: usize) -> bool {
+        match kind {
+            FlagKind::Game => self.game_watchpoints.contains(&id),
+            FlagKind::Map => self.map_watchpoints.contains(&id),
+        }
+    }
+
+    /// Logs the change and pauses the game.
+    pub fn record(&mut self, change: FlagChange) {
+        let name = match change.kind {
+            FlagKind::Game => "flag",
+            FlagKind::Map => "map flag",
+        };
+
+        match change.source {
+            Some((event, opcode)) => log::info!(
+                "Watched {} {} set to {} by event #{:04} at <{}.",
+                name,
+                change.id,
+                change.value,
+                event,
+                opcode
+            ),
+            None => log::info!("Watched {} {} set to {} outside of a script.", name, change.id, change.value),
+        }
+
+        if self.changes.len() >= MAX_RECORDED_CHANGES {
+            self.changes.remove(0);
+        }
+
+        self.changes.push(change);
+        self.paused = true;
+    }
+}
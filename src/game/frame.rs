@@ -3,7 +3,7 @@ use crate::game::shared_game_state::SharedGameState;
 use crate::game::stage::Stage;
 use crate::util::rng::RNG;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[repr(u8)]
 pub enum UpdateTarget {
     Player,
@@ -11,6 +11,7 @@ pub enum UpdateTarget {
     Boss(u16),
 }
 
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Frame {
     pub x: i32,
     pub y: i32,
@@ -0,0 +1,457 @@
+//! Recording of gameplay frames, used by the `--record-frames` and `--render-replay` launch options
+//! and the recording debug hotkey.
+//!
+//! Frames are read back from the renderer once per game tick and encoded on a separate thread,
+//! so recording doesn't slow down the game more than necessary. Only a few frames are queued,
+//! if the encoder falls behind the game waits for it instead of piling up frames in memory.
+
+use std::fs;
+use std::io::{BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::JoinHandle;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::FilterType;
+use image::{Delay, Frame, ImageOutputFormat, RgbaImage};
+
+use crate::framework::context::Context;
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::graphics;
+use crate::game::replay_verifier;
+use crate::game::shared_game_state::SharedGameState;
+use crate::graphics::texture_set::{G_MAG, I_MAG};
+use crate::scene::Scene;
+
+/// Number of captured frames that can wait for the encoder.
+const FRAME_QUEUE_SIZE: usize = 8;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CaptureFormat {
+    /// Numbered PNG files in a directory, one per game tick.
+    PngSequence,
+    /// Animated GIF, with delays rounded to the 10ms precision of the format.
+    Gif,
+    /// Animated PNG with exact frame delays.
+    Apng,
+}
+
+impl CaptureFormat {
+    /// Picks the format from the output path, `.gif` and `.png`/`.apng` files are animated,
+    /// anything else is treated as a directory for a PNG sequence.
+    pub fn from_path(path: &Path) -> CaptureFormat {
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            Some("gif") => CaptureFormat::Gif,
+            Some("png") | Some("apng") => CaptureFormat::Apng,
+            _ => CaptureFormat::PngSequence,
+        }
+    }
+}
+
+struct CapturedFrame {
+    image: RgbaImage,
+    /// Number of game ticks the frame is shown for.
+    ticks: u32,
+}
+
+enum FrameEncoder {
+    PngSequence {
+        dir: PathBuf,
+        next_index: u32,
+    },
+    Gif {
+        encoder: GifEncoder<BufWriter<fs::File>>,
+        elapsed_ticks: u64,
+    },
+    /// APNG needs the frame count up front, so the frames are written compressed to a temporary file
+    /// next to the output until the recording ends, only their sizes and durations are kept in memory.
+    Apng {
+        path: PathBuf,
+        spill_path: PathBuf,
+        spill: BufWriter<fs::File>,
+        frames: Vec<(u64, u32)>,
+    },
+}
+
+impl FrameEncoder {
+    fn new(format: CaptureFormat, path: &Path) -> GameResult<FrameEncoder> {
+        match format {
+            CaptureFormat::PngSequence => {
+                fs::create_dir_all(path)?;
+                Ok(FrameEncoder::PngSequence { dir: path.to_path_buf(), next_index: 0 })
+            }
+            CaptureFormat::Gif => {
+                let mut encoder = GifEncoder::new(BufWriter::new(fs::File::create(path)?));
+                encoder.set_repeat(Repeat::Infinite)?;
+                Ok(FrameEncoder::Gif { encoder, elapsed_ticks: 0 })
+            }
+            CaptureFormat::Apng => {
+                // fail early if the file can't be written
+                fs::File::create(path)?;
+
+                let spill_path = apng_spill_path(path);
+                let spill = BufWriter::new(fs::File::create(&spill_path)?);
+                Ok(FrameEncoder::Apng { path: path.to_path_buf(), spill_path, spill, frames: Vec::new() })
+            }
+        }
+    }
+
+    fn add(&mut self, frame: CapturedFrame, tps: u32) -> GameResult {
+        match self {
+            FrameEncoder::PngSequence { dir, next_index } => {
+                let first_path = dir.join(sequence_file_name(*next_index));
+                frame.image.save_with_format(&first_path, image::ImageFormat::Png)?;
+
+                // frames held for multiple ticks are repeated, so the file numbers always match the ticks
+                for index in *next_index + 1..*next_index + frame.ticks {
+                    fs::copy(&first_path, dir.join(sequence_file_name(index)))?;
+                }
+
+                *next_index += frame.ticks;
+            }
+            FrameEncoder::Gif { encoder, elapsed_ticks } => {
+                let delay = Delay::from_numer_denom_ms(gif_delay_ms(*elapsed_ticks, frame.ticks, tps), 1);
+                *elapsed_ticks += frame.ticks as u64;
+
+                encoder.encode_frame(Frame::from_parts(frame.image, 0, 0, delay))?;
+            }
+            FrameEncoder::Apng { spill, frames, .. } => {
+                let mut data = Vec::new();
+                frame.image.write_to(&mut Cursor::new(&mut data), ImageOutputFormat::Png)?;
+                spill.write_all(&data)?;
+                frames.push((data.len() as u64, frame.ticks));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn finish(self, size: (u32, u32), tps: u32) -> GameResult {
+        let FrameEncoder::Apng { path, spill_path, spill, frames } = self else {
+            return Ok(());
+        };
+
+        drop(spill);
+        let result = FrameEncoder::write_apng(&path, &spill_path, &frames, size, tps);
+        let _ = fs::remove_file(&spill_path);
+
+        result
+    }
+
+    fn write_apng(path: &Path, spill_path: &Path, frames: &[(u64, u32)], size: (u32, u32), tps: u32) -> GameResult {
+        if frames.is_empty() {
+            return Ok(());
+        }
+
+        let png_error = |e: png::EncodingError| GameError::RenderError(format!("Failed to encode APNG: {}", e));
+
+        let mut encoder = png::Encoder::new(BufWriter::new(fs::File::create(path)?), size.0, size.1);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(frames.len() as u32, 0).map_err(png_error)?;
+
+        let mut spill = BufReader::new(fs::File::open(spill_path)?);
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        for &(len, ticks) in frames {
+            let mut data = Vec::new();
+            spill.by_ref().take(len).read_to_end(&mut data)?;
+            let image = image::load_from_memory_with_format(&data, image::ImageFormat::Png)?.to_rgba8();
+
+            writer.set_frame_delay(ticks.min(u16::MAX as u32) as u16, tps as u16).map_err(png_error)?;
+            writer.write_image_data(image.as_raw()).map_err(png_error)?;
+        }
+        writer.finish().map_err(png_error)?;
+
+        Ok(())
+    }
+}
+
+/// Temporary file the compressed frames of an APNG recording are kept in.
+fn apng_spill_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".frames");
+    path.with_file_name(name)
+}
+
+/// File name of the frame shown at given tick in a PNG sequence.
+fn sequence_file_name(index: u32) -> String {
+    format!("{:06}.png", index)
+}
+
+/// Delay of a GIF frame shown for `ticks` ticks, starting `elapsed_ticks` ticks into the recording.
+///
+/// GIF delays are in centiseconds, the rounding error is accumulated instead of letting the clip drift.
+fn gif_delay_ms(elapsed_ticks: u64, ticks: u32, tps: u32) -> u32 {
+    let start = elapsed_ticks * 100 / tps as u64;
+    let end = (elapsed_ticks + ticks as u64) * 100 / tps as u64;
+
+    ((end - start) * 10) as u32
+}
+
+/// Encodes the received frames until the recorder is stopped, returns the number of recorded ticks.
+fn encode_frames(format: CaptureFormat, path: PathBuf, tps: u32, receiver: Receiver<CapturedFrame>) -> GameResult<u64> {
+    let mut encoder = FrameEncoder::new(format, &path)?;
+    let mut size = None;
+    let mut recorded_ticks = 0;
+
+    for mut frame in receiver {
+        // animations need all frames to have the same size, so frames captured after a resize are scaled
+        let (width, height) = *size.get_or_insert(frame.image.dimensions());
+        if frame.image.dimensions() != (width, height) {
+            frame.image = image::imageops::resize(&frame.image, width, height, FilterType::Nearest);
+        }
+
+        recorded_ticks += frame.ticks as u64;
+        encoder.add(frame, tps)?;
+    }
+
+    encoder.finish(size.unwrap_or((0, 0)), tps)?;
+
+    Ok(recorded_ticks)
+}
+
+pub struct FrameRecorder {
+    path: PathBuf,
+    sender: Option<SyncSender<CapturedFrame>>,
+    worker: Option<JoinHandle<GameResult<u64>>>,
+}
+
+impl FrameRecorder {
+    /// Starts recording into `path`, the format is picked with [CaptureFormat::from_path].
+    /// `tps` is the tick rate of the game, which determines the frame rate of animated formats.
+    pub fn new(path: &Path, tps: usize) -> GameResult<FrameRecorder> {
+        let format = CaptureFormat::from_path(path);
+        // frame synchronized timing has no fixed tick rate, assume the common refresh rate
+        let tps = if tps == 0 { 60 } else { tps as u32 };
+        let (sender, receiver) = mpsc::sync_channel(FRAME_QUEUE_SIZE);
+
+        let worker_path = path.to_path_buf();
+        let worker = std::thread::Builder::new()
+            .name("frame encoder".to_owned())
+            .spawn(move || encode_frames(format, worker_path, tps, receiver))?;
+
+        log::info!("Recording frames to {:?} as {:?}.", path, format);
+
+        Ok(FrameRecorder { path: path.to_path_buf(), sender: Some(sender), worker: Some(worker) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads back the current framebuffer and queues it for encoding, `ticks` is the number
+    /// of game ticks that passed since the previous frame. Frames without any ticks are skipped.
+    pub fn capture(&mut self, ctx: &mut Context, ticks: u32) -> GameResult {
+        if ticks == 0 {
+            return Ok(());
+        }
+
+        let (width, height, pixels) = graphics::read_framebuffer(ctx)?;
+        self.add_frame(width, height, pixels, ticks)
+    }
+
+    /// Queues RGBA8 pixels for encoding, for frames read back with [graphics::read_texture].
+    pub fn add_frame(&mut self, width: u16, height: u16, pixels: Vec<u8>, ticks: u32) -> GameResult {
+        let image = RgbaImage::from_raw(width as u32, height as u32, pixels).ok_or_else(|| {
+            GameError::InvalidValue(format!("Frame data doesn't match its size {}x{}.", width, height))
+        })?;
+
+        let sent = self.sender.as_ref().map_or(false, |sender| sender.send(CapturedFrame { image, ticks }).is_ok());
+        if !sent {
+            // the encoder thread stopped early, the reason is returned when joining it
+            self.finish()?;
+            return Err(GameError::RenderError("Frame encoder has stopped.".to_owned()));
+        }
+
+        Ok(())
+    }
+
+    /// Waits for the queued frames to be written and returns the number of recorded ticks.
+    pub fn finish(&mut self) -> GameResult<u64> {
+        self.sender = None;
+
+        let Some(worker) = self.worker.take() else {
+            return Ok(0);
+        };
+
+        let ticks =
+            worker.join().map_err(|_| GameError::RenderError("Frame encoder thread panicked.".to_owned()))??;
+        log::info!("Recorded {} ticks to {:?}.", ticks, self.path);
+
+        Ok(ticks)
+    }
+}
+
+impl Drop for FrameRecorder {
+    fn drop(&mut self) {
+        if let Err(err) = self.finish() {
+            log::error!("Failed to finish the recording: {}", err);
+        }
+    }
+}
+
+/// Draws a frame of the scene the same way the game loop does, without presenting it.
+fn draw_frame(scene: &mut dyn Scene, state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
+    unsafe {
+        G_MAG = if state.settings.subpixel_coords { state.scale } else { 1.0 };
+        I_MAG = state.scale;
+    }
+
+    scene.draw_tick(state)?;
+    graphics::prepare_draw(ctx)?;
+    graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+    scene.draw(state, ctx)
+}
+
+/// Renders a replay with the software renderer into `output` and returns the process exit code,
+/// which is the same as the one of `--verify-replay`.
+///
+/// With `audio_output`, the audio of the playback is captured and written there as a WAV file.
+/// With `start_tick`, the rendering starts at that tick, see [crate::components::replay::Replay::seek].
+pub fn render_replay(replay: &Path, output: &Path, audio_output: Option<&Path>, start_tick: Option<usize>) -> i32 {
+    let mut recorder = None;
+    let mut samples = Vec::new();
+
+    let result = replay_verifier::play(replay, start_tick, true, audio_output.is_some(), |scene, state, ctx| {
+        replay_verifier::collect_audio(state, &mut samples);
+
+        let recorder = match &mut recorder {
+            Some(recorder) => recorder,
+            None => recorder.insert(FrameRecorder::new(output, state.settings.timing_mode.get_tps())?),
+        };
+
+        draw_frame(scene.as_mut(), state, ctx)?;
+        recorder.capture(ctx, 1)
+    })
+    .and_then(|result| {
+        if let Some(recorder) = &mut recorder {
+            recorder.finish()?;
+        }
+
+        if let Some(audio_output) = audio_output {
+            replay_verifier::write_audio(audio_output, &samples)?;
+        }
+
+        Ok(result)
+    });
+
+    replay_verifier::report(result)
+}
+
+#[test]
+fn test_capture_format_from_path() {
+    assert_eq!(CaptureFormat::from_path(Path::new("out/run.gif")), CaptureFormat::Gif);
+    assert_eq!(CaptureFormat::from_path(Path::new("out/run.GIF")), CaptureFormat::Gif);
+    assert_eq!(CaptureFormat::from_path(Path::new("run.png")), CaptureFormat::Apng);
+    assert_eq!(CaptureFormat::from_path(Path::new("run.apng")), CaptureFormat::Apng);
+    assert_eq!(CaptureFormat::from_path(Path::new("frames")), CaptureFormat::PngSequence);
+    assert_eq!(CaptureFormat::from_path(Path::new("frames.d/run")), CaptureFormat::PngSequence);
+}
+
+#[test]
+fn test_gif_frame_delay() {
+    // 50 ticks per second is exactly 2 centiseconds per tick
+    assert_eq!(gif_delay_ms(0, 1, 50), 20);
+    assert_eq!(gif_delay_ms(7, 3, 50), 60);
+
+    // 60 ticks per second alternates between 1 and 2 centiseconds, but a second of ticks is exactly a second
+    let mut total = 0;
+    for tick in 0..60 {
+        let delay = gif_delay_ms(tick, 1, 60);
+        assert!(delay == 10 || delay == 20, "delay {} at tick {}", delay, tick);
+        total += delay;
+    }
+    assert_eq!(total, 1000);
+}
+
+#[test]
+fn test_png_sequence_naming() {
+    assert_eq!(sequence_file_name(0), "000000.png");
+    assert_eq!(sequence_file_name(1234), "001234.png");
+
+    let dir = std::env::temp_dir().join(format!("doukutsu-rs-frame-capture-{}", std::process::id()));
+    let mut encoder = FrameEncoder::new(CaptureFormat::PngSequence, &dir).unwrap();
+
+    // a frame held for 3 ticks is written 3 times, so the next frame is numbered after the ticks
+    encoder.add(CapturedFrame { image: RgbaImage::new(2, 2), ticks: 3 }, 50).unwrap();
+    encoder.add(CapturedFrame { image: RgbaImage::new(2, 2), ticks: 1 }, 50).unwrap();
+    encoder.finish((2, 2), 50).unwrap();
+
+    let mut files: Vec<_> =
+        fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned()).collect();
+    files.sort();
+    let _ = fs::remove_dir_all(&dir);
+
+    assert_eq!(files, ["000000.png", "000001.png", "000002.png", "000003.png"]);
+}
+
+#[test]
+fn test_apng_spill() {
+    let path = std::env::temp_dir().join(format!("doukutsu-rs-frame-capture-{}.png", std::process::id()));
+    let mut encoder = FrameEncoder::new(CaptureFormat::Apng, &path).unwrap();
+    assert!(apng_spill_path(&path).exists());
+
+    encoder.add(CapturedFrame { image: RgbaImage::new(2, 2), ticks: 3 }, 50).unwrap();
+    encoder.add(CapturedFrame { image: RgbaImage::from_pixel(2, 2, image::Rgba([255; 4])), ticks: 1 }, 50).unwrap();
+    encoder.finish((2, 2), 50).unwrap();
+
+    // the frames are read back from the temporary file, which is removed afterwards
+    assert!(!apng_spill_path(&path).exists());
+    let decoder = png::Decoder::new(fs::File::open(&path).unwrap());
+    let reader = decoder.read_info().unwrap();
+    let frames = reader.info().animation_control.map(|control| control.num_frames);
+    let _ = fs::remove_file(&path);
+
+    assert_eq!(frames, Some(2));
+}
+
+/// Renders the stage a new game starts on with the software renderer and compares it against
+/// `frame_capture_ref.png`. The game data isn't part of the repository, so the test only runs with
+/// `CAVESTORY_DATA_DIR` pointing to it, and `DOUKUTSU_BLESS_REFERENCE=1` writes the reference image
+/// instead after an intended change to the rendering.
+#[test]
+fn test_render_game_frame() {
+    use crate::common::FadeState;
+    use crate::framework::render_software::compare_with_png;
+    use crate::game::init_headless;
+    use crate::game::scripting::tsc::text_script::TextScriptExecutionState;
+    use crate::scene::game_scene::GameScene;
+    use crate::util::rng::XorShift;
+
+    if std::env::var_os("CAVESTORY_DATA_DIR").is_none() {
+        eprintln!("CAVESTORY_DATA_DIR isn't set, skipping the game frame test.");
+        return;
+    }
+
+    let (mut context, mut state) = init_headless(true, false).unwrap();
+    let ctx = &mut *context;
+
+    // same setup as a new game, without the opening script and the fade-in
+    state.game_rng = XorShift::new(0);
+    let stage_id = state.constants.game.new_game_stage as usize;
+    let mut scene = GameScene::new(&mut state, ctx, stage_id).unwrap();
+    let (pos_x, pos_y) = state.constants.game.new_game_player_pos;
+    scene.player1.cond.set_alive(true);
+    scene.player1.x = pos_x as i32 * scene.stage.map.tile_size.as_int() * 0x200;
+    scene.player1.y = pos_y as i32 * scene.stage.map.tile_size.as_int() * 0x200;
+    scene.init(&mut state, ctx).unwrap();
+    state.fade_state = FadeState::Visible;
+    state.textscript_vm.state = TextScriptExecutionState::Ended;
+
+    draw_frame(&mut scene, &mut state, ctx).unwrap();
+    let (width, height, pixels) = graphics::read_framebuffer(ctx).unwrap();
+
+    let reference = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/game/frame_capture_ref.png");
+    if std::env::var_os("DOUKUTSU_BLESS_REFERENCE").is_some() {
+        let image = RgbaImage::from_raw(width as u32, height as u32, pixels).unwrap();
+        image.save_with_format(&reference, image::ImageFormat::Png).unwrap();
+        return;
+    }
+
+    let file = fs::File::open(&reference)
+        .unwrap_or_else(|err| panic!("{:?}: {}, create it with DOUKUTSU_BLESS_REFERENCE=1", reference, err));
+    let diff = compare_with_png(width, height, &pixels, file, 1).unwrap();
+    assert!(diff.is_match(), "frame differs from {:?}: {:?}", reference, diff);
+}
@@ -6,11 +6,11 @@ use crate::game::shared_game_state::SharedGameState;
 use crate::game::weapon::{Weapon, WeaponLevel, WeaponType};
 use crate::game::weapon::bullet::BulletManager;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
 /// (id, amount)
 pub struct Item(pub u16, pub u16);
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Inventory {
     pub current_item: u16,
     pub current_weapon: u16,
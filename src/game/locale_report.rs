@@ -0,0 +1,57 @@
+//! Prints how much of the reference locale each locale of the active data root translates, used by the
+//! `--locale-coverage` launch option.
+
+use crate::framework::error::GameError::ResourceLoadError;
+use crate::framework::error::GameResult;
+use crate::game::init_headless;
+use crate::i18n::FALLBACK_LOCALE;
+
+/// Exit code returned when the report was printed.
+pub const EXIT_OK: i32 = 0;
+/// Exit code returned when the locales couldn't be loaded.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Prints the coverage and the missing strings of every locale and returns the process exit code.
+pub fn run() -> i32 {
+    match report() {
+        Ok(()) => EXIT_OK,
+        Err(err) => {
+            log::error!("Failed to report locale coverage: {}", err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn report() -> GameResult {
+    let (_context, state) = init_headless(false, false)?;
+    let locales = &state.constants.locales;
+
+    let reference = locales
+        .iter()
+        .find(|locale| locale.code == FALLBACK_LOCALE)
+        .ok_or_else(|| ResourceLoadError(format!("Reference locale {} not found.", FALLBACK_LOCALE)))?;
+
+    let mut sorted: Vec<_> = locales.iter().filter(|locale| locale.code != reference.code).collect();
+    sorted.sort_by(|a, b| a.code.cmp(&b.code));
+
+    for locale in sorted {
+        let (translated, total) = locale.coverage(reference);
+        let percent = if total == 0 { 100.0 } else { translated as f32 * 100.0 / total as f32 };
+
+        println!(
+            "{} ({}): {}/{} strings ({:.1}%), falls back to {}",
+            locale.code,
+            locale.name,
+            translated,
+            total,
+            percent,
+            locale.fallback_chain(locales).join(" -> ")
+        );
+
+        for key in locale.missing_keys(reference) {
+            println!("  missing: {}", key);
+        }
+    }
+
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::io;
 use std::io::{BufRead, BufReader, Read};
 use std::sync::Arc;
 
-use byteorder::{ReadBytesExt, LE};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 
 use crate::common::{Color, Rect};
 use crate::framework::context::Context;
@@ -66,6 +66,30 @@ impl Map {
         Ok(Map { width, height, tiles, attrib, tile_size: TileSize::Tile16x16 })
     }
 
+    /// Writes the map in the format read by [Map::load_pxm].
+    pub fn save_pxm<W: io::Write>(&self, mut map_data: W, mut attrib_data: W) -> GameResult {
+        if self.tiles.len() != self.width as usize * self.height as usize {
+            return Err(GameError::InvalidValue(format!(
+                "Map size {}x{} doesn't match the tile count {}.",
+                self.width,
+                self.height,
+                self.tiles.len()
+            )));
+        }
+
+        map_data.write_all(b"PXM")?;
+        map_data.write_u8(SUPPORTED_PXM_VERSIONS[0])?;
+        map_data.write_u16::<LE>(self.width)?;
+        map_data.write_u16::<LE>(self.height)?;
+        map_data.write_all(&self.tiles)?;
+        map_data.flush()?;
+
+        attrib_data.write_all(&self.attrib)?;
+        attrib_data.flush()?;
+
+        Ok(())
+    }
+
     pub fn load_pxpack<R: io::Read>(
         mut map_data: R,
         roots: &Vec<String>,
@@ -508,6 +532,33 @@ impl NPCData {
 
         Ok(npcs)
     }
+
+    /// Writes the NPCs in the format read by [NPCData::load_from]. The layer field is only written
+    /// (as version 0x10) if any NPC uses a layer other than 0, to keep the files compatible with other tools.
+    pub fn save_to<W: io::Write>(npcs: &[NPCData], mut data: W) -> GameResult {
+        let has_layers = npcs.iter().any(|npc| npc.layer != 0);
+
+        data.write_all(b"PXE")?;
+        data.write_u8(if has_layers { 0x10 } else { 0 })?;
+        data.write_u32::<LE>(npcs.len() as u32)?;
+
+        for npc in npcs.iter() {
+            data.write_i16::<LE>(npc.x)?;
+            data.write_i16::<LE>(npc.y)?;
+            data.write_u16::<LE>(npc.flag_num)?;
+            data.write_u16::<LE>(npc.event_num)?;
+            data.write_u16::<LE>(npc.npc_type)?;
+            data.write_u16::<LE>(npc.flags)?;
+
+            if has_layers {
+                data.write_u8(npc.layer)?;
+            }
+        }
+
+        data.flush()?;
+
+        Ok(())
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -613,3 +664,31 @@ impl WaterParams {
         self.entries.get(&tile).unwrap_or(&DEFAULT_ENTRY)
     }
 }
+
+#[test]
+fn test_map_serialization() {
+    let mut attrib = [0u8; 0x100];
+    attrib[1] = 0x41;
+    let map = Map { width: 3, height: 2, tiles: vec![0, 1, 2, 3, 4, 5], attrib, tile_size: TileSize::Tile16x16 };
+
+    let (mut map_data, mut attrib_data) = (Vec::new(), Vec::new());
+    map.save_pxm(&mut map_data, &mut attrib_data).unwrap();
+    let loaded = Map::load_pxm(&map_data[..], &attrib_data[..]).unwrap();
+
+    assert_eq!((loaded.width, loaded.height), (3, 2));
+    assert_eq!(loaded.tiles, map.tiles);
+    assert_eq!(loaded.attrib, map.attrib);
+
+    let npcs = vec![
+        NPCData { id: 170, x: 10, y: -2, flag_num: 300, event_num: 200, npc_type: 46, flags: 0x2100, layer: 0 },
+        NPCData { id: 171, x: 4, y: 8, flag_num: 0, event_num: 0, npc_type: 1, flags: 0, layer: 2 },
+    ];
+
+    for npcs in [&npcs[..1], &npcs[..]] {
+        let mut data = Vec::new();
+        NPCData::save_to(npcs, &mut data).unwrap();
+        let loaded = NPCData::load_from(&data[..]).unwrap();
+
+        assert_eq!(format!("{:?}", loaded), format!("{:?}", npcs));
+    }
+}
@@ -0,0 +1,198 @@
+//! Extracts the messages of the scripts of the active data root into a JSON catalog for translation and
+//! rebuilds the scripts from a translated catalog, used by the `--extract-tsc-messages` and
+//! `--inject-tsc-messages` launch options.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::Path;
+
+use crate::framework::context::Context;
+use crate::framework::error::GameError::InvalidValue;
+use crate::framework::error::GameResult;
+use crate::framework::filesystem;
+use crate::game::init_headless;
+use crate::game::scripting::tsc::encryption::{decrypt_tsc, encrypt_tsc};
+use crate::game::scripting::tsc::messages::{extract_messages, inject_messages};
+use crate::game::shared_game_state::SharedGameState;
+
+/// Exit code returned when the catalog or the scripts were written.
+pub const EXIT_OK: i32 = 0;
+/// Exit code returned when the game data or the catalog couldn't be processed.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Message catalog of every script of a data root.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MessageCatalog {
+    /// Encoding the rebuilt scripts are written in, the one of the game data by default.
+    pub encoding: String,
+    pub messages: Vec<CatalogEntry>,
+}
+
+/// A message, keyed by the script file, the event and its position in that event.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CatalogEntry {
+    pub file: String,
+    pub event: u16,
+    pub index: usize,
+    /// Line of the message in the script, for reference only.
+    pub line: usize,
+    pub source: String,
+    /// Translated text, an empty translation keeps the source text.
+    #[serde(default)]
+    pub translation: String,
+}
+
+/// Writes the message catalog of the active data root to given file and returns the process exit code.
+pub fn run_extract(output: &Path) -> i32 {
+    match extract(output) {
+        Ok(count) => {
+            log::info!("Extracted {} messages to {:?}.", count, output);
+            EXIT_OK
+        }
+        Err(err) => {
+            log::error!("Failed to extract script messages: {}", err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Rebuilds the scripts of the active data root with the translations of given catalog into the output
+/// directory and returns the process exit code.
+pub fn run_inject(catalog: &Path, output_dir: &Path) -> i32 {
+    match inject(catalog, output_dir) {
+        Ok(count) => {
+            log::info!("Wrote {} scripts to {:?}.", count, output_dir);
+            EXIT_OK
+        }
+        Err(err) => {
+            log::error!("Failed to inject script messages: {}", err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+pub fn extract(output: &Path) -> GameResult<usize> {
+    let (mut context, state) = init_headless(false, false)?;
+    let ctx = &mut *context;
+    let encoding: &'static encoding_rs::Encoding = state.constants.textscript.encoding.into();
+
+    let mut catalog = MessageCatalog { encoding: encoding.name().to_owned(), messages: Vec::new() };
+
+    for path in script_paths(&state, ctx) {
+        let data = read_script(&state, ctx, &path)?;
+
+        catalog.messages.extend(extract_messages(&data, encoding).into_iter().map(|message| CatalogEntry {
+            file: path.clone(),
+            event: message.event,
+            index: message.index,
+            line: message.line,
+            source: message.text,
+            translation: String::new(),
+        }));
+    }
+
+    serde_json::to_writer_pretty(BufWriter::new(fs::File::create(output)?), &catalog)?;
+
+    Ok(catalog.messages.len())
+}
+
+pub fn inject(catalog: &Path, output_dir: &Path) -> GameResult<usize> {
+    let catalog: MessageCatalog = serde_json::from_reader(BufReader::new(fs::File::open(catalog)?))?;
+    let out_encoding = encoding_rs::Encoding::for_label(catalog.encoding.as_bytes())
+        .ok_or_else(|| InvalidValue(format!("Unknown encoding: {}", catalog.encoding)))?;
+
+    let mut translations: HashMap<&str, Vec<&CatalogEntry>> = HashMap::new();
+    for entry in catalog.messages.iter().filter(|entry| !entry.translation.is_empty()) {
+        translations.entry(entry.file.as_str()).or_default().push(entry);
+    }
+
+    let (mut context, state) = init_headless(false, false)?;
+    let ctx = &mut *context;
+    let encoding: &'static encoding_rs::Encoding = state.constants.textscript.encoding.into();
+
+    let mut count = 0;
+    for path in script_paths(&state, ctx) {
+        let Some(entries) = translations.remove(path.as_str()) else {
+            continue;
+        };
+
+        let data = read_script(&state, ctx, &path)?;
+        let sources: HashMap<_, _> = extract_messages(&data, encoding)
+            .into_iter()
+            .map(|message| ((message.event, message.index), message.text))
+            .collect();
+
+        let mut texts = HashMap::new();
+        for entry in entries {
+            match sources.get(&(entry.event, entry.index)) {
+                Some(source) if *source == entry.source => {}
+                Some(_) => log::warn!(
+                    "{}: source of message {} in event {} has changed since extraction.",
+                    path,
+                    entry.index,
+                    entry.event
+                ),
+                None => {
+                    log::warn!("{}: message {} in event {} no longer exists.", path, entry.index, entry.event);
+                    continue;
+                }
+            }
+
+            texts.insert((entry.event, entry.index), entry.translation.clone());
+        }
+
+        // untranslated messages are kept, but have to be re-encoded if the catalog changes the encoding
+        if out_encoding != encoding {
+            for (key, source) in sources {
+                texts.entry(key).or_insert(source);
+            }
+        }
+
+        let mut data =
+            inject_messages(&data, out_encoding, &texts).map_err(|err| InvalidValue(format!("{}: {}", path, err)))?;
+
+        if state.constants.textscript.encrypted {
+            encrypt_tsc(&mut data);
+        }
+
+        let out_path = output_dir.join(&path);
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(out_path, data)?;
+        count += 1;
+    }
+
+    for file in translations.keys() {
+        log::warn!("{}: script not found in the game data, skipping.", file);
+    }
+
+    Ok(count)
+}
+
+/// Returns the paths of the scripts with messages, relative to the data root.
+fn script_paths(state: &SharedGameState, ctx: &mut Context) -> Vec<String> {
+    let mut paths = vec!["Head.tsc".to_owned(), "ArmsItem.tsc".to_owned(), "StageSelect.tsc".to_owned()];
+
+    for stage in state.stages.iter() {
+        let path = ["Stage/", &stage.map, ".tsc"].join("");
+        if !stage.map.is_empty() && !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    paths.retain(|path| filesystem::exists_find(ctx, &state.constants.base_paths, path));
+    paths
+}
+
+fn read_script(state: &SharedGameState, ctx: &mut Context, path: &str) -> GameResult<Vec<u8>> {
+    let mut data = Vec::new();
+    filesystem::open_find(ctx, &state.constants.base_paths, path)?.read_to_end(&mut data)?;
+
+    if state.constants.textscript.encrypted {
+        decrypt_tsc(&mut data);
+    }
+
+    Ok(data)
+}
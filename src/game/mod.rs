@@ -2,6 +2,7 @@ use std::backtrace::Backtrace;
 use std::cell::UnsafeCell;
 use std::panic::PanicInfo;
 use std::path::PathBuf;
+use std::pin::Pin;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
@@ -11,7 +12,7 @@ use lazy_static::lazy_static;
 use log::LevelFilter as LogLevel;
 use scripting::tsc::text_script::ScriptMode;
 
-use crate::framework::backend::WindowParams;
+use crate::framework::backend::{init_backend, WindowParams};
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::framework::graphics;
@@ -23,16 +24,27 @@ use crate::game::shared_game_state::{Fps, SharedGameState, TimingMode, WindowMod
 use crate::graphics::texture_set::{G_MAG, I_MAG};
 use crate::scene::loading_scene::LoadingScene;
 use crate::scene::Scene;
+use crate::sound::offline;
+use crate::sound::offline::RenderOptions;
+use crate::sound::InterpolationMode;
 
 pub mod caret;
 pub mod filesystem_container;
+pub mod flag_watcher;
 pub mod frame;
+pub mod frame_capture;
 pub mod inventory;
+pub mod locale_report;
 pub mod map;
+pub mod message_catalog;
 pub mod npc;
 pub mod physics;
 pub mod player;
 pub mod profile;
+pub mod replay_library;
+pub mod replay_verifier;
+pub mod save_state;
+pub mod script_linter;
 pub mod scripting;
 pub mod settings;
 pub mod shared_game_state;
@@ -63,6 +75,104 @@ pub struct LaunchOptions {
     ///
     /// Possible values: error, warn, info, debug, trace.
     pub log_level: LogLevel,
+
+    #[arg(long, value_name = "FILE")]
+    /// Play back a replay file without a window, print the results and exit.
+    ///
+    /// The exit code is nonzero if the replay desynced or couldn't be played back.
+    pub verify_replay: Option<PathBuf>,
+
+    #[arg(long, value_name = "TICK", requires = "verify_replay")]
+    /// Take a save state at given tick of `--verify-replay` and restore it right away.
+    ///
+    /// The replay fails to verify if the restored state differs from the snapshot or the playback desyncs afterwards.
+    pub verify_save_state: Option<usize>,
+
+    #[arg(long)]
+    /// Check the scripts of every stage for errors, print the diagnostics as JSON lines and exit.
+    ///
+    /// The exit code is nonzero if any errors were found.
+    pub lint_tsc: bool,
+
+    #[arg(long, requires = "lint_tsc")]
+    /// Also warn about flags that are set by a script but never read by one.
+    ///
+    /// NPCs and the engine read flags as well, so the warnings need to be checked by hand.
+    pub lint_unread_flags: bool,
+
+    #[arg(long)]
+    /// Print how many strings of the English locale every other locale translates, along with the missing ones,
+    /// and exit.
+    pub locale_coverage: bool,
+
+    #[arg(long, value_name = "FILE")]
+    /// Write the messages of every script to a JSON catalog for translation and exit.
+    pub extract_tsc_messages: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE", requires = "tsc_output")]
+    /// Rebuild the scripts with the translations of a JSON catalog into the `--tsc-output` directory and exit.
+    ///
+    /// Only the message text is replaced, the commands of the scripts are kept as they are.
+    pub inject_tsc_messages: Option<PathBuf>,
+
+    #[arg(long, value_name = "DIR")]
+    /// Output directory of `--inject-tsc-messages`.
+    pub tsc_output: Option<PathBuf>,
+
+    #[arg(long, value_name = "PATH")]
+    /// Record gameplay frames from the start, once per game tick.
+    ///
+    /// A `.gif` or `.png` path is encoded as an animation, any other path is a directory for numbered PNG files.
+    pub record_frames: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE", requires = "record_frames")]
+    /// Render a replay file without a window into the `--record-frames` path and exit.
+    ///
+    /// Uses the software renderer, so the output is the same on every machine.
+    pub render_replay: Option<PathBuf>,
+
+    #[arg(long, value_name = "FILE")]
+    /// Capture the audio of `--verify-replay` or `--render-replay` and write it to a WAV file.
+    ///
+    /// The audio is mixed once per game tick, so the output is the same on every machine.
+    pub replay_audio: Option<PathBuf>,
+
+    #[arg(long, value_name = "TICK")]
+    /// Start `--verify-replay` or `--render-replay` at given tick.
+    ///
+    /// The playback restores the last keyframe of the replay before that tick and runs the rest of the ticks
+    /// without drawing them.
+    pub replay_start: Option<usize>,
+
+    #[arg(long, value_name = "FILE", requires = "audio_output")]
+    /// Render an Organya song to the `--audio-output` WAV file and exit.
+    pub render_org: Option<PathBuf>,
+
+    #[arg(long, requires = "audio_output")]
+    /// Render every PixTone sound effect of the game data to WAV files in the `--audio-output` directory and exit.
+    pub render_pixtone: bool,
+
+    #[arg(long, value_name = "PATH")]
+    /// Output path of `--render-org` and `--render-pixtone`.
+    pub audio_output: Option<PathBuf>,
+
+    #[arg(long, value_name = "HZ", default_value_t = RenderOptions::default().sample_rate)]
+    /// Sample rate of the rendered audio.
+    pub sample_rate: u32,
+
+    #[arg(long, value_name = "MODE", default_value_t = RenderOptions::default().interpolation)]
+    /// Interpolation of the rendered Organya instruments.
+    ///
+    /// Possible values: nearest, linear, cosine, cubic, polyphase.
+    pub interpolation: InterpolationMode,
+
+    #[arg(long, value_name = "COUNT", default_value_t = RenderOptions::default().loops)]
+    /// Number of times the looped part of a rendered song is repeated.
+    pub loop_count: usize,
+
+    #[arg(long, value_name = "SECONDS", default_value_t = RenderOptions::default().fadeout)]
+    /// Length of the fade-out at the end of a rendered song.
+    pub fadeout: f32,
 }
 
 impl Default for LaunchOptions {
@@ -73,6 +183,25 @@ impl Default for LaunchOptions {
             window_width: None,
             window_fullscreen: cfg!(target_os = "android"),
             log_level: if cfg!(debug_assertions) { LogLevel::Debug } else { LogLevel::Info },
+            verify_replay: None,
+            verify_save_state: None,
+            lint_tsc: false,
+            lint_unread_flags: false,
+            locale_coverage: false,
+            extract_tsc_messages: None,
+            inject_tsc_messages: None,
+            tsc_output: None,
+            record_frames: None,
+            render_replay: None,
+            replay_audio: None,
+            replay_start: None,
+            render_org: None,
+            render_pixtone: false,
+            audio_output: None,
+            sample_rate: RenderOptions::default().sample_rate,
+            interpolation: RenderOptions::default().interpolation,
+            loop_count: RenderOptions::default().loops,
+            fadeout: RenderOptions::default().fadeout,
         }
     }
 }
@@ -87,6 +216,15 @@ impl LaunchOptions {
         }
     }
 
+    pub fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            sample_rate: self.sample_rate,
+            interpolation: self.interpolation,
+            loops: self.loop_count,
+            fadeout: self.fadeout,
+        }
+    }
+
     pub fn window(&self) -> WindowParams {
         let default = WindowParams::default();
 
@@ -174,11 +312,13 @@ impl Game {
 
                     for _ in 0..self.loops {
                         scene.tick(state_ref, ctx)?;
+                        state_ref.sound_manager.end_tick(state_ref.settings.timing_mode.get_tps());
                     }
                     self.fps.tick_count = self.fps.tick_count.saturating_add(self.loops as u32);
                 }
                 TimingMode::FrameSynchronized => {
                     scene.tick(state_ref, ctx)?;
+                    state_ref.sound_manager.end_tick(state_ref.settings.timing_mode.get_tps());
                 }
             }
         }
@@ -223,12 +363,13 @@ impl Game {
         }
 
         if ctx.headless {
-            self.loops = 0;
             state_ref.frame_time = 1.0;
-            return Ok(());
-        }
 
-        if state_ref.settings.timing_mode != TimingMode::FrameSynchronized {
+            if !ctx.software_rendering {
+                self.loops = 0;
+                return Ok(());
+            }
+        } else if state_ref.settings.timing_mode != TimingMode::FrameSynchronized {
             let mut elapsed = self.start_time.elapsed().as_nanos();
 
             // Even with the non-monotonic Instant mitigation at the start of the event loop, there's still a chance of it not working.
@@ -245,6 +386,10 @@ impl Game {
             G_MAG = if state_ref.settings.subpixel_coords { state_ref.scale } else { 1.0 };
             I_MAG = state_ref.scale;
         }
+        let ticks = match state_ref.settings.timing_mode {
+            TimingMode::FrameSynchronized => 1,
+            _ => self.loops,
+        };
         self.loops = 0;
 
         graphics::prepare_draw(ctx)?;
@@ -252,6 +397,14 @@ impl Game {
 
         if let Some(scene) = &mut self.scene {
             scene.draw(state_ref, ctx)?;
+
+            if let Some(recorder) = &mut state_ref.frame_recorder {
+                if let Err(err) = recorder.capture(ctx, ticks) {
+                    log::error!("Failed to capture a frame, recording stopped: {}", err);
+                    state_ref.stop_recording();
+                }
+            }
+
             if state_ref.settings.touch_controls && state_ref.settings.display_touch_controls {
                 state_ref.touch_controls.draw(
                     state_ref.canvas_size,
@@ -354,10 +507,84 @@ fn panic_hook(info: &PanicInfo<'_>) {
     }
 }
 
+/// Creates a windowless context with the game data loaded, for tools that run the engine from command line.
+///
+/// With `software_rendering`, textures are loaded and frames are drawn on the CPU, so they can be read back
+/// with [graphics::read_framebuffer]. With `audio_capture`, the audio is mixed into memory once per tick,
+/// see [crate::sound::capture].
+pub(crate) fn init_headless(
+    software_rendering: bool,
+    audio_capture: bool,
+) -> GameResult<(Pin<Box<Context>>, SharedGameState)> {
+    let mut context = Box::pin(Context::new());
+    let ctx = &mut *context;
+    ctx.headless = true;
+    ctx.software_rendering = software_rendering;
+    ctx.audio_capture = audio_capture;
+
+    let mut fs_container = FilesystemContainer::new();
+    fs_container.mount_fs(ctx)?;
+
+    let backend = init_backend(true, ctx.window)?;
+    let event_loop = backend.create_event_loop(ctx)?;
+    ctx.renderer = Some(event_loop.new_renderer(&mut *ctx as *mut Context)?);
+
+    let mut state = SharedGameState::new(ctx)?;
+    state.fs_container = Some(fs_container);
+
+    ctx.screen_size = (640.0, 480.0);
+    state.handle_resize(ctx)?;
+    state.reload_resources(ctx)?;
+
+    Ok((context, state))
+}
+
 pub fn init(mut options: LaunchOptions) -> GameResult {
     let _ = init_logger(&options);
     std::panic::set_hook(Box::new(panic_hook));
 
+    if let Some(path) = &options.verify_replay {
+        std::process::exit(replay_verifier::run(
+            path,
+            options.replay_audio.as_deref(),
+            options.verify_save_state,
+            options.replay_start,
+        ));
+    }
+
+    if options.lint_tsc {
+        std::process::exit(script_linter::run(options.lint_unread_flags));
+    }
+
+    if options.locale_coverage {
+        std::process::exit(locale_report::run());
+    }
+
+    if let Some(output) = &options.extract_tsc_messages {
+        std::process::exit(message_catalog::run_extract(output));
+    }
+
+    if let (Some(catalog), Some(output)) = (&options.inject_tsc_messages, &options.tsc_output) {
+        std::process::exit(message_catalog::run_inject(catalog, output));
+    }
+
+    if let (Some(replay), Some(output)) = (&options.render_replay, &options.record_frames) {
+        std::process::exit(frame_capture::render_replay(
+            replay,
+            output,
+            options.replay_audio.as_deref(),
+            options.replay_start,
+        ));
+    }
+
+    if let (Some(song), Some(output)) = (&options.render_org, &options.audio_output) {
+        std::process::exit(offline::run_organya(song, output, options.render_options()));
+    }
+
+    if let (true, Some(output)) = (options.render_pixtone, &options.audio_output) {
+        std::process::exit(offline::run_pixtone(output, options.render_options()));
+    }
+
     let mut context = Box::pin(Context::new());
 
     let mut fs_container = FilesystemContainer::new();
@@ -381,9 +608,15 @@ pub fn init(mut options: LaunchOptions) -> GameResult {
 
     context.window = options.window();
 
+    if let Some(path) = &options.record_frames {
+        game.state.get_mut().start_recording(path)?;
+    }
+
     game.state.get_mut().next_scene = Some(Box::new(LoadingScene::new()));
     log::info!("Starting main loop...");
-    context.run(game.as_mut().get_mut())?;
+    let result = context.run(game.as_mut().get_mut());
+    game.state.get_mut().stop_recording();
+    result?;
 
     Ok(())
 }
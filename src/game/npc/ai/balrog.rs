@@ -48,7 +48,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num = 1;
                     self.action_counter = 0;
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
                 }
@@ -116,7 +116,7 @@ impl BorrowedNPC<'_> {
 
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.x, self.y);
 
                     if self.action_counter2 == 0 {
                         self.action_num = 3;
@@ -147,7 +147,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num = 2;
                     self.damage = 0;
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
                 }
@@ -271,7 +271,7 @@ impl BorrowedNPC<'_> {
                 if self.y < 0 {
                     self.npc_type = 0;
                     self.spritesheet_id = 20; // NpcSym
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
                 }
@@ -306,7 +306,7 @@ impl BorrowedNPC<'_> {
                         let _ = npc_list.spawn(0x100, npc.clone());
                     }
 
-                    state.sound_manager.play_sfx(72);
+                    state.sound_manager.play_sfx_at(72, self.x, self.y);
                 }
 
                 self.target_x = 1; // ???
@@ -392,7 +392,7 @@ impl BorrowedNPC<'_> {
 
                     self.anim_num += 1;
                     if self.anim_num == 10 || self.anim_num == 11 {
-                        state.sound_manager.play_sfx(23);
+                        state.sound_manager.play_sfx_at(23, self.x, self.y);
                     }
 
                     if self.anim_num > 12 {
@@ -411,7 +411,7 @@ impl BorrowedNPC<'_> {
                     self.action_num = 71;
                     self.action_counter = 64;
                     self.anim_num = 13;
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
 
                 self.action_counter -= 1;
@@ -469,7 +469,7 @@ impl BorrowedNPC<'_> {
                 let y = clamp(self.y / (0x2000), 0, stage.map.height as i32) as usize;
 
                 if y <= 34 && stage.change_tile(x, y, 0) {
-                    state.sound_manager.play_sfx(44);
+                    state.sound_manager.play_sfx_at(44, self.x, self.y);
                     state.super_quake_counter = 10;
                     state.super_quake_rumble_counter = 10;
 
@@ -558,8 +558,8 @@ impl BorrowedNPC<'_> {
                         let _ = npc_list.spawn(0x100, npc.clone());
                     }
 
-                    state.sound_manager.play_sfx(12);
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(12, self.x, self.y);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
 
@@ -576,7 +576,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num = 2;
                     self.action_counter = 0;
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
                 }
@@ -690,7 +690,7 @@ impl BorrowedNPC<'_> {
                     npc.y = self.y + 0x800;
 
                     let _ = npc_list.spawn(0x100, npc);
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.x, self.y);
 
                     if self.vel_x2 == 0 {
                         self.action_num = 3;
@@ -729,7 +729,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num += 1;
                     if self.anim_num > 5 {
                         self.anim_num = 4;
-                        state.sound_manager.play_sfx(47);
+                        state.sound_manager.play_sfx_at(47, self.x, self.y);
                     }
                 }
 
@@ -755,8 +755,8 @@ impl BorrowedNPC<'_> {
                     self.anim_num = 2;
                     self.damage = 0;
 
-                    state.sound_manager.play_sfx(25);
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(25, self.x, self.y);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
 
@@ -855,7 +855,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num += 1;
 
                     if self.anim_num == 2 || self.anim_num == 4 {
-                        state.sound_manager.play_sfx(23);
+                        state.sound_manager.play_sfx_at(23, self.x, self.y);
                     }
 
                     if self.anim_num > 4 {
@@ -895,7 +895,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num = 8;
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
 
                 let pi = self.get_closest_player_idx_mut(&players);
@@ -955,7 +955,7 @@ impl BorrowedNPC<'_> {
             20 | 21 => {
                 if self.action_num == 20 {
                     let player = &mut players[self.vel_y2 as usize];
-                    state.sound_manager.play_sfx(25);
+                    state.sound_manager.play_sfx_at(25, self.x, self.y);
                     player.cond.set_hidden(false);
 
                     self.direction = self.direction.opposite();
@@ -1034,7 +1034,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num += 1;
 
                     if self.anim_num == 2 || self.anim_num == 4 {
-                        state.sound_manager.play_sfx(23);
+                        state.sound_manager.play_sfx_at(23, self.x, self.y);
                     }
 
                     if self.anim_num > 4 {
@@ -1098,7 +1098,7 @@ impl BorrowedNPC<'_> {
 
                 self.action_counter += 1;
                 if self.action_counter <= 29 && self.action_counter % 6 == 1 {
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.x, self.y);
 
                     let mut npc = NPC::create(170, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -1114,7 +1114,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num = 8;
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
                 if self.action_counter > 7
                     && self.x - 0x1800 < player.x
@@ -1164,7 +1164,7 @@ impl BorrowedNPC<'_> {
                 let player = &mut players[self.target_x as usize];
 
                 if self.action_num == 20 {
-                    state.sound_manager.play_sfx(25);
+                    state.sound_manager.play_sfx_at(25, self.x, self.y);
                     player.cond.set_hidden(false);
 
                     if self.direction != Direction::Left {
@@ -1218,7 +1218,7 @@ impl BorrowedNPC<'_> {
         if (self.direction == Direction::Left && self.flags.hit_left_wall())
             || (self.direction == Direction::Right && self.flags.hit_right_wall())
         {
-            state.sound_manager.play_sfx(44);
+            state.sound_manager.play_sfx_at(44, self.x, self.y);
             npc_list.create_death_smoke(self.x, self.y, 0, 3, state, &self.rng);
             self.vanish(state);
 
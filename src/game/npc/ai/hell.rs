@@ -44,7 +44,7 @@ impl NPC {
                 if self.action_num == 10 {
                     self.action_num = 11;
                     self.action_counter = 0;
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
 
                 self.action_counter += 1;
@@ -201,7 +201,7 @@ impl NPC {
                     self.vel_x /= 2;
                     self.anim_num = 2;
                     self.action_num = 30;
-                    state.sound_manager.play_sfx(30);
+                    state.sound_manager.play_sfx_at(30, self.x, self.y);
                 }
             }
             30 => {
@@ -451,7 +451,7 @@ impl NPC {
                 self.display_bounds.left = 0x1800;
                 self.vel_y = -0x200;
                 self.vel_x = 0x100 * self.direction.opposite().vector_x();
-                state.sound_manager.play_sfx(50);
+                state.sound_manager.play_sfx_at(50, self.x, self.y);
             }
             1 if self.flags.hit_bottom_wall() => {
                 self.action_num = 2;
@@ -618,7 +618,7 @@ impl NPC {
                     self.action_counter = 0;
                     self.action_num = 12;
                     self.anim_num = 3;
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.x, self.y);
                 }
             }
             12 => {
@@ -659,7 +659,7 @@ impl NPC {
                 self.anim_num = 0;
                 self.vel_y = -0x200;
                 self.vel_x = 0x40 * self.direction.opposite().vector_x();
-                state.sound_manager.play_sfx(54);
+                state.sound_manager.play_sfx_at(54, self.x, self.y);
             }
             1 if self.flags.hit_bottom_wall() => {
                 self.action_num = 2;
@@ -729,7 +729,7 @@ impl NPC {
                 self.y += self.vel_y;
 
                 if self.flags.hit_bottom_wall() {
-                    state.sound_manager.play_sfx(12);
+                    state.sound_manager.play_sfx_at(12, self.x, self.y);
                     npc_list.create_death_smoke(self.x, self.y, 0, 3, state, &self.rng);
                     self.cond.set_alive(false);
                 }
@@ -753,7 +753,7 @@ impl NPC {
             self.action_counter = 0;
             self.npc_flags.set_shootable(false);
             self.npc_flags.set_invulnerable(true);
-            state.sound_manager.play_sfx(22);
+            state.sound_manager.play_sfx_at(22, self.x, self.y);
         }
 
         match self.action_num {
@@ -813,7 +813,7 @@ impl NPC {
 
                         state.quake_counter = 10;
                         state.quake_rumble_counter = 10;
-                        state.sound_manager.play_sfx(26);
+                        state.sound_manager.play_sfx_at(26, self.x, self.y);
                         npc_list.create_death_smoke(self.x, self.y, 0x6000, 40 as usize, state, &self.rng);
 
                         let x = (self.x / (state.tile_size.as_int() * 0x100)) as usize;
@@ -60,7 +60,7 @@ impl BorrowedNPC<'_> {
                     self.action_num = 3;
                     self.anim_num = 2;
                     self.vel_y = -0x5FF;
-                    state.sound_manager.play_sfx(30);
+                    state.sound_manager.play_sfx_at(30, self.x, self.y);
 
                     let player = self.get_closest_player_mut(players);
                     if self.x <= player.x {
@@ -95,7 +95,7 @@ impl BorrowedNPC<'_> {
                     self.anim_num = 2;
                 } else {
                     if self.action_counter % 4 == 1 {
-                        state.sound_manager.play_sfx(109);
+                        state.sound_manager.play_sfx_at(109, self.x, self.y);
                     }
                     if self.flags.hit_bottom_wall() {
                         self.vel_y = -0x200;
@@ -112,7 +112,7 @@ impl BorrowedNPC<'_> {
                         npc.vel_y = (angle.sin() * -1536.0) as i32;
 
                         let _ = npc_list.spawn(0x100, npc);
-                        state.sound_manager.play_sfx(39);
+                        state.sound_manager.play_sfx_at(39, self.x, self.y);
                     }
                     self.anim_counter += 1;
                     if self.anim_counter > 0 {
@@ -131,7 +131,7 @@ impl BorrowedNPC<'_> {
                     self.action_counter = 0;
                     self.anim_num = 0;
                     self.action_num = 1;
-                    state.sound_manager.play_sfx(23);
+                    state.sound_manager.play_sfx_at(23, self.x, self.y);
                 }
             }
             _ => (),
@@ -265,7 +265,7 @@ impl BorrowedNPC<'_> {
                         self.vel_y = -0x5FF;
                         self.action_num = 20;
                         if !player.cond.hidden() {
-                            state.sound_manager.play_sfx(30);
+                            state.sound_manager.play_sfx_at(30, self.x, self.y);
                         }
                     }
                 } else {
@@ -273,7 +273,7 @@ impl BorrowedNPC<'_> {
                     self.vel_y = -0x5FF;
                     self.action_num = 20;
                     if !player.cond.hidden() {
-                        state.sound_manager.play_sfx(30);
+                        state.sound_manager.play_sfx_at(30, self.x, self.y);
                     }
                 }
             }
@@ -307,7 +307,7 @@ impl BorrowedNPC<'_> {
                     self.vel_x = 0;
 
                     if !player.cond.hidden() {
-                        state.sound_manager.play_sfx(23);
+                        state.sound_manager.play_sfx_at(23, self.x, self.y);
                     }
                 }
             }
@@ -353,7 +353,7 @@ impl BorrowedNPC<'_> {
                     Direction::Right => self.vel_x = -0x100,
                     _ => (),
                 };
-                state.sound_manager.play_sfx(53);
+                state.sound_manager.play_sfx_at(53, self.x, self.y);
             }
             1 if self.flags.hit_bottom_wall() => {
                 self.action_num = 2;
@@ -449,7 +449,7 @@ impl BorrowedNPC<'_> {
                     let _ = npc_list.spawn(0x100, npc);
 
                     if !player.cond.hidden() {
-                        state.sound_manager.play_sfx(39);
+                        state.sound_manager.play_sfx_at(39, self.x, self.y);
                     }
                     self.action_num = 1;
                     self.action_counter = self.rng.range(70..150) as u16;
@@ -561,8 +561,8 @@ impl BorrowedNPC<'_> {
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
 
-                    state.sound_manager.play_sfx(26);
-                    state.sound_manager.play_sfx(72);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
+                    state.sound_manager.play_sfx_at(72, self.x, self.y);
                 }
 
                 let player = self.get_closest_player_mut(players);
@@ -709,7 +709,7 @@ impl BorrowedNPC<'_> {
             0 | 1 => {
                 if self.action_num == 0 {
                     npc_list.kill_npcs_by_type(161, true, state, self);
-                    state.sound_manager.play_sfx(72);
+                    state.sound_manager.play_sfx_at(72, self.x, self.y);
 
                     let mut npc = NPC::create(4, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -774,7 +774,7 @@ impl BorrowedNPC<'_> {
                     let _ = npc_list.spawn(0x100, npc);
                 }
                 if self.action_counter3 % 4 == 2 {
-                    state.sound_manager.play_sfx(21);
+                    state.sound_manager.play_sfx_at(21, self.x, self.y);
                 }
             }
             3 => {
@@ -887,7 +887,7 @@ impl BorrowedNPC<'_> {
                     self.action_counter = 0;
                     self.vel_y = -0x400;
                     self.vel_x = 0x100;
-                    state.sound_manager.play_sfx(25);
+                    state.sound_manager.play_sfx_at(25, self.x, self.y);
                 }
 
                 self.vel_y += 0x10;
@@ -895,7 +895,7 @@ impl BorrowedNPC<'_> {
                 self.y += self.vel_y;
 
                 if self.action_counter != 0 && self.flags.hit_bottom_wall() {
-                    state.sound_manager.play_sfx(35);
+                    state.sound_manager.play_sfx_at(35, self.x, self.y);
                     state.quake_counter = 40;
                     state.quake_rumble_counter = 40;
                     self.action_num = 0;
@@ -1044,7 +1044,7 @@ impl BorrowedNPC<'_> {
                     if self.action_counter > 3 {
                         self.action_counter3 += 1;
                         if self.action_counter3 == 3 {
-                            state.sound_manager.play_sfx(30);
+                            state.sound_manager.play_sfx_at(30, self.x, self.y);
                             self.action_counter3 = 0;
                             self.action_num = 25;
                             self.action_counter = 0;
@@ -1057,7 +1057,7 @@ impl BorrowedNPC<'_> {
                                 self.vel_x = 0x80;
                             }
                         } else {
-                            state.sound_manager.play_sfx(30);
+                            state.sound_manager.play_sfx_at(30, self.x, self.y);
                             self.action_num = 20;
                             self.anim_num = 2;
                             self.vel_y = -0x200;
@@ -1072,7 +1072,7 @@ impl BorrowedNPC<'_> {
                 20 => {
                     self.action_counter += 1;
                     if self.flags.hit_bottom_wall() {
-                        state.sound_manager.play_sfx(23);
+                        state.sound_manager.play_sfx_at(23, self.x, self.y);
                         self.anim_num = 1;
                         self.action_num = 30;
                         self.action_counter = 0;
@@ -1092,7 +1092,7 @@ impl BorrowedNPC<'_> {
                         npc.vel_y = (angle.sin() * -1536.0) as i32;
 
                         let _ = npc_list.spawn(0x100, npc);
-                        state.sound_manager.play_sfx(39);
+                        state.sound_manager.play_sfx_at(39, self.x, self.y);
 
                         self.anim_num = 3;
                         state.npc_curly_counter = self.rng.range(80..100) as u16;
@@ -1104,7 +1104,7 @@ impl BorrowedNPC<'_> {
                     }
 
                     if self.flags.hit_bottom_wall() {
-                        state.sound_manager.play_sfx(23);
+                        state.sound_manager.play_sfx_at(23, self.x, self.y);
                         self.anim_num = 1;
                         self.action_num = 30;
                         self.action_counter = 0;
@@ -1182,7 +1182,7 @@ impl BorrowedNPC<'_> {
                 if hit {
                     self.action_num = 2;
                     self.action_counter2 += 1;
-                    state.sound_manager.play_sfx(31);
+                    state.sound_manager.play_sfx_at(31, self.x, self.y);
                 }
             }
             2 => {
@@ -1309,7 +1309,7 @@ impl BorrowedNPC<'_> {
                 }
                 let _ = npc_list.spawn(0x100, npc);
 
-                state.sound_manager.play_sfx(39);
+                state.sound_manager.play_sfx_at(39, self.x, self.y);
                 self.action_num = 0;
                 self.anim_num = 0;
 
@@ -1416,7 +1416,7 @@ impl BorrowedNPC<'_> {
 
                 self.action_counter += 1;
                 if (self.action_counter & 7) == 0 {
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
                 state.quake_counter = 20;
                 state.quake_rumble_counter = 20;
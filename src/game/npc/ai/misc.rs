@@ -737,7 +737,7 @@ impl BorrowedNPC<'_> {
                 let player = self.get_closest_player_mut(players);
 
                 if (player.x - self.x).abs() < 0x1000 && player.y < self.y + 0x1000 && player.y > self.y - 0x2000 {
-                    state.sound_manager.play_sfx(43);
+                    state.sound_manager.play_sfx_at(43, self.x, self.y);
                     self.action_num = 1;
                 }
             }
@@ -1076,7 +1076,7 @@ impl BorrowedNPC<'_> {
 
                         state.quake_counter = 10;
                         state.quake_rumble_counter = 10;
-                        state.sound_manager.play_sfx(26);
+                        state.sound_manager.play_sfx_at(26, self.x, self.y);
                     }
 
                     self.action_num = 1;
@@ -1125,7 +1125,7 @@ impl BorrowedNPC<'_> {
         if self.life < 990 {
             npc_list.create_death_smoke(self.x, self.y, self.display_bounds.right as usize, 8, state, &self.rng);
             self.cond.set_alive(false);
-            state.sound_manager.play_sfx(70);
+            state.sound_manager.play_sfx_at(70, self.x, self.y);
 
             match self.direction {
                 // hidden heart
@@ -1185,7 +1185,7 @@ impl BorrowedNPC<'_> {
                 self.action_counter += 1;
                 if self.action_counter > 10 {
                     self.action_num = 2;
-                    state.sound_manager.play_sfx(101);
+                    state.sound_manager.play_sfx_at(101, self.x, self.y);
                 }
             }
             2 => {
@@ -1241,7 +1241,7 @@ impl BorrowedNPC<'_> {
             11 => {
                 self.action_counter += 1;
                 if self.action_counter % 10 == 6 {
-                    state.sound_manager.play_sfx(107);
+                    state.sound_manager.play_sfx_at(107, self.x, self.y);
                 }
 
                 if self.flags.hit_left_wall() {
@@ -1251,7 +1251,7 @@ impl BorrowedNPC<'_> {
 
                     state.quake_counter = 10;
                     state.quake_rumble_counter = 10;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
 
                     let mut npc = NPC::create(4, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -1293,7 +1293,7 @@ impl BorrowedNPC<'_> {
             21 => {
                 self.action_counter += 1;
                 if self.action_counter % 10 == 6 {
-                    state.sound_manager.play_sfx(107);
+                    state.sound_manager.play_sfx_at(107, self.x, self.y);
                 }
 
                 if self.flags.hit_right_wall() {
@@ -1303,7 +1303,7 @@ impl BorrowedNPC<'_> {
 
                     state.quake_counter = 10;
                     state.quake_rumble_counter = 10;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
 
                     let mut npc = NPC::create(4, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -1386,7 +1386,7 @@ impl BorrowedNPC<'_> {
             11 => {
                 self.action_counter += 1;
                 if self.action_counter % 10 == 6 {
-                    state.sound_manager.play_sfx(107);
+                    state.sound_manager.play_sfx_at(107, self.x, self.y);
                 }
 
                 if self.flags.hit_top_wall() {
@@ -1396,7 +1396,7 @@ impl BorrowedNPC<'_> {
 
                     state.quake_counter = 10;
                     state.quake_rumble_counter = 10;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
 
                     let mut npc = NPC::create(4, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -1438,7 +1438,7 @@ impl BorrowedNPC<'_> {
             21 => {
                 self.action_counter += 1;
                 if self.action_counter % 10 == 6 {
-                    state.sound_manager.play_sfx(107);
+                    state.sound_manager.play_sfx_at(107, self.x, self.y);
                 }
 
                 if self.flags.hit_bottom_wall() {
@@ -1448,7 +1448,7 @@ impl BorrowedNPC<'_> {
 
                     state.quake_counter = 10;
                     state.quake_rumble_counter = 10;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
 
                     let mut npc = NPC::create(4, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -1551,7 +1551,7 @@ impl BorrowedNPC<'_> {
                     let _ = npc_list.spawn(0x100, npc.clone());
                 }
 
-                state.sound_manager.play_sfx(72);
+                state.sound_manager.play_sfx_at(72, self.x, self.y);
                 self.cond.set_alive(false);
             }
             20 => self.animate(10, 0, 1),
@@ -1831,7 +1831,7 @@ impl BorrowedNPC<'_> {
                         npc.y = self.y + self.rng.range(-8..8) * 0x200;
                         let _ = npc_list.spawn(0x100, npc.clone());
 
-                        state.sound_manager.play_sfx(12);
+                        state.sound_manager.play_sfx_at(12, self.x, self.y);
                     }
                 }
             }
@@ -1955,7 +1955,7 @@ impl BorrowedNPC<'_> {
 
                         state.quake_counter = 10;
                         state.quake_rumble_counter = 10;
-                        state.sound_manager.play_sfx(26);
+                        state.sound_manager.play_sfx_at(26, self.x, self.y);
                     }
 
                     self.action_num = 20;
@@ -1998,7 +1998,7 @@ impl BorrowedNPC<'_> {
             self.create_xp_drop_custom(self.x, self.y, self.flag_num, state, npc_list);
             npc_list.create_death_smoke(self.x, self.y, self.display_bounds.right as usize, 8, state, &self.rng);
 
-            state.sound_manager.play_sfx(25);
+            state.sound_manager.play_sfx_at(25, self.x, self.y);
         }
 
         self.anim_rect = state.constants.npc.n253_experience_capsule[self.anim_num as usize];
@@ -2149,7 +2149,7 @@ impl BorrowedNPC<'_> {
                     self.vel_y = -0x200;
                     self.action_num = 110;
                     self.npc_flags.set_ignore_solidity(true);
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                     state.quake_counter = 10;
                     state.quake_rumble_counter = 10;
 
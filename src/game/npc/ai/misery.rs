@@ -48,7 +48,7 @@ impl BorrowedNPC<'_> {
                 if (self.x - self.target_x).abs() < 0x600 && (self.y - self.target_y).abs() < 0x600 {
                     self.action_num = 2;
                     self.anim_num = 2;
-                    state.sound_manager.play_sfx(21);
+                    state.sound_manager.play_sfx_at(21, self.x, self.y);
 
                     if let Some(npc) = npc_list.get_npc(self.action_counter2 as usize) {
                         let mut npc = npc.borrow_mut_unmanaged();
@@ -93,7 +93,7 @@ impl BorrowedNPC<'_> {
                     self.target_x = self.x;
                     self.target_y = self.y;
 
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
 
                 self.x = self.target_x + self.rng.range(-1..1) as i32 * 0x200;
@@ -129,7 +129,7 @@ impl BorrowedNPC<'_> {
                 self.clamp_fall_speed();
 
                 if self.flags.hit_bottom_wall() {
-                    state.sound_manager.play_sfx(23);
+                    state.sound_manager.play_sfx_at(23, self.x, self.y);
                     self.vel_y = 0;
                     self.action_num = 14;
                     self.npc_flags.set_ignore_solidity(true);
@@ -145,7 +145,7 @@ impl BorrowedNPC<'_> {
 
                 self.action_counter += 1;
                 if self.action_counter == 30 {
-                    state.sound_manager.play_sfx(21);
+                    state.sound_manager.play_sfx_at(21, self.x, self.y);
                     let mut npc = NPC::create(66, &state.npc_table);
                     npc.cond.set_alive(true);
                     npc.x = self.x;
@@ -188,7 +188,7 @@ impl BorrowedNPC<'_> {
 
                 self.action_counter += 1;
                 if self.action_counter == 30 {
-                    state.sound_manager.play_sfx(101);
+                    state.sound_manager.play_sfx_at(101, self.x, self.y);
                     flash.set_blink();
                     self.action_num = 27;
                     self.anim_num = 7;
@@ -271,7 +271,7 @@ impl BorrowedNPC<'_> {
 
                 self.action_counter += 1;
                 if self.action_counter == 30 {
-                    state.sound_manager.play_sfx(21);
+                    state.sound_manager.play_sfx_at(21, self.x, self.y);
 
                     let mut npc = NPC::create(66, &state.npc_table);
                     npc.x = self.x;
@@ -318,7 +318,7 @@ impl BorrowedNPC<'_> {
                     self.action_num = 27;
                     self.anim_num = 7;
 
-                    state.sound_manager.play_sfx(101);
+                    state.sound_manager.play_sfx_at(101, self.x, self.y);
                     flash.set_blink();
                 }
             }
@@ -360,7 +360,7 @@ impl BorrowedNPC<'_> {
 
                 self.action_counter += 1;
                 if self.action_counter == 30 || self.action_counter == 40 || self.action_counter == 50 {
-                    state.sound_manager.play_sfx(33);
+                    state.sound_manager.play_sfx_at(33, self.x, self.y);
 
                     let mut npc = NPC::create(11, &state.npc_table);
                     npc.x = self.x + 0x1000;
@@ -529,7 +529,7 @@ impl BorrowedNPC<'_> {
                     npc.vel_y = (angle.sin() * -2048.0) as i32;
 
                     let _ = npc_list.spawn(0x100, npc);
-                    state.sound_manager.play_sfx(34);
+                    state.sound_manager.play_sfx_at(34, self.x, self.y);
                 }
 
                 if self.action_counter > 30 {
@@ -573,7 +573,7 @@ impl BorrowedNPC<'_> {
                     self.target_x = self.rng.range(9..31) * 0x2000;
                     self.target_y = self.rng.range(5..7) * 0x2000;
 
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
 
                 self.action_counter += 1;
@@ -647,7 +647,7 @@ impl BorrowedNPC<'_> {
                     npc.y = self.y + 0x800;
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(34);
+                    state.sound_manager.play_sfx_at(34, self.x, self.y);
                 }
 
                 if self.action_counter > 72 {
@@ -792,7 +792,7 @@ impl BorrowedNPC<'_> {
 
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(101);
+                    state.sound_manager.play_sfx_at(101, self.x, self.y);
                     self.cond.set_alive(false);
 
                     return Ok(());
@@ -925,7 +925,7 @@ impl BorrowedNPC<'_> {
                 if self.action_num == 0 {
                     self.action_num = 1;
                     self.y -= 0x1000;
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
 
                 self.action_counter += 1;
@@ -1000,7 +1000,7 @@ impl BorrowedNPC<'_> {
                     self.vel_x = 0;
                     self.vel_y = 0;
 
-                    state.sound_manager.play_sfx(103);
+                    state.sound_manager.play_sfx_at(103, self.x, self.y);
 
                     let player = self.get_closest_player_ref(&players);
                     self.direction = if self.x > player.x { Direction::Left } else { Direction::Right };
@@ -1011,7 +1011,7 @@ impl BorrowedNPC<'_> {
                 self.anim_num = if self.action_counter & 2 != 0 { 4 } else { 5 };
 
                 if self.action_counter % 6 == 1 {
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.x, self.y);
 
                     let mut npc = NPC::create(self.action_counter3, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -1063,7 +1063,7 @@ impl BorrowedNPC<'_> {
                     self.vel_y = 0;
                     self.direction = if self.x > player.x { Direction::Left } else { Direction::Right };
 
-                    state.sound_manager.play_sfx(103);
+                    state.sound_manager.play_sfx_at(103, self.x, self.y);
                 }
 
                 self.action_counter += 1;
@@ -1071,7 +1071,7 @@ impl BorrowedNPC<'_> {
 
                 let period = if player.equip.has_booster_2_0() { 10 } else { 24 };
                 if self.action_counter % period == 1 {
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.x, self.y);
 
                     let mut npc = NPC::create(301, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -1181,7 +1181,7 @@ impl BorrowedNPC<'_> {
                     self.action_counter2 += 1;
                     self.action_num = if self.action_counter2 > 4 { 12 } else { 10 };
 
-                    state.sound_manager.play_sfx(30);
+                    state.sound_manager.play_sfx_at(30, self.x, self.y);
                     self.vel_x = self.direction.vector_x() * 0x200;
                     self.vel_y = -0x600;
                 }
@@ -418,7 +418,7 @@ impl NPC {
                         npc.y = self.y + self.rng.range(-8..8) * 0x200;
                         let _ = npc_list.spawn(0x100, npc.clone());
 
-                        state.sound_manager.play_sfx(12);
+                        state.sound_manager.play_sfx_at(12, self.x, self.y);
                     }
                 }
 
@@ -432,7 +432,7 @@ impl NPC {
                     state.create_caret(self.x + 0x1400, self.y + 0x1000, CaretType::Exhaust, Direction::Bottom);
                 }
                 if self.action_counter % 4 == 1 {
-                    state.sound_manager.play_sfx(34);
+                    state.sound_manager.play_sfx_at(34, self.x, self.y);
                 }
 
                 let player = self.get_closest_player_ref(&players);
@@ -451,7 +451,7 @@ impl NPC {
                         npc.y = self.y + self.rng.range(-8..8) * 0x200;
                         let _ = npc_list.spawn(0x100, npc.clone());
 
-                        state.sound_manager.play_sfx(12);
+                        state.sound_manager.play_sfx_at(12, self.x, self.y);
                     }
                 }
             }
@@ -468,7 +468,7 @@ impl NPC {
                     }
 
                     if self.action_counter % 16 == 1 {
-                        state.sound_manager.play_sfx(34);
+                        state.sound_manager.play_sfx_at(34, self.x, self.y);
                     }
                 }
 
@@ -762,7 +762,7 @@ impl NPC {
                 }
 
                 if self.action_counter % 10 == 3 {
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.x, self.y);
 
                     let mut npc = NPC::create(237, &state.npc_table);
                     npc.cond.set_alive(true);
@@ -819,7 +819,7 @@ impl NPC {
                     state.create_caret(self.x, self.y, CaretType::Bubble, Direction::Left);
                 }
 
-                state.sound_manager.play_sfx(21);
+                state.sound_manager.play_sfx_at(21, self.x, self.y);
                 self.cond.set_alive(false);
                 return Ok(());
             }
@@ -938,7 +938,7 @@ impl NPC {
 
         self.action_counter += 1;
         if self.action_counter % 5 == 0 {
-            state.sound_manager.play_sfx(110);
+            state.sound_manager.play_sfx_at(110, self.x, self.y);
         }
 
         self.anim_num += 1;
@@ -1020,7 +1020,7 @@ impl NPC {
 
                         let _ = npc_list.spawn(0x100, npc);
 
-                        state.sound_manager.play_sfx(39);
+                        state.sound_manager.play_sfx_at(39, self.x, self.y);
                     }
                 }
 
@@ -57,7 +57,7 @@ impl BorrowedNPC<'_> {
             }
             6 | 7 => {
                 if self.action_num == 6 {
-                    state.sound_manager.play_sfx(50);
+                    state.sound_manager.play_sfx_at(50, self.x, self.y);
                     self.action_counter = 0;
                     self.action_num = 7;
                     self.anim_num = 7;
@@ -70,7 +70,7 @@ impl BorrowedNPC<'_> {
             }
             8 | 9 => {
                 if self.action_num == 8 {
-                    state.sound_manager.play_sfx(50);
+                    state.sound_manager.play_sfx_at(50, self.x, self.y);
                     self.action_counter = 0;
                     self.action_num = 9;
                     self.anim_num = 7;
@@ -289,7 +289,7 @@ impl BorrowedNPC<'_> {
                     self.anim_counter = 0;
                     self.x += 0xc00;
                     self.target_x = self.x;
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
 
                 self.action_counter += 1;
@@ -304,7 +304,7 @@ impl BorrowedNPC<'_> {
                     self.action_num = 4;
                     self.action_counter = 0;
                     self.anim_num = 1;
-                    state.sound_manager.play_sfx(23);
+                    state.sound_manager.play_sfx_at(23, self.x, self.y);
                 }
             }
             _ => (),
@@ -346,7 +346,7 @@ impl BorrowedNPC<'_> {
                     self.y -= 0x800;
                     self.action_counter3 = self.life;
 
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
 
                 self.action_counter += 1;
@@ -499,7 +499,7 @@ impl BorrowedNPC<'_> {
                 self.animate(1, 4, 7);
 
                 if self.action_counter % 5 == 1 {
-                    state.sound_manager.play_sfx(109);
+                    state.sound_manager.play_sfx_at(109, self.x, self.y);
                 }
             }
             40 | 41 => {
@@ -124,7 +124,7 @@ impl BossNPC {
                     self.parts[0].display_bounds.top = 64 * 0x200;
                     self.parts[0].display_bounds.bottom = 24 * 0x200;
 
-                    state.sound_manager.play_sfx(25);
+                    state.sound_manager.play_sfx_at(25, self.parts[0].x, self.parts[0].y);
                 }
             }
             104 => {
@@ -178,7 +178,7 @@ impl BossNPC {
 
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.parts[0].x, self.parts[0].y);
                 }
             }
             110 | 111 => {
@@ -245,7 +245,7 @@ impl BossNPC {
 
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.parts[0].x, self.parts[0].y);
 
                     if self.parts[0].vel_x2 == 0 || (self.parts[0].life as i32) < self.parts[0].target_x - 90 {
                         self.parts[0].action_num = 114;
@@ -303,7 +303,7 @@ impl BossNPC {
                     self.parts[0].display_bounds.top = 0x8000;
                     self.parts[0].display_bounds.bottom = 0x3000;
 
-                    state.sound_manager.play_sfx(25);
+                    state.sound_manager.play_sfx_at(25, self.parts[0].x, self.parts[0].y);
                 }
             }
             124 => {
@@ -356,7 +356,7 @@ impl BossNPC {
                         self.parts[0].direction = Direction::Left;
                     }
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.parts[0].x, self.parts[0].y);
                     state.quake_counter = 60;
                     state.quake_rumble_counter = 60;
                 }
@@ -371,7 +371,7 @@ impl BossNPC {
                     self.parts[1].cond.set_alive(false);
                     self.parts[2].cond.set_alive(false);
 
-                    state.sound_manager.play_sfx(72);
+                    state.sound_manager.play_sfx_at(72, self.parts[0].x, self.parts[0].y);
 
                     let mut npc = NPC::create(4, &state.npc_table);
                     for _ in 0..8 {
@@ -457,7 +457,7 @@ impl BossNPC {
                 if self.parts[0].y < 0 {
                     self.parts[0].cond.set_alive(false);
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.parts[0].x, self.parts[0].y);
                     state.quake_counter = 30;
                     state.quake_rumble_counter = 30;
                 }
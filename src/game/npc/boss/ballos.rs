@@ -66,7 +66,7 @@ impl NPC {
             0 | 1 => {
                 if self.action_num == 0 {
                     self.action_num = 1;
-                    state.sound_manager.play_sfx(44);
+                    state.sound_manager.play_sfx_at(44, self.x, self.y);
                     self.vel_x = self.direction.vector_x() * 0x400;
                 }
                 self.animate(1, 0, 2);
@@ -81,7 +81,7 @@ impl NPC {
                     npc.vel_y = -0x400;
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(12);
+                    state.sound_manager.play_sfx_at(12, self.x, self.y);
                 }
             }
             _ => (),
@@ -110,7 +110,7 @@ impl NPC {
                     self.target_x = self.x;
                     self.target_y = self.y;
                     self.y = self.get_closest_player_mut(players).y;
-                    state.sound_manager.play_sfx(103);
+                    state.sound_manager.play_sfx_at(103, self.x, self.y);
                 }
                 self.action_counter += 1;
                 self.anim_num = 1 - (self.action_counter & 2) / 2;
@@ -317,7 +317,7 @@ impl NPC {
                     self.damage = 10;
 
                     self.face_player(player);
-                    state.sound_manager.play_sfx(25);
+                    state.sound_manager.play_sfx_at(25, self.x, self.y);
                 }
 
                 self.vel_x = if self.direction == Direction::Left { -0x800 } else { 0x800 };
@@ -333,7 +333,7 @@ impl NPC {
                     self.damage = 3;
                     state.super_quake_counter = 10;
                     state.super_quake_rumble_counter = 10;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
 
                 if self.action_counter2 < 4 && player.x > self.x - 0x2000 && player.x < self.x + 0x2000 {
@@ -358,7 +358,7 @@ impl NPC {
                     self.vel_x = 0;
                     self.damage = 10;
                     self.direction = if self.action_num == 221 { Direction::Left } else { Direction::Right };
-                    state.sound_manager.play_sfx(25);
+                    state.sound_manager.play_sfx_at(25, self.x, self.y);
                 }
                 self.vel_y = if self.action_num == 221 { -0x800 } else { 0x800 };
 
@@ -406,7 +406,7 @@ impl NPC {
 
                     state.super_quake_counter = 10;
                     state.super_quake_rumble_counter = 10;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
 
                 if self.action_counter2 < 4 && player.y > self.y - 0x2000 && player.y < self.y + 0x2000 {
@@ -535,7 +535,7 @@ impl NPC {
                     // I think Pixel meant for the smoke radius to be 16 pixels (0x2000) instead of 16 units,
                     // because as it is, this just gets divided by 0x200 units/px and becomes 0
                     npc_list.create_death_smoke(self.x, self.y, 16, 16, state, &self.rng);
-                    state.sound_manager.play_sfx(72);
+                    state.sound_manager.play_sfx_at(72, self.x, self.y);
                 }
                 self.vel_y += 0x20;
                 self.clamp_fall_speed();
@@ -575,7 +575,7 @@ impl NPC {
                     self.action_num = 1005;
                     self.action_counter = 0;
                     flash.set_blink();
-                    state.sound_manager.play_sfx(29);
+                    state.sound_manager.play_sfx_at(29, self.x, self.y);
                 }
                 self.anim_num = self.anim_num.max(8);
                 self.animate(1, 8, 9);
@@ -690,7 +690,7 @@ impl NPC {
                     self.action_num = 22;
                     self.npc_flags.set_shootable(false);
                     npc_list.create_death_smoke(self.x, self.y, 0x2000, 32, state, &self.rng);
-                    state.sound_manager.play_sfx(71);
+                    state.sound_manager.play_sfx_at(71, self.x, self.y);
                 }
 
                 boss.parts[0].action_counter2 = 4;
@@ -771,7 +771,7 @@ impl NPC {
                     self.npc_flags.set_ignore_solidity(false);
                     self.npc_flags.set_shootable(false);
                     npc_list.create_death_smoke(self.x, self.y, 0x2000, 32, state, &self.rng);
-                    state.sound_manager.play_sfx(71);
+                    state.sound_manager.play_sfx_at(71, self.x, self.y);
                 }
 
                 if self.flags.hit_left_wall() {
@@ -786,7 +786,7 @@ impl NPC {
                     }
 
                     self.vel_y = -0x800;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
 
                 self.vel_y += 0x20;
@@ -813,7 +813,7 @@ impl NPC {
                     }
                 } else {
                     npc_list.create_death_smoke(self.x, self.y, 0x2000, 32, state, &self.rng);
-                    state.sound_manager.play_sfx(71);
+                    state.sound_manager.play_sfx_at(71, self.x, self.y);
                     self.vanish(state);
                     return Ok(());
                 }
@@ -836,7 +836,7 @@ impl NPC {
                     npc.y = self.y + 0x1800;
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
                 (Direction::Up, 268) => {
                     let mut npc = NPC::create(4, &state.npc_table);
@@ -851,7 +851,7 @@ impl NPC {
                     npc.y = self.y - 0x1000;
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
                 (Direction::Right, 396) => {
                     let mut npc = NPC::create(4, &state.npc_table);
@@ -872,7 +872,7 @@ impl NPC {
                     npc.y = self.y - 0x1800;
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
                 (Direction::Bottom, 12) => {
                     let mut npc = NPC::create(4, &state.npc_table);
@@ -887,7 +887,7 @@ impl NPC {
                     npc.y = self.y - 0x1000;
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
                 }
                 _ => (),
             }
@@ -980,7 +980,7 @@ impl NPC {
                     self.action_num = 110;
                     self.vel_y = -0x200;
                     self.npc_flags.set_ignore_solidity(true);
-                    state.sound_manager.play_sfx(12);
+                    state.sound_manager.play_sfx_at(12, self.x, self.y);
                     state.quake_counter = 10;
                     state.quake_rumble_counter = 10;
 
@@ -1393,8 +1393,8 @@ impl NPC {
                     self.action_counter = 0;
                     state.quake_counter = 20;
                     state.quake_rumble_counter = 20;
-                    state.sound_manager.play_sfx(26);
-                    state.sound_manager.play_sfx(12);
+                    state.sound_manager.play_sfx_at(26, self.x, self.y);
+                    state.sound_manager.play_sfx_at(12, self.x, self.y);
 
                     self.x += 0x2000 * self.direction.vector_x();
 
@@ -1514,7 +1514,7 @@ impl BossNPC {
                     self.parts[0].action_counter = 0;
                     state.super_quake_counter = 30;
                     state.super_quake_rumble_counter = 30;
-                    state.sound_manager.play_sfx(44);
+                    state.sound_manager.play_sfx_at(44, self.parts[0].x, self.parts[0].y);
 
                     if player.y > self.parts[0].y + 0x6000
                         && player.x < self.parts[0].x + 0x3000
@@ -1597,8 +1597,8 @@ impl BossNPC {
                     self.parts[0].action_counter = 0;
                     state.super_quake_counter = 30;
                     state.super_quake_rumble_counter = 30;
-                    state.sound_manager.play_sfx(26);
-                    state.sound_manager.play_sfx(44);
+                    state.sound_manager.play_sfx_at(26, self.parts[0].x, self.parts[0].y);
+                    state.sound_manager.play_sfx_at(44, self.parts[0].x, self.parts[0].y);
 
                     if player.y > self.parts[0].y + 0x7000 {
                         player.damage(16, state, npc_list);
@@ -1654,7 +1654,7 @@ impl BossNPC {
                     self.parts[0].action_counter = 0;
                     state.super_quake_counter = 30;
                     state.super_quake_rumble_counter = 30;
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.parts[0].x, self.parts[0].y);
 
                     for _ in 0..16 {
                         let mut npc = NPC::create(4, &state.npc_table);
@@ -1829,7 +1829,7 @@ impl BossNPC {
                 }
 
                 if (self.parts[0].action_counter / 3 % 2) > 0 {
-                    state.sound_manager.play_sfx(26);
+                    state.sound_manager.play_sfx_at(26, self.parts[0].x, self.parts[0].y);
                 }
 
                 if self.parts[0].action_counter > 540 {
@@ -1843,7 +1843,7 @@ impl BossNPC {
                     self.parts[0].anim_counter = 0;
                     state.super_quake_counter = 30;
                     state.super_quake_rumble_counter = 30;
-                    state.sound_manager.play_sfx(35);
+                    state.sound_manager.play_sfx_at(35, self.parts[0].x, self.parts[0].y);
 
                     self.parts[1].action_num = 102;
                     self.parts[2].action_num = 102;
@@ -1888,7 +1888,7 @@ impl BossNPC {
                 self.parts[0].action_counter += 1;
 
                 if self.parts[0].action_counter % 12 == 0 {
-                    state.sound_manager.play_sfx(44);
+                    state.sound_manager.play_sfx_at(44, self.parts[0].x, self.parts[0].y);
                 }
 
                 npc_list.create_death_smoke(
@@ -1904,7 +1904,7 @@ impl BossNPC {
                     self.parts[0].action_counter = 0;
                     self.parts[0].action_num = 1002;
                     flash.set_cross(self.parts[0].x, self.parts[0].y);
-                    state.sound_manager.play_sfx(35);
+                    state.sound_manager.play_sfx_at(35, self.parts[0].x, self.parts[0].y);
                 }
             }
             1002 => {
@@ -1959,7 +1959,7 @@ impl BossNPC {
                     npc.direction = Direction::Up;
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, self.parts[0].x, self.parts[0].y);
 
                     for _ in 0..4 {
                         let mut npc = NPC::create(4, &state.npc_table);
@@ -209,7 +209,7 @@ impl BossNPC {
                 if self.parts[0].action_counter > 400 {
                     self.parts[0].action_counter2 += 1;
 
-                    state.sound_manager.play_sfx(115);
+                    state.sound_manager.play_sfx_at(115, self.parts[0].x, self.parts[0].y);
 
                     if self.parts[0].action_counter2 < 4 {
                         self.parts[0].action_num = 210;
@@ -316,7 +316,7 @@ impl BossNPC {
                 }
 
                 if [300, 350, 400].contains(&self.parts[0].action_counter) {
-                    state.sound_manager.play_sfx(101);
+                    state.sound_manager.play_sfx_at(101, self.parts[0].x, self.parts[0].y);
                     let mut npc = NPC::create(218, &state.npc_table);
                     let angle = f64::atan2(
                         (self.parts[0].y - players[idx].y) as f64,
@@ -413,7 +413,7 @@ impl BossNPC {
         if flag {
             state.quake_counter = 20;
             state.quake_rumble_counter = 20;
-            state.sound_manager.play_sfx(26);
+            state.sound_manager.play_sfx_at(26, self.parts[0].x, self.parts[0].y);
 
             self.parts[1].action_num = 100;
             self.parts[2].action_num = 100;
@@ -654,7 +654,7 @@ impl BossNPC {
 
                     let _ = npc_list.spawn(0x100, npc);
 
-                    state.sound_manager.play_sfx(39);
+                    state.sound_manager.play_sfx_at(39, part.x, part.y);
                 }
             }
             140 => {
@@ -91,7 +91,7 @@ impl NPC {
         }
 
         if self.action_counter3 % 4 == 1 {
-            state.sound_manager.play_sfx(46);
+            state.sound_manager.play_sfx_at(46, self.x, self.y);
         }
 
         Ok(())
@@ -271,7 +271,7 @@ impl BossNPC {
                 if self.parts[0].direction == Direction::Left {
                     self.parts[0].action_counter += 1;
                     if [300, 310, 320].contains(&self.parts[0].action_counter) {
-                        state.sound_manager.play_sfx(39);
+                        state.sound_manager.play_sfx_at(39, self.parts[0].x, self.parts[0].y);
 
                         let mut npc = NPC::create(198, &state.npc_table);
                         npc.cond.set_alive(true);
@@ -0,0 +1,570 @@
+//! NPC types declared by mods in `custom_npcs.json` files of the data roots.
+//!
+//! Positions, velocities and accelerations use the same fixed point units as the engine (0x200 is a pixel),
+//! while sprite rects, bounds, offsets and ranges are in pixels.
+
+use std::f64::consts::PI;
+
+use crate::common::{Direction, Rect};
+use crate::framework::error::GameResult;
+use crate::game::npc::list::NPCList;
+use crate::game::npc::{NPCContext, NPCTable, NPC};
+use crate::game::shared_game_state::SharedGameState;
+use crate::util::rng::RNG;
+
+/// Number of NPC types with built-in behavior, custom NPCs must use types from this one upwards.
+pub const VANILLA_NPC_TYPES: u16 = 371;
+
+/// Value of [NPC::shock] right after the NPC got hit.
+const HIT_SHOCK: u16 = 16;
+
+#[derive(serde::Deserialize)]
+pub(super) struct CustomNPCTable {
+    pub npcs: Vec<CustomNPCDefinition>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct CustomNPCDefinition {
+    pub id: u16,
+    /// Raw [NPCFlag](crate::game::npc::NPCFlag) bits, same as in `npc.tbl`.
+    #[serde(default)]
+    pub flags: u16,
+    #[serde(default)]
+    pub life: u16,
+    #[serde(default = "default_spritesheet")]
+    pub spritesheet: u8,
+    #[serde(default)]
+    pub hurt_sound: u8,
+    #[serde(default)]
+    pub death_sound: u8,
+    /// Amount of smoke created on death, from 1 to 3.
+    #[serde(default = "default_size")]
+    pub size: u8,
+    #[serde(default)]
+    pub experience: u32,
+    #[serde(default)]
+    pub damage: u32,
+    /// Left, top, right and bottom extents of the sprite from the center of the NPC.
+    pub display_bounds: [u8; 4],
+    /// Left, top, right and bottom extents of the hitbox from the center of the NPC.
+    pub hit_bounds: [u8; 4],
+    #[serde(default)]
+    pub animation: Animation,
+    /// Applied in order every tick.
+    #[serde(default)]
+    pub movement: Vec<Movement>,
+    #[serde(default)]
+    pub shooting: Vec<ShootingPattern>,
+    #[serde(default)]
+    pub hit: HitBehavior,
+    #[serde(default)]
+    pub death: DeathBehavior,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default)]
+pub struct Animation {
+    /// Frames facing left as left, top, right and bottom coordinates in the spritesheet.
+    pub left: Vec<[u16; 4]>,
+    /// Frames facing right, the frames facing left are used if empty.
+    pub right: Vec<[u16; 4]>,
+    /// Number of ticks each frame is shown for.
+    pub frame_time: u16,
+    /// Index of the frame shown while the NPC is hurt.
+    pub hurt_frame: Option<usize>,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Animation { left: Vec::new(), right: Vec::new(), frame_time: 4, hurt_frame: None }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Movement {
+    /// Falls down until the maximum speed is reached.
+    Gravity {
+        #[serde(default = "default_gravity")]
+        acceleration: i32,
+        #[serde(default = "default_max_fall_speed")]
+        max_speed: i32,
+    },
+    /// Faces the closest player and accelerates towards them, slows down if they are out of range.
+    WalkTowardPlayer {
+        #[serde(default = "default_walk_speed")]
+        speed: i32,
+        #[serde(default = "default_walk_acceleration")]
+        acceleration: i32,
+        /// Horizontal distance in pixels, zero follows the player everywhere.
+        #[serde(default)]
+        range: i32,
+    },
+    /// Jumps after standing on the ground for a while and bounces off walls.
+    Bounce {
+        speed: i32,
+        #[serde(default)]
+        interval: u16,
+        #[serde(default)]
+        sound: Option<u8>,
+    },
+    /// Floats up and down around the spawn height.
+    Hover {
+        #[serde(default = "default_hover_acceleration")]
+        acceleration: i32,
+        #[serde(default = "default_hover_speed")]
+        max_speed: i32,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Aim {
+    Player,
+    Forward,
+    Up,
+    Down,
+}
+
+/// Volley of projectiles spawned periodically as existing NPC types, like the ones shot by vanilla enemies.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ShootingPattern {
+    pub npc_type: u16,
+    /// Number of ticks between volleys.
+    #[serde(default = "default_interval")]
+    pub interval: u16,
+    #[serde(default = "default_count")]
+    pub count: u16,
+    /// Angle between the projectiles of a volley in degrees.
+    #[serde(default)]
+    pub spread: f64,
+    #[serde(default = "default_projectile_speed")]
+    pub speed: i32,
+    #[serde(default = "default_aim")]
+    pub aim: Aim,
+    /// Distance to the closest player in pixels, zero shoots regardless of the distance.
+    #[serde(default)]
+    pub range: i32,
+    /// Offset from the center in pixels, mirrored when facing right.
+    #[serde(default)]
+    pub offset: [i32; 2],
+    #[serde(default)]
+    pub sound: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct HitBehavior {
+    /// Horizontal speed the NPC is pushed away from the player with when it gets hit.
+    pub knockback: i32,
+    /// Moves at half speed while hurt.
+    pub stun: bool,
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct DeathBehavior {
+    /// NPCs spawned where the NPC died, in addition to the usual drops.
+    pub spawn: Vec<DeathSpawn>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DeathSpawn {
+    pub npc_type: u16,
+    #[serde(default = "default_count")]
+    pub count: u16,
+    /// Speed the NPCs are thrown in random directions with.
+    #[serde(default)]
+    pub speed: i32,
+}
+
+fn default_spritesheet() -> u8 {
+    21
+}
+
+fn default_size() -> u8 {
+    1
+}
+
+fn default_gravity() -> i32 {
+    0x40
+}
+
+fn default_max_fall_speed() -> i32 {
+    0x5ff
+}
+
+fn default_walk_speed() -> i32 {
+    0x200
+}
+
+fn default_walk_acceleration() -> i32 {
+    0x20
+}
+
+fn default_hover_acceleration() -> i32 {
+    0x10
+}
+
+fn default_hover_speed() -> i32 {
+    0x100
+}
+
+fn default_interval() -> u16 {
+    100
+}
+
+fn default_count() -> u16 {
+    1
+}
+
+fn default_projectile_speed() -> i32 {
+    0x400
+}
+
+fn default_aim() -> Aim {
+    Aim::Player
+}
+
+impl CustomNPCDefinition {
+    /// Spawns the NPCs thrown out on death, called after the usual smoke and drops were created.
+    pub fn spawn_death_npcs(&self, npc: &NPC, npc_table: &NPCTable, npc_list: &NPCList) {
+        for spawn in self.death.spawn.iter() {
+            for _ in 0..spawn.count {
+                let angle = npc.rng.range(0..255) as f64 * PI / 128.0;
+
+                let mut child = NPC::create(spawn.npc_type, npc_table);
+                child.cond.set_alive(true);
+                child.direction = npc.direction;
+                child.x = npc.x;
+                child.y = npc.y;
+                child.vel_x = (angle.cos() * spawn.speed as f64) as i32;
+                child.vel_y = (angle.sin() * spawn.speed as f64) as i32;
+
+                let _ = npc_list.spawn(0x100, child);
+            }
+        }
+    }
+}
+
+impl NPC {
+    pub(crate) fn tick_custom(
+        &mut self,
+        state: &mut SharedGameState,
+        NPCContext { players, npc_list, .. }: NPCContext,
+        def: &CustomNPCDefinition,
+    ) -> GameResult {
+        let player = self.get_closest_player_mut(players);
+        let (x, y) = (self.x, self.y);
+
+        for sound in self.step_custom(def, (player.x, player.y), &state.npc_table, npc_list) {
+            state.sound_manager.play_sfx_at(sound, x, y);
+        }
+
+        Ok(())
+    }
+
+    /// Simulates a tick of a custom NPC with the closest player at given position, returns the sound effects
+    /// to play from where the NPC was at the start of the tick.
+    fn step_custom(
+        &mut self,
+        def: &CustomNPCDefinition,
+        player_pos: (i32, i32),
+        npc_table: &NPCTable,
+        npc_list: &NPCList,
+    ) -> Vec<u8> {
+        let mut sounds = Vec::new();
+
+        if self.action_num == 0 {
+            self.action_num = 1;
+            self.target_x = self.x;
+            self.target_y = self.y;
+        }
+
+        if self.shock == HIT_SHOCK && def.hit.knockback != 0 {
+            self.vel_x = if self.x < player_pos.0 { -def.hit.knockback } else { def.hit.knockback };
+        }
+
+        for movement in def.movement.iter() {
+            sounds.extend(self.apply_movement(movement, player_pos.0));
+        }
+
+        self.action_counter3 = self.action_counter3.wrapping_add(1);
+        for pattern in def.shooting.iter() {
+            if pattern.interval != 0 && self.action_counter3 % pattern.interval == 0 {
+                sounds.extend(self.shoot(npc_table, npc_list, pattern, player_pos));
+            }
+        }
+
+        if def.hit.stun && self.shock > 0 {
+            self.x += self.vel_x / 2;
+            self.y += self.vel_y / 2;
+        } else {
+            self.x += self.vel_x;
+            self.y += self.vel_y;
+        }
+
+        self.animate_custom(def);
+
+        sounds
+    }
+
+    /// Returns the sound effect to play, if any.
+    fn apply_movement(&mut self, movement: &Movement, player_x: i32) -> Option<u8> {
+        match *movement {
+            Movement::Gravity { acceleration, max_speed } => {
+                self.vel_y = (self.vel_y + acceleration).min(max_speed);
+            }
+            Movement::WalkTowardPlayer { speed, acceleration, range } => {
+                if range == 0 || (self.x - player_x).abs() < range * 0x200 {
+                    self.direction = if self.x > player_x { Direction::Left } else { Direction::Right };
+                    self.vel_x = (self.vel_x + self.direction.vector_x() * acceleration).clamp(-speed, speed);
+                } else if self.vel_x.abs() <= acceleration {
+                    self.vel_x = 0;
+                } else {
+                    self.vel_x -= self.vel_x.signum() * acceleration;
+                }
+            }
+            Movement::Bounce { speed, interval, sound } => {
+                if (self.flags.hit_left_wall() && self.vel_x < 0) || (self.flags.hit_right_wall() && self.vel_x > 0) {
+                    self.vel_x = -self.vel_x;
+                    self.direction = self.direction.opposite();
+                }
+
+                if self.flags.hit_bottom_wall() {
+                    self.action_counter += 1;
+
+                    if self.action_counter > interval {
+                        self.action_counter = 0;
+                        self.vel_y = -speed;
+
+                        return sound;
+                    }
+                }
+            }
+            Movement::Hover { acceleration, max_speed } => {
+                self.vel_y -= ((self.y - self.target_y).signum() | 1) * acceleration;
+                self.vel_y = self.vel_y.clamp(-max_speed, max_speed);
+            }
+        }
+
+        None
+    }
+
+    /// Spawns a volley of projectiles if the player is in range, returns the sound effect to play.
+    fn shoot(
+        &self,
+        npc_table: &NPCTable,
+        npc_list: &NPCList,
+        pattern: &ShootingPattern,
+        (player_x, player_y): (i32, i32),
+    ) -> Option<u8> {
+        if pattern.range != 0
+            && ((self.x - player_x).abs() > pattern.range * 0x200 || (self.y - player_y).abs() > pattern.range * 0x200)
+        {
+            return None;
+        }
+
+        let x = self.x + self.direction.opposite().vector_x() * pattern.offset[0] * 0x200;
+        let y = self.y + pattern.offset[1] * 0x200;
+
+        let base_angle = match pattern.aim {
+            Aim::Player => f64::atan2((player_y - y) as f64, (player_x - x) as f64),
+            Aim::Forward if self.direction == Direction::Left => PI,
+            Aim::Forward => 0.0,
+            Aim::Up => -PI / 2.0,
+            Aim::Down => PI / 2.0,
+        };
+
+        for i in 0..pattern.count {
+            let angle = base_angle + (i as f64 - (pattern.count - 1) as f64 / 2.0) * pattern.spread.to_radians();
+
+            let mut npc = NPC::create(pattern.npc_type, npc_table);
+            npc.cond.set_alive(true);
+            npc.direction = self.direction;
+            npc.x = x;
+            npc.y = y;
+            npc.vel_x = (angle.cos() * pattern.speed as f64) as i32;
+            npc.vel_y = (angle.sin() * pattern.speed as f64) as i32;
+
+            let _ = npc_list.spawn(0x100, npc);
+        }
+
+        pattern.sound
+    }
+
+    fn animate_custom(&mut self, def: &CustomNPCDefinition) {
+        let animation = &def.animation;
+        let frames = if self.direction == Direction::Right && !animation.right.is_empty() {
+            &animation.right
+        } else {
+            &animation.left
+        };
+
+        if frames.is_empty() {
+            return;
+        }
+
+        self.anim_counter += 1;
+        if self.anim_counter >= animation.frame_time.max(1) {
+            self.anim_counter = 0;
+            self.anim_num += 1;
+        }
+
+        if self.anim_num as usize >= frames.len() {
+            self.anim_num = 0;
+        }
+
+        let frame = match animation.hurt_frame {
+            Some(hurt_frame) if self.shock > 0 && hurt_frame < frames.len() => hurt_frame,
+            _ => self.anim_num as usize,
+        };
+
+        let [left, top, right, bottom] = frames[frame];
+        self.anim_rect = Rect { left, top, right, bottom };
+    }
+}
+
+#[test]
+fn test_custom_npc_definition() {
+    let json = r#"{
+        "npcs": [
+            {
+                "id": 400,
+                "flags": 32,
+                "life": 5,
+                "display_bounds": [8, 8, 8, 8],
+                "hit_bounds": [6, 6, 6, 6],
+                "animation": { "left": [[0, 0, 16, 16], [16, 0, 32, 16]], "frame_time": 8 },
+                "movement": [
+                    { "type": "gravity" },
+                    { "type": "walk_toward_player", "speed": 256, "range": 160 }
+                ],
+                "shooting": [{ "npc_type": 84, "count": 3, "spread": 15 }],
+                "hit": { "knockback": 512 },
+                "death": { "spawn": [{ "npc_type": 86, "count": 2 }] }
+            }
+        ]
+    }"#;
+
+    let table: CustomNPCTable = serde_json::from_str(json).unwrap();
+    let def = &table.npcs[0];
+
+    assert_eq!(def.id, 400);
+    assert_eq!(def.spritesheet, 21);
+    assert_eq!(def.animation.left.len(), 2);
+    assert!(def.animation.right.is_empty());
+    assert!(matches!(def.movement[0], Movement::Gravity { acceleration: 0x40, max_speed: 0x5ff }));
+    assert!(matches!(def.movement[1], Movement::WalkTowardPlayer { speed: 256, acceleration: 0x20, range: 160 }));
+    assert_eq!(def.shooting[0].interval, 100);
+    assert_eq!(def.shooting[0].aim, Aim::Player);
+    assert_eq!(def.hit.knockback, 512);
+    assert_eq!(def.death.spawn[0].count, 2);
+}
+
+#[test]
+fn test_custom_npc_movement_and_animation() {
+    let def: CustomNPCDefinition = serde_json::from_str(
+        r#"{
+            "id": 400,
+            "display_bounds": [8, 8, 8, 8],
+            "hit_bounds": [6, 6, 6, 6],
+            "animation": {
+                "left": [[0, 0, 16, 16], [16, 0, 32, 16]],
+                "right": [[0, 16, 16, 32], [16, 16, 32, 32]],
+                "frame_time": 2
+            },
+            "movement": [{ "type": "gravity" }, { "type": "walk_toward_player" }]
+        }"#,
+    )
+    .unwrap();
+    let npc_table = NPCTable::new();
+    let (npc_list, _) = NPCList::new();
+
+    let mut npc = NPC::empty();
+    npc.cond.set_alive(true);
+    let player_pos = (0x10000, 0);
+
+    assert!(npc.step_custom(&def, player_pos, &npc_table, &npc_list).is_empty());
+    assert_eq!(npc.direction, Direction::Right);
+    assert_eq!((npc.vel_x, npc.vel_y), (0x20, 0x40));
+    assert_eq!((npc.x, npc.y), (0x20, 0x40));
+    assert_eq!(npc.anim_rect, Rect { left: 0, top: 16, right: 16, bottom: 32 });
+
+    npc.step_custom(&def, player_pos, &npc_table, &npc_list);
+    assert_eq!((npc.x, npc.y), (0x60, 0xc0));
+    assert_eq!(npc.anim_rect, Rect { left: 16, top: 16, right: 32, bottom: 32 });
+
+    for _ in 0..100 {
+        npc.step_custom(&def, player_pos, &npc_table, &npc_list);
+    }
+    assert_eq!((npc.vel_x, npc.vel_y), (0x200, 0x5ff));
+
+    // turns around once the player is behind it
+    npc.step_custom(&def, (-0x10000, 0), &npc_table, &npc_list);
+    assert_eq!(npc.direction, Direction::Left);
+    assert_eq!(npc.vel_x, 0x1e0);
+}
+
+#[test]
+fn test_custom_npc_shooting_interval() {
+    let def: CustomNPCDefinition = serde_json::from_str(
+        r#"{
+            "id": 400,
+            "display_bounds": [8, 8, 8, 8],
+            "hit_bounds": [6, 6, 6, 6],
+            "shooting": [{ "npc_type": 84, "interval": 3, "count": 2, "spread": 90, "aim": "up", "sound": 39 }]
+        }"#,
+    )
+    .unwrap();
+    let npc_table = NPCTable::new();
+    let (npc_list, token) = NPCList::new();
+
+    let mut npc = NPC::empty();
+    npc.cond.set_alive(true);
+
+    let mut volleys = Vec::new();
+    for tick in 1..=7 {
+        if !npc.step_custom(&def, (0, 0), &npc_table, &npc_list).is_empty() {
+            volleys.push(tick);
+        }
+    }
+
+    assert_eq!(volleys, [3, 6]);
+
+    let projectiles: Vec<_> = npc_list.iter_alive(&token).map(|npc| (npc.npc_type, npc.vel_x, npc.vel_y)).collect();
+    assert_eq!(projectiles.len(), 4);
+    assert!(projectiles.iter().all(|&(npc_type, _, vel_y)| npc_type == 84 && vel_y < 0));
+    assert!(projectiles[0].1 < 0 && projectiles[1].1 > 0);
+}
+
+#[test]
+fn test_custom_npc_death_spawns() {
+    let def: CustomNPCDefinition = serde_json::from_str(
+        r#"{
+            "id": 400,
+            "display_bounds": [8, 8, 8, 8],
+            "hit_bounds": [6, 6, 6, 6],
+            "death": { "spawn": [{ "npc_type": 86, "count": 3, "speed": 512 }, { "npc_type": 87 }] }
+        }"#,
+    )
+    .unwrap();
+    let npc_table = NPCTable::new();
+    let (npc_list, token) = NPCList::new();
+
+    let mut npc = NPC::empty();
+    npc.x = 0x1000;
+    npc.y = 0x2000;
+    npc.init_rng(1234);
+
+    def.spawn_death_npcs(&npc, &npc_table, &npc_list);
+
+    let spawned: Vec<_> =
+        npc_list.iter_alive(&token).map(|npc| (npc.npc_type, npc.x, npc.y, npc.vel_x, npc.vel_y)).collect();
+    assert_eq!(spawned.iter().map(|npc| npc.0).collect::<Vec<_>>(), [86, 86, 86, 87]);
+    assert!(spawned.iter().all(|npc| (npc.1, npc.2) == (0x1000, 0x2000)));
+    for &(_, _, _, vel_x, vel_y) in &spawned[..3] {
+        assert!(((vel_x as f64).hypot(vel_y as f64) - 512.0).abs() < 2.0);
+    }
+    assert_eq!((spawned[3].3, spawned[3].4), (0, 0));
+}
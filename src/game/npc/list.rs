@@ -151,6 +151,11 @@ impl NPCList {
         self.seed = seed;
     }
 
+    /// Returns the seed used to initialize random number generators of spawned NPCs.
+    pub fn rng_seed(&self) -> i32 {
+        self.seed
+    }
+
     /// Inserts NPC into list in first available slot after given ID.
     pub fn spawn(&self, min_id: u16, mut npc: NPC) -> GameResult {
         let npc_len = self.npcs.len();
@@ -282,6 +287,26 @@ impl NPCList {
     pub fn max_capacity(&self) -> u16 {
         NPC_LIST_MAX_CAP as u16
     }
+
+    /// Replaces the contents of this list with given slots, keeping the NPCs as they are, including their RNG state.
+    pub fn restore(&self, token: &mut NPCAccessToken, npcs: &[NPC]) -> GameResult {
+        if npcs.len() > NPC_LIST_MAX_CAP {
+            return Err(GameError::InvalidValue("Too many NPCs to restore".to_string()));
+        }
+
+        self.clear(token);
+
+        for (idx, (npc_ref, saved)) in self.npcs.iter().zip(npcs.iter()).enumerate() {
+            let mut npc = npc_ref.borrow_mut(token);
+
+            *npc = saved.clone();
+            npc.id = idx as u16;
+        }
+
+        self.max_npc.replace(npcs.len() as u16);
+
+        Ok(())
+    }
 }
 
 pub struct NPCListAliveIterator<'a> {
@@ -15,8 +15,10 @@ use crate::components::number_popup::NumberPopup;
 use crate::entity::GameEntity;
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
+use crate::framework::filesystem;
 use crate::game::frame::Frame;
 use crate::game::npc::boss::BossNPC;
+use crate::game::npc::custom::{CustomNPCDefinition, CustomNPCTable, VANILLA_NPC_TYPES};
 use crate::game::npc::list::{BorrowedNPC, NPCList};
 use crate::game::physics::PhysicalEntity;
 use crate::game::player::Player;
@@ -29,11 +31,12 @@ use super::physics::HitExtents;
 
 pub mod ai;
 pub mod boss;
+pub mod custom;
 pub mod list;
 pub mod utils;
 
 bitfield! {
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
     pub struct NPCFlag(u16);
     impl Debug;
     /// Represented by 0x01
@@ -70,7 +73,7 @@ bitfield! {
     pub show_damage, set_show_damage: 15;
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialOrd, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialOrd, PartialEq, serde::Serialize, serde::Deserialize)]
 #[repr(u8)]
 pub enum NPCLayer {
     Background = 0,
@@ -79,7 +82,7 @@ pub enum NPCLayer {
 }
 
 /// Represents an NPC object.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[repr(C)]
 pub struct NPC {
     pub id: u16,
@@ -614,7 +617,10 @@ impl GameEntity<NPCContext<'_>> for BorrowedNPC<'_> {
             368 => self.tick_n368_gclone(state, ctx),
             369 => self.tick_n369_gclone_curly_clone(state, ctx),
             370 => self.tick_n370_second_quote(state, ctx),
-            _ => Ok(()),
+            _ => match state.npc_table.get_custom(self.npc_type) {
+                Some(def) => self.tick_custom(state, ctx, &def),
+                None => Ok(()),
+            },
         }?;
 
         // I don't know where the best place to put this is, but let's try putting it here
@@ -808,6 +814,26 @@ pub struct NPCTableEntry {
     pub damage: u32,
     pub display_bounds: Rect<u8>,
     pub hit_bounds: Rect<u8>,
+    /// Behavior of NPC types declared by mods, `None` for the built-in ones.
+    pub custom: Option<Rc<CustomNPCDefinition>>,
+}
+
+impl NPCTableEntry {
+    fn empty() -> NPCTableEntry {
+        NPCTableEntry {
+            npc_flags: NPCFlag(0),
+            life: 0,
+            spritesheet_id: 0,
+            death_sound: 0,
+            hurt_sound: 0,
+            size: 0,
+            experience: 0,
+            damage: 0,
+            display_bounds: Rect::new(0, 0, 0, 0),
+            hit_bounds: Rect::new(0, 0, 0, 0),
+            custom: None,
+        }
+    }
 }
 
 pub struct NPCTable {
@@ -831,18 +857,7 @@ impl NPCTable {
         let mut f = Cursor::new(buf);
 
         for _ in 0..count {
-            table.entries.push(NPCTableEntry {
-                npc_flags: NPCFlag(0),
-                life: 0,
-                spritesheet_id: 0,
-                death_sound: 0,
-                hurt_sound: 0,
-                size: 0,
-                experience: 0,
-                damage: 0,
-                display_bounds: Rect::new(0, 0, 0, 0),
-                hit_bounds: Rect::new(0, 0, 0, 0),
-            });
+            table.entries.push(NPCTableEntry::empty());
         }
 
         for npc in &mut table.entries {
@@ -894,10 +909,68 @@ impl NPCTable {
         Ok(table)
     }
 
+    /// Registers NPC types declared in `custom_npcs.json` of the data roots, definitions from roots
+    /// with higher priority replace the ones with the same type.
+    pub fn load_custom_npcs(&mut self, ctx: &mut Context, roots: &[String]) {
+        for root in roots.iter().rev() {
+            let Ok(file) = filesystem::open(ctx, format!("{}custom_npcs.json", root)) else {
+                continue;
+            };
+
+            match serde_json::from_reader::<_, CustomNPCTable>(file) {
+                Ok(table) => {
+                    for def in table.npcs {
+                        self.register_custom(def);
+                    }
+                }
+                Err(err) => log::warn!("Failed to deserialize custom NPCs from {}: {}", root, err),
+            }
+        }
+    }
+
+    pub fn register_custom(&mut self, def: CustomNPCDefinition) {
+        if def.id < VANILLA_NPC_TYPES {
+            log::warn!(
+                "Custom NPC type {} overlaps the built-in ones, it must be at least {}.",
+                def.id,
+                VANILLA_NPC_TYPES
+            );
+            return;
+        }
+
+        let index = def.id as usize;
+        if self.entries.len() <= index {
+            self.entries.resize_with(index + 1, NPCTableEntry::empty);
+        }
+
+        let [left, top, right, bottom] = def.display_bounds;
+        let display_bounds = Rect { left, top, right, bottom };
+        let [left, top, right, bottom] = def.hit_bounds;
+        let hit_bounds = Rect { left, top, right, bottom };
+
+        self.entries[index] = NPCTableEntry {
+            npc_flags: NPCFlag(def.flags),
+            life: def.life,
+            spritesheet_id: def.spritesheet,
+            death_sound: def.death_sound,
+            hurt_sound: def.hurt_sound,
+            size: def.size,
+            experience: def.experience,
+            damage: def.damage,
+            display_bounds,
+            hit_bounds,
+            custom: Some(Rc::new(def)),
+        };
+    }
+
     pub fn get_entry(&self, npc_type: u16) -> Option<&NPCTableEntry> {
         self.entries.get(npc_type as usize)
     }
 
+    pub fn get_custom(&self, npc_type: u16) -> Option<Rc<CustomNPCDefinition>> {
+        self.entries.get(npc_type as usize).and_then(|entry| entry.custom.clone())
+    }
+
     pub fn get_display_bounds(&self, npc_type: u16) -> Rect<u32> {
         if let Some(npc) = self.entries.get(npc_type as usize) {
             Rect {
@@ -275,7 +275,7 @@ impl NPCList {
 
             if smoke {
                 if let Some(table_entry) = state.npc_table.get_entry(npc.npc_type) {
-                    state.sound_manager.play_sfx(table_entry.death_sound);
+                    state.sound_manager.play_sfx_at(table_entry.death_sound, npc.x, npc.y);
                 }
 
                 match npc.size {
@@ -300,7 +300,7 @@ impl NPCList {
             let mut npc = npc.borrow_mut(token);
 
             if let Some(table_entry) = state.npc_table.get_entry(npc.npc_type) {
-                state.sound_manager.play_sfx(table_entry.death_sound);
+                state.sound_manager.play_sfx_at(table_entry.death_sound, npc.x, npc.y);
             }
 
             match npc.size {
@@ -345,6 +345,10 @@ impl NPCList {
                 }
             }
 
+            if let Some(def) = state.npc_table.get_custom(npc.npc_type) {
+                def.spawn_death_npcs(&npc, &state.npc_table, self);
+            }
+
             state.set_flag(npc.flag_num as usize, true);
 
             if npc.npc_flags.show_damage() {
@@ -5,6 +5,7 @@ use num_traits::clamp;
 
 use crate::common::{interpolate_fix9_scale, Condition, Direction, Equipment, Flag, Rect};
 use crate::components::number_popup::NumberPopup;
+use crate::components::replay::GhostFrame;
 use crate::entity::GameEntity;
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
@@ -24,14 +25,14 @@ use super::physics::HitExtents;
 mod player_hit;
 pub mod skin;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, serde::Serialize, serde::Deserialize)]
 #[repr(u8)]
 pub enum ControlMode {
     Normal = 0,
     IronHead,
 }
 
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum TargetPlayer {
     Player1,
     Player2,
@@ -44,7 +45,7 @@ impl TargetPlayer {
     }
 }
 
-#[derive(PartialEq, Eq, Copy, Clone)]
+#[derive(PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 enum BoosterSwitch {
     None,
     Up,
@@ -53,7 +54,7 @@ enum BoosterSwitch {
     Down,
 }
 
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 struct DogStack {
     pub offset_x: f32,
     pub speed: f32,
@@ -76,7 +77,15 @@ impl DogStack {
     }
 }
 
-#[derive(Clone)]
+fn placeholder_skin() -> Box<dyn PlayerSkin> {
+    Box::new(BasicPlayerSkin::placeholder())
+}
+
+fn placeholder_controller() -> Box<dyn PlayerController> {
+    Box::new(DummyPlayerController::new())
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Player {
     pub x: i32,
     pub y: i32,
@@ -108,7 +117,11 @@ pub struct Player {
     pub damage: u16,
     pub air_counter: u16,
     pub air: u16,
+    /// Not part of save states, restored snapshots keep the skin of the live player.
+    #[serde(skip, default = "placeholder_skin")]
     pub skin: Box<dyn PlayerSkin>,
+    /// Not part of save states, restored snapshots keep the controller of the live player.
+    #[serde(skip, default = "placeholder_controller")]
     pub controller: Box<dyn PlayerController>,
     pub damage_popup: NumberPopup,
     pub exp_popup: NumberPopup,
@@ -927,6 +940,77 @@ impl Player {
             self.teleport_counter = 0;
         }
     }
+
+    /// Returns what is drawn of the player this tick, for the ghost track of a replay.
+    pub fn ghost_frame(&self, stage_id: usize) -> GhostFrame {
+        GhostFrame {
+            stage_id: stage_id as u16,
+            x: self.x,
+            y: self.y,
+            direction: self.direction,
+            visible: self.cond.alive() && !self.cond.hidden() && self.shock_counter / 2 % 2 == 0,
+            anim_rect: self.anim_rect,
+            current_weapon: self.current_weapon,
+            weapon_offset_y: self.weapon_offset_y,
+            weapon_rect: self.weapon_rect,
+        }
+    }
+
+    /// Moves a ghost player to given frame of a ghost track, `prev` is the frame of the previous tick.
+    pub fn set_ghost_frame(&mut self, frame: &GhostFrame, prev: Option<&GhostFrame>) {
+        let prev = prev.filter(|prev| prev.stage_id == frame.stage_id).unwrap_or(frame);
+
+        self.prev_x = prev.x;
+        self.prev_y = prev.y;
+        self.x = frame.x;
+        self.y = frame.y;
+        self.direction = frame.direction;
+        self.anim_rect = frame.anim_rect;
+        self.current_weapon = frame.current_weapon;
+        self.weapon_offset_y = frame.weapon_offset_y;
+        self.weapon_rect = frame.weapon_rect;
+        self.cond.set_alive(true);
+        self.cond.set_hidden(!frame.visible);
+    }
+
+    /// Draws the player and its weapon translucent, used for replay ghosts.
+    pub fn draw_ghost(&self, state: &mut SharedGameState, ctx: &mut Context, frame: &Frame, alpha: u8) -> GameResult {
+        if !self.cond.alive() || self.cond.hidden() {
+            return Ok(());
+        }
+
+        let (frame_x, frame_y) = frame.xy_interpolated(state.frame_time);
+        let x = interpolate_fix9_scale(
+            self.prev_x - self.display_bounds.left as i32,
+            self.x - self.display_bounds.left as i32,
+            state.frame_time,
+        ) - frame_x;
+        let y = interpolate_fix9_scale(
+            self.prev_y - self.display_bounds.top as i32,
+            self.y - self.display_bounds.top as i32,
+            state.frame_time,
+        ) - frame_y;
+        let color = (255, 255, 255, alpha);
+
+        if self.current_weapon != 0 {
+            let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, "Arms")?;
+            let (gun_off_x, gun_off_y) = self.skin.get_gun_offset();
+
+            batch.add_rect_tinted(
+                x + if self.direction == Direction::Left { -8.0 - gun_off_x as f32 } else { gun_off_x as f32 },
+                y + self.weapon_offset_y as f32 + gun_off_y as f32,
+                color,
+                &self.weapon_rect,
+            );
+            batch.draw(ctx)?;
+        }
+
+        let batch = state.texture_set.get_or_load_batch(ctx, &state.constants, self.skin.get_skin_texture_name())?;
+        batch.add_rect_tinted(x, y, color, &self.anim_rect);
+        batch.draw(ctx)?;
+
+        Ok(())
+    }
 }
 
 impl GameEntity<&NPCList> for Player {
@@ -117,6 +117,20 @@ impl BasicPlayerSkin {
         }
     }
 
+    /// Creates the default skin without looking up its metadata, used until the actual skin is assigned.
+    pub fn placeholder() -> BasicPlayerSkin {
+        BasicPlayerSkin {
+            texture_name: "MyChar".to_string(),
+            color: Color::new(1.0, 1.0, 1.0, 1.0),
+            state: PlayerAnimationState::Idle,
+            appearance: PlayerAppearanceState::Default,
+            direction: Direction::Left,
+            metadata: DEFAULT_SKINMETA.clone(),
+            tick: 0,
+            skinsheet_offset: 0,
+        }
+    }
+
     fn get_y_offset_by(&self, y: u16) -> u16 {
         return self
             .skinsheet_offset
@@ -1,14 +1,16 @@
-use std::io;
+use std::io::{self, Cursor, Read};
+use std::time::Duration;
 
 use byteorder::{BE, LE, ReadBytesExt, WriteBytesExt};
 use num_traits::{clamp, FromPrimitive};
 
 use crate::common::{Direction, FadeState, get_timestamp};
 use crate::framework::context::Context;
-use crate::framework::error::GameError::ResourceLoadError;
+use crate::framework::error::GameError::{InvalidValue, ResourceLoadError};
 use crate::framework::error::GameResult;
-use crate::game::player::{ControlMode, TargetPlayer};
-use crate::game::shared_game_state::{GameDifficulty, SharedGameState};
+use crate::game::inventory::Inventory;
+use crate::game::player::{ControlMode, Player, TargetPlayer};
+use crate::game::shared_game_state::{GameDifficulty, PlayerSkinLocation, SharedGameState};
 use crate::game::weapon::{WeaponLevel, WeaponType};
 use crate::scene::game_scene::GameScene;
 
@@ -25,6 +27,91 @@ pub struct TeleporterSlotData {
     pub event_num: u32,
 }
 
+/// State of the player that isn't stored in the vanilla part of the profile.
+pub struct PartnerData {
+    pub max_life: u16,
+    pub stars: u16,
+    pub life: u16,
+    pub current_weapon: u32,
+    pub current_item: u32,
+    pub equipment: u32,
+    pub weapon_data: [WeaponData; 8],
+    pub items: [u32; 32],
+}
+
+/// Extended profile section present in saves made during a co-op session, appended after the
+/// vanilla data so original readers just ignore it.
+pub struct CoopData {
+    /// Player whose state is stored in the vanilla part of the profile.
+    pub main_player: TargetPlayer,
+    pub partner: PartnerData,
+    pub player2_skin_location: PlayerSkinLocation,
+}
+
+/// Downscaled screenshot taken when the game was saved, stored as packed RGB8 rows.
+#[derive(Clone)]
+pub struct SaveThumbnail {
+    pub width: u16,
+    pub height: u16,
+    pub data: Vec<u8>,
+}
+
+impl SaveThumbnail {
+    pub const MAX_WIDTH: u16 = 64;
+    pub const MAX_HEIGHT: u16 = 48;
+
+    /// Box-filters a RGBA8 framebuffer down to fit within `MAX_WIDTH`x`MAX_HEIGHT`, keeping the aspect ratio.
+    pub fn from_rgba(src_width: u16, src_height: u16, pixels: &[u8]) -> Option<SaveThumbnail> {
+        if src_width == 0 || src_height == 0 || pixels.len() < src_width as usize * src_height as usize * 4 {
+            return None;
+        }
+
+        let scale =
+            f32::max(src_width as f32 / Self::MAX_WIDTH as f32, src_height as f32 / Self::MAX_HEIGHT as f32).max(1.0);
+        let width = ((src_width as f32 / scale) as u16).max(1);
+        let height = ((src_height as f32 / scale) as u16).max(1);
+
+        let mut data = Vec::with_capacity(width as usize * height as usize * 3);
+        for y in 0..height as usize {
+            let y0 = y * src_height as usize / height as usize;
+            let y1 = ((y + 1) * src_height as usize / height as usize).max(y0 + 1);
+
+            for x in 0..width as usize {
+                let x0 = x * src_width as usize / width as usize;
+                let x1 = ((x + 1) * src_width as usize / width as usize).max(x0 + 1);
+
+                let mut sum = [0u32; 3];
+                for sy in y0..y1 {
+                    for sx in x0..x1 {
+                        let offset = (sy * src_width as usize + sx) * 4;
+                        sum[0] += pixels[offset] as u32;
+                        sum[1] += pixels[offset + 1] as u32;
+                        sum[2] += pixels[offset + 2] as u32;
+                    }
+                }
+
+                let count = ((y1 - y0) * (x1 - x0)) as u32;
+                data.extend(sum.iter().map(|&c| (c / count) as u8));
+            }
+        }
+
+        Some(SaveThumbnail { width, height, data })
+    }
+
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.data.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff]).collect()
+    }
+}
+
+/// Extended profile section holding information displayed in the save select menu.
+#[derive(Clone)]
+pub struct SaveMetadata {
+    /// Total time spent in game, in milliseconds.
+    pub play_time: u64,
+    pub mod_name: String,
+    pub thumbnail: Option<SaveThumbnail>,
+}
+
 pub struct GameProfile {
     pub current_map: u32,
     pub current_song: u32,
@@ -46,6 +133,265 @@ pub struct GameProfile {
     pub flags: [u8; 1000],
     pub timestamp: u64,
     pub difficulty: u8,
+    pub coop: Option<CoopData>,
+    pub metadata: Option<SaveMetadata>,
+    /// Script variables set with `<VAR` and related opcodes, by ID.
+    pub variables: Vec<(u16, i32)>,
+}
+
+const COOP_SIGNATURE: u32 = 0x434f4f50; // COOP
+const META_SIGNATURE: u32 = 0x4d455441; // META
+const VARS_SIGNATURE: u32 = 0x56415253; // VARS
+
+/// Writes an extended section: its signature, the `u32` length of its contents and the contents,
+/// so that readers can skip sections they don't know.
+fn write_section<W: io::Write>(
+    data: &mut W,
+    signature: u32,
+    write_contents: impl FnOnce(&mut Vec<u8>) -> GameResult,
+) -> GameResult {
+    let mut contents = Vec::new();
+    write_contents(&mut contents)?;
+
+    data.write_u32::<BE>(signature)?;
+    data.write_u32::<LE>(contents.len() as u32)?;
+    data.write_all(&contents)?;
+
+    Ok(())
+}
+
+fn empty_weapon_data() -> [WeaponData; 8] {
+    [
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+        WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
+    ]
+}
+
+fn dump_inventory(inventory: &Inventory) -> ([WeaponData; 8], [u32; 32]) {
+    let mut weapon_data = empty_weapon_data();
+    let mut items = [0u32; 32];
+
+    for (idx, weap) in weapon_data.iter_mut().enumerate() {
+        if let Some(weapon) = inventory.get_weapon(idx) {
+            weap.weapon_id = weapon.wtype as u32;
+            weap.level = weapon.level as u32;
+            weap.exp = weapon.experience as u32;
+            weap.max_ammo = weapon.max_ammo as u32;
+            weap.ammo = weapon.ammo as u32;
+        }
+    }
+
+    for (idx, item) in items.iter_mut().enumerate() {
+        if let Some(sitem) = inventory.get_item_idx(idx) {
+            *item = sitem.0 as u32 + (((sitem.1 - 1) as u32) << 16);
+        }
+    }
+
+    (weapon_data, items)
+}
+
+fn apply_inventory(
+    state: &mut SharedGameState,
+    ctx: &mut Context,
+    inventory: &mut Inventory,
+    weapon_data: &[WeaponData; 8],
+    items: &[u32; 32],
+) {
+    for weapon in weapon_data {
+        if weapon.weapon_id == 0 {
+            continue;
+        }
+
+        let _ = state.mod_requirements.append_weapon(ctx, weapon.weapon_id as u16);
+        let weapon_type: Option<WeaponType> = FromPrimitive::from_u8(weapon.weapon_id as u8);
+
+        if let Some(wtype) = weapon_type {
+            inventory.add_weapon_data(
+                wtype,
+                weapon.ammo as u16,
+                weapon.max_ammo as u16,
+                weapon.exp as u16,
+                match weapon.level {
+                    2 => WeaponLevel::Level2,
+                    3 => WeaponLevel::Level3,
+                    _ => WeaponLevel::Level1,
+                },
+            );
+        }
+    }
+
+    for item in items.iter().copied() {
+        let item_id = item as u16;
+        let _ = state.mod_requirements.append_item(ctx, item_id);
+
+        let amount = (item >> 16) as u16;
+        if item_id == 0 {
+            break;
+        }
+
+        inventory.add_item_amount(item_id, amount + 1);
+    }
+}
+
+fn write_weapon_data<W: io::Write>(data: &mut W, weapon_data: &[WeaponData; 8]) -> GameResult {
+    for weapon in weapon_data {
+        data.write_u32::<LE>(weapon.weapon_id)?;
+        data.write_u32::<LE>(weapon.level)?;
+        data.write_u32::<LE>(weapon.exp)?;
+        data.write_u32::<LE>(weapon.max_ammo)?;
+        data.write_u32::<LE>(weapon.ammo)?;
+    }
+
+    Ok(())
+}
+
+fn read_weapon_data<R: io::Read>(data: &mut R) -> GameResult<[WeaponData; 8]> {
+    let mut weapon_data = empty_weapon_data();
+
+    for WeaponData { weapon_id, level, exp, max_ammo, ammo } in &mut weapon_data {
+        *weapon_id = data.read_u32::<LE>()?;
+        *level = data.read_u32::<LE>()?;
+        *exp = data.read_u32::<LE>()?;
+        *max_ammo = data.read_u32::<LE>()?;
+        *ammo = data.read_u32::<LE>()?;
+    }
+
+    Ok(weapon_data)
+}
+
+impl PartnerData {
+    pub fn dump(player: &Player, inventory: &Inventory) -> PartnerData {
+        let (weapon_data, items) = dump_inventory(inventory);
+
+        PartnerData {
+            max_life: player.max_life,
+            stars: player.stars as u16,
+            life: player.life,
+            current_weapon: inventory.current_weapon as u32,
+            current_item: inventory.current_item as u32,
+            equipment: player.equip.0 as u32,
+            weapon_data,
+            items,
+        }
+    }
+
+    pub fn apply(
+        &self,
+        state: &mut SharedGameState,
+        ctx: &mut Context,
+        player: &mut Player,
+        inventory: &mut Inventory,
+    ) {
+        *inventory = Inventory::new();
+        apply_inventory(state, ctx, inventory, &self.weapon_data, &self.items);
+        inventory.current_weapon = self.current_weapon as u16;
+        inventory.current_item = self.current_item as u16;
+
+        player.equip.0 = self.equipment as u16;
+        player.life = self.life;
+        player.max_life = self.max_life;
+        player.stars = clamp(self.stars, 0, 3) as u8;
+    }
+}
+
+impl CoopData {
+    fn write_to<W: io::Write>(&self, data: &mut W) -> GameResult {
+        data.write_u8(self.main_player.index() as u8)?;
+        data.write_u16::<LE>(self.player2_skin_location.texture_index)?;
+        data.write_u16::<LE>(self.player2_skin_location.offset)?;
+
+        let partner = &self.partner;
+        data.write_u16::<LE>(partner.max_life)?;
+        data.write_u16::<LE>(partner.stars)?;
+        data.write_u16::<LE>(partner.life)?;
+        data.write_u32::<LE>(partner.current_weapon)?;
+        data.write_u32::<LE>(partner.current_item)?;
+        data.write_u32::<LE>(partner.equipment)?;
+        write_weapon_data(data, &partner.weapon_data)?;
+
+        for item in partner.items.iter().copied() {
+            data.write_u32::<LE>(item)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_from<R: io::Read>(data: &mut R) -> GameResult<CoopData> {
+        let main_player = if data.read_u8()? == 1 { TargetPlayer::Player2 } else { TargetPlayer::Player1 };
+        let texture_index = data.read_u16::<LE>()?;
+        let offset = data.read_u16::<LE>()?;
+
+        let max_life = data.read_u16::<LE>()?;
+        let stars = data.read_u16::<LE>()?;
+        let life = data.read_u16::<LE>()?;
+        let current_weapon = data.read_u32::<LE>()?;
+        let current_item = data.read_u32::<LE>()?;
+        let equipment = data.read_u32::<LE>()?;
+        let weapon_data = read_weapon_data(data)?;
+
+        let mut items = [0u32; 32];
+        for item in &mut items {
+            *item = data.read_u32::<LE>()?;
+        }
+
+        Ok(CoopData {
+            main_player,
+            partner: PartnerData { max_life, stars, life, current_weapon, current_item, equipment, weapon_data, items },
+            player2_skin_location: PlayerSkinLocation::new(texture_index, offset),
+        })
+    }
+}
+
+impl SaveMetadata {
+    fn write_to<W: io::Write>(&self, data: &mut W) -> GameResult {
+        data.write_u64::<LE>(self.play_time)?;
+
+        let mod_name = self.mod_name.as_bytes();
+        data.write_u16::<LE>(mod_name.len() as u16)?;
+        data.write_all(mod_name)?;
+
+        if let Some(thumbnail) = &self.thumbnail {
+            data.write_u16::<LE>(thumbnail.width)?;
+            data.write_u16::<LE>(thumbnail.height)?;
+            data.write_all(&thumbnail.data)?;
+        } else {
+            data.write_u16::<LE>(0)?;
+            data.write_u16::<LE>(0)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_from<R: io::Read>(data: &mut R) -> GameResult<SaveMetadata> {
+        let play_time = data.read_u64::<LE>()?;
+
+        let mut mod_name = vec![0u8; data.read_u16::<LE>()? as usize];
+        data.read_exact(&mut mod_name)?;
+        let mod_name = String::from_utf8_lossy(&mod_name).into_owned();
+
+        let width = data.read_u16::<LE>()?;
+        let height = data.read_u16::<LE>()?;
+        if width > SaveThumbnail::MAX_WIDTH || height > SaveThumbnail::MAX_HEIGHT {
+            return Err(InvalidValue(format!("Save thumbnail is too large: {}x{}", width, height)));
+        }
+
+        let thumbnail = if width != 0 && height != 0 {
+            let mut pixels = vec![0u8; width as usize * height as usize * 3];
+            data.read_exact(&mut pixels)?;
+
+            Some(SaveThumbnail { width, height, data: pixels })
+        } else {
+            None
+        };
+
+        Ok(SaveMetadata { play_time, mod_name, thumbnail })
+    }
 }
 
 impl GameProfile {
@@ -58,40 +404,7 @@ impl GameProfile {
 
         game_scene.inventory_player1.current_weapon = self.current_weapon as u16;
         game_scene.inventory_player1.current_item = self.current_item as u16;
-        for weapon in &self.weapon_data {
-            if weapon.weapon_id == 0 {
-                continue;
-            }
-
-            let _ = state.mod_requirements.append_weapon(ctx, weapon.weapon_id as u16);
-            let weapon_type: Option<WeaponType> = FromPrimitive::from_u8(weapon.weapon_id as u8);
-
-            if let Some(wtype) = weapon_type {
-                game_scene.inventory_player1.add_weapon_data(
-                    wtype,
-                    weapon.ammo as u16,
-                    weapon.max_ammo as u16,
-                    weapon.exp as u16,
-                    match weapon.level {
-                        2 => WeaponLevel::Level2,
-                        3 => WeaponLevel::Level3,
-                        _ => WeaponLevel::Level1,
-                    },
-                );
-            }
-        }
-
-        for item in self.items.iter().copied() {
-            let item_id = item as u16;
-            let _ = state.mod_requirements.append_item(ctx, item_id);
-
-            let amount = (item >> 16) as u16;
-            if item_id == 0 {
-                break;
-            }
-
-            game_scene.inventory_player1.add_item_amount(item_id, amount + 1);
-        }
+        apply_inventory(state, ctx, &mut game_scene.inventory_player1, &self.weapon_data, &self.items);
 
         for slot in &self.teleporter_slots {
             if slot.event_num == 0 {
@@ -149,6 +462,26 @@ impl GameProfile {
         game_scene.player2 = game_scene.player1.clone();
         game_scene.inventory_player2 = game_scene.inventory_player1.clone();
 
+        if let Some(coop) = &self.coop {
+            // the vanilla section holds the player who saved the game, the other one is restored from the co-op section.
+            let (partner, partner_inventory) = match coop.main_player {
+                TargetPlayer::Player1 => (&mut game_scene.player2, &mut game_scene.inventory_player2),
+                TargetPlayer::Player2 => (&mut game_scene.player1, &mut game_scene.inventory_player1),
+            };
+
+            coop.partner.apply(state, ctx, partner, partner_inventory);
+            state.player2_skin_location = coop.player2_skin_location;
+        }
+
+        if let Some(metadata) = &self.metadata {
+            state.play_time = Duration::from_millis(metadata.play_time);
+        }
+
+        state.script_variables.clear();
+        for &(id, value) in self.variables.iter() {
+            state.script_variables.set(id, value);
+        }
+
         game_scene.player1.cond.0 = 0x80;
 
         state.difficulty = GameDifficulty::from_primitive(self.difficulty);
@@ -158,12 +491,14 @@ impl GameProfile {
     }
 
     pub fn dump(state: &mut SharedGameState, game_scene: &mut GameScene, target_player: Option<TargetPlayer>) -> GameProfile {
-        let player = match target_player.unwrap_or(TargetPlayer::Player1) {
+        let main_player = target_player.unwrap_or(TargetPlayer::Player1);
+
+        let player = match main_player {
             TargetPlayer::Player1 => &game_scene.player1,
             TargetPlayer::Player2 => &game_scene.player2,
         };
 
-        let inventory_player = match target_player.unwrap_or(TargetPlayer::Player1) {
+        let inventory_player = match main_player {
             TargetPlayer::Player1 => &game_scene.inventory_player1,
             TargetPlayer::Player2 => &game_scene.inventory_player2,
         };
@@ -181,17 +516,7 @@ impl GameProfile {
         let equipment = player.equip.0 as u32;
         let control_mode = player.control_mode as u32;
         let counter = 0; // TODO
-        let mut weapon_data = [
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-        ];
-        let mut items = [0u32; 32];
+        let (weapon_data, items) = dump_inventory(inventory_player);
         let mut teleporter_slots = [
             TeleporterSlotData { index: 0, event_num: 0 },
             TeleporterSlotData { index: 0, event_num: 0 },
@@ -203,22 +528,6 @@ impl GameProfile {
             TeleporterSlotData { index: 0, event_num: 0 },
         ];
 
-        for (idx, weap) in weapon_data.iter_mut().enumerate() {
-            if let Some(weapon) = inventory_player.get_weapon(idx) {
-                weap.weapon_id = weapon.wtype as u32;
-                weap.level = weapon.level as u32;
-                weap.exp = weapon.experience as u32;
-                weap.max_ammo = weapon.max_ammo as u32;
-                weap.ammo = weapon.ammo as u32;
-            }
-        }
-
-        for (idx, item) in items.iter_mut().enumerate() {
-            if let Some(sitem) = inventory_player.get_item_idx(idx) {
-                *item = sitem.0 as u32 + (((sitem.1 - 1) as u32) << 16);
-            }
-        }
-
         for (idx, slot) in teleporter_slots.iter_mut().enumerate() {
             if let Some(&(index, event_num)) = state.teleporter_slots.get(idx) {
                 slot.index = index as u32;
@@ -241,6 +550,30 @@ impl GameProfile {
         let timestamp = get_timestamp();
         let difficulty = state.difficulty as u8;
 
+        let coop = if game_scene.player2.cond.alive() {
+            let (partner, partner_inventory) = match main_player {
+                TargetPlayer::Player1 => (&game_scene.player2, &game_scene.inventory_player2),
+                TargetPlayer::Player2 => (&game_scene.player1, &game_scene.inventory_player1),
+            };
+
+            Some(CoopData {
+                main_player,
+                partner: PartnerData::dump(partner, partner_inventory),
+                player2_skin_location: state.player2_skin_location,
+            })
+        } else {
+            None
+        };
+
+        let mod_name = state
+            .mod_path
+            .clone()
+            .and_then(|mod_path| state.mod_list.get_info_from_path(mod_path))
+            .and_then(|mod_info| mod_info.name.clone())
+            .unwrap_or_default();
+        let metadata = Some(SaveMetadata { play_time: state.play_time.as_millis() as u64, mod_name, thumbnail: None });
+        let variables = state.script_variables.iter().collect();
+
         GameProfile {
             current_map,
             current_song,
@@ -262,6 +595,9 @@ impl GameProfile {
             flags,
             timestamp,
             difficulty,
+            coop,
+            metadata,
+            variables,
         }
     }
 
@@ -283,13 +619,7 @@ impl GameProfile {
         data.write_u32::<LE>(self.control_mode)?;
         data.write_u32::<LE>(self.counter)?;
 
-        for weapon in &self.weapon_data {
-            data.write_u32::<LE>(weapon.weapon_id)?;
-            data.write_u32::<LE>(weapon.level)?;
-            data.write_u32::<LE>(weapon.exp)?;
-            data.write_u32::<LE>(weapon.max_ammo)?;
-            data.write_u32::<LE>(weapon.ammo)?;
-        }
+        write_weapon_data(&mut data, &self.weapon_data)?;
 
         for item in self.items.iter().copied() {
             data.write_u32::<LE>(item)?;
@@ -311,6 +641,27 @@ impl GameProfile {
         data.write_u64::<LE>(self.timestamp)?;
         data.write_u8(self.difficulty)?;
 
+        if let Some(coop) = &self.coop {
+            write_section(&mut data, COOP_SIGNATURE, |contents| coop.write_to(contents))?;
+        }
+
+        if let Some(metadata) = &self.metadata {
+            write_section(&mut data, META_SIGNATURE, |contents| metadata.write_to(contents))?;
+        }
+
+        if !self.variables.is_empty() {
+            write_section(&mut data, VARS_SIGNATURE, |contents| {
+                contents.write_u32::<LE>(self.variables.len() as u32)?;
+
+                for &(id, value) in self.variables.iter() {
+                    contents.write_u16::<LE>(id)?;
+                    contents.write_i32::<LE>(value)?;
+                }
+
+                Ok(())
+            })?;
+        }
+
         Ok(())
     }
 
@@ -335,16 +686,6 @@ impl GameProfile {
         let equipment = data.read_u32::<LE>()?;
         let control_mode = data.read_u32::<LE>()?;
         let counter = data.read_u32::<LE>()?;
-        let mut weapon_data = [
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-            WeaponData { weapon_id: 0, level: 0, exp: 0, max_ammo: 0, ammo: 0 },
-        ];
         let mut items = [0u32; 32];
         let mut teleporter_slots = [
             TeleporterSlotData { index: 0, event_num: 0 },
@@ -357,13 +698,7 @@ impl GameProfile {
             TeleporterSlotData { index: 0, event_num: 0 },
         ];
 
-        for WeaponData { weapon_id, level, exp, max_ammo, ammo } in &mut weapon_data {
-            *weapon_id = data.read_u32::<LE>()?;
-            *level = data.read_u32::<LE>()?;
-            *exp = data.read_u32::<LE>()?;
-            *max_ammo = data.read_u32::<LE>()?;
-            *ammo = data.read_u32::<LE>()?;
-        }
+        let weapon_data = read_weapon_data(&mut data)?;
 
         for item in &mut items {
             *item = data.read_u32::<LE>()?;
@@ -389,6 +724,35 @@ impl GameProfile {
         let timestamp = data.read_u64::<LE>().unwrap_or(0);
         let difficulty = data.read_u8().unwrap_or(0);
 
+        let mut coop = None;
+        let mut metadata = None;
+        let mut variables = Vec::new();
+
+        // extended sections, absent in saves made by other engines or older versions.
+        // each one is prefixed with its length, so sections added by newer versions are skipped.
+        while let Ok(signature) = data.read_u32::<BE>() {
+            let len = data.read_u32::<LE>()?;
+
+            let mut contents = Vec::new();
+            data.by_ref().take(len as u64).read_to_end(&mut contents)?;
+            if contents.len() != len as usize {
+                return Err(ResourceLoadError(format!("Save section {:08x} is truncated.", signature)));
+            }
+
+            let mut contents = Cursor::new(contents);
+            match signature {
+                COOP_SIGNATURE => coop = Some(CoopData::read_from(&mut contents)?),
+                META_SIGNATURE => metadata = Some(SaveMetadata::read_from(&mut contents)?),
+                VARS_SIGNATURE => {
+                    let count = contents.read_u32::<LE>()?;
+                    for _ in 0..count {
+                        variables.push((contents.read_u16::<LE>()?, contents.read_i32::<LE>()?));
+                    }
+                }
+                _ => log::debug!("Skipping unknown save section {:08x}.", signature),
+            }
+        }
+
         Ok(GameProfile {
             current_map,
             current_song,
@@ -410,6 +774,187 @@ impl GameProfile {
             flags,
             timestamp,
             difficulty,
+            coop,
+            metadata,
+            variables,
         })
     }
 }
+
+#[cfg(test)]
+fn test_profile() -> GameProfile {
+    GameProfile {
+        current_map: 13,
+        current_song: 8,
+        pos_x: 0x1000,
+        pos_y: -0x2000,
+        direction: Direction::Right,
+        max_life: 10,
+        stars: 3,
+        life: 7,
+        current_weapon: 1,
+        current_item: 0,
+        equipment: 0x20,
+        control_mode: 0,
+        counter: 0,
+        weapon_data: empty_weapon_data(),
+        items: [0; 32],
+        teleporter_slots: [
+            TeleporterSlotData { index: 1, event_num: 1001 },
+            TeleporterSlotData { index: 0, event_num: 0 },
+            TeleporterSlotData { index: 0, event_num: 0 },
+            TeleporterSlotData { index: 0, event_num: 0 },
+            TeleporterSlotData { index: 0, event_num: 0 },
+            TeleporterSlotData { index: 0, event_num: 0 },
+            TeleporterSlotData { index: 0, event_num: 0 },
+            TeleporterSlotData { index: 0, event_num: 0 },
+        ],
+        map_flags: [0; 128],
+        flags: [0; 1000],
+        timestamp: 1234,
+        difficulty: 0,
+        coop: None,
+        metadata: None,
+        variables: Vec::new(),
+    }
+}
+
+#[test]
+fn test_variables_round_trip() {
+    let mut profile = test_profile();
+    profile.variables = vec![(1, 42), (7, -5), (9999, i32::MAX)];
+
+    let mut data = Vec::new();
+    profile.write_save(&mut data).unwrap();
+    let loaded = GameProfile::load_from_save(data.as_slice()).unwrap();
+
+    assert_eq!(loaded.variables, profile.variables);
+    assert_eq!(loaded.current_map, 13);
+}
+
+#[test]
+fn test_load_without_variables() {
+    let mut profile = test_profile();
+    profile.metadata = Some(SaveMetadata { play_time: 5000, mod_name: String::new(), thumbnail: None });
+
+    let mut data = Vec::new();
+    profile.write_save(&mut data).unwrap();
+    let loaded = GameProfile::load_from_save(data.as_slice()).unwrap();
+
+    assert!(loaded.variables.is_empty());
+    assert_eq!(loaded.metadata.map(|metadata| metadata.play_time), Some(5000));
+}
+
+#[test]
+fn test_coop_round_trip() {
+    let mut partner_weapons = empty_weapon_data();
+    partner_weapons[0] = WeaponData { weapon_id: 2, level: 3, exp: 40, max_ammo: 0, ammo: 0 };
+    let mut partner_items = [0u32; 32];
+    partner_items[0] = 5;
+
+    let mut profile = test_profile();
+    profile.coop = Some(CoopData {
+        main_player: TargetPlayer::Player2,
+        partner: PartnerData {
+            max_life: 15,
+            stars: 2,
+            life: 11,
+            current_weapon: 0,
+            current_item: 1,
+            equipment: 0x8,
+            weapon_data: partner_weapons,
+            items: partner_items,
+        },
+        player2_skin_location: PlayerSkinLocation::new(1, 64),
+    });
+
+    let mut data = Vec::new();
+    profile.write_save(&mut data).unwrap();
+    let coop = GameProfile::load_from_save(data.as_slice()).unwrap().coop.unwrap();
+
+    assert!(coop.main_player == TargetPlayer::Player2);
+    assert!(coop.player2_skin_location == PlayerSkinLocation::new(1, 64));
+    let partner = &coop.partner;
+    assert_eq!((partner.max_life, partner.stars, partner.life), (15, 2, 11));
+    assert_eq!((partner.current_weapon, partner.current_item, partner.equipment), (0, 1, 0x8));
+    let weapon = &partner.weapon_data[0];
+    assert_eq!((weapon.weapon_id, weapon.level, weapon.exp), (2, 3, 40));
+    assert_eq!(partner.items, partner_items);
+}
+
+#[test]
+fn test_load_vanilla_save() {
+    let mut profile = test_profile();
+    profile.variables = vec![(1, 1)];
+
+    let mut data = Vec::new();
+    profile.write_save(&mut data).unwrap();
+    // saves made by the original game end right after the flags.
+    data.truncate(0x604);
+    let loaded = GameProfile::load_from_save(data.as_slice()).unwrap();
+
+    assert_eq!((loaded.current_map, loaded.life, loaded.timestamp), (13, 7, 0));
+    assert!(loaded.coop.is_none());
+    assert!(loaded.metadata.is_none());
+    assert!(loaded.variables.is_empty());
+}
+
+#[test]
+fn test_skip_unknown_section() {
+    let mut profile = test_profile();
+    profile.variables = vec![(3, 30)];
+
+    let mut data = Vec::new();
+    profile.write_save(&mut data).unwrap();
+
+    // a section from a newer version, followed by the ones this version knows
+    let mut extended = data[..0x604].to_vec();
+    write_section(&mut extended, 0x4e455753, |contents| {
+        contents.extend_from_slice(&[1, 2, 3]);
+        Ok(())
+    })
+    .unwrap();
+    extended.extend_from_slice(&data[0x604..]);
+
+    let loaded = GameProfile::load_from_save(extended.as_slice()).unwrap();
+    assert_eq!(loaded.variables, [(3, 30)]);
+
+    // a section claiming to be longer than the rest of the save
+    let len = data.len();
+    data.truncate(len - 1);
+    assert!(GameProfile::load_from_save(data.as_slice()).is_err());
+}
+
+#[test]
+fn test_metadata_round_trip() {
+    let thumbnail = SaveThumbnail { width: 2, height: 1, data: vec![255, 0, 0, 0, 128, 255] };
+
+    let mut profile = test_profile();
+    profile.metadata =
+        Some(SaveMetadata { play_time: 3_600_000, mod_name: "Wind Fortress".to_owned(), thumbnail: Some(thumbnail) });
+
+    let mut data = Vec::new();
+    profile.write_save(&mut data).unwrap();
+    let metadata = GameProfile::load_from_save(data.as_slice()).unwrap().metadata.unwrap();
+
+    assert_eq!(metadata.play_time, 3_600_000);
+    assert_eq!(metadata.mod_name, "Wind Fortress");
+    let thumbnail = metadata.thumbnail.unwrap();
+    assert_eq!((thumbnail.width, thumbnail.height), (2, 1));
+    assert_eq!(thumbnail.data, [255, 0, 0, 0, 128, 255]);
+}
+
+#[test]
+fn test_metadata_oversized_thumbnail() {
+    let mut data = Vec::new();
+    data.write_u64::<LE>(0).unwrap();
+    data.write_u16::<LE>(0).unwrap();
+    data.write_u16::<LE>(u16::MAX).unwrap();
+    data.write_u16::<LE>(u16::MAX).unwrap();
+
+    assert!(SaveMetadata::read_from(&mut data.as_slice()).is_err());
+
+    let len = data.len();
+    data[len - 4..].copy_from_slice(&[SaveThumbnail::MAX_WIDTH as u8, 0, SaveThumbnail::MAX_HEIGHT as u8 + 1, 0]);
+    assert!(SaveMetadata::read_from(&mut data.as_slice()).is_err());
+}
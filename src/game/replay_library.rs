@@ -0,0 +1,234 @@
+use std::io;
+
+use chrono::{Local, TimeZone};
+
+use crate::components::replay::{GhostFrame, ReplayData, ReplayHeader, ReplaySummary};
+use crate::framework::context::Context;
+use crate::framework::error::GameError::{InvalidValue, ResourceLoadError};
+use crate::framework::error::GameResult;
+use crate::framework::filesystem;
+use crate::game::player::Player;
+
+/// Directory in the user data directory where the replay library is stored.
+const REPLAY_LIBRARY_DIR: &str = "/replays";
+
+const REPLAY_EXTENSION: &str = "rep";
+
+/// Number of replays kept in the library, the oldest ones are removed when a new one is added.
+const REPLAY_LIBRARY_SIZE: usize = 100;
+
+/// Opacity of the ghost player drawn while racing a replay.
+pub const GHOST_ALPHA: u8 = 0x80;
+
+/// A replay in the library along with the metadata shown in the replay browser.
+pub struct ReplayLibraryEntry {
+    pub name: String,
+    pub header: ReplayHeader,
+    pub ticks: usize,
+    pub has_ghost: bool,
+}
+
+impl ReplayLibraryEntry {
+    /// Length of the run as `m:ss.t`, based on the timing mode it was recorded with.
+    pub fn duration(&self) -> String {
+        // frame synchronized timing has no fixed tick length, assume the display runs at 60Hz.
+        let tps = match self.header.settings.timing_mode.get_tps() {
+            0 => 60,
+            tps => tps,
+        };
+
+        let millis = self.ticks as u64 * 1000 / tps as u64;
+        format!("{}:{:02}.{}", millis / 60000, (millis / 1000) % 60, (millis / 100) % 10)
+    }
+
+    /// Local date and time the replay was recorded at, if it's known.
+    pub fn recorded_at(&self) -> Option<String> {
+        if self.header.recorded_at == 0 {
+            return None;
+        }
+
+        Local
+            .timestamp_opt(self.header.recorded_at as i64, 0)
+            .single()
+            .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+    }
+}
+
+/// Lists the replays in the library, newest first. Files that can't be read are skipped.
+pub fn list(ctx: &Context) -> Vec<ReplayLibraryEntry> {
+    let mut entries = Vec::new();
+
+    let Ok(paths) = filesystem::user_read_dir(ctx, REPLAY_LIBRARY_DIR) else {
+        return entries;
+    };
+
+    for path in paths {
+        if path.extension().map_or(true, |ext| ext != REPLAY_EXTENSION) {
+            continue;
+        }
+
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+
+        match load_summary(ctx, name) {
+            Ok(summary) => entries.push(ReplayLibraryEntry {
+                name: name.to_owned(),
+                header: summary.header,
+                ticks: summary.ticks,
+                has_ghost: summary.has_ghost,
+            }),
+            Err(err) => log::warn!("Failed to read replay '{}': {}", name, err),
+        }
+    }
+
+    entries.sort_by(|a, b| b.header.recorded_at.cmp(&a.header.recorded_at).then_with(|| a.name.cmp(&b.name)));
+    entries
+}
+
+pub fn exists(ctx: &Context, name: &str) -> bool {
+    replay_path(name).map_or(false, |path| filesystem::user_exists(ctx, path))
+}
+
+pub fn load(ctx: &Context, name: &str) -> GameResult<ReplayData> {
+    let file = filesystem::user_open(ctx, replay_path(name)?)?;
+    ReplayData::read_from(io::BufReader::new(file))
+}
+
+/// Reads only the header and length of a replay, used to list the library without loading every replay.
+pub fn load_summary(ctx: &Context, name: &str) -> GameResult<ReplaySummary> {
+    let file = filesystem::user_open(ctx, replay_path(name)?)?;
+    ReplayData::read_summary(io::BufReader::new(file))
+}
+
+/// Writes a replay to the library under given name, replacing an existing one.
+pub fn save(ctx: &Context, name: &str, data: &ReplayData) -> GameResult {
+    let path = replay_path(name)?;
+
+    if !filesystem::user_is_dir(ctx, REPLAY_LIBRARY_DIR) {
+        filesystem::user_create_dir(ctx, REPLAY_LIBRARY_DIR)?;
+    }
+
+    let file = filesystem::user_create(ctx, path)?;
+    data.write_to(io::BufWriter::new(file))
+}
+
+/// Adds a replay to the library under a name derived from its mod and recording date and returns that name.
+pub fn add(ctx: &Context, data: &ReplayData) -> GameResult<String> {
+    let name = unique_name(&default_name(&data.header), |name| exists(ctx, name));
+    save(ctx, &name, data)?;
+
+    Ok(name)
+}
+
+/// Removes the oldest replays if the library has more than it keeps.
+pub fn prune(ctx: &Context) {
+    for entry in list(ctx).iter().skip(REPLAY_LIBRARY_SIZE) {
+        match delete(ctx, &entry.name) {
+            Ok(()) => log::info!("Removed old replay '{}' from the library.", entry.name),
+            Err(err) => log::warn!("Failed to remove old replay '{}': {}", entry.name, err),
+        }
+    }
+}
+
+pub fn rename(ctx: &Context, name: &str, new_name: &str) -> GameResult {
+    if name == new_name {
+        return Ok(());
+    }
+
+    let new_path = replay_path(new_name)?;
+    if filesystem::user_exists(ctx, &new_path) {
+        return Err(InvalidValue(format!("A replay named '{}' already exists.", new_name)));
+    }
+
+    let mut file = filesystem::user_open(ctx, replay_path(name)?)?;
+    let mut new_file = filesystem::user_create(ctx, new_path)?;
+    io::copy(&mut file, &mut new_file)?;
+
+    delete(ctx, name)
+}
+
+pub fn delete(ctx: &Context, name: &str) -> GameResult {
+    filesystem::user_delete(ctx, replay_path(name)?)
+}
+
+/// Returns the path of a replay in the library, only plain file names are accepted.
+fn replay_path(name: &str) -> GameResult<String> {
+    if name.is_empty() || !name.chars().all(is_name_char) {
+        return Err(InvalidValue(format!("Invalid replay name: '{}'.", name)));
+    }
+
+    Ok(format!("{}/{}.{}", REPLAY_LIBRARY_DIR, name, REPLAY_EXTENSION))
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+fn default_name(header: &ReplayHeader) -> String {
+    let prefix = if header.mod_id.is_empty() {
+        "replay".to_owned()
+    } else {
+        header.mod_id.chars().map(|c| if is_name_char(c) { c } else { '_' }).collect()
+    };
+
+    match Local.timestamp_opt(header.recorded_at as i64, 0).single() {
+        Some(date) if header.recorded_at != 0 => format!("{}-{}", prefix, date.format("%Y%m%d-%H%M%S")),
+        _ => prefix,
+    }
+}
+
+fn unique_name(base: &str, exists: impl Fn(&str) -> bool) -> String {
+    let mut name = base.to_owned();
+    let mut counter = 2;
+
+    while exists(&name) {
+        name = format!("{}-{}", base, counter);
+        counter += 1;
+    }
+
+    name
+}
+
+/// A recorded run raced against, drawn as a translucent player next to the live one while a new run is recorded.
+pub struct ReplayGhost {
+    pub name: String,
+    track: Vec<GhostFrame>,
+}
+
+impl ReplayGhost {
+    pub fn load(ctx: &Context, name: &str) -> GameResult<ReplayGhost> {
+        let data = load(ctx, name)?;
+        if data.ghost.is_empty() {
+            return Err(ResourceLoadError(format!("Replay '{}' has no ghost track.", name)));
+        }
+
+        Ok(ReplayGhost { name: name.to_owned(), track: data.ghost })
+    }
+
+    /// Moves the ghost player to the frame recorded at given tick of the run. The ghost is hidden
+    /// while the recorded player is on another stage and after the recording ends.
+    pub fn update(&self, player: &mut Player, stage_id: usize, tick: usize) {
+        match self.track.get(tick) {
+            Some(frame) if frame.stage_id as usize == stage_id => {
+                player.set_ghost_frame(frame, tick.checked_sub(1).and_then(|prev| self.track.get(prev)))
+            }
+            _ => player.cond.set_alive(false),
+        }
+    }
+}
+
+#[test]
+fn test_replay_library_names() {
+    assert_eq!(replay_path("boss-rush_2").unwrap(), "/replays/boss-rush_2.rep");
+    assert!(replay_path("").is_err());
+    assert!(replay_path("../290").is_err());
+
+    let mut header = ReplayHeader::new();
+    header.mod_id = "csp.boss rush".to_owned();
+    assert_eq!(default_name(&header), "csp_boss_rush");
+
+    let taken = ["csp_boss_rush", "csp_boss_rush-2"];
+    assert_eq!(unique_name("csp_boss_rush", |name| taken.contains(&name)), "csp_boss_rush-3");
+    assert_eq!(unique_name("other", |name| taken.contains(&name)), "other");
+}
@@ -0,0 +1,269 @@
+//! Headless replay verification, used by the `--verify-replay` launch option.
+//!
+//! The replay is played back on the null backend without any frame pacing, so a full run
+//! is simulated as fast as the CPU allows.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::components::replay::ReplayData;
+use crate::framework::context::Context;
+use crate::framework::error::{GameError, GameResult};
+use crate::game::init_headless;
+use crate::game::save_state::SaveState;
+use crate::game::shared_game_state::{ReplayKind, ReplayState, SharedGameState, TimingMode};
+use crate::scene::game_scene::GameScene;
+use crate::scene::Scene;
+use crate::sound::capture;
+
+/// Exit code returned when the replay played back without issues.
+pub const EXIT_OK: i32 = 0;
+/// Exit code returned when the simulation diverged from the recorded checksums.
+pub const EXIT_DESYNC: i32 = 1;
+/// Exit code returned when the replay couldn't be loaded or the game returned an error.
+pub const EXIT_ERROR: i32 = 2;
+
+pub struct ReplayVerification {
+    pub mod_id: String,
+    pub stage_id: u16,
+    pub engine_version: String,
+    pub ticks_played: usize,
+    pub total_ticks: usize,
+    pub timing_mode: TimingMode,
+    /// Nikumaru counter at the end of the run, or at the last played tick if the run didn't finish.
+    pub time: usize,
+    pub life: u16,
+    pub max_life: u16,
+    pub flags: Vec<usize>,
+    pub finished: bool,
+    pub desync_tick: Option<usize>,
+}
+
+impl ReplayVerification {
+    fn format_time(&self) -> String {
+        let tps = match self.timing_mode {
+            TimingMode::_60Hz => 60,
+            _ => 50,
+        };
+
+        let minutes = self.time / (tps * 60);
+        let seconds = (self.time / tps) % 60;
+        let tenths = (self.time % tps) * 10 / tps;
+
+        format!("{}:{:02}.{}", minutes, seconds, tenths)
+    }
+
+    pub fn print(&self) {
+        let flags = self.flags.iter().map(|f| f.to_string()).collect::<Vec<_>>().join(",");
+
+        println!("mod: {}", if self.mod_id.is_empty() { "-" } else { &self.mod_id });
+        println!("stage: {}", self.stage_id);
+        println!("engine_version: {}", self.engine_version);
+        println!("ticks: {}/{}", self.ticks_played, self.total_ticks);
+        println!("time: {} ({} ticks)", self.format_time(), self.time);
+        println!("hp: {}/{}", self.life, self.max_life);
+        println!("flags: {}", flags);
+        println!("finished: {}", self.finished);
+
+        match self.desync_tick {
+            Some(tick) => println!("status: desync at tick {}", tick),
+            None => println!("status: ok"),
+        }
+    }
+}
+
+/// Plays back given replay file without a window and returns the process exit code.
+///
+/// With `audio_output`, the audio of the playback is captured and written there as a WAV file.
+/// With `save_state_tick`, a save state is taken and restored at that tick, see [restore_save_state_at].
+/// With `start_tick`, the playback seeks to that tick first, see [crate::components::replay::Replay::seek].
+pub fn run(
+    path: &Path,
+    audio_output: Option<&Path>,
+    mut save_state_tick: Option<usize>,
+    start_tick: Option<usize>,
+) -> i32 {
+    let mut samples = Vec::new();
+    let result = play(path, start_tick, false, audio_output.is_some(), |scene, state, ctx| {
+        if let Some(tick) = save_state_tick {
+            if restore_save_state_at(scene, state, ctx, tick)? {
+                save_state_tick = None;
+            }
+        }
+
+        collect_audio(state, &mut samples);
+        Ok(())
+    })
+    .and_then(|result| {
+        if let Some(audio_output) = audio_output {
+            write_audio(audio_output, &samples)?;
+        }
+
+        if let Some(tick) = save_state_tick {
+            return Err(GameError::InvalidValue(format!("Replay ended before the save state tick {}.", tick)));
+        }
+
+        Ok(result)
+    });
+
+    report(result)
+}
+
+/// Takes a save state once the replay reaches given tick and restores it right away, returns whether it did.
+///
+/// Fails if the restored state differs from the snapshot. State the snapshot misses makes the rest
+/// of the playback desync instead.
+pub fn restore_save_state_at(
+    scene: &mut Box<dyn Scene>,
+    state: &mut SharedGameState,
+    ctx: &mut Context,
+    tick: usize,
+) -> GameResult<bool> {
+    let Ok(game_scene) = scene.downcast_mut::<GameScene>() else {
+        return Ok(false);
+    };
+
+    if game_scene.replay.current_tick() != tick {
+        return Ok(false);
+    }
+
+    let mut snapshot = Vec::new();
+    SaveState::capture(state, game_scene)?.write_to(&mut snapshot)?;
+    SaveState::read_from(snapshot.as_slice())?.apply(state, game_scene, ctx)?;
+
+    let mut restored = Vec::new();
+    SaveState::capture(state, game_scene)?.write_to(&mut restored)?;
+
+    if restored != snapshot {
+        return Err(GameError::InvalidValue(format!("Save state taken at tick {} changed after restoring it.", tick)));
+    }
+
+    Ok(true)
+}
+
+/// Moves the audio captured so far into `samples`.
+pub fn collect_audio(state: &mut SharedGameState, samples: &mut Vec<i16>) {
+    if let Some(capture) = state.sound_manager.capture_mut() {
+        samples.append(&mut capture.samples);
+    }
+}
+
+/// Writes audio collected with [collect_audio] to a WAV file.
+pub fn write_audio(path: &Path, samples: &[i16]) -> GameResult {
+    capture::samples_to_wav(samples).write_to(BufWriter::new(File::create(path)?))?;
+    Ok(())
+}
+
+/// Prints the result of a playback and returns the matching process exit code.
+pub fn report(result: GameResult<ReplayVerification>) -> i32 {
+    match result {
+        Ok(result) => {
+            result.print();
+
+            if result.desync_tick.is_some() {
+                EXIT_DESYNC
+            } else {
+                EXIT_OK
+            }
+        }
+        Err(err) => {
+            println!("status: error: {}", err);
+            EXIT_ERROR
+        }
+    }
+}
+
+pub fn verify(path: &Path) -> GameResult<ReplayVerification> {
+    play(path, None, false, false, |_, _, _| Ok(()))
+}
+
+/// Plays back given replay file on the null backend, calling `on_tick` after every tick of the scene.
+///
+/// With `start_tick`, the playback seeks to that tick before the first call of `on_tick`.
+/// With `software_rendering`, textures are loaded and the scene can be drawn in `on_tick`.
+/// With `audio_capture`, the audio of every tick is mixed into memory, see [crate::sound::capture].
+pub fn play(
+    path: &Path,
+    start_tick: Option<usize>,
+    software_rendering: bool,
+    audio_capture: bool,
+    mut on_tick: impl FnMut(&mut Box<dyn Scene>, &mut SharedGameState, &mut Context) -> GameResult,
+) -> GameResult<ReplayVerification> {
+    let data = ReplayData::read_from(std::fs::File::open(path)?)?;
+
+    let (mut context, mut state) = init_headless(software_rendering, audio_capture)?;
+    let ctx = &mut *context;
+
+    if !data.header.mod_id.is_empty() {
+        let mod_path = state
+            .mod_list
+            .mods
+            .iter()
+            .find(|mod_info| mod_info.id == data.header.mod_id)
+            .map(|mod_info| mod_info.path.clone())
+            .ok_or_else(|| GameError::ResourceLoadError(format!("Mod '{}' is not installed.", data.header.mod_id)))?;
+
+        state.mod_path = Some(mod_path);
+        state.reload_resources(ctx)?;
+    }
+
+    let mut result = ReplayVerification {
+        mod_id: data.header.mod_id.clone(),
+        stage_id: data.header.stage_id,
+        engine_version: data.header.engine_version.clone(),
+        ticks_played: 0,
+        total_ticks: data.inputs.len(),
+        timing_mode: data.header.settings.timing_mode,
+        time: 0,
+        life: 0,
+        max_life: 0,
+        flags: Vec::new(),
+        finished: false,
+        desync_tick: None,
+    };
+
+    state.replay_state = ReplayState::Playback(ReplayKind::Best);
+    state.start_new_game(ctx)?;
+
+    let mut scene = state.next_scene.take().unwrap();
+    if let Ok(game_scene) = scene.downcast_mut::<GameScene>() {
+        game_scene.replay.preload(data);
+
+        if let Some(tick) = start_tick {
+            game_scene.replay.seek(tick);
+        }
+    }
+    scene.init(&mut state, ctx)?;
+
+    loop {
+        scene.tick(&mut state, ctx)?;
+        state.sound_manager.end_tick(state.settings.timing_mode.get_tps());
+        on_tick(&mut scene, &mut state, ctx)?;
+
+        let Ok(game_scene) = scene.downcast_ref::<GameScene>() else {
+            break;
+        };
+
+        result.ticks_played = game_scene.replay.current_tick();
+        result.time = game_scene.replay.finish_time.unwrap_or(game_scene.nikumaru.tick);
+        result.life = game_scene.player1.life;
+        result.max_life = game_scene.player1.max_life;
+        result.finished = game_scene.replay.finish_time.is_some();
+        result.desync_tick = game_scene.replay.desync_tick;
+
+        if state.replay_state == ReplayState::None || state.shutdown {
+            break;
+        }
+
+        if let Some(next_scene) = state.next_scene.take() {
+            scene = next_scene;
+            scene.init(&mut state, ctx)?;
+        }
+    }
+
+    result.flags = state.game_flags.iter().enumerate().filter(|(_, set)| *set).map(|(id, _)| id).collect();
+    state.restore_replay_settings();
+
+    Ok(result)
+}
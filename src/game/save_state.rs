@@ -0,0 +1,371 @@
+use std::io;
+use std::mem;
+use std::time::Duration;
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use crate::common::{ControlFlags, FadeState};
+use crate::components::boss_life_bar::BossLifeBar;
+use crate::components::flash::Flash;
+use crate::components::nikumaru::NikumaruCounter;
+use crate::components::replay::ReplayData;
+use crate::components::whimsical_star::WhimsicalStar;
+use crate::framework::context::Context;
+use crate::framework::error::GameError::{InvalidValue, ResourceLoadError};
+use crate::framework::error::GameResult;
+use crate::framework::filesystem;
+use crate::game::caret::Caret;
+use crate::game::frame::Frame;
+use crate::game::inventory::Inventory;
+use crate::game::npc::boss::BossNPC;
+use crate::game::npc::NPC;
+use crate::game::player::Player;
+use crate::game::scripting::tsc::text_script::SavedTextScriptState;
+use crate::game::scripting::tsc::variables::ScriptVariables;
+use crate::game::shared_game_state::{PlayerCount, ReplayState, SharedGameState};
+use crate::game::weapon::bullet::Bullet;
+use crate::scene::game_scene::{GameScene, LightingMode};
+use crate::sound::SongPosition;
+use crate::util::bitvec::BitVec;
+
+/// Version of the save state format written by this build, snapshots with a different version are rejected.
+pub const SAVE_STATE_VERSION: u32 = 1;
+
+/// Largest decompressed size of a compressed save state.
+const MAX_DECOMPRESSED_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Directory in the user data directory where named save states are stored.
+const SAVE_STATE_DIR: &str = "/savestates";
+
+/// A snapshot of the whole simulation state of a `GameScene` and the `SharedGameState`.
+///
+/// Unlike profiles, save states capture everything needed to continue the game from the exact same tick,
+/// including NPCs, bullets, the script VM and the random number generators. They are only valid for the game
+/// data they were made with.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct SaveState {
+    pub version: u32,
+    pub stage_id: usize,
+    tick: u32,
+    two_players: bool,
+    player1: Player,
+    player2: Player,
+    inventory_player1: Inventory,
+    inventory_player2: Inventory,
+    frame: Frame,
+    /// Slots of the NPC list up to its current capacity, including dead ones.
+    npcs: Vec<NPC>,
+    npc_seed: i32,
+    boss: BossNPC,
+    bullets: Vec<Bullet>,
+    bullet_seed: u64,
+    tiles: Vec<u8>,
+    lighting_mode: LightingMode,
+    boss_life_bar: BossLifeBar,
+    nikumaru: NikumaruCounter,
+    flash: Flash,
+    #[serde(default)]
+    whimsical_star: WhimsicalStar,
+    #[serde(default)]
+    map_name_counter: u16,
+    control_flags: ControlFlags,
+    game_flags: BitVec,
+    map_flags: BitVec,
+    #[serde(default)]
+    script_variables: ScriptVariables,
+    fade_state: FadeState,
+    game_rng: u64,
+    effect_rng: u64,
+    quake_counter: u16,
+    super_quake_counter: u16,
+    quake_rumble_counter: u32,
+    super_quake_rumble_counter: u32,
+    teleporter_slots: Vec<(u16, u16)>,
+    carets: Vec<Caret>,
+    npc_super_pos: (i32, i32),
+    npc_curly_target: (i32, i32),
+    npc_curly_counter: u16,
+    water_level: i32,
+    #[serde(default)]
+    tutorial_counter: u16,
+    play_time: Duration,
+    text_script: SavedTextScriptState,
+    song: SongPosition,
+    /// Replay recorded up to this snapshot without its keyframes, encoded with `ReplayData::write_without_keyframes`.
+    replay: Option<Vec<u8>>,
+}
+
+impl SaveState {
+    pub fn capture(state: &mut SharedGameState, game_scene: &GameScene) -> GameResult<SaveState> {
+        let mut save_state = SaveState::capture_keyframe(state, game_scene);
+
+        if state.replay_state == ReplayState::Recording {
+            let mut data = Vec::new();
+            game_scene.replay.data.write_without_keyframes(&mut data)?;
+            save_state.replay = Some(data);
+        }
+
+        Ok(save_state)
+    }
+
+    /// Captures the simulation state without the replay, used for the keyframes of a replay.
+    pub fn capture_keyframe(state: &mut SharedGameState, game_scene: &GameScene) -> SaveState {
+        let npc_list = &game_scene.npc_list;
+        let npcs = (0..npc_list.current_capacity() as usize)
+            .filter_map(|id| npc_list.get_npc(id))
+            .map(|npc| npc.borrow(&game_scene.npc_token).clone())
+            .collect();
+
+        SaveState {
+            version: SAVE_STATE_VERSION,
+            stage_id: game_scene.stage_id,
+            tick: game_scene.tick,
+            two_players: state.player_count == PlayerCount::Two,
+            player1: game_scene.player1.clone(),
+            player2: game_scene.player2.clone(),
+            inventory_player1: game_scene.inventory_player1.clone(),
+            inventory_player2: game_scene.inventory_player2.clone(),
+            frame: game_scene.frame.clone(),
+            npcs,
+            npc_seed: npc_list.rng_seed(),
+            boss: game_scene.boss.clone(),
+            bullets: game_scene.bullet_manager.bullets.clone(),
+            bullet_seed: game_scene.bullet_manager.seeder.dump_state(),
+            tiles: game_scene.stage.map.tiles.clone(),
+            lighting_mode: game_scene.lighting_mode,
+            boss_life_bar: game_scene.boss_life_bar.clone(),
+            nikumaru: game_scene.nikumaru,
+            flash: game_scene.flash.clone(),
+            whimsical_star: game_scene.whimsical_star.clone(),
+            map_name_counter: game_scene.map_name_counter,
+            control_flags: state.control_flags,
+            game_flags: state.game_flags.clone(),
+            map_flags: state.map_flags.clone(),
+            script_variables: state.script_variables.clone(),
+            fade_state: state.fade_state,
+            game_rng: state.game_rng.dump_state(),
+            effect_rng: state.effect_rng.dump_state(),
+            quake_counter: state.quake_counter,
+            super_quake_counter: state.super_quake_counter,
+            quake_rumble_counter: state.quake_rumble_counter,
+            super_quake_rumble_counter: state.super_quake_rumble_counter,
+            teleporter_slots: state.teleporter_slots.clone(),
+            carets: state.carets.clone(),
+            npc_super_pos: state.npc_super_pos,
+            npc_curly_target: state.npc_curly_target,
+            npc_curly_counter: state.npc_curly_counter,
+            water_level: state.water_level,
+            tutorial_counter: state.tutorial_counter,
+            play_time: state.play_time,
+            text_script: state.textscript_vm.save_state(),
+            song: state.sound_manager.song_position(),
+            replay: None,
+        }
+    }
+
+    /// Restores this snapshot. If it was made on another stage, the stage is loaded as the next scene
+    /// and the snapshot is applied once it's initialized.
+    pub fn restore(self, state: &mut SharedGameState, game_scene: &mut GameScene, ctx: &mut Context) -> GameResult {
+        if let ReplayState::Playback(_) = state.replay_state {
+            return Err(InvalidValue("Save states cannot be loaded during replay playback.".to_owned()));
+        }
+
+        self.restore_keyframe(state, game_scene, ctx)
+    }
+
+    /// Same as [SaveState::restore], but also allowed during playback to restore replay keyframes.
+    pub fn restore_keyframe(
+        self,
+        state: &mut SharedGameState,
+        game_scene: &mut GameScene,
+        ctx: &mut Context,
+    ) -> GameResult {
+        if self.stage_id == game_scene.stage_id {
+            return self.apply(state, game_scene, ctx);
+        }
+
+        if self.stage_id >= state.stages.len() {
+            return Err(InvalidValue(format!("Save state refers to an unknown stage {}.", self.stage_id)));
+        }
+
+        state.player_count = if self.two_players { PlayerCount::Two } else { PlayerCount::One };
+
+        let mut next_scene = GameScene::new(state, ctx, self.stage_id)?;
+        next_scene.replay = game_scene.replay.clone();
+        next_scene.pending_save_state = Some(Box::new(self));
+        state.next_scene = Some(Box::new(next_scene));
+
+        Ok(())
+    }
+
+    /// Overwrites the state of given scene, which has to be an initialized scene of the same stage.
+    pub(crate) fn apply(
+        &self,
+        state: &mut SharedGameState,
+        game_scene: &mut GameScene,
+        ctx: &mut Context,
+    ) -> GameResult {
+        if self.tiles.len() != game_scene.stage.map.tiles.len() {
+            return Err(InvalidValue("Save state doesn't match the stage map.".to_owned()));
+        }
+
+        match state.replay_state {
+            ReplayState::Recording => match &self.replay {
+                // continuing the recording from the snapshot keeps it playable from the start
+                Some(data) => {
+                    let mut replay = ReplayData::read_from(data.as_slice())?;
+
+                    // keyframes aren't part of the snapshot, the ones before it are kept if it's the same recording
+                    let current = &mut game_scene.replay.data;
+                    if current.header.rng_seed == replay.header.rng_seed
+                        && current.header.recorded_at == replay.header.recorded_at
+                    {
+                        replay.keyframes = mem::take(&mut current.keyframes);
+                        replay.keyframes.retain(|keyframe| keyframe.tick as usize <= replay.inputs.len());
+                    }
+
+                    game_scene.replay.data = replay;
+                }
+                None => {
+                    log::warn!("Save state was made without recording a replay, replay recording stopped.");
+                    state.replay_state = ReplayState::None;
+                }
+            },
+            // keyframes and the replay verifier, the playback continues with the restored state
+            ReplayState::Playback(_) | ReplayState::None => {}
+        }
+
+        if self.two_players {
+            if !game_scene.player2.cond.alive() {
+                // loads the skin, the rest of the player is overwritten below
+                game_scene.add_player2(state, ctx);
+            }
+            state.player_count = PlayerCount::Two;
+        } else {
+            state.player_count = PlayerCount::One;
+        }
+
+        game_scene.tick = self.tick;
+        restore_player(&mut game_scene.player1, &self.player1);
+        restore_player(&mut game_scene.player2, &self.player2);
+        game_scene.inventory_player1 = self.inventory_player1.clone();
+        game_scene.inventory_player2 = self.inventory_player2.clone();
+        game_scene.frame = self.frame.clone();
+
+        game_scene.npc_list.set_rng_seed(self.npc_seed);
+        game_scene.npc_list.restore(&mut game_scene.npc_token, &self.npcs)?;
+        game_scene.npc_debug.selected = None;
+        game_scene.boss = self.boss.clone();
+        game_scene.bullet_manager.bullets = self.bullets.clone();
+        game_scene.bullet_manager.new_bullets.clear();
+        game_scene.bullet_manager.seeder.load_state(self.bullet_seed);
+
+        game_scene.stage.map.tiles.copy_from_slice(&self.tiles);
+        game_scene.lighting_mode = self.lighting_mode;
+        game_scene.boss_life_bar = self.boss_life_bar.clone();
+        game_scene.nikumaru = self.nikumaru;
+        game_scene.flash = self.flash.clone();
+        game_scene.whimsical_star = self.whimsical_star.clone();
+        // the sprites of the stars depend on the skin, which isn't part of the snapshot
+        game_scene.whimsical_star.init(&game_scene.player1);
+        game_scene.map_name_counter = self.map_name_counter;
+
+        state.control_flags = self.control_flags;
+        state.game_flags = self.game_flags.clone();
+        state.map_flags = self.map_flags.clone();
+        state.script_variables = self.script_variables.clone();
+        state.fade_state = self.fade_state;
+        state.game_rng.load_state(self.game_rng);
+        state.effect_rng.load_state(self.effect_rng);
+        state.quake_counter = self.quake_counter;
+        state.super_quake_counter = self.super_quake_counter;
+        state.quake_rumble_counter = self.quake_rumble_counter;
+        state.super_quake_rumble_counter = self.super_quake_rumble_counter;
+        state.teleporter_slots = self.teleporter_slots.clone();
+        state.carets = self.carets.clone();
+        state.npc_super_pos = self.npc_super_pos;
+        state.npc_curly_target = self.npc_curly_target;
+        state.npc_curly_counter = self.npc_curly_counter;
+        state.water_level = self.water_level;
+        state.tutorial_counter = self.tutorial_counter;
+        state.play_time = self.play_time;
+        state.textscript_vm.restore_state(self.text_script.clone());
+
+        let _ = state.sound_manager.set_song_position(&self.song, &state.constants, &state.settings, ctx);
+
+        Ok(())
+    }
+
+    pub fn write_to<W: io::Write>(&self, data: W) -> GameResult {
+        serde_json::to_writer(data, self)?;
+
+        Ok(())
+    }
+
+    /// Writes this snapshot deflate compressed, used for the keyframes of replays.
+    pub fn write_compressed<W: io::Write>(&self, data: W) -> GameResult {
+        let mut encoder = DeflateEncoder::new(data, Compression::default());
+        self.write_to(&mut encoder)?;
+        encoder.finish()?;
+
+        Ok(())
+    }
+
+    pub fn read_compressed<R: io::Read>(data: R) -> GameResult<SaveState> {
+        SaveState::read_from(io::Read::take(DeflateDecoder::new(data), MAX_DECOMPRESSED_SIZE))
+    }
+
+    pub fn read_from<R: io::Read>(data: R) -> GameResult<SaveState> {
+        let save_state: SaveState = serde_json::from_reader(data)
+            .map_err(|err| ResourceLoadError(format!("Failed to parse save state: {}", err)))?;
+
+        if save_state.version != SAVE_STATE_VERSION {
+            return Err(ResourceLoadError(format!("Unsupported save state version {}.", save_state.version)));
+        }
+
+        Ok(save_state)
+    }
+
+    /// Writes this snapshot to the save state directory under given name.
+    pub fn save_to_file(&self, ctx: &mut Context, name: &str) -> GameResult {
+        if !filesystem::user_is_dir(ctx, SAVE_STATE_DIR) {
+            filesystem::user_create_dir(ctx, SAVE_STATE_DIR)?;
+        }
+
+        let file = filesystem::user_create(ctx, save_state_path(name)?)?;
+        self.write_to(file)
+    }
+
+    /// Reads a snapshot with given name from the save state directory.
+    pub fn load_from_file(ctx: &mut Context, name: &str) -> GameResult<SaveState> {
+        let file = filesystem::user_open(ctx, save_state_path(name)?)?;
+        SaveState::read_from(file)
+    }
+}
+
+/// Returns the path of a named save state, only plain file names are accepted.
+fn save_state_path(name: &str) -> GameResult<String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(InvalidValue(format!("Invalid save state name: '{}'.", name)));
+    }
+
+    Ok(format!("{}/{}.json", SAVE_STATE_DIR, name))
+}
+
+/// Copies the simulation state of a player, keeping its skin and controller.
+fn restore_player(player: &mut Player, saved: &Player) {
+    let mut restored = saved.clone();
+    mem::swap(&mut restored.skin, &mut player.skin);
+    mem::swap(&mut restored.controller, &mut player.controller);
+    *player = restored;
+}
+
+#[test]
+fn test_save_state_path() {
+    assert_eq!(save_state_path("boss-rush_2").unwrap(), "/savestates/boss-rush_2.json");
+    assert!(save_state_path("").is_err());
+    assert!(save_state_path("../Profile").is_err());
+    assert!(save_state_path("a/b").is_err());
+}
@@ -0,0 +1,102 @@
+//! Checks the scripts of the active data root, used by the `--lint-tsc` launch option.
+//!
+//! Every diagnostic is printed to stdout as a single line JSON object, logs go to stderr as usual.
+
+use std::io::Read;
+
+use crate::framework::context::Context;
+use crate::framework::error::GameResult;
+use crate::framework::filesystem;
+use crate::game::init_headless;
+use crate::game::map::NPCData;
+use crate::game::npc::NPC;
+use crate::game::scripting::tsc::encryption::decrypt_tsc;
+use crate::game::scripting::tsc::linter::{LintDiagnostic, LintScriptKind, LintSeverity, TscLinter};
+use crate::game::shared_game_state::SharedGameState;
+
+/// Exit code returned when no errors were found, warnings don't affect it.
+pub const EXIT_OK: i32 = 0;
+/// Exit code returned when at least one script has errors.
+pub const EXIT_ERRORS: i32 = 1;
+/// Exit code returned when the game data couldn't be loaded.
+pub const EXIT_FAILURE: i32 = 2;
+
+/// Lints every script of the active data root and returns the process exit code.
+///
+/// `check_unread_flags` enables the `unread-flag` check, see [TscLinter::set_check_unread_flags].
+pub fn run(check_unread_flags: bool) -> i32 {
+    match lint(check_unread_flags) {
+        Ok(diagnostics) => {
+            for diagnostic in diagnostics.iter() {
+                match serde_json::to_string(diagnostic) {
+                    Ok(line) => println!("{}", line),
+                    Err(err) => log::error!("Failed to serialize diagnostic: {}", err),
+                }
+            }
+
+            if diagnostics.iter().any(|d| d.severity == LintSeverity::Error) {
+                EXIT_ERRORS
+            } else {
+                EXIT_OK
+            }
+        }
+        Err(err) => {
+            log::error!("Failed to lint scripts: {}", err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+pub fn lint(check_unread_flags: bool) -> GameResult<Vec<LintDiagnostic>> {
+    let (mut context, state) = init_headless(false, false)?;
+    let ctx = &mut *context;
+
+    let mut linter = TscLinter::new(state.stages.len());
+    linter.set_check_unread_flags(check_unread_flags);
+
+    add_script(&mut linter, &state, ctx, "Head.tsc", LintScriptKind::Head)?;
+    add_script(&mut linter, &state, ctx, "ArmsItem.tsc", LintScriptKind::Inventory)?;
+    add_script(&mut linter, &state, ctx, "StageSelect.tsc", LintScriptKind::StageSelect)?;
+
+    for (id, stage) in state.stages.iter().enumerate() {
+        let path = ["Stage/", &stage.map, ".tsc"].join("");
+        if stage.map.is_empty() || !filesystem::exists_find(ctx, &state.constants.base_paths, &path) {
+            log::debug!("Stage {} has no script, skipping.", id);
+            continue;
+        }
+
+        add_script(&mut linter, &state, ctx, &path, LintScriptKind::Stage(id))?;
+
+        // NPCs that appear or disappear depending on a flag read it as well.
+        let pxe_path = ["Stage/", &stage.map, ".pxe"].join("");
+        if let Ok(pxe_file) = filesystem::open_find(ctx, &state.constants.base_paths, &pxe_path) {
+            for npc_data in NPCData::load_from(pxe_file)?.iter() {
+                let npc = NPC::create_from_data(npc_data, &state.npc_table, state.tile_size);
+                if npc.npc_flags.appear_when_flag_set() || npc.npc_flags.hide_unless_flag_set() {
+                    linter.add_flag_read(npc_data.flag_num);
+                }
+            }
+        }
+    }
+
+    Ok(linter.finish())
+}
+
+fn add_script(
+    linter: &mut TscLinter,
+    state: &SharedGameState,
+    ctx: &mut Context,
+    path: &str,
+    kind: LintScriptKind,
+) -> GameResult {
+    let mut data = Vec::new();
+    filesystem::open_find(ctx, &state.constants.base_paths, path)?.read_to_end(&mut data)?;
+
+    if state.constants.textscript.encrypted {
+        decrypt_tsc(&mut data);
+    }
+
+    linter.add_script(path, kind, &data);
+
+    Ok(())
+}
@@ -1,8 +1,14 @@
-use std::io::{Cursor, Read};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 
 use crate::framework::error::GameError::ParseError;
 use crate::framework::error::GameResult;
-use crate::game::scripting::tsc::text_script::TextScriptEncoding;
+use crate::game::scripting::tsc::text_script::{TextScript, TextScriptEncoding};
+
+const BYTECODE_MAGIC: &[u8; 4] = b"TSCB";
+const BYTECODE_VERSION: u16 = 1;
 
 pub fn put_varint(val: i32, out: &mut Vec<u8>) {
     let mut x = ((val as u32) >> 31) ^ ((val as u32) << 1);
@@ -79,6 +85,54 @@ pub fn put_string(buffer: &mut Vec<u8>, out: &mut Vec<u8>, encoding: TextScriptE
     out.append(&mut tmp_buf);
 }
 
+impl TextScript {
+    /// Writes the compiled events into a standalone bytecode file.
+    pub fn write_bytecode<W: Write>(&self, mut out: W) -> GameResult {
+        let ids = self.get_event_ids();
+
+        out.write_all(BYTECODE_MAGIC)?;
+        out.write_u16::<LE>(BYTECODE_VERSION)?;
+        out.write_u32::<LE>(ids.len() as u32)?;
+
+        for id in ids {
+            let bytecode = &self.event_map[&id];
+
+            out.write_u16::<LE>(id)?;
+            out.write_u32::<LE>(bytecode.len() as u32)?;
+            out.write_all(bytecode)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads events from a bytecode file created by [TextScript::write_bytecode].
+    pub fn read_bytecode<R: Read>(mut data: R) -> GameResult<TextScript> {
+        let mut magic = [0u8; 4];
+        data.read_exact(&mut magic)?;
+        if &magic != BYTECODE_MAGIC {
+            return Err(ParseError("Invalid bytecode file magic.".to_owned()));
+        }
+
+        let version = data.read_u16::<LE>()?;
+        if version != BYTECODE_VERSION {
+            return Err(ParseError(format!("Unsupported bytecode version: {}", version)));
+        }
+
+        let count = data.read_u32::<LE>()?;
+        let mut event_map = HashMap::new();
+
+        for _ in 0..count {
+            let id = data.read_u16::<LE>()?;
+            let mut bytecode = vec![0u8; data.read_u32::<LE>()? as usize];
+            data.read_exact(&mut bytecode)?;
+
+            event_map.insert(id, bytecode);
+        }
+
+        Ok(TextScript { event_map })
+    }
+}
+
 #[test]
 fn test_varint() {
     for n in -4000..=4000 {
@@ -92,3 +146,28 @@ fn test_varint() {
         assert_eq!(result, n);
     }
 }
+
+#[test]
+fn test_bytecode_roundtrip() {
+    let script = "#0090\r\n<MNA<CMU0008<FAI0000<END\r\n#0091\r\n<MSGHello!<NOD<FL+0010<TRA0012:0094:0002:0003\r\n";
+    let compiled = TextScript::compile(script.as_bytes(), true, TextScriptEncoding::UTF8).unwrap();
+
+    let mut file = Vec::new();
+    compiled.write_bytecode(&mut file).unwrap();
+    let loaded = TextScript::read_bytecode(Cursor::new(file)).unwrap();
+    assert_eq!(loaded.event_map, compiled.event_map);
+
+    let decompiled = loaded.decompile_to_tsc(TextScriptEncoding::UTF8).unwrap();
+    let recompiled = TextScript::compile(&decompiled, true, TextScriptEncoding::UTF8).unwrap();
+    assert_eq!(recompiled.event_map, compiled.event_map);
+}
+
+#[test]
+fn test_variable_opcodes_roundtrip() {
+    let script = b"#0091\r\n<VAR0001:0010<VA+0001:0005<VA-0001:0002<VRN0002:0001:0006<VAJ0001:0005:0013:0092\
+                   <MSG<VNM0001<END";
+    let compiled = TextScript::compile(script, true, TextScriptEncoding::UTF8).unwrap();
+
+    let decompiled = compiled.decompile_to_tsc(TextScriptEncoding::UTF8).unwrap();
+    assert_eq!(decompiled, script);
+}
@@ -9,7 +9,7 @@ use crate::framework::error::GameResult;
 use crate::game::scripting::tsc::bytecode_utils::{put_string, put_varint};
 use crate::game::scripting::tsc::credit_script::CreditScript;
 use crate::game::scripting::tsc::opcodes::{CreditOpCode, TSCOpCode};
-use crate::game::scripting::tsc::parse_utils::{expect_char, read_number, skip_until};
+use crate::game::scripting::tsc::parse_utils::{consumed, error_at, expect_char, read_number, skip_until};
 use crate::game::scripting::tsc::text_script::{TextScript, TextScriptEncoding};
 
 impl TextScript {
@@ -23,7 +23,8 @@ impl TextScript {
             match chr {
                 b'#' => {
                     iter.next();
-                    let event_num = read_number(&mut iter)? as u16;
+                    let event_num =
+                        read_number(&mut iter).map_err(|e| error_at(data, consumed(data, &iter), e))? as u16;
                     if iter.peek().is_some() {
                         skip_until(b'\n', &mut iter)?;
                         iter.next();
@@ -32,7 +33,11 @@ impl TextScript {
 
                     if event_map.contains_key(&event_num) {
                         if strict {
-                            return Err(ParseError(format!("Event {} has been defined twice.", event_num)));
+                            return Err(error_at(
+                                data,
+                                consumed(data, &iter),
+                                ParseError(format!("Event {} has been defined twice.", event_num)),
+                            ));
                         }
 
                         match skip_until(b'#', &mut iter).ok() {
@@ -45,7 +50,8 @@ impl TextScript {
                         }
                     }
 
-                    let bytecode = TextScript::compile_event(&mut iter, strict, encoding)?;
+                    let bytecode = TextScript::compile_event(&mut iter, strict, encoding)
+                        .map_err(|e| error_at(data, consumed(data, &iter), e))?;
                     log::debug!("Successfully compiled event #{} ({} bytes generated).", event_num, bytecode.len());
                     event_map.insert(event_num, bytecode);
                 }
@@ -59,7 +65,11 @@ impl TextScript {
                         continue;
                     }
 
-                    return Err(ParseError(format!("Unexpected token in event {}: {}", last_event, n as char)));
+                    return Err(error_at(
+                        data,
+                        consumed(data, &iter) + 1,
+                        ParseError(format!("Unexpected token in event {}: {}", last_event, n as char)),
+                    ));
                 }
             }
         }
@@ -139,173 +149,20 @@ impl TextScript {
     ) -> GameResult {
         let instr = TSCOpCode::from_str(code).map_err(|_| ParseError(format!("Unknown opcode: {}", code)))?;
 
-        match instr {
-            // Zero operand codes
-            TSCOpCode::AEp
-            | TSCOpCode::CAT
-            | TSCOpCode::CIL
-            | TSCOpCode::CLO
-            | TSCOpCode::CLR
-            | TSCOpCode::CPS
-            | TSCOpCode::CRE
-            | TSCOpCode::CSS
-            | TSCOpCode::END
-            | TSCOpCode::ESC
-            | TSCOpCode::FLA
-            | TSCOpCode::FMU
-            | TSCOpCode::FRE
-            | TSCOpCode::HMC
-            | TSCOpCode::INI
-            | TSCOpCode::KEY
-            | TSCOpCode::LDP
-            | TSCOpCode::MLP
-            | TSCOpCode::MM0
-            | TSCOpCode::MNA
-            | TSCOpCode::MS2
-            | TSCOpCode::MS3
-            | TSCOpCode::MSG
-            | TSCOpCode::NOD
-            | TSCOpCode::PRI
-            | TSCOpCode::RMU
-            | TSCOpCode::SAT
-            | TSCOpCode::SLP
-            | TSCOpCode::SMC
-            | TSCOpCode::SPS
-            | TSCOpCode::STC
-            | TSCOpCode::SVP
-            | TSCOpCode::TUR
-            | TSCOpCode::WAS
-            | TSCOpCode::ZAM
-            | TSCOpCode::HM2
-            | TSCOpCode::POP
-            | TSCOpCode::KE2
-            | TSCOpCode::FR2 => {
-                put_varint(instr as i32, out);
-            }
-            // One operand codes
-            TSCOpCode::BOA
-            | TSCOpCode::BSL
-            | TSCOpCode::FOM
-            | TSCOpCode::QUA
-            | TSCOpCode::UNI
-            | TSCOpCode::MYB
-            | TSCOpCode::MYD
-            | TSCOpCode::FAI
-            | TSCOpCode::FAO
-            | TSCOpCode::WAI
-            | TSCOpCode::FAC
-            | TSCOpCode::GIT
-            | TSCOpCode::NUM
-            | TSCOpCode::DNA
-            | TSCOpCode::DNP
-            | TSCOpCode::FLm
-            | TSCOpCode::FLp
-            | TSCOpCode::MPp
-            | TSCOpCode::SKm
-            | TSCOpCode::SKp
-            | TSCOpCode::EQp
-            | TSCOpCode::EQm
-            | TSCOpCode::MLp
-            | TSCOpCode::ITp
-            | TSCOpCode::ITm
-            | TSCOpCode::AMm
-            | TSCOpCode::MPJ
-            | TSCOpCode::YNJ
-            | TSCOpCode::EVE
-            | TSCOpCode::XX1
-            | TSCOpCode::SIL
-            | TSCOpCode::LIp
-            | TSCOpCode::SOU
-            | TSCOpCode::CMU
-            | TSCOpCode::SSS
-            | TSCOpCode::ACH
-            | TSCOpCode::S2MV
-            | TSCOpCode::S2PJ
-            | TSCOpCode::PSH => {
-                let operand = read_number(iter)?;
-                put_varint(instr as i32, out);
-                put_varint(operand as i32, out);
-            }
-            // Two operand codes
-            TSCOpCode::FON
-            | TSCOpCode::FOB
-            | TSCOpCode::MOV
-            | TSCOpCode::AMp
-            | TSCOpCode::NCJ
-            | TSCOpCode::ECJ
-            | TSCOpCode::FLJ
-            | TSCOpCode::ITJ
-            | TSCOpCode::SKJ
-            | TSCOpCode::AMJ
-            | TSCOpCode::UNJ
-            | TSCOpCode::SMP
-            | TSCOpCode::PSp
-            | TSCOpCode::IpN
-            | TSCOpCode::FFm => {
-                let operand_a = read_number(iter)?;
-                if strict {
-                    expect_char(b':', iter)?;
-                } else {
-                    iter.next().ok_or_else(|| ParseError("Script unexpectedly ended.".to_owned()))?;
-                }
-                let operand_b = read_number(iter)?;
+        let operand_count = instr.operand_count().ok_or_else(|| ParseError(format!("Unknown opcode: {}", code)))?;
 
-                put_varint(instr as i32, out);
-                put_varint(operand_a as i32, out);
-                put_varint(operand_b as i32, out);
-            }
-            // Three operand codes
-            TSCOpCode::ANP | TSCOpCode::CNP | TSCOpCode::INP | TSCOpCode::TAM | TSCOpCode::CMP | TSCOpCode::INJ => {
-                let operand_a = read_number(iter)?;
+        put_varint(instr as i32, out);
+        for idx in 0..operand_count {
+            if idx != 0 {
                 if strict {
                     expect_char(b':', iter)?;
                 } else {
                     iter.next().ok_or_else(|| ParseError("Script unexpectedly ended.".to_owned()))?;
                 }
-                let operand_b = read_number(iter)?;
-                if strict {
-                    expect_char(b':', iter)?;
-                } else {
-                    iter.next().ok_or_else(|| ParseError("Script unexpectedly ended.".to_owned()))?;
-                }
-                let operand_c = read_number(iter)?;
-
-                put_varint(instr as i32, out);
-                put_varint(operand_a as i32, out);
-                put_varint(operand_b as i32, out);
-                put_varint(operand_c as i32, out);
             }
-            // Four operand codes
-            TSCOpCode::TRA | TSCOpCode::MNP | TSCOpCode::SNP => {
-                let operand_a = read_number(iter)?;
-                if strict {
-                    expect_char(b':', iter)?;
-                } else {
-                    iter.next().ok_or_else(|| ParseError("Script unexpectedly ended.".to_owned()))?;
-                }
-                let operand_b = read_number(iter)?;
-                if strict {
-                    expect_char(b':', iter)?;
-                } else {
-                    iter.next().ok_or_else(|| ParseError("Script unexpectedly ended.".to_owned()))?;
-                }
-                let operand_c = read_number(iter)?;
-                if strict {
-                    expect_char(b':', iter)?;
-                } else {
-                    iter.next().ok_or_else(|| ParseError("Script unexpectedly ended.".to_owned()))?;
-                }
-                let operand_d = read_number(iter)?;
 
-                put_varint(instr as i32, out);
-                put_varint(operand_a as i32, out);
-                put_varint(operand_b as i32, out);
-                put_varint(operand_c as i32, out);
-                put_varint(operand_d as i32, out);
-            }
-            TSCOpCode::_NOP | TSCOpCode::_UNI | TSCOpCode::_STR | TSCOpCode::_END => {
-                unreachable!()
-            }
+            let operand = read_number(iter)?;
+            put_varint(operand as i32, out);
         }
 
         Ok(())
@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+use std::io::Cursor;
+use std::str::FromStr;
+
+use num_traits::FromPrimitive;
+
+use crate::game::scripting::tsc::bytecode_utils::read_cur_varint;
+use crate::game::scripting::tsc::opcodes::TSCOpCode;
+
+/// Breakpoints and stepping state of the text script VM, driven by the live debugger.
+///
+/// The VM asks [TextScriptDebugger::should_pause] before executing each instruction, and stays
+/// at the same instruction pointer for as long as it returns true.
+pub struct TextScriptDebugger {
+    /// Pauses execution before the next instruction.
+    pub paused: bool,
+    /// Events that pause execution before their first instruction.
+    pub event_breakpoints: BTreeSet<u16>,
+    /// Opcode names (eg. `FL+`) that pause execution before they're executed.
+    pub opcode_breakpoints: BTreeSet<String>,
+    /// Event and instruction pointer where the execution is paused at.
+    pub position: Option<(u16, u32)>,
+    run_to_end: bool,
+    resume_from: Option<(u16, u32)>,
+}
+
+impl TextScriptDebugger {
+    pub fn new() -> TextScriptDebugger {
+        TextScriptDebugger {
+            paused: false,
+            event_breakpoints: BTreeSet::new(),
+            opcode_breakpoints: BTreeSet::new(),
+            position: None,
+            run_to_end: false,
+            resume_from: None,
+        }
+    }
+
+    /// Returns true if the debugger has nothing to do, which lets the VM skip decoding the opcode.
+    pub fn is_idle(&self) -> bool {
+        !self.paused && !self.run_to_end && self.event_breakpoints.is_empty() && self.opcode_breakpoints.is_empty()
+    }
+
+    pub fn is_running_to_end(&self) -> bool {
+        self.run_to_end
+    }
+
+    /// Adds a breakpoint on an event number (eg. `#0200`) or opcode (eg. `<TRA`).
+    /// Returns false if the input is neither.
+    pub fn add_breakpoint(&mut self, input: &str) -> bool {
+        let input = input.trim().trim_start_matches(['#', '<']);
+
+        if let Ok(event) = input.parse::<u16>() {
+            self.event_breakpoints.insert(event);
+            return true;
+        }
+
+        let name = input.to_ascii_uppercase();
+        if TSCOpCode::from_str(&name).ok().and_then(|op| op.operand_count()).is_none() {
+            return false;
+        }
+
+        self.opcode_breakpoints.insert(name);
+        true
+    }
+
+    /// Executes the instruction the script is paused at and pauses again before the next one.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.resume_from = self.position;
+        }
+    }
+
+    /// Resumes execution until the next breakpoint.
+    pub fn resume(&mut self) {
+        self.resume_from = self.position.take();
+        self.paused = false;
+    }
+
+    /// Resumes execution and pauses again before the next `<END`.
+    pub fn run_to_end(&mut self) {
+        self.resume();
+        self.run_to_end = true;
+    }
+
+    /// Forgets the pending stepping commands, called when the script ends or another one is started.
+    pub fn reset(&mut self) {
+        self.run_to_end = false;
+        self.resume_from = None;
+        self.position = None;
+    }
+
+    /// Checks the breakpoints and returns true if the VM should stop before executing the instruction at `ip`.
+    pub fn should_pause(&mut self, event: u16, ip: u32, bytecode: &[u8]) -> bool {
+        if self.is_idle() {
+            return false;
+        }
+
+        if self.resume_from.take() == Some((event, ip)) {
+            self.position = None;
+            return false;
+        }
+
+        let mut cursor = Cursor::new(bytecode);
+        cursor.set_position(ip as u64);
+        let op: Option<TSCOpCode> = read_cur_varint(&mut cursor).ok().and_then(FromPrimitive::from_i32);
+
+        if self.run_to_end && matches!(op, Some(TSCOpCode::END) | Some(TSCOpCode::_END) | None) {
+            self.run_to_end = false;
+            self.paused = true;
+        }
+
+        if ip == 0 && self.event_breakpoints.contains(&event) {
+            self.paused = true;
+        }
+
+        if let Some(op) = op {
+            let name: &'static str = op.into();
+            if self.opcode_breakpoints.contains(name) {
+                self.paused = true;
+            }
+        }
+
+        if self.paused {
+            self.position = Some((event, ip));
+        }
+
+        self.paused
+    }
+}
+
+#[test]
+fn test_tsc_debugger_stepping() {
+    use crate::game::scripting::tsc::text_script::{TextScript, TextScriptEncoding};
+
+    let script = TextScript::compile(b"#0100\n<KEY<FL+0001<END\n", true, TextScriptEncoding::UTF8).unwrap();
+    let bytecode = &script.event_map[&100];
+    let offsets: Vec<u32> = TextScript::disassemble(bytecode).unwrap().iter().map(|(offset, _)| *offset).collect();
+
+    let mut debugger = TextScriptDebugger::new();
+    assert!(debugger.add_breakpoint("#0100"));
+    assert!(debugger.add_breakpoint("<fl+"));
+    assert!(!debugger.add_breakpoint("<ZZZ"));
+
+    assert!(debugger.should_pause(100, offsets[0], bytecode));
+    assert!(debugger.should_pause(100, offsets[0], bytecode));
+
+    debugger.step();
+    assert!(!debugger.should_pause(100, offsets[0], bytecode));
+    assert!(debugger.should_pause(100, offsets[1], bytecode));
+
+    debugger.event_breakpoints.clear();
+    debugger.opcode_breakpoints.clear();
+    debugger.run_to_end();
+    assert!(!debugger.should_pause(100, offsets[1], bytecode));
+    assert!(debugger.should_pause(100, offsets[2], bytecode));
+    assert_eq!(debugger.position, Some((100, offsets[2])));
+}
@@ -7,181 +7,171 @@ use crate::framework::error::GameError::InvalidValue;
 use crate::framework::error::GameResult;
 use crate::game::scripting::tsc::bytecode_utils::read_cur_varint;
 use crate::game::scripting::tsc::opcodes::TSCOpCode;
-use crate::game::scripting::tsc::text_script::TextScript;
+use crate::game::scripting::tsc::text_script::{TextScript, TextScriptEncoding};
+
+/// Encodes an operand as 4 TSC digits, values outside of 0..=9999 can't be represented.
+fn encode_number(value: i32) -> GameResult<[u8; 4]> {
+    if !(0..=9999).contains(&value) {
+        return Err(InvalidValue(format!("Operand {} can't be encoded as TSC number.", value)));
+    }
+
+    let mut digits = [0u8; 4];
+    digits.copy_from_slice(format!("{:04}", value).as_bytes());
+
+    Ok(digits)
+}
 
 impl TextScript {
-    pub fn decompile_event(&self, id: u16) -> GameResult<String> {
-        if let Some(bytecode) = self.event_map.get(&id) {
-            let mut result = String::new();
-            let mut cursor: Cursor<&[u8]> = Cursor::new(bytecode);
+    /// Converts compiled events back into TSC source, with text encoded using given encoding.
+    pub fn decompile_to_tsc(&self, encoding: TextScriptEncoding) -> GameResult<Vec<u8>> {
+        let encoding: &encoding_rs::Encoding = encoding.into();
+        let mut result = Vec::new();
+
+        for id in self.get_event_ids() {
+            let mut cursor: Cursor<&[u8]> = Cursor::new(&self.event_map[&id]);
+
+            result.extend_from_slice(format!("#{:04}\r\n", id).as_bytes());
 
             while let Ok(op_num) = read_cur_varint(&mut cursor) {
-                let op_maybe: Option<TSCOpCode> = FromPrimitive::from_i32(op_num);
-
-                if let Some(op) = op_maybe {
-                    match op {
-                        // Zero operand codes
-                        TSCOpCode::AEp
-                        | TSCOpCode::CAT
-                        | TSCOpCode::CIL
-                        | TSCOpCode::CLO
-                        | TSCOpCode::CLR
-                        | TSCOpCode::CPS
-                        | TSCOpCode::CRE
-                        | TSCOpCode::CSS
-                        | TSCOpCode::END
-                        | TSCOpCode::ESC
-                        | TSCOpCode::FLA
-                        | TSCOpCode::FMU
-                        | TSCOpCode::FRE
-                        | TSCOpCode::HMC
-                        | TSCOpCode::INI
-                        | TSCOpCode::KEY
-                        | TSCOpCode::LDP
-                        | TSCOpCode::MLP
-                        | TSCOpCode::MM0
-                        | TSCOpCode::MNA
-                        | TSCOpCode::MS2
-                        | TSCOpCode::MS3
-                        | TSCOpCode::MSG
-                        | TSCOpCode::NOD
-                        | TSCOpCode::PRI
-                        | TSCOpCode::RMU
-                        | TSCOpCode::SAT
-                        | TSCOpCode::SLP
-                        | TSCOpCode::SMC
-                        | TSCOpCode::SPS
-                        | TSCOpCode::STC
-                        | TSCOpCode::SVP
-                        | TSCOpCode::TUR
-                        | TSCOpCode::WAS
-                        | TSCOpCode::ZAM
-                        | TSCOpCode::HM2
-                        | TSCOpCode::POP
-                        | TSCOpCode::KE2
-                        | TSCOpCode::FR2 => {
-                            writeln!(&mut result, "{:?}()", op).unwrap();
-                        }
-                        // One operand codes
-                        TSCOpCode::BOA
-                        | TSCOpCode::BSL
-                        | TSCOpCode::FOM
-                        | TSCOpCode::QUA
-                        | TSCOpCode::UNI
-                        | TSCOpCode::MYB
-                        | TSCOpCode::MYD
-                        | TSCOpCode::FAI
-                        | TSCOpCode::FAO
-                        | TSCOpCode::WAI
-                        | TSCOpCode::FAC
-                        | TSCOpCode::GIT
-                        | TSCOpCode::NUM
-                        | TSCOpCode::DNA
-                        | TSCOpCode::DNP
-                        | TSCOpCode::FLm
-                        | TSCOpCode::FLp
-                        | TSCOpCode::MPp
-                        | TSCOpCode::SKm
-                        | TSCOpCode::SKp
-                        | TSCOpCode::EQp
-                        | TSCOpCode::EQm
-                        | TSCOpCode::MLp
-                        | TSCOpCode::ITp
-                        | TSCOpCode::ITm
-                        | TSCOpCode::AMm
-                        | TSCOpCode::UNJ
-                        | TSCOpCode::MPJ
-                        | TSCOpCode::YNJ
-                        | TSCOpCode::EVE
-                        | TSCOpCode::XX1
-                        | TSCOpCode::SIL
-                        | TSCOpCode::LIp
-                        | TSCOpCode::SOU
-                        | TSCOpCode::CMU
-                        | TSCOpCode::SSS
-                        | TSCOpCode::ACH
-                        | TSCOpCode::S2MV
-                        | TSCOpCode::S2PJ
-                        | TSCOpCode::PSH => {
-                            let par_a = read_cur_varint(&mut cursor)?;
-
-                            writeln!(&mut result, "{:?}({})", op, par_a).unwrap();
-                        }
-                        // Two operand codes
-                        TSCOpCode::FON
-                        | TSCOpCode::FOB
-                        | TSCOpCode::MOV
-                        | TSCOpCode::AMp
-                        | TSCOpCode::NCJ
-                        | TSCOpCode::ECJ
-                        | TSCOpCode::FLJ
-                        | TSCOpCode::ITJ
-                        | TSCOpCode::SKJ
-                        | TSCOpCode::AMJ
-                        | TSCOpCode::SMP
-                        | TSCOpCode::PSp
-                        | TSCOpCode::IpN
-                        | TSCOpCode::FFm => {
-                            let par_a = read_cur_varint(&mut cursor)?;
-                            let par_b = read_cur_varint(&mut cursor)?;
-
-                            writeln!(&mut result, "{:?}({}, {})", op, par_a, par_b).unwrap();
-                        }
-                        // Three operand codes
-                        TSCOpCode::ANP | TSCOpCode::CNP | TSCOpCode::INP | TSCOpCode::TAM | TSCOpCode::CMP | TSCOpCode::INJ => {
-                            let par_a = read_cur_varint(&mut cursor)?;
-                            let par_b = read_cur_varint(&mut cursor)?;
-                            let par_c = read_cur_varint(&mut cursor)?;
+                let op: TSCOpCode = FromPrimitive::from_i32(op_num)
+                    .ok_or_else(|| InvalidValue(format!("Unknown opcode {} in event {}.", op_num, id)))?;
 
-                            writeln!(&mut result, "{:?}({}, {}, {})", op, par_a, par_b, par_c).unwrap();
-                        }
-                        // Four operand codes
-                        TSCOpCode::TRA | TSCOpCode::MNP | TSCOpCode::SNP => {
-                            let par_a = read_cur_varint(&mut cursor)?;
-                            let par_b = read_cur_varint(&mut cursor)?;
-                            let par_c = read_cur_varint(&mut cursor)?;
-                            let par_d = read_cur_varint(&mut cursor)?;
-
-                            writeln!(&mut result, "{:?}({}, {}, {}, {})", op, par_a, par_b, par_c, par_d).unwrap();
+                match op {
+                    TSCOpCode::_STR => {
+                        let len = read_cur_varint(&mut cursor)?;
+                        let mut text = String::new();
+                        for _ in 0..len {
+                            text.push(std::char::from_u32(read_cur_varint(&mut cursor)? as u32).unwrap_or('?'));
                         }
-                        TSCOpCode::_STR => {
-                            let len = read_cur_varint(&mut cursor)?;
-
-                            write!(&mut result, "%string(len = {}, value = \"", len).unwrap();
-                            for _ in 0..len {
-                                let chr = std::char::from_u32(read_cur_varint(&mut cursor)? as u32).unwrap_or('?');
-                                match chr {
-                                    '\n' => {
-                                        result.push_str("\\n");
-                                    }
-                                    '\r' => {
-                                        result.push_str("\\r");
-                                    }
-                                    '\t' => {
-                                        result.push_str("\\t");
-                                    }
-                                    '\u{0000}'..='\u{001f}' | '\u{0080}'..='\u{ffff}' => {
-                                        result.push_str(&chr.escape_unicode().to_string());
-                                    }
-                                    _ => {
-                                        result.push(chr);
-                                    }
-                                }
+
+                        // carriage returns are dropped by the compiler.
+                        let text = text.replace('\n', "\r\n");
+                        result.extend_from_slice(&encoding.encode(&text).0);
+                    }
+                    TSCOpCode::_END => break,
+                    TSCOpCode::_NOP | TSCOpCode::_UNI => {}
+                    op => {
+                        let name: &'static str = op.into();
+                        result.push(b'<');
+                        result.extend_from_slice(name.as_bytes());
+
+                        for idx in 0..op.operand_count().unwrap_or(0) {
+                            if idx != 0 {
+                                result.push(b':');
                             }
-                            result.push_str("\")\n");
+
+                            result.extend_from_slice(&encode_number(read_cur_varint(&mut cursor)?)?);
                         }
-                        TSCOpCode::_NOP => result.push_str("%no_op()\n"),
-                        TSCOpCode::_UNI => result.push_str("%unimplemented()\n"),
-                        TSCOpCode::_END => result.push_str("%end_marker()\n"),
                     }
-                } else {
-                    break;
                 }
             }
+        }
+
+        Ok(result)
+    }
 
-            Ok(result)
+    pub fn decompile_event(&self, id: u16) -> GameResult<String> {
+        let mut result = String::new();
+        for (_, line) in self.disassemble_event(id)? {
+            result.push_str(&line);
+            result.push('\n');
+        }
+
+        Ok(result)
+    }
+
+    pub fn disassemble_event(&self, id: u16) -> GameResult<Vec<(u32, String)>> {
+        if let Some(bytecode) = self.event_map.get(&id) {
+            TextScript::disassemble(bytecode)
         } else {
             Err(InvalidValue("Unknown script.".to_string()))
         }
     }
+
+    /// Returns a listing of event's bytecode with one instruction per line, along with its offset.
+    pub fn disassemble(bytecode: &[u8]) -> GameResult<Vec<(u32, String)>> {
+        let mut lines = Vec::new();
+        let mut cursor: Cursor<&[u8]> = Cursor::new(bytecode);
+
+        loop {
+            let offset = cursor.position() as u32;
+            let Ok(op_num) = read_cur_varint(&mut cursor) else {
+                break;
+            };
+
+            let mut result = String::new();
+            let op_maybe: Option<TSCOpCode> = FromPrimitive::from_i32(op_num);
+
+            if let Some(op) = op_maybe {
+                match op {
+                    TSCOpCode::_STR => {
+                        let len = read_cur_varint(&mut cursor)?;
+
+                        write!(&mut result, "%string(len = {}, value = \"", len).unwrap();
+                        for _ in 0..len {
+                            let chr = std::char::from_u32(read_cur_varint(&mut cursor)? as u32).unwrap_or('?');
+                            match chr {
+                                '\n' => {
+                                    result.push_str("\\n");
+                                }
+                                '\r' => {
+                                    result.push_str("\\r");
+                                }
+                                '\t' => {
+                                    result.push_str("\\t");
+                                }
+                                '\u{0000}'..='\u{001f}' | '\u{0080}'..='\u{ffff}' => {
+                                    result.push_str(&chr.escape_unicode().to_string());
+                                }
+                                _ => {
+                                    result.push(chr);
+                                }
+                            }
+                        }
+                        result.push_str("\")\n");
+                    }
+                    TSCOpCode::_NOP => result.push_str("%no_op()\n"),
+                    TSCOpCode::_UNI => result.push_str("%unimplemented()\n"),
+                    TSCOpCode::_END => result.push_str("%end_marker()\n"),
+                    op => {
+                        write!(&mut result, "{:?}(", op).unwrap();
+                        for idx in 0..op.operand_count().unwrap_or(0) {
+                            if idx != 0 {
+                                result.push_str(", ");
+                            }
+
+                            write!(&mut result, "{}", read_cur_varint(&mut cursor)?).unwrap();
+                        }
+                        result.push(')');
+                    }
+                }
+            } else {
+                break;
+            }
+
+            lines.push((offset, result.trim_end_matches('\n').to_owned()));
+        }
+
+        Ok(lines)
+    }
+}
+
+#[test]
+fn test_disassemble_operands() {
+    let script =
+        TextScript::compile(b"#0100\n<KEY<UNJ0001:0200<TRA0012:0090:0010:0008<END\n", true, TextScriptEncoding::UTF8)
+            .unwrap();
+    let lines: Vec<String> =
+        TextScript::disassemble(&script.event_map[&100]).unwrap().into_iter().map(|(_, line)| line).collect();
+
+    assert_eq!(lines, ["KEY()", "UNJ(1, 200)", "TRA(12, 90, 10, 8)", "END()"]);
+}
+
+#[test]
+fn test_encode_number() {
+    assert_eq!(&encode_number(0).unwrap(), b"0000");
+    assert_eq!(&encode_number(1234).unwrap(), b"1234");
+    assert_eq!(&encode_number(9999).unwrap(), b"9999");
+    assert!(encode_number(10000).is_err());
+    assert!(encode_number(-1).is_err());
 }
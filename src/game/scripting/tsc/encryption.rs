@@ -1,6 +1,6 @@
 pub fn decrypt_tsc(buf: &mut [u8]) {
     let half = buf.len() / 2;
-    let key = if let Some(0) = buf.get(half) { 0x7 } else { *buf.get(half).unwrap() };
+    let key = buf.get(half).copied().filter(|&key| key != 0).unwrap_or(0x7);
     log::debug!("Decrypting TSC using key {:#x}", key);
 
     for (idx, byte) in buf.iter_mut().enumerate() {
@@ -11,3 +11,32 @@ pub fn decrypt_tsc(buf: &mut [u8]) {
         *byte = byte.wrapping_sub(key);
     }
 }
+
+/// Encrypts a TSC script in place, reverse of [decrypt_tsc].
+///
+/// The byte in the middle of the file is left as is and used as the key.
+pub fn encrypt_tsc(buf: &mut [u8]) {
+    let half = buf.len() / 2;
+    let key = buf.get(half).copied().filter(|&key| key != 0).unwrap_or(0x7);
+    log::debug!("Encrypting TSC using key {:#x}", key);
+
+    for (idx, byte) in buf.iter_mut().enumerate() {
+        if idx == half {
+            continue;
+        }
+
+        *byte = byte.wrapping_add(key);
+    }
+}
+
+#[test]
+fn test_tsc_encryption() {
+    let script = b"#0090\r\n<MNA<CMU0008<FAI0000<END\r\n".to_vec();
+
+    let mut buf = script.clone();
+    encrypt_tsc(&mut buf);
+    assert_ne!(buf, script);
+
+    decrypt_tsc(&mut buf);
+    assert_eq!(buf, script);
+}
@@ -0,0 +1,395 @@
+//! Static analysis of decrypted TSC scripts.
+//!
+//! Unlike the compiler, the linter doesn't stop at the first error, so it works on the raw script source.
+//! Scripts are collected first and checked together in [TscLinter::finish], since events, stages and flags
+//! are shared between all scripts of the game.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::game::scripting::tsc::opcodes::TSCOpCode;
+use crate::game::scripting::tsc::parse_utils::{read_number, LineIndex};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LintSeverity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LintDiagnostic {
+    pub severity: LintSeverity,
+    /// Stable identifier of the check, eg. `missing-event`.
+    pub code: &'static str,
+    pub file: String,
+    pub event: Option<u16>,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+/// Determines where the engine looks up events jumped to from the script.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LintScriptKind {
+    /// Head.tsc, its events are available in every stage.
+    Head,
+    /// Script of the stage with given id in the stage table.
+    Stage(usize),
+    /// ArmsItem.tsc
+    Inventory,
+    /// StageSelect.tsc
+    StageSelect,
+}
+
+struct LintInstruction {
+    op: TSCOpCode,
+    operands: Vec<i32>,
+    line: usize,
+    column: usize,
+}
+
+struct LintEvent {
+    id: u16,
+    line: usize,
+    column: usize,
+    instructions: Vec<LintInstruction>,
+}
+
+struct LintScript {
+    file: String,
+    kind: LintScriptKind,
+    events: Vec<LintEvent>,
+}
+
+pub struct TscLinter {
+    stage_count: usize,
+    scripts: Vec<LintScript>,
+    flag_reads: HashSet<u16>,
+    check_unread_flags: bool,
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+impl TscLinter {
+    pub fn new(stage_count: usize) -> TscLinter {
+        TscLinter {
+            stage_count,
+            scripts: Vec::new(),
+            flag_reads: HashSet::new(),
+            check_unread_flags: false,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Enables the `unread-flag` check. It's off by default, since NPC code and the engine itself read flags
+    /// the linter can't see, so it reports flags that are read just fine.
+    pub fn set_check_unread_flags(&mut self, enabled: bool) {
+        self.check_unread_flags = enabled;
+    }
+
+    /// Marks a flag as read by something other than a script, eg. a NPC that only appears if the flag is set.
+    pub fn add_flag_read(&mut self, flag: u16) {
+        self.flag_reads.insert(flag);
+    }
+
+    /// Parses a decrypted script, reporting unknown opcodes right away.
+    pub fn add_script(&mut self, file: &str, kind: LintScriptKind, data: &[u8]) {
+        let lines = LineIndex::new(data);
+        let mut events = Vec::new();
+        let mut pos = 0;
+
+        while pos < data.len() {
+            if data[pos] != b'#' {
+                pos += 1;
+                continue;
+            }
+
+            let (line, column) = lines.line_column(pos);
+            let Some(id) = number_at(data, pos + 1) else {
+                break;
+            };
+
+            pos += 5;
+            while pos < data.len() && data[pos] != b'\n' {
+                pos += 1;
+            }
+
+            let mut event = LintEvent { id: id as u16, line, column, instructions: Vec::new() };
+            pos = self.parse_event(file, data, &lines, pos, &mut event);
+            events.push(event);
+        }
+
+        self.scripts.push(LintScript { file: file.to_owned(), kind, events });
+    }
+
+    fn parse_event(
+        &mut self,
+        file: &str,
+        data: &[u8],
+        lines: &LineIndex,
+        mut pos: usize,
+        event: &mut LintEvent,
+    ) -> usize {
+        let mut allow_next_event = true;
+
+        while pos < data.len() {
+            match data[pos] {
+                b'#' if allow_next_event => break,
+                b'<' => {
+                    allow_next_event = false;
+
+                    let (line, column) = lines.line_column(pos);
+                    let Some(name) = data.get(pos + 1..pos + 4) else {
+                        let message = "Script ends in the middle of an opcode.".to_owned();
+                        self.report(LintSeverity::Error, "unexpected-eof", file, event.id, (line, column), message);
+                        return data.len();
+                    };
+                    let name = String::from_utf8_lossy(name).into_owned();
+                    pos += 4;
+
+                    let opcode = TSCOpCode::from_str(&name).ok();
+                    let Some((op, count)) = opcode.and_then(|op| op.operand_count().map(|count| (op, count))) else {
+                        let message = format!("Unknown opcode: <{}", name);
+                        self.report(LintSeverity::Error, "unknown-opcode", file, event.id, (line, column), message);
+                        continue;
+                    };
+
+                    let mut operands = Vec::with_capacity(count);
+                    for i in 0..count {
+                        // operands are separated by a single character, usually ':'
+                        let start = if i == 0 { pos } else { pos + 1 };
+                        let Some(operand) = number_at(data, start) else {
+                            let message = format!("Script ends in the middle of <{} operands.", name);
+                            self.report(LintSeverity::Error, "unexpected-eof", file, event.id, (line, column), message);
+                            return data.len();
+                        };
+
+                        operands.push(operand);
+                        pos = start + 4;
+                    }
+
+                    event.instructions.push(LintInstruction { op, operands, line, column });
+                }
+                b'\n' => {
+                    allow_next_event = true;
+                    pos += 1;
+                }
+                _ => {
+                    pos += 1;
+                }
+            }
+        }
+
+        pos
+    }
+
+    fn report(
+        &mut self,
+        severity: LintSeverity,
+        code: &'static str,
+        file: &str,
+        event: u16,
+        (line, column): (usize, usize),
+        message: String,
+    ) {
+        self.diagnostics.push(LintDiagnostic {
+            severity,
+            code,
+            file: file.to_owned(),
+            event: Some(event),
+            line,
+            column,
+            message,
+        });
+    }
+
+    /// Runs the checks spanning multiple scripts and returns all diagnostics sorted by their location.
+    pub fn finish(mut self) -> Vec<LintDiagnostic> {
+        let mut event_ids: HashMap<LintScriptKind, HashSet<u16>> = HashMap::new();
+        for script in self.scripts.iter() {
+            event_ids.entry(script.kind).or_default().extend(script.events.iter().map(|e| e.id));
+        }
+
+        let empty = HashSet::new();
+        let (event_ids, empty) = (&event_ids, &empty);
+        let ids_of = move |kind: LintScriptKind| event_ids.get(&kind).unwrap_or(empty);
+        let head_ids = ids_of(LintScriptKind::Head);
+        let event_exists = move |kind: LintScriptKind, id: u16| match kind {
+            LintScriptKind::Stage(_) => ids_of(kind).contains(&id) || head_ids.contains(&id),
+            // Head.tsc events run in context of the current stage, so any stage may provide the target.
+            LintScriptKind::Head => event_ids.iter().any(|(kind, ids)| {
+                matches!(kind, LintScriptKind::Head | LintScriptKind::Stage(_)) && ids.contains(&id)
+            }),
+            LintScriptKind::Inventory | LintScriptKind::StageSelect => ids_of(kind).contains(&id),
+        };
+
+        let mut flag_sets: HashMap<u16, (String, u16, usize, usize)> = HashMap::new();
+        let mut diagnostics = Vec::new();
+
+        for script in self.scripts.iter() {
+            for event in script.events.iter() {
+                let mut terminated = false;
+
+                for instr in event.instructions.iter() {
+                    let mut report = |severity: LintSeverity, code: &'static str, message: String| {
+                        diagnostics.push(LintDiagnostic {
+                            severity,
+                            code,
+                            file: script.file.clone(),
+                            event: Some(event.id),
+                            line: instr.line,
+                            column: instr.column,
+                            message,
+                        });
+                    };
+                    let op_name: &'static str = instr.op.into();
+
+                    if let Some(target) = jump_target(instr) {
+                        if !event_exists(script.kind, target) {
+                            report(
+                                LintSeverity::Error,
+                                "missing-event",
+                                format!("<{} jumps to event {} which doesn't exist.", op_name, target),
+                            );
+                        }
+                    }
+
+                    match instr.op {
+                        TSCOpCode::TRA => {
+                            let stage_id = instr.operands[0] as usize;
+                            let target = instr.operands[1] as u16;
+
+                            if stage_id >= self.stage_count {
+                                report(
+                                    LintSeverity::Error,
+                                    "unknown-stage",
+                                    format!(
+                                        "<TRA to stage {} which isn't in the stage table ({} stages).",
+                                        stage_id, self.stage_count
+                                    ),
+                                );
+                            } else if event_ids.contains_key(&LintScriptKind::Stage(stage_id))
+                                && !event_exists(LintScriptKind::Stage(stage_id), target)
+                            {
+                                report(
+                                    LintSeverity::Error,
+                                    "missing-event",
+                                    format!("<TRA runs event {} which doesn't exist in stage {}.", target, stage_id),
+                                );
+                            }
+                        }
+                        TSCOpCode::FLp => {
+                            flag_sets
+                                .entry(instr.operands[0] as u16)
+                                .or_insert_with(|| (script.file.clone(), event.id, instr.line, instr.column));
+                        }
+                        TSCOpCode::FLJ => {
+                            self.flag_reads.insert(instr.operands[0] as u16);
+                        }
+                        _ => (),
+                    }
+
+                    if is_terminator(instr.op) {
+                        terminated = true;
+                    }
+                }
+
+                if !terminated {
+                    diagnostics.push(LintDiagnostic {
+                        severity: LintSeverity::Warning,
+                        code: "missing-end",
+                        file: script.file.clone(),
+                        event: Some(event.id),
+                        line: event.line,
+                        column: event.column,
+                        message: format!("Event {} has no <END and runs into whatever follows it.", event.id),
+                    });
+                }
+            }
+        }
+
+        for (flag, (file, event, line, column)) in flag_sets {
+            if self.check_unread_flags && !self.flag_reads.contains(&flag) {
+                diagnostics.push(LintDiagnostic {
+                    severity: LintSeverity::Warning,
+                    code: "unread-flag",
+                    file,
+                    event: Some(event),
+                    line,
+                    column,
+                    message: format!("Flag {} is set but never read.", flag),
+                });
+            }
+        }
+
+        self.diagnostics.append(&mut diagnostics);
+        self.diagnostics.sort_by(|a, b| (&a.file, a.line, a.column).cmp(&(&b.file, b.line, b.column)));
+        self.diagnostics
+    }
+}
+
+fn number_at(data: &[u8], pos: usize) -> Option<i32> {
+    let digits = data.get(pos..pos + 4)?;
+
+    read_number(&mut digits.iter().copied().peekable()).ok()
+}
+
+/// Returns the event that the instruction may jump to.
+fn jump_target(instr: &LintInstruction) -> Option<u16> {
+    match instr.op {
+        TSCOpCode::EVE | TSCOpCode::YNJ | TSCOpCode::MPJ => Some(instr.operands[0] as u16),
+        TSCOpCode::FLJ
+        | TSCOpCode::ITJ
+        | TSCOpCode::NCJ
+        | TSCOpCode::ECJ
+        | TSCOpCode::SKJ
+        | TSCOpCode::AMJ
+        | TSCOpCode::UNJ => Some(instr.operands[1] as u16),
+        TSCOpCode::VAJ => Some(instr.operands[3] as u16),
+        _ => None,
+    }
+}
+
+/// Returns true if the event can't continue past given instruction.
+fn is_terminator(op: TSCOpCode) -> bool {
+    matches!(
+        op,
+        TSCOpCode::END
+            | TSCOpCode::EVE
+            | TSCOpCode::TRA
+            | TSCOpCode::ESC
+            | TSCOpCode::INI
+            | TSCOpCode::LDP
+            | TSCOpCode::POP
+    )
+}
+
+#[test]
+fn test_tsc_linter() {
+    let head = b"#0016\r\n<PRI<MSGSaved.<NOD<END\r\n";
+    let stage = b"#0100\r\n<KEY<FL+0500<FLJ0501:0102<EVE0103\r\n#0101\r\n<XYZ<TRA0099:0094:0001:0001<END\r\n\
+                  #0102\r\n<MSG<FL+0501<NOD<CLO\r\n#0103\r\n<EVE0016\r\n";
+
+    let mut linter = TscLinter::new(10);
+    linter.add_script("Head.tsc", LintScriptKind::Head, head);
+    linter.add_script("Stage/Test.tsc", LintScriptKind::Stage(1), stage);
+    let diagnostics = linter.finish();
+    assert!(diagnostics.iter().all(|d| d.code != "unread-flag"));
+
+    let mut linter = TscLinter::new(10);
+    linter.set_check_unread_flags(true);
+    linter.add_script("Head.tsc", LintScriptKind::Head, head);
+    linter.add_script("Stage/Test.tsc", LintScriptKind::Stage(1), stage);
+    let diagnostics = linter.finish();
+
+    let codes: Vec<_> = diagnostics.iter().map(|d| (d.code, d.event, d.line)).collect();
+    assert_eq!(
+        codes,
+        vec![
+            ("unread-flag", Some(100), 2),
+            ("unknown-opcode", Some(101), 4),
+            ("unknown-stage", Some(101), 4),
+            ("missing-end", Some(102), 5),
+        ]
+    );
+}
@@ -0,0 +1,194 @@
+//! Extraction of the message text of decrypted TSC scripts and re-injection of translated text.
+//!
+//! Scripts are scanned the same way the compiler splits them into events, commands and text, but the source
+//! is kept as is, so rebuilding a script only replaces the text and leaves every command byte for byte intact.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use crate::framework::error::GameError::InvalidValue;
+use crate::framework::error::GameResult;
+use crate::game::scripting::tsc::opcodes::TSCOpCode;
+use crate::game::scripting::tsc::parse_utils::{read_number, LineIndex};
+
+/// Text between two commands of an event, without the line breaks around it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TscMessage {
+    pub event: u16,
+    /// Position of the message among the messages of its event, starting at 0.
+    pub index: usize,
+    pub line: usize,
+    pub text: String,
+}
+
+struct TextRun {
+    event: u16,
+    index: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Returns every message of a decrypted script, in order of appearance.
+pub fn extract_messages(data: &[u8], encoding: &'static encoding_rs::Encoding) -> Vec<TscMessage> {
+    let lines = LineIndex::new(data);
+
+    scan(data)
+        .into_iter()
+        .map(|run| TscMessage {
+            event: run.event,
+            index: run.index,
+            line: lines.line_column(run.start).0,
+            text: encoding.decode_without_bom_handling(&data[run.start..run.end]).0.replace('\r', ""),
+        })
+        .collect()
+}
+
+/// Rebuilds a decrypted script with the messages replaced by the translations keyed by event and index.
+///
+/// Messages without a translation are kept. The translated text is written in given encoding and has to be
+/// plain text, since a `<` or a `#` at the start of a line would change the commands of the script.
+pub fn inject_messages(
+    data: &[u8],
+    encoding: &'static encoding_rs::Encoding,
+    translations: &HashMap<(u16, usize), String>,
+) -> GameResult<Vec<u8>> {
+    let crlf = data.windows(2).any(|w| w == b"\r\n");
+    let mut out = Vec::with_capacity(data.len());
+    let mut copied = 0;
+
+    for run in scan(data) {
+        let Some(text) = translations.get(&(run.event, run.index)) else {
+            continue;
+        };
+
+        let text = text.replace('\r', "");
+        if text.contains('<') || text.split('\n').any(|line| line.starts_with('#')) {
+            return Err(InvalidValue(format!(
+                "Translation of message {} in event {} contains commands or event headers.",
+                run.index, run.event
+            )));
+        }
+
+        let text = if crlf { text.replace('\n', "\r\n") } else { text };
+        let (bytes, _, had_errors) = encoding.encode(&text);
+        if had_errors {
+            return Err(InvalidValue(format!(
+                "Translation of message {} in event {} can't be encoded in {}.",
+                run.index,
+                run.event,
+                encoding.name()
+            )));
+        }
+
+        out.extend_from_slice(&data[copied..run.start]);
+        out.extend_from_slice(&bytes);
+        copied = run.end;
+    }
+
+    out.extend_from_slice(&data[copied..]);
+
+    Ok(out)
+}
+
+fn scan(data: &[u8]) -> Vec<TextRun> {
+    let mut runs = Vec::new();
+    let mut counters: HashMap<u16, usize> = HashMap::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        if data[pos] != b'#' {
+            pos += 1;
+            continue;
+        }
+
+        let Some(event) = data.get(pos + 1..pos + 5).and_then(|n| read_number(&mut n.iter().copied().peekable()).ok())
+        else {
+            break;
+        };
+
+        pos += 5;
+        while pos < data.len() && data[pos] != b'\n' {
+            pos += 1;
+        }
+
+        // duplicated events keep counting, so every message of the file has its own key
+        let counter = counters.entry(event as u16).or_insert(0);
+        pos = scan_event(data, pos, event as u16, counter, &mut runs);
+    }
+
+    runs
+}
+
+fn scan_event(data: &[u8], mut pos: usize, event: u16, counter: &mut usize, runs: &mut Vec<TextRun>) -> usize {
+    let mut allow_next_event = true;
+    let mut text_start = pos;
+
+    while pos < data.len() {
+        match data[pos] {
+            b'#' if allow_next_event => break,
+            b'<' => {
+                push_run(data, text_start, pos, event, counter, runs);
+                allow_next_event = false;
+
+                let name = data.get(pos + 1..pos + 4).map(String::from_utf8_lossy).unwrap_or_default();
+                let operands = TSCOpCode::from_str(&name).ok().and_then(|op| op.operand_count()).unwrap_or(0);
+
+                // operands are separated by a single character, usually ':'
+                pos += 4 + operands * 5 - operands.min(1);
+                pos = pos.min(data.len());
+                text_start = pos;
+            }
+            b'\n' => {
+                allow_next_event = true;
+                pos += 1;
+            }
+            _ => {
+                pos += 1;
+            }
+        }
+    }
+
+    push_run(data, text_start, pos, event, counter, runs);
+
+    pos
+}
+
+fn push_run(data: &[u8], start: usize, end: usize, event: u16, counter: &mut usize, runs: &mut Vec<TextRun>) {
+    let text = &data[start..end];
+    let is_break = |c: &u8| *c == b'\r' || *c == b'\n';
+
+    let Some(first) = text.iter().position(|c| !is_break(c)) else {
+        return;
+    };
+    let last = text.iter().rposition(|c| !is_break(c)).unwrap_or(first);
+
+    if text[first..=last].iter().all(|&c| c == b' ' || c == b'\t') {
+        return;
+    }
+
+    runs.push(TextRun { event, index: *counter, start: start + first, end: start + last + 1 });
+    *counter += 1;
+}
+
+#[test]
+fn test_tsc_messages() {
+    let script = b"#0200\r\n<KEY<MSG<FAC0005Hello.<NOD<CLR\r\nHow are you?\r\nFine.<NOD<END\r\n\
+                   #0201\r\n<PRI<FLJ0100:0202<MSG  Indented<WAI0010<END\r\n";
+    let encoding = encoding_rs::SHIFT_JIS;
+
+    let messages = extract_messages(script, encoding);
+    let texts: Vec<_> = messages.iter().map(|m| (m.event, m.index, m.line, m.text.as_str())).collect();
+    assert_eq!(texts, vec![(200, 0, 2, "Hello."), (200, 1, 3, "How are you?\nFine."), (201, 0, 6, "  Indented")]);
+
+    let mut translations = HashMap::new();
+    translations.insert((200, 1), "Wie geht's?\nGut.".to_owned());
+    translations.insert((201, 0), "Eingerückt".to_owned());
+
+    let rebuilt = inject_messages(script, encoding_rs::WINDOWS_1252, &translations).unwrap();
+    let expected = b"#0200\r\n<KEY<MSG<FAC0005Hello.<NOD<CLR\r\nWie geht's?\r\nGut.<NOD<END\r\n\
+                     #0201\r\n<PRI<FLJ0100:0202<MSGEinger\xfcckt<WAI0010<END\r\n";
+    assert_eq!(rebuilt, expected);
+
+    translations.insert((200, 0), "<END".to_owned());
+    assert!(inject_messages(script, encoding, &translations).is_err());
+}
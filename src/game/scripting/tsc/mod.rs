@@ -1,8 +1,12 @@
 mod bytecode_utils;
 mod compiler;
 pub mod credit_script;
+pub mod debugger;
 mod decompiler;
-mod encryption;
+pub mod encryption;
+pub mod linter;
+pub mod messages;
 mod opcodes;
 mod parse_utils;
 pub mod text_script;
+pub mod variables;
@@ -1,7 +1,7 @@
 use num_derive::FromPrimitive;
 
 /// Engine's text script VM operation codes.
-#[derive(EnumString, Debug, FromPrimitive, PartialEq, Copy, Clone)]
+#[derive(EnumString, IntoStaticStr, Debug, FromPrimitive, PartialEq, Copy, Clone)]
 pub enum TSCOpCode {
     // ---- Internal opcodes (used by bytecode, no TSC representation)
     /// internal: no operation
@@ -265,6 +265,135 @@ pub enum TSCOpCode {
     /// <FRE related to player 2?
     FR2,
     // ---- Custom opcodes, for use by modders ----
+    /// <VARxxxx:yyyy, Sets script variable xxxx to yyyy
+    VAR,
+    /// <VA+xxxx:yyyy, Adds yyyy to script variable xxxx
+    #[strum(serialize = "VA+")]
+    VAp,
+    /// <VA-xxxx:yyyy, Subtracts yyyy from script variable xxxx
+    #[strum(serialize = "VA-")]
+    VAm,
+    /// <VAJxxxx:yyyy:zzzz:wwww, Jumps to event wwww if script variable xxxx compared to zzzz matches yyyy
+    /// 0000 - equal, 0001 - not equal, 0002 - less, 0003 - less or equal, 0004 - greater, 0005 - greater or equal
+    VAJ,
+    /// <VRNxxxx:yyyy:zzzz, Sets script variable xxxx to a random number from yyyy to zzzz
+    VRN,
+    /// <VNMxxxx, Prints the value of script variable xxxx
+    VNM,
+}
+
+impl TSCOpCode {
+    /// Number of 4 digit operands following the opcode in TSC source, `None` for internal opcodes.
+    pub fn operand_count(self) -> Option<usize> {
+        match self {
+            TSCOpCode::_NOP | TSCOpCode::_UNI | TSCOpCode::_STR | TSCOpCode::_END => None,
+            TSCOpCode::AEp
+            | TSCOpCode::CAT
+            | TSCOpCode::CIL
+            | TSCOpCode::CLO
+            | TSCOpCode::CLR
+            | TSCOpCode::CPS
+            | TSCOpCode::CRE
+            | TSCOpCode::CSS
+            | TSCOpCode::END
+            | TSCOpCode::ESC
+            | TSCOpCode::FLA
+            | TSCOpCode::FMU
+            | TSCOpCode::FRE
+            | TSCOpCode::HMC
+            | TSCOpCode::INI
+            | TSCOpCode::KEY
+            | TSCOpCode::LDP
+            | TSCOpCode::MLP
+            | TSCOpCode::MM0
+            | TSCOpCode::MNA
+            | TSCOpCode::MS2
+            | TSCOpCode::MS3
+            | TSCOpCode::MSG
+            | TSCOpCode::NOD
+            | TSCOpCode::PRI
+            | TSCOpCode::RMU
+            | TSCOpCode::SAT
+            | TSCOpCode::SLP
+            | TSCOpCode::SMC
+            | TSCOpCode::SPS
+            | TSCOpCode::STC
+            | TSCOpCode::SVP
+            | TSCOpCode::TUR
+            | TSCOpCode::WAS
+            | TSCOpCode::ZAM
+            | TSCOpCode::HM2
+            | TSCOpCode::POP
+            | TSCOpCode::KE2
+            | TSCOpCode::FR2 => Some(0),
+            TSCOpCode::BOA
+            | TSCOpCode::BSL
+            | TSCOpCode::FOM
+            | TSCOpCode::QUA
+            | TSCOpCode::UNI
+            | TSCOpCode::MYB
+            | TSCOpCode::MYD
+            | TSCOpCode::FAI
+            | TSCOpCode::FAO
+            | TSCOpCode::WAI
+            | TSCOpCode::FAC
+            | TSCOpCode::GIT
+            | TSCOpCode::NUM
+            | TSCOpCode::DNA
+            | TSCOpCode::DNP
+            | TSCOpCode::FLm
+            | TSCOpCode::FLp
+            | TSCOpCode::MPp
+            | TSCOpCode::SKm
+            | TSCOpCode::SKp
+            | TSCOpCode::EQp
+            | TSCOpCode::EQm
+            | TSCOpCode::MLp
+            | TSCOpCode::ITp
+            | TSCOpCode::ITm
+            | TSCOpCode::AMm
+            | TSCOpCode::MPJ
+            | TSCOpCode::YNJ
+            | TSCOpCode::EVE
+            | TSCOpCode::XX1
+            | TSCOpCode::SIL
+            | TSCOpCode::LIp
+            | TSCOpCode::SOU
+            | TSCOpCode::CMU
+            | TSCOpCode::SSS
+            | TSCOpCode::ACH
+            | TSCOpCode::S2MV
+            | TSCOpCode::S2PJ
+            | TSCOpCode::PSH
+            | TSCOpCode::VNM => Some(1),
+            TSCOpCode::FON
+            | TSCOpCode::FOB
+            | TSCOpCode::MOV
+            | TSCOpCode::AMp
+            | TSCOpCode::NCJ
+            | TSCOpCode::ECJ
+            | TSCOpCode::FLJ
+            | TSCOpCode::ITJ
+            | TSCOpCode::SKJ
+            | TSCOpCode::AMJ
+            | TSCOpCode::UNJ
+            | TSCOpCode::SMP
+            | TSCOpCode::PSp
+            | TSCOpCode::IpN
+            | TSCOpCode::FFm
+            | TSCOpCode::VAR
+            | TSCOpCode::VAp
+            | TSCOpCode::VAm => Some(2),
+            TSCOpCode::ANP
+            | TSCOpCode::CNP
+            | TSCOpCode::INP
+            | TSCOpCode::TAM
+            | TSCOpCode::CMP
+            | TSCOpCode::INJ
+            | TSCOpCode::VRN => Some(3),
+            TSCOpCode::TRA | TSCOpCode::MNP | TSCOpCode::SNP | TSCOpCode::VAJ => Some(4),
+        }
+    }
 }
 
 #[derive(FromPrimitive, PartialEq, Copy, Clone)]
@@ -1,7 +1,7 @@
 use std::iter::Peekable;
 
 use crate::framework::error::GameError::ParseError;
-use crate::framework::error::GameResult;
+use crate::framework::error::{GameError, GameResult};
 
 pub fn expect_char<I: Iterator<Item=u8>>(expect: u8, iter: &mut I) -> GameResult {
     let res = iter.next();
@@ -35,3 +35,63 @@ pub fn read_number<I: Iterator<Item=u8>>(iter: &mut Peekable<I>) -> GameResult<i
         .and_then(|result| iter.next().map(|v| result + v.wrapping_sub(b'0') as i32))
         .ok_or_else(|| ParseError("Script unexpectedly ended.".to_string()))
 }
+
+/// Returns the number of bytes of `data` already consumed by the iterator.
+pub fn consumed<I: ExactSizeIterator<Item=u8>>(data: &[u8], iter: &Peekable<I>) -> usize {
+    data.len() - iter.len()
+}
+
+/// Offsets of the line starts of a script, for looking up many positions in the same file.
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(data: &[u8]) -> LineIndex {
+        let line_starts = std::iter::once(0)
+            .chain(data.iter().enumerate().filter(|(_, &c)| c == b'\n').map(|(i, _)| i + 1))
+            .collect();
+
+        LineIndex { line_starts }
+    }
+
+    /// Returns the 1-based line and column of the byte at `offset` (0-based).
+    pub fn line_column(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+
+        (line, offset - self.line_starts[line - 1] + 1)
+    }
+}
+
+/// Returns the 1-based line and column of the byte at `offset` (0-based) in `data`.
+pub fn line_column(data: &[u8], offset: usize) -> (usize, usize) {
+    let offset = offset.min(data.len());
+
+    LineIndex::new(&data[..offset]).line_column(offset)
+}
+
+/// Appends the line and column of the `offset`th byte (1-based) of `data` to parse errors.
+pub fn error_at(data: &[u8], offset: usize, error: GameError) -> GameError {
+    match error {
+        ParseError(msg) => {
+            let (line, column) = line_column(data, offset.clamp(1, data.len().max(1)) - 1);
+
+            ParseError(format!("{} (line {}, column {})", msg, line, column))
+        }
+        error => error,
+    }
+}
+
+#[test]
+fn test_line_index() {
+    let data = b"#0100\r\n<MSGab\n\n<END";
+    let lines = LineIndex::new(data);
+
+    assert_eq!(lines.line_column(0), (1, 1));
+    assert_eq!(lines.line_column(6), (1, 7));
+    assert_eq!(lines.line_column(7), (2, 1));
+    assert_eq!(lines.line_column(13), (2, 7));
+    assert_eq!(lines.line_column(14), (3, 1));
+    assert_eq!(lines.line_column(data.len()), (4, 5));
+    assert!((0..=data.len()).all(|offset| line_column(data, offset) == lines.line_column(offset)));
+}
@@ -24,6 +24,7 @@ use crate::game::npc::NPCContext;
 use crate::game::npc::NPC;
 use crate::game::player::{ControlMode, TargetPlayer};
 use crate::game::scripting::tsc::bytecode_utils::read_cur_varint;
+use crate::game::scripting::tsc::debugger::TextScriptDebugger;
 use crate::game::scripting::tsc::encryption::decrypt_tsc;
 use crate::game::scripting::tsc::opcodes::TSCOpCode;
 use crate::game::shared_game_state::ReplayState;
@@ -37,6 +38,7 @@ use crate::scene::game_scene::GameScene;
 const TSC_SUBSTITUTION_MAP_SIZE: usize = 1;
 
 bitfield! {
+    #[derive(Clone, Copy, serde::Serialize, serde::Deserialize)]
     pub struct TextScriptFlags(u16);
     impl Debug;
     pub render, set_render: 0;
@@ -180,7 +182,7 @@ impl From<TextScriptEncoding> for &'static encoding_rs::Encoding {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 #[repr(u8)]
 pub enum TextScriptLine {
     Line1 = 0,
@@ -188,14 +190,14 @@ pub enum TextScriptLine {
     Line3,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 #[repr(u8)]
 pub enum ConfirmSelection {
     Yes,
     No,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 #[repr(u8)]
 pub enum ScriptMode {
     Map,
@@ -216,7 +218,7 @@ impl Not for ConfirmSelection {
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum TextScriptExecutionState {
     Ended,
     Running(u16, u32),
@@ -234,7 +236,7 @@ pub enum TextScriptExecutionState {
     Reset,
 }
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(PartialEq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum IllustrationState {
     Hidden,
     Shown,
@@ -268,6 +270,29 @@ pub struct TextScriptVM {
     pub illustration_state: IllustrationState,
     prev_char: char,
     pub substitution_rect_map: [(char, Rect<u16>); TSC_SUBSTITUTION_MAP_SIZE],
+    pub debugger: TextScriptDebugger,
+}
+
+/// Execution state of the `TextScriptVM`, without the scripts themselves.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct SavedTextScriptState {
+    state: TextScriptExecutionState,
+    stack: Vec<TextScriptExecutionState>,
+    flags: TextScriptFlags,
+    mode: ScriptMode,
+    executor_player: TargetPlayer,
+    suspend: bool,
+    reset_invicibility: bool,
+    numbers: [u16; 4],
+    face: u16,
+    item: u16,
+    current_line: TextScriptLine,
+    line_1: Vec<char>,
+    line_2: Vec<char>,
+    line_3: Vec<char>,
+    current_illustration: Option<String>,
+    illustration_state: IllustrationState,
+    prev_char: char,
 }
 
 pub struct Scripts {
@@ -344,9 +369,54 @@ impl TextScriptVM {
             illustration_state: IllustrationState::Hidden,
             prev_char: '\x00',
             substitution_rect_map: [('=', Rect::new(0, 0, 0, 0))],
+            debugger: TextScriptDebugger::new(),
         }
     }
 
+    pub fn save_state(&self) -> SavedTextScriptState {
+        SavedTextScriptState {
+            state: self.state,
+            stack: self.stack.clone(),
+            flags: self.flags,
+            mode: self.mode,
+            executor_player: self.executor_player,
+            suspend: self.suspend,
+            reset_invicibility: self.reset_invicibility,
+            numbers: self.numbers,
+            face: self.face,
+            item: self.item,
+            current_line: self.current_line,
+            line_1: self.line_1.clone(),
+            line_2: self.line_2.clone(),
+            line_3: self.line_3.clone(),
+            current_illustration: self.current_illustration.clone(),
+            illustration_state: self.illustration_state,
+            prev_char: self.prev_char,
+        }
+    }
+
+    /// Restores a state saved with `save_state`, the scripts it was running have to be loaded already.
+    pub fn restore_state(&mut self, saved: SavedTextScriptState) {
+        self.state = saved.state;
+        self.stack = saved.stack;
+        self.flags = saved.flags;
+        self.mode = saved.mode;
+        self.executor_player = saved.executor_player;
+        self.suspend = saved.suspend;
+        self.reset_invicibility = saved.reset_invicibility;
+        self.numbers = saved.numbers;
+        self.face = saved.face;
+        self.item = saved.item;
+        self.current_line = saved.current_line;
+        self.line_1 = saved.line_1;
+        self.line_2 = saved.line_2;
+        self.line_3 = saved.line_3;
+        self.current_illustration = saved.current_illustration;
+        self.illustration_state = saved.illustration_state;
+        self.prev_char = saved.prev_char;
+        self.debugger.reset();
+    }
+
     pub fn set_global_script(&mut self, script: TextScript) {
         {
             let mut scripts = self.scripts.borrow_mut();
@@ -395,6 +465,7 @@ impl TextScriptVM {
         self.illustration_state = IllustrationState::Hidden;
         self.face = 0;
         self.clear_text_box();
+        self.debugger.reset();
     }
 
     pub fn clear_text_box(&mut self) {
@@ -405,6 +476,17 @@ impl TextScriptVM {
         self.line_3.clear();
     }
 
+    /// Appends text to the current line of the text box as-is, without waiting for it to be typed out.
+    pub fn append_text(&mut self, text: &str) {
+        let line = match self.current_line {
+            TextScriptLine::Line1 => &mut self.line_1,
+            TextScriptLine::Line2 => &mut self.line_2,
+            TextScriptLine::Line3 => &mut self.line_3,
+        };
+
+        line.extend(text.chars());
+    }
+
     pub fn set_mode(&mut self, mode: ScriptMode) {
         self.reset();
         self.mode = mode;
@@ -418,6 +500,21 @@ impl TextScriptVM {
         log::info!("Started script: #{:04}", event_num);
     }
 
+    /// Returns the event and name of the opcode that is being executed, if any.
+    pub fn current_instruction(&self) -> Option<(u16, &'static str)> {
+        let TextScriptExecutionState::Running(event, ip) = self.state else {
+            return None;
+        };
+
+        let scripts = self.scripts.borrow();
+        let bytecode = scripts.find_script(self.mode, event)?;
+        let mut cursor: Cursor<&[u8]> = Cursor::new(bytecode);
+        cursor.seek(SeekFrom::Start(ip as u64)).ok()?;
+
+        let op: TSCOpCode = FromPrimitive::from_i32(read_cur_varint(&mut cursor).ok()?)?;
+        Some((event, op.into()))
+    }
+
     pub fn run(state: &mut SharedGameState, game_scene: &mut GameScene, ctx: &mut Context) -> GameResult {
         let scripts_ref = state.textscript_vm.scripts.clone();
         let scripts = scripts_ref.borrow();
@@ -449,6 +546,10 @@ impl TextScriptVM {
                     }
 
                     state.textscript_vm.state = if let Some((_, bytecode)) = cached_event {
+                        if state.textscript_vm.debugger.should_pause(event, ip, bytecode) {
+                            break;
+                        }
+
                         TextScriptVM::execute(bytecode, event, ip, state, game_scene, ctx)?
                     } else {
                         TextScriptExecutionState::Ended
@@ -1334,6 +1435,7 @@ impl TextScriptVM {
                 new_scene.frame.wait = game_scene.frame.wait;
                 new_scene.nikumaru = game_scene.nikumaru;
                 new_scene.replay = game_scene.replay.clone();
+                state.split_timer.on_stage_change(map_id, state.replay_state);
                 // Reset player invincibility (kind of hacky, but oh well)
                 if state.constants.textscript.reset_invicibility_on_any_script {
                     new_scene.player1.shock_counter = 0;
@@ -1958,10 +2060,18 @@ impl TextScriptVM {
                 );
             }
             TSCOpCode::STC => {
-                let new_record = game_scene.nikumaru.save_counter(state, ctx)?;
-
-                if state.replay_state == ReplayState::Recording {
-                    game_scene.replay.stop_recording(state, ctx, new_record)?;
+                match state.replay_state {
+                    ReplayState::Recording => {
+                        let new_record = game_scene.nikumaru.save_counter(state, ctx)?;
+                        game_scene.replay.stop_recording(state, ctx, new_record)?;
+                    }
+                    // Played back runs shouldn't overwrite the player's records.
+                    ReplayState::Playback(_) => {
+                        game_scene.replay.finish_time = Some(game_scene.nikumaru.tick);
+                    }
+                    ReplayState::None => {
+                        game_scene.nikumaru.save_counter(state, ctx)?;
+                    }
                 }
 
                 exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
@@ -1985,6 +2095,57 @@ impl TextScriptVM {
 
                 log::info!("achievement get: {}", idx);
 
+                exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
+            }
+            TSCOpCode::VAR => {
+                let id = read_cur_varint(&mut cursor)? as u16;
+                let value = read_cur_varint(&mut cursor)?;
+
+                state.script_variables.set(id, value);
+
+                exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
+            }
+            TSCOpCode::VAp | TSCOpCode::VAm => {
+                let id = read_cur_varint(&mut cursor)? as u16;
+                let amount = read_cur_varint(&mut cursor)?;
+                let amount = if op == TSCOpCode::VAm { amount.saturating_neg() } else { amount };
+
+                state.script_variables.add(id, amount);
+
+                exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
+            }
+            TSCOpCode::VAJ => {
+                let id = read_cur_varint(&mut cursor)? as u16;
+                let comparison = read_cur_varint(&mut cursor)?;
+                let operand = read_cur_varint(&mut cursor)?;
+                let event_num = read_cur_varint(&mut cursor)? as u16;
+
+                let matches = state.script_variables.compare(id, comparison, operand).unwrap_or_else(|| {
+                    log::warn!("Unknown <VAJ comparison: {}", comparison);
+                    false
+                });
+
+                if matches {
+                    state.textscript_vm.clear_text_box();
+                    exec_state = TextScriptExecutionState::Running(event_num, 0);
+                } else {
+                    exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
+                }
+            }
+            TSCOpCode::VRN => {
+                let id = read_cur_varint(&mut cursor)? as u16;
+                let min = read_cur_varint(&mut cursor)?;
+                let max = read_cur_varint(&mut cursor)?;
+
+                state.script_variables.randomize(id, min, max, &state.game_rng);
+
+                exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
+            }
+            TSCOpCode::VNM => {
+                let id = read_cur_varint(&mut cursor)? as u16;
+                let value = state.script_variables.get(id);
+                state.textscript_vm.append_text(&value.to_string());
+
                 exec_state = TextScriptExecutionState::Running(event, cursor.position() as u32);
             }
         }
@@ -2031,3 +2192,20 @@ impl TextScript {
         self.event_map.contains_key(&id)
     }
 }
+
+#[test]
+fn test_show_variable_in_message() {
+    use crate::game::scripting::tsc::variables::ScriptVariables;
+
+    let mut vars = ScriptVariables::new();
+    vars.set(1, -42);
+
+    let mut vm = TextScriptVM::new();
+    vm.line_1.extend("Ammo: ".chars());
+    vm.append_text(&vars.get(1).to_string());
+    vm.current_line = TextScriptLine::Line2;
+    vm.append_text(&vars.get(2).to_string());
+
+    assert_eq!(vm.line_1.iter().collect::<String>(), "Ammo: -42");
+    assert_eq!(vm.line_2.iter().collect::<String>(), "0");
+}
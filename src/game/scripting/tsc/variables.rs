@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use crate::util::rng::RNG;
+
+/// Integer variables of the scripts, see `<VAR`. Variables that were never set are 0 and aren't stored.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct ScriptVariables(BTreeMap<u16, i32>);
+
+impl ScriptVariables {
+    pub fn new() -> ScriptVariables {
+        ScriptVariables(BTreeMap::new())
+    }
+
+    pub fn get(&self, id: u16) -> i32 {
+        self.0.get(&id).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, id: u16, value: i32) {
+        if value == 0 {
+            self.0.remove(&id);
+        } else {
+            self.0.insert(id, value);
+        }
+    }
+
+    /// `<VA+` and `<VA-`, saturates instead of overflowing.
+    pub fn add(&mut self, id: u16, amount: i32) {
+        self.set(id, self.get(id).saturating_add(amount));
+    }
+
+    /// `<VAJ` comparison of the variable against an operand, returns `None` for unknown comparisons.
+    ///
+    /// 0 - equal, 1 - not equal, 2 - less, 3 - less or equal, 4 - greater, 5 - greater or equal.
+    pub fn compare(&self, id: u16, comparison: i32, operand: i32) -> Option<bool> {
+        let value = self.get(id);
+
+        match comparison {
+            0 => Some(value == operand),
+            1 => Some(value != operand),
+            2 => Some(value < operand),
+            3 => Some(value <= operand),
+            4 => Some(value > operand),
+            5 => Some(value >= operand),
+            _ => None,
+        }
+    }
+
+    /// `<VRN`, sets the variable to a random value between both bounds, inclusive. Bounds can be given in any order.
+    pub fn randomize(&mut self, id: u16, min: i32, max: i32, rng: &dyn RNG) {
+        self.set(id, rng.range(min.min(max)..min.max(max)));
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Iterates over the variables with a non-zero value, ordered by ID.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, i32)> + '_ {
+        self.0.iter().map(|(&id, &value)| (id, value))
+    }
+}
+
+#[test]
+fn test_set_add_sub() {
+    let mut vars = ScriptVariables::new();
+    assert_eq!(vars.get(1), 0);
+
+    vars.set(1, 10);
+    vars.add(1, 5);
+    vars.add(1, -2);
+    assert_eq!(vars.get(1), 13);
+
+    vars.add(2, i32::MAX);
+    vars.add(2, 1);
+    assert_eq!(vars.get(2), i32::MAX);
+
+    vars.add(1, -13);
+    assert_eq!(vars.get(1), 0);
+    assert_eq!(vars.iter().collect::<Vec<_>>(), [(2, i32::MAX)]);
+}
+
+#[test]
+fn test_compare() {
+    let mut vars = ScriptVariables::new();
+    vars.set(1, 5);
+
+    let results: Vec<_> = (0..6).map(|cmp| (vars.compare(1, cmp, 5), vars.compare(1, cmp, 6))).collect();
+    assert_eq!(
+        results,
+        [
+            (Some(true), Some(false)),
+            (Some(false), Some(true)),
+            (Some(false), Some(true)),
+            (Some(true), Some(true)),
+            (Some(false), Some(false)),
+            (Some(true), Some(false)),
+        ]
+    );
+    assert_eq!(vars.compare(1, 6, 5), None);
+    assert_eq!(vars.compare(2, 0, 0), Some(true));
+}
+
+#[test]
+fn test_randomize_bounds() {
+    use crate::util::rng::XorShift;
+
+    let rng = XorShift::new(0x1234);
+    let mut vars = ScriptVariables::new();
+
+    for (min, max) in [(1, 6), (6, 1)] {
+        let mut seen = [false; 6];
+        for _ in 0..1000 {
+            vars.randomize(1, min, max, &rng);
+            let value = vars.get(1);
+            assert!((1..=6).contains(&value), "{} out of range", value);
+            seen[value as usize - 1] = true;
+        }
+
+        assert_eq!(seen, [true; 6]);
+    }
+
+    vars.randomize(2, 3, 3, &rng);
+    assert_eq!(vars.get(2), 3);
+}
@@ -35,6 +35,9 @@ pub struct Settings {
     pub bgm_volume: f32,
     #[serde(default = "default_vol")]
     pub sfx_volume: f32,
+    /// Pan and attenuate sound effects depending on where they come from on the stage.
+    #[serde(default)]
+    pub positional_sfx: bool,
     #[serde(default = "default_timing")]
     pub timing_mode: TimingMode,
     #[serde(default = "default_pause_on_focus_loss")]
@@ -449,6 +452,7 @@ impl Default for Settings {
             soundtrack: "Organya".to_string(),
             bgm_volume: 1.0,
             sfx_volume: 1.0,
+            positional_sfx: false,
             timing_mode: default_timing(),
             pause_on_focus_loss: default_pause_on_focus_loss(),
             organya_interpolation: InterpolationMode::Linear,
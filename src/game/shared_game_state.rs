@@ -1,9 +1,13 @@
+use std::path::Path;
+use std::time::Duration;
 use std::{cmp, ops::Div};
 
 use chrono::{Datelike, Local};
 
 use crate::common::{Color, ControlFlags, Direction, FadeState};
 use crate::components::draw_common::{draw_number, Alignment};
+use crate::components::replay::ReplaySettings;
+use crate::components::split_timer::SplitTimer;
 use crate::data::vanilla::VanillaExtractor;
 #[cfg(feature = "discord-rpc")]
 use crate::discord::DiscordRPC;
@@ -15,13 +19,18 @@ use crate::framework::graphics::{create_texture_mutable, set_render_target};
 use crate::framework::vfs::OpenOptions;
 use crate::framework::{filesystem, graphics};
 use crate::game::caret::{Caret, CaretType};
+use crate::game::flag_watcher::{FlagChange, FlagKind, FlagWatcher};
+use crate::game::frame_capture::FrameRecorder;
 use crate::game::npc::NPCTable;
 use crate::game::player::TargetPlayer;
-use crate::game::profile::GameProfile;
+use crate::game::profile::{GameProfile, SaveThumbnail};
+use crate::game::replay_library::ReplayGhost;
+use crate::game::save_state::SaveState;
 use crate::game::scripting::tsc::credit_script::{CreditScript, CreditScriptVM};
 use crate::game::scripting::tsc::text_script::{
     ScriptMode, TextScript, TextScriptEncoding, TextScriptExecutionState, TextScriptVM,
 };
+use crate::game::scripting::tsc::variables::ScriptVariables;
 use crate::game::settings::Settings;
 use crate::game::stage::StageData;
 use crate::graphics::bmfont::BMFont;
@@ -296,6 +305,7 @@ impl Default for PlayerSkinLocation {
 pub struct SharedGameState {
     pub control_flags: ControlFlags,
     pub game_flags: BitVec,
+    pub script_variables: ScriptVariables,
     pub skip_flags: BitVec,
     pub map_flags: BitVec,
     pub fade_state: FadeState,
@@ -328,6 +338,10 @@ pub struct SharedGameState {
     pub preferred_viewport_size: (f32, f32),
     pub next_scene: Option<Box<dyn Scene>>,
     pub textscript_vm: TextScriptVM,
+    pub flag_watcher: FlagWatcher,
+    pub split_timer: SplitTimer,
+    /// Active frame recording, frames are captured after every drawn frame that followed a game tick.
+    pub frame_recorder: Option<FrameRecorder>,
     pub creditscript_vm: CreditScriptVM,
     pub lightmap_canvas: Option<Box<dyn BackendTexture>>,
     pub season: Season,
@@ -344,6 +358,14 @@ pub struct SharedGameState {
     pub player_count_modified_in_game: bool,
     pub player2_skin_location: PlayerSkinLocation,
     pub replay_state: ReplayState,
+    /// User settings overridden by a replay during playback, restored once it ends.
+    pub replay_settings_backup: Option<ReplaySettings>,
+    /// Replay from the library raced against while recording, drawn as a ghost player.
+    pub replay_ghost: Option<ReplayGhost>,
+    /// In-memory save state slot used by quick save state / quick load state.
+    pub save_state_slot: Option<Box<SaveState>>,
+    /// Time spent in game since the current save was started.
+    pub play_time: Duration,
     pub mod_requirements: ModRequirements,
     pub loc: Locale,
     pub tutorial_counter: u16,
@@ -406,22 +428,11 @@ impl SharedGameState {
 
         let mod_list = ModList::load(ctx, &constants)?;
 
-        for i in 0..0xffu8 {
-            let path = format!("pxt/fx{:02x}.pxt", i);
-            if let Ok(file) = filesystem::open_find(ctx, &constants.base_paths, path) {
-                sound_manager.set_sample_params_from_file(i, file)?;
-                continue;
-            }
-
-            let path = format!("PixTone/{:03}.pxt", i);
-            if let Ok(file) = filesystem::open_find(ctx, &constants.base_paths, path) {
-                sound_manager.set_sample_params_from_file(i, file)?;
-                continue;
-            }
-        }
+        sound_manager.load_pixtone_overrides(ctx, &constants.base_paths)?;
 
         sound_manager.set_song_volume(settings.bgm_volume);
         sound_manager.set_sfx_volume(settings.sfx_volume);
+        sound_manager.set_positional_sfx(settings.positional_sfx);
 
         let current_time = Local::now();
         let more_rust = (current_time.month() == 7 && current_time.day() == 7) || settings.more_rust;
@@ -436,6 +447,7 @@ impl SharedGameState {
         Ok(SharedGameState {
             control_flags: ControlFlags(0),
             game_flags: BitVec::with_size(8000),
+            script_variables: ScriptVariables::new(),
             skip_flags: BitVec::with_size(64),
             map_flags: BitVec::with_size(128),
             fade_state: FadeState::Hidden,
@@ -466,6 +478,9 @@ impl SharedGameState {
             preferred_viewport_size: (320.0, 240.0),
             next_scene: None,
             textscript_vm: TextScriptVM::new(),
+            flag_watcher: FlagWatcher::new(),
+            split_timer: SplitTimer::new(),
+            frame_recorder: None,
             creditscript_vm: CreditScriptVM::new(),
             lightmap_canvas: None,
             season,
@@ -482,6 +497,10 @@ impl SharedGameState {
             player_count_modified_in_game: false,
             player2_skin_location: PlayerSkinLocation::default(),
             replay_state: ReplayState::None,
+            replay_settings_backup: None,
+            replay_ghost: None,
+            save_state_slot: None,
+            play_time: Duration::ZERO,
             mod_requirements,
             loc: locale,
             tutorial_counter: 0,
@@ -521,7 +540,8 @@ impl SharedGameState {
         self.reload_stage_table(ctx)?;
 
         let npc_tbl = filesystem::open_find(ctx, &self.constants.base_paths, "npc.tbl")?;
-        let npc_table = NPCTable::load_from(npc_tbl)?;
+        let mut npc_table = NPCTable::load_from(npc_tbl)?;
+        npc_table.load_custom_npcs(ctx, &self.constants.base_paths);
         self.npc_table = npc_table;
 
         let head_tsc = filesystem::open_find(ctx, &self.constants.base_paths, "Head.tsc")?;
@@ -667,6 +687,9 @@ impl SharedGameState {
         self.textscript_vm.state = TextScriptExecutionState::Running(self.constants.game.new_game_event, 0);
         self.tutorial_counter = 300;
 
+        self.split_timer.load(ctx);
+        self.split_timer.start(self.settings.timing_mode);
+
         self.next_scene = Some(Box::new(next_scene));
 
         Ok(())
@@ -705,8 +728,17 @@ impl SharedGameState {
         target_player: Option<TargetPlayer>,
     ) -> GameResult {
         if let Some(save_path) = self.get_save_filename(self.save_slot) {
-            if let Ok(data) = filesystem::open_options(ctx, save_path, OpenOptions::new().write(true).create(true)) {
-                let profile = GameProfile::dump(self, game_scene, target_player);
+            if let Ok(data) =
+                filesystem::open_options(ctx, save_path, OpenOptions::new().write(true).create(true).truncate(true))
+            {
+                let mut profile = GameProfile::dump(self, game_scene, target_player);
+
+                if let Some(metadata) = &mut profile.metadata {
+                    metadata.thumbnail = graphics::read_framebuffer(ctx)
+                        .ok()
+                        .and_then(|(width, height, pixels)| SaveThumbnail::from_rgba(width, height, &pixels));
+                }
+
                 profile.write_save(data)?;
             } else {
                 log::warn!("Cannot open save file.");
@@ -718,12 +750,32 @@ impl SharedGameState {
         Ok(())
     }
 
+    /// Takes a snapshot of the whole simulation into the in-memory save state slot.
+    pub fn quick_save_state(&mut self, game_scene: &GameScene) -> GameResult {
+        self.save_state_slot = Some(Box::new(SaveState::capture(self, game_scene)?));
+
+        Ok(())
+    }
+
+    /// Restores the snapshot from the in-memory save state slot, it's kept so it can be loaded again.
+    pub fn quick_load_state(&mut self, game_scene: &mut GameScene, ctx: &mut Context) -> GameResult {
+        if let Some(save_state) = self.save_state_slot.clone() {
+            save_state.restore(self, game_scene, ctx)?;
+        } else {
+            log::info!("No save state to load.");
+        }
+
+        Ok(())
+    }
+
     pub fn load_or_start_game(&mut self, ctx: &mut Context) -> GameResult {
         if let Some(save_path) = self.get_save_filename(self.save_slot) {
             if let Ok(data) = filesystem::user_open(ctx, save_path) {
                 match GameProfile::load_from_save(data) {
                     Ok(profile) => {
                         self.reset();
+                        // runs are timed from a new game only
+                        self.split_timer.stop();
                         let mut next_scene = GameScene::new(self, ctx, profile.current_map as usize)?;
 
                         profile.apply(self, &mut next_scene, ctx);
@@ -751,9 +803,11 @@ impl SharedGameState {
     pub fn reset(&mut self) {
         self.control_flags.0 = 0;
         self.game_flags = BitVec::with_size(8000);
+        self.script_variables.clear();
         self.fade_state = FadeState::Hidden;
         self.game_rng = XorShift::new(chrono::Local::now().timestamp() as i32);
         self.teleporter_slots.clear();
+        self.play_time = Duration::ZERO;
         self.quake_counter = 0;
         self.carets.clear();
         self.textscript_vm.set_mode(ScriptMode::Map);
@@ -814,6 +868,14 @@ impl SharedGameState {
 
     pub fn set_flag(&mut self, id: usize, value: bool) {
         if id < self.game_flags.len() {
+            if self.flag_watcher.is_watched(FlagKind::Game, id) && self.game_flags.get(id) != Some(value) {
+                self.record_flag_change(FlagKind::Game, id, value);
+            }
+
+            if value && self.game_flags.get(id) == Some(false) {
+                self.split_timer.on_flag_set(id, self.replay_state);
+            }
+
             self.game_flags.set(id, value);
         } else {
             log::warn!("Attempted to set an out-of-bounds flag: {} to {}.", id, value);
@@ -828,6 +890,11 @@ impl SharedGameState {
         }
     }
 
+    fn record_flag_change(&mut self, kind: FlagKind, id: usize, value: bool) {
+        let source = self.textscript_vm.current_instruction();
+        self.flag_watcher.record(FlagChange { kind, id, value, source });
+    }
+
     pub fn reset_skip_flags(&mut self) {
         self.skip_flags = BitVec::with_size(64);
     }
@@ -854,6 +921,10 @@ impl SharedGameState {
 
     pub fn set_map_flag(&mut self, id: usize, value: bool) {
         if id < self.map_flags.len() {
+            if self.flag_watcher.is_watched(FlagKind::Map, id) && self.map_flags.get(id) != Some(value) {
+                self.record_flag_change(FlagKind::Map, id, value);
+            }
+
             self.map_flags.set(id, value);
         } else {
             log::warn!("Attempted to set an out-of-bounds map flag {}:", id);
@@ -907,6 +978,29 @@ impl SharedGameState {
         Ok(())
     }
 
+    pub fn restore_replay_settings(&mut self) {
+        if let Some(settings) = self.replay_settings_backup.take() {
+            settings.apply(&mut self.settings);
+        }
+    }
+
+    /// Starts recording frames into `path`, replacing the active recording.
+    pub fn start_recording(&mut self, path: &Path) -> GameResult {
+        self.stop_recording();
+        self.frame_recorder = Some(FrameRecorder::new(path, self.settings.timing_mode.get_tps())?);
+
+        Ok(())
+    }
+
+    /// Stops the active recording and waits until all captured frames are written.
+    pub fn stop_recording(&mut self) {
+        if let Some(mut recorder) = self.frame_recorder.take() {
+            if let Err(err) = recorder.finish() {
+                log::error!("Failed to finish the recording: {}", err);
+            }
+        }
+    }
+
     pub fn get_damage(&self, hp: i32) -> i32 {
         match self.difficulty {
             GameDifficulty::Easy => cmp::max(hp / 2, 1),
@@ -960,7 +1054,7 @@ impl SharedGameState {
             .soundtracks
             .iter()
             .find(|s| s.id == id)
-            .map_or_else(|| id.to_owned(), |s| self.loc.t(format!("soundtrack.{}", s.id).as_str()).to_owned())
+            .map_or_else(|| id.to_owned(), |s| s.localized_name(&self.loc))
     }
 
     pub fn tt(&self, key: &str, args: &[(&str, &str)]) -> String {
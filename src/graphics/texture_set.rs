@@ -488,6 +488,22 @@ impl TextureSet {
         self.tex_map.clear();
     }
 
+    /// Registers a texture created at runtime under given name, drawn at 1:1 scale.
+    pub fn insert_texture(&mut self, name: &str, texture: Box<dyn BackendTexture>) {
+        let (width, height) = texture.dimensions();
+        let main_batch = SubBatch {
+            batch: texture,
+            width,
+            height,
+            real_width: width,
+            real_height: height,
+            scale_x: 1.0,
+            scale_y: 1.0,
+        };
+
+        self.tex_map.insert(name.to_owned(), Box::new(CombinedBatch { main_batch, glow_batch: None }));
+    }
+
     fn make_transparent(rgba: &mut RgbaImage) {
         for (r, g, b, a) in rgba.iter_mut().tuples() {
             if *r == 0 && *g == 0 && *b == 0 {
@@ -615,7 +631,7 @@ impl TextureSet {
         constants: &EngineConstants,
         name: &str,
     ) -> GameResult<&mut Box<dyn SpriteBatch>> {
-        if ctx.headless {
+        if ctx.headless && !ctx.software_rendering {
             return Ok(&mut self.dummy_batch);
         }
 
@@ -2,18 +2,33 @@ use std::collections::HashMap;
 
 use crate::engine_constants::DataType;
 use crate::framework::context::Context;
+use crate::framework::error::GameError::ResourceLoadError;
+use crate::framework::error::GameResult;
 use crate::framework::filesystem;
 use crate::game::scripting::tsc::text_script::TextScriptEncoding;
 use crate::game::shared_game_state::FontData;
 
+/// Locale every other locale eventually falls back to, also the reference of completeness reports.
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// Keys describing the locale itself rather than strings to translate.
+const METADATA_KEYS: [&str; 6] = ["name", "parent", "font", "font_scale", "encoding", "stage_encoding"];
+
+/// Keys that only apply to the locale defining them.
+const NON_INHERITED_KEYS: [&str; 2] = ["name", "parent"];
+
 #[derive(Debug, Clone)]
 pub struct Locale {
     pub code: String,
     pub name: String,
+    /// Locale missing strings are looked up in, `pt-BR` falls back to `pt` unless set in the locale file.
+    pub parent: Option<String>,
     pub font: FontData,
     pub encoding: Option<TextScriptEncoding>,
     pub stage_encoding: Option<TextScriptEncoding>,
     strings: HashMap<String, String>,
+    /// Strings taken from the fallback chain, see `Locale::resolve_inheritance`.
+    inherited: HashMap<String, String>,
 
     // Properties of the translation data "root"
     pub is_present: bool,
@@ -26,10 +41,12 @@ impl Default for Locale {
         Locale {
             code: "en".to_owned(),
             name: "English".to_owned(),
+            parent: None,
             font: FontData { path: String::new(), scale: 1.0, space_offset: 0.0 },
             encoding: None,
             stage_encoding: None,
             strings: HashMap::new(),
+            inherited: HashMap::new(),
 
             is_present: false,
             is_complete: false,
@@ -39,41 +56,46 @@ impl Default for Locale {
 }
 
 impl Locale {
-    pub fn new(ctx: &mut Context, base_paths: &Vec<String>, code: &str) -> Locale {
-        let file = filesystem::open_find(ctx, base_paths, &format!("locale/{code}.json")).unwrap();
-        let json: serde_json::Value = serde_json::from_reader(file).unwrap();
+    pub fn new(ctx: &mut Context, base_paths: &Vec<String>, code: &str) -> GameResult<Locale> {
+        let file = filesystem::open_find(ctx, base_paths, &format!("locale/{code}.json"))?;
+        let json: serde_json::Value = serde_json::from_reader(file)
+            .map_err(|err| ResourceLoadError(format!("Failed to parse locale {}: {}", code, err)))?;
 
-        let strings = Locale::flatten(&json);
-
-        let name = strings["name"].clone();
+        if !json.is_object() {
+            return Err(ResourceLoadError(format!("Locale {} is not a JSON object.", code)));
+        }
 
-        let font_name = strings["font"].clone();
-        let font_scale = strings["font_scale"].parse::<f32>().unwrap_or(1.0);
-        let font = FontData::new(font_name, font_scale, 0.0);
+        Ok(Locale::from_strings(code, Locale::flatten(&json)))
+    }
 
-        let encoding = if let Some(enc) = strings.get("encoding").clone() {
-            Some(TextScriptEncoding::from(enc.as_str()))
-        } else {
-            None
-        };
-        let stage_encoding = if let Some(enc) = strings.get("stage_encoding").clone() {
-            Some(TextScriptEncoding::from(enc.as_str()))
-        } else {
-            None
+    fn from_strings(code: &str, strings: HashMap<String, String>) -> Locale {
+        let name = strings.get("name").cloned().unwrap_or_else(|| code.to_owned());
+        let parent = match strings.get("parent") {
+            Some(parent) if parent.is_empty() => None,
+            Some(parent) => Some(parent.clone()),
+            None => Locale::default_parent(code),
         };
 
-        // This info will be set by the caller
-        let is_present = false;
-        let is_complete = false;
-        let data_type = None;
+        // properties of the translation data root are set by the caller
+        let mut locale = Locale { code: code.to_owned(), name, parent, strings, ..Locale::default() };
+        locale.update_metadata();
 
-        Locale { code: code.to_string(), name, font, encoding, stage_encoding, strings, is_present, is_complete, data_type }
+        locale
+    }
+
+    /// Regional variants fall back to their language, languages to English.
+    fn default_parent(code: &str) -> Option<String> {
+        match code.rsplit_once(|c| c == '-' || c == '_') {
+            Some((language, _)) => Some(language.to_owned()),
+            None if code != FALLBACK_LOCALE => Some(FALLBACK_LOCALE.to_owned()),
+            None => None,
+        }
     }
 
     fn flatten(json: &serde_json::Value) -> HashMap<String, String> {
         let mut strings = HashMap::new();
 
-        for (key, value) in json.as_object().unwrap() {
+        for (key, value) in json.as_object().into_iter().flatten() {
             match value {
                 serde_json::Value::String(string) => {
                     strings.insert(key.to_owned(), string.to_owned());
@@ -92,9 +114,99 @@ impl Locale {
         strings
     }
 
+    fn get(&self, key: &str) -> Option<&String> {
+        self.strings.get(key).or_else(|| self.inherited.get(key))
+    }
+
+    fn update_metadata(&mut self) {
+        let font_name = self.get("font").cloned().unwrap_or_default();
+        let font_scale = self.get("font_scale").and_then(|scale| scale.parse::<f32>().ok()).unwrap_or(1.0);
+        self.font = FontData::new(font_name, font_scale, 0.0);
+
+        self.encoding = self.get("encoding").map(|enc| TextScriptEncoding::from(enc.as_str()));
+        self.stage_encoding = self.get("stage_encoding").map(|enc| TextScriptEncoding::from(enc.as_str()));
+    }
+
+    /// Returns the codes of the loaded locales missing strings are looked up in, nearest first.
+    pub fn fallback_chain(&self, locales: &[Locale]) -> Vec<String> {
+        let mut chain = Vec::new();
+        let mut visited = vec![self.code.clone()];
+        let mut next = self.parent.clone();
+
+        while let Some(code) = next {
+            if visited.contains(&code) {
+                log::warn!("Fallback chain of locale {} loops at {}.", self.code, code);
+                break;
+            }
+
+            // a missing intermediate locale is skipped, so `pt-BR` still falls back to English without `pt`
+            let parent = locales.iter().find(|locale| locale.code == code);
+            next = match parent {
+                Some(parent) => parent.parent.clone(),
+                None => Locale::default_parent(&code),
+            };
+
+            if parent.is_some() {
+                chain.push(code.clone());
+            }
+            visited.push(code);
+        }
+
+        chain
+    }
+
+    /// Fills the strings missing from each locale with the ones of its fallback chain.
+    pub fn resolve_inheritance(locales: &mut [Locale]) {
+        let inherited: Vec<_> = locales
+            .iter()
+            .map(|locale| {
+                let mut inherited = HashMap::new();
+
+                for code in locale.fallback_chain(locales) {
+                    let Some(parent) = locales.iter().find(|parent| parent.code == code) else {
+                        continue;
+                    };
+
+                    for (key, value) in parent.strings.iter() {
+                        if !locale.strings.contains_key(key) && !NON_INHERITED_KEYS.contains(&key.as_str()) {
+                            inherited.entry(key.clone()).or_insert_with(|| value.clone());
+                        }
+                    }
+                }
+
+                inherited
+            })
+            .collect();
+
+        for (locale, inherited) in locales.iter_mut().zip(inherited) {
+            locale.inherited = inherited;
+            locale.update_metadata();
+        }
+    }
+
+    /// Returns the sorted keys of the reference locale this locale doesn't translate itself.
+    pub fn missing_keys<'a>(&self, reference: &'a Locale) -> Vec<&'a str> {
+        let mut missing: Vec<_> = reference
+            .strings
+            .keys()
+            .map(String::as_str)
+            .filter(|key| !METADATA_KEYS.contains(key) && !self.strings.contains_key(*key))
+            .collect();
+        missing.sort_unstable();
+
+        missing
+    }
+
+    /// Returns the number of strings of the reference locale translated by this locale and the total count.
+    pub fn coverage(&self, reference: &Locale) -> (usize, usize) {
+        let total = reference.strings.keys().filter(|key| !METADATA_KEYS.contains(&key.as_str())).count();
+
+        (total - self.missing_keys(reference).len(), total)
+    }
+
     /// if the key does not exists, return the origin key instead
     pub fn t<'a: 'b, 'b>(&'a self, key: &'b str) -> &'b str {
-        if let Some(str) = self.strings.get(key) {
+        if let Some(str) = self.get(key) {
             str
         } else {
             key
@@ -115,3 +227,37 @@ impl Locale {
         self.font = font;
     }
 }
+
+#[test]
+fn test_locale_inheritance() {
+    let locale = |code: &str, json: serde_json::Value| Locale::from_strings(code, Locale::flatten(&json));
+
+    let mut locales = vec![
+        locale(
+            "en",
+            serde_json::json!({"name": "English", "font": "csfont.fnt", "menus": {"start": "Start", "quit": "Quit"}}),
+        ),
+        locale("pt", serde_json::json!({"name": "Portuguese", "font_scale": "0.5", "menus": {"start": "Iniciar"}})),
+        locale("pt-BR", serde_json::json!({"name": "Brazilian Portuguese", "menus": {"quit": "Sair"}})),
+        locale("zh_TW", serde_json::json!({"menus": {}})),
+    ];
+    Locale::resolve_inheritance(&mut locales);
+
+    let (en, pt, pt_br, zh_tw) = (&locales[0], &locales[1], &locales[2], &locales[3]);
+    assert_eq!(en.fallback_chain(&locales), Vec::<String>::new());
+    assert_eq!(pt_br.fallback_chain(&locales), vec!["pt", "en"]);
+    assert_eq!(zh_tw.fallback_chain(&locales), vec!["en"]);
+
+    assert_eq!(pt_br.t("menus.start"), "Iniciar");
+    assert_eq!(pt_br.t("menus.quit"), "Sair");
+    assert_eq!(pt.t("menus.quit"), "Quit");
+    assert_eq!(pt_br.t("menus.missing"), "menus.missing");
+    assert_eq!(pt_br.name, "Brazilian Portuguese");
+    assert_eq!(zh_tw.name, "zh_TW");
+    assert_eq!(pt_br.font.path, "csfont.fnt");
+    assert_eq!(pt_br.font.scale, 0.5);
+
+    assert_eq!(pt.missing_keys(en), vec!["menus.quit"]);
+    assert_eq!(pt_br.coverage(en), (1, 2));
+    assert_eq!(zh_tw.coverage(en), (0, 2));
+}
@@ -3,13 +3,18 @@ use itertools::Itertools;
 
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
-use crate::game::scripting::tsc::text_script::TextScriptExecutionState;
+use crate::game::flag_watcher::{FlagKind, FlagNames};
+use crate::game::replay_library;
+use crate::game::save_state::SaveState;
+use crate::game::scripting::tsc::text_script::{TextScript, TextScriptExecutionState};
 use crate::game::shared_game_state::SharedGameState;
 use crate::scene::game_scene::GameScene;
 
 use self::command_line::CommandLineParser;
+use self::npc_inspector::NPCInspector;
 
 pub mod command_line;
+pub mod npc_inspector;
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[repr(u8)]
@@ -23,9 +28,14 @@ pub enum ScriptType {
 pub struct LiveDebugger {
     map_selector_visible: bool,
     events_visible: bool,
+    tsc_debugger_visible: bool,
     flags_visible: bool,
+    flag_inspector_visible: bool,
     npc_inspector_visible: bool,
+    save_states_visible: bool,
+    replays_visible: bool,
     hotkey_list_visible: bool,
+    npc_inspector: NPCInspector,
     command_line_parser: CommandLineParser,
     command_line_focused: bool,
     last_stage_id: usize,
@@ -35,6 +45,16 @@ pub struct LiveDebugger {
     event_ids: Vec<(ScriptType, u16)>,
     selected_event: i32,
     text_windows: Vec<(u32, ImString, ImString)>,
+    breakpoint_input: String,
+    disassembly: Option<(u16, Vec<(u32, String)>)>,
+    flag_names: Option<FlagNames>,
+    flag_filter: String,
+    only_set_flags: bool,
+    save_state_name: String,
+    /// Names of the replays in the library, listed again when the window is opened or the library changes.
+    replays: Option<Vec<ImString>>,
+    selected_replay: i32,
+    replay_name: String,
     error: Option<ImString>,
 }
 
@@ -43,9 +63,14 @@ impl LiveDebugger {
         Self {
             map_selector_visible: false,
             events_visible: false,
+            tsc_debugger_visible: false,
             flags_visible: false,
+            flag_inspector_visible: false,
             npc_inspector_visible: false,
+            save_states_visible: false,
+            replays_visible: false,
             hotkey_list_visible: false,
+            npc_inspector: NPCInspector::new(),
             command_line_parser: CommandLineParser::new(),
             command_line_focused: false,
             last_stage_id: usize::MAX,
@@ -55,6 +80,15 @@ impl LiveDebugger {
             event_ids: Vec::new(),
             selected_event: -1,
             text_windows: Vec::new(),
+            breakpoint_input: String::new(),
+            disassembly: None,
+            flag_names: None,
+            flag_filter: String::new(),
+            only_set_flags: false,
+            save_state_name: "quick".to_owned(),
+            replays: None,
+            selected_replay: -1,
+            replay_name: String::new(),
             error: None,
         }
     }
@@ -70,6 +104,7 @@ impl LiveDebugger {
             self.last_stage_id = game_scene.stage_id;
             self.events.clear();
             self.selected_event = -1;
+            self.disassembly = None;
         }
 
         if state.command_line {
@@ -193,11 +228,21 @@ impl LiveDebugger {
                     self.events_visible = !self.events_visible;
                 }
 
+                ui.same_line();
+                if ui.button("TSC Debugger") {
+                    self.tsc_debugger_visible = !self.tsc_debugger_visible;
+                }
+
                 ui.same_line();
                 if ui.button("Flags") {
                     self.flags_visible = !self.flags_visible;
                 }
 
+                ui.same_line();
+                if ui.button("Flag Inspector") {
+                    self.flag_inspector_visible = !self.flag_inspector_visible;
+                }
+
                 if game_scene.player2.cond.alive() {
                     if ui.button("Drop Player 2") {
                         game_scene.drop_player2();
@@ -229,6 +274,17 @@ impl LiveDebugger {
                     state.command_line = !state.command_line;
                 }
 
+                ui.same_line();
+                if ui.button("Save States") {
+                    self.save_states_visible = !self.save_states_visible;
+                }
+
+                ui.same_line();
+                if ui.button("Replays") {
+                    self.replays_visible = !self.replays_visible;
+                    self.replays = None;
+                }
+
                 ui.checkbox("noclip", &mut state.settings.noclip);
                 ui.same_line();
                 ui.checkbox("more rust", &mut state.more_rust);
@@ -384,6 +440,131 @@ impl LiveDebugger {
                 });
         }
 
+        if self.tsc_debugger_visible {
+            ui.window("TSC Debugger")
+                .position([390.0, 80.0], Condition::FirstUseEver)
+                .size([320.0, 420.0], Condition::FirstUseEver)
+                .build(|| {
+                    let vm = &mut state.textscript_vm;
+                    let position = script_position(vm.state);
+
+                    match position {
+                        Some((event, ip)) => ui.text(format!("Event: #{:04}, IP: {}", event, ip)),
+                        None => ui.text("No script running."),
+                    }
+
+                    ui.text(format!(
+                        "Status: {}",
+                        if vm.debugger.paused {
+                            "paused"
+                        } else if vm.debugger.is_running_to_end() {
+                            "running to <END"
+                        } else {
+                            "running"
+                        }
+                    ));
+                    ui.text(format!("Wait: {}", describe_wait(vm.state)));
+                    ui.text(format!("Fade: {:?}", state.fade_state));
+
+                    if vm.debugger.paused {
+                        if ui.button("Continue") {
+                            vm.debugger.resume();
+                        }
+                    } else if ui.button("Pause") {
+                        vm.debugger.paused = true;
+                    }
+
+                    ui.same_line();
+                    if ui.button("Step") {
+                        vm.debugger.step();
+                    }
+
+                    ui.same_line();
+                    if ui.button("Run to <END") {
+                        vm.debugger.run_to_end();
+                    }
+
+                    ui.separator();
+                    {
+                        let _iw = ui.push_item_width(120.0);
+                        ui.input_text("##Breakpoint", &mut self.breakpoint_input).build();
+                    }
+
+                    ui.same_line();
+                    if ui.button("Add breakpoint") {
+                        if vm.debugger.add_breakpoint(&self.breakpoint_input) {
+                            self.breakpoint_input.clear();
+                        } else {
+                            self.error = Some(ImString::new(format!(
+                                "'{}' is neither an event number nor an opcode.",
+                                self.breakpoint_input
+                            )));
+                        }
+                    }
+
+                    let mut remove_event = None;
+                    for event in vm.debugger.event_breakpoints.iter() {
+                        if ui.small_button(format!("x##E{}", event)) {
+                            remove_event = Some(*event);
+                        }
+                        ui.same_line();
+                        ui.text(format!("Event #{:04}", event));
+                    }
+
+                    if let Some(event) = remove_event {
+                        vm.debugger.event_breakpoints.remove(&event);
+                    }
+
+                    let mut remove_opcode = None;
+                    for opcode in vm.debugger.opcode_breakpoints.iter() {
+                        if ui.small_button(format!("x##O{}", opcode)) {
+                            remove_opcode = Some(opcode.clone());
+                        }
+                        ui.same_line();
+                        ui.text(format!("Opcode <{}", opcode));
+                    }
+
+                    if let Some(opcode) = remove_opcode {
+                        vm.debugger.opcode_breakpoints.remove(&opcode);
+                    }
+
+                    ui.separator();
+
+                    let Some((event, ip)) = position else {
+                        return;
+                    };
+
+                    if self.disassembly.as_ref().map(|(id, _)| *id) != Some(event) {
+                        let scripts = vm.scripts.borrow();
+                        let lines = match scripts.find_script(vm.mode, event) {
+                            Some(bytecode) => TextScript::disassemble(bytecode).unwrap_or_default(),
+                            None => Vec::new(),
+                        };
+
+                        self.disassembly = Some((event, lines));
+                    }
+
+                    if let Some((_, lines)) = &self.disassembly {
+                        // while text is being printed the IP points inside of the string, so highlight the
+                        // last instruction that starts before it.
+                        let current = lines.iter().rposition(|(offset, _)| *offset <= ip);
+
+                        ui.child_window("##Disassembly").build(|| {
+                            for (idx, (offset, line)) in lines.iter().enumerate() {
+                                if Some(idx) == current {
+                                    ui.text_colored([1.0, 1.0, 0.0, 1.0], format!("> {:4} {}", offset, line));
+                                    if vm.debugger.paused {
+                                        ui.set_scroll_here_y();
+                                    }
+                                } else {
+                                    ui.text(format!("  {:4} {}", offset, line));
+                                }
+                            }
+                        });
+                    }
+                });
+        }
+
         if self.flags_visible {
             ui.window("Flags")
                 .position([80.0, 80.0], Condition::FirstUseEver)
@@ -416,57 +597,171 @@ impl LiveDebugger {
                 });
         }
 
+        if self.flag_inspector_visible {
+            if self.flag_names.is_none() {
+                self.flag_names = Some(FlagNames::load(ctx, &state.constants.base_paths));
+            }
+
+            ui.window("Flag Inspector")
+                .position([120.0, 80.0], Condition::FirstUseEver)
+                .size([320.0, 460.0], Condition::FirstUseEver)
+                .build(|| {
+                    if state.flag_watcher.paused {
+                        ui.text_colored([1.0, 0.5, 0.0, 1.0], "Paused by a watchpoint.");
+                        ui.same_line();
+                        if ui.button("Resume") {
+                            state.flag_watcher.paused = false;
+                        }
+                    }
+
+                    if CollapsingHeader::new("Watchpoint hits").default_open(true).build(ui) {
+                        for change in state.flag_watcher.changes.iter().rev().take(10) {
+                            let kind = match change.kind {
+                                FlagKind::Game => "Flag",
+                                FlagKind::Map => "Map flag",
+                            };
+                            let source = match change.source {
+                                Some((event, opcode)) => format!("#{:04} <{}", event, opcode),
+                                None => "no script".to_owned(),
+                            };
+
+                            ui.text(format!("{} {:04} = {} ({})", kind, change.id, change.value as u8, source));
+                        }
+
+                        if ui.button("Clear hits") {
+                            state.flag_watcher.changes.clear();
+                        }
+                    }
+
+                    {
+                        let _iw = ui.push_item_width(140.0);
+                        ui.input_text("Filter", &mut self.flag_filter).build();
+                    }
+
+                    ui.same_line();
+                    ui.checkbox("Only set", &mut self.only_set_flags);
+
+                    if ui.button("Reload names") {
+                        self.flag_names = Some(FlagNames::load(ctx, &state.constants.base_paths));
+                    }
+
+                    ui.text_disabled("Left checkbox toggles the watchpoint.");
+
+                    let names = self.flag_names.as_ref().unwrap();
+                    for (kind, title) in [(FlagKind::Game, "Flags"), (FlagKind::Map, "Map flags")] {
+                        if CollapsingHeader::new(title).default_open(kind == FlagKind::Game).build(ui) {
+                            flag_list(ui, state, names, kind, &self.flag_filter, self.only_set_flags);
+                        }
+                    }
+                });
+        }
+
         if self.npc_inspector_visible {
-            ui.window("NPC Inspector")
-                .position([80.0, 80.0], Condition::FirstUseEver)
-                .size([280.0, 300.0], Condition::FirstUseEver)
-                .scrollable(true)
-                .always_vertical_scrollbar(true)
+            self.npc_inspector.draw(ui, game_scene, state);
+        }
+
+        if self.save_states_visible {
+            ui.window("Save States")
+                .position([400.0, 320.0], Condition::FirstUseEver)
+                .size([260.0, 140.0], Condition::FirstUseEver)
+                .resizable(false)
                 .build(|| {
-                    game_scene.npc_list.for_each_alive_mut(&mut game_scene.npc_token, |mut npc| {
-                        if CollapsingHeader::new(&ImString::from(format!("id={} type={}", npc.id, npc.npc_type)))
-                            .default_open(false)
-                            .build(ui)
-                        {
-                            let mut position = [npc.x as f32 / 512.0, npc.y as f32 / 512.0];
-                            ui.input_float2("Position:", &mut position).build();
-
-                            npc.x = (position[0] * 512.0) as i32;
-                            npc.y = (position[1] * 512.0) as i32;
-
-                            let content = &ImString::from(format!(
-                                "\
-                                    Velocity: ({:.1},{:.1})\n\
-                                    Vel2/State2: ({:.1},{:.1} / {} {})\n\
-                                    Animation: frame={}, counter={}\n\
-                                    Action: num={}, counter={}, counter2={}\n\
-                                    Health: {}, Experience drop: {}\n\
-                                    Event ID: {}, Flag ID: {}\n\
-                                    Parent: {}, Shock: {}, Size: {}",
-                                npc.vel_x as f32 / 512.0,
-                                npc.vel_y as f32 / 512.0,
-                                npc.vel_x2 as f32 / 512.0,
-                                npc.vel_y2 as f32 / 512.0,
-                                npc.vel_x2,
-                                npc.vel_y2,
-                                npc.anim_num,
-                                npc.anim_counter,
-                                npc.action_num,
-                                npc.action_counter,
-                                npc.action_counter2,
-                                npc.life,
-                                npc.exp,
-                                npc.event_num,
-                                npc.flag_num,
-                                npc.parent_id,
-                                npc.shock,
-                                npc.size
-                            ));
-                            ui.text_wrapped(content);
-
-                            cond_flags(ui, &mut npc.cond);
+                    match &state.save_state_slot {
+                        Some(save_state) => ui.text(format!(
+                            "Slot: {} ({})",
+                            state.stages.get(save_state.stage_id).map_or("?", |s| s.name.as_str()),
+                            save_state.stage_id
+                        )),
+                        None => ui.text("Slot: empty"),
+                    }
+
+                    if ui.button("Save State") {
+                        if let Err(e) = state.quick_save_state(game_scene) {
+                            self.error = Some(ImString::new(e.to_string()));
                         }
+                    }
+
+                    ui.same_line();
+                    if ui.button("Load State") {
+                        if let Err(e) = state.quick_load_state(game_scene, ctx) {
+                            self.error = Some(ImString::new(e.to_string()));
+                        }
+                    }
+
+                    ui.separator();
+                    ui.input_text("Name", &mut self.save_state_name).build();
+
+                    if ui.button("Save to file") {
+                        let result = SaveState::capture(state, game_scene)
+                            .and_then(|save_state| save_state.save_to_file(ctx, &self.save_state_name));
+
+                        if let Err(e) = result {
+                            self.error = Some(ImString::new(e.to_string()));
+                        }
+                    }
+
+                    ui.same_line();
+                    if ui.button("Load from file") {
+                        let result = SaveState::load_from_file(ctx, &self.save_state_name)
+                            .and_then(|save_state| save_state.restore(state, game_scene, ctx));
+
+                        if let Err(e) = result {
+                            self.error = Some(ImString::new(e.to_string()));
+                        }
+                    }
+                });
+        }
+
+        if self.replays_visible {
+            ui.window("Replay Library")
+                .position([80.0, 370.0], Condition::FirstUseEver)
+                .size([300.0, 260.0], Condition::FirstUseEver)
+                .resizable(false)
+                .build(|| {
+                    let replays = self.replays.get_or_insert_with(|| {
+                        replay_library::list(ctx).into_iter().map(|replay| ImString::new(replay.name)).collect()
                     });
+                    let names: Vec<&ImStr> = replays.iter().map(|e| e.as_ref()).collect();
+
+                    ui.push_item_width(-1.0);
+                    if ui.list_box("##SelectedReplay", &mut self.selected_replay, &names, 8) {
+                        if let Some(name) = replays.get(self.selected_replay as usize) {
+                            self.replay_name = name.to_string();
+                        }
+                    }
+
+                    ui.input_text("Name", &mut self.replay_name).build();
+
+                    let selected = replays.get(self.selected_replay as usize).map(|name| name.to_string());
+                    if let Some(selected) = selected {
+                        if ui.button("Rename") {
+                            match replay_library::rename(ctx, &selected, &self.replay_name) {
+                                Ok(()) => {
+                                    self.replays = None;
+                                    self.selected_replay = -1;
+                                }
+                                Err(e) => self.error = Some(ImString::new(e.to_string())),
+                            }
+                        }
+
+                        ui.same_line();
+                        if ui.button("Delete") {
+                            match replay_library::delete(ctx, &selected) {
+                                Ok(()) => {
+                                    self.replays = None;
+                                    self.selected_replay = -1;
+                                }
+                                Err(e) => self.error = Some(ImString::new(e.to_string())),
+                            }
+                        }
+
+                        ui.same_line();
+                    }
+
+                    if ui.button("Refresh") {
+                        self.replays = None;
+                        self.selected_replay = -1;
+                    }
                 });
         }
 
@@ -491,6 +786,8 @@ impl LiveDebugger {
                         "` > Toggle Command Line",
                         "Ctrl + F3 > Reload Sound Manager",
                         "Ctrl + S > Quick Save",
+                        "Ctrl + F1 > Quick Save State",
+                        "Ctrl + F2 > Quick Load State",
                     ];
                     for hotkeys in key.iter() {
                         match hotkeys {
@@ -564,6 +861,119 @@ impl LiveDebugger {
     }
 }
 
+fn flag_list(
+    ui: &imgui::Ui,
+    state: &mut SharedGameState,
+    names: &FlagNames,
+    kind: FlagKind,
+    filter: &str,
+    only_set: bool,
+) {
+    // drawing thousands of checkboxes each frame is slow, show the first matching flags only.
+    const MAX_SHOWN: usize = 300;
+
+    let filter = filter.trim().to_lowercase();
+    let count = match kind {
+        FlagKind::Game => state.game_flags.len(),
+        FlagKind::Map => state.map_flags.len(),
+    };
+
+    let mut shown = 0;
+    let mut hidden = 0;
+
+    ui.child_window(format!("##{:?}Flags", kind)).size([0.0, 200.0]).build(|| {
+        for id in 0..count {
+            let mut value = match kind {
+                FlagKind::Game => state.get_flag(id),
+                FlagKind::Map => state.get_map_flag(id),
+            };
+            let name = names.get(kind, id);
+
+            if only_set && !value {
+                continue;
+            }
+
+            if !filter.is_empty()
+                && !format!("{:04}", id).contains(&filter)
+                && !name.map_or(false, |name| name.to_lowercase().contains(&filter))
+            {
+                continue;
+            }
+
+            if shown >= MAX_SHOWN {
+                hidden += 1;
+                continue;
+            }
+            shown += 1;
+
+            let mut watched = state.flag_watcher.is_watched(kind, id);
+            if ui.checkbox(format!("##W{:?}{}", kind, id), &mut watched) {
+                if watched {
+                    state.flag_watcher.watchpoints_mut(kind).insert(id);
+                } else {
+                    state.flag_watcher.watchpoints_mut(kind).remove(&id);
+                }
+            }
+
+            ui.same_line();
+            let label = match name {
+                Some(name) => format!("{:04} {}##{:?}{}", id, name, kind, id),
+                None => format!("{:04}##{:?}{}", id, kind, id),
+            };
+
+            // toggled directly, so changes made from the debugger don't trigger the watchpoints.
+            if ui.checkbox(label, &mut value) {
+                match kind {
+                    FlagKind::Game => state.game_flags.set(id, value),
+                    FlagKind::Map => state.map_flags.set(id, value),
+                }
+            }
+        }
+
+        if hidden > 0 {
+            ui.text_disabled(format!("...and {} more, use the filter to narrow down the list.", hidden));
+        }
+    });
+}
+
+/// Returns the event and instruction pointer of the running script.
+fn script_position(state: TextScriptExecutionState) -> Option<(u16, u32)> {
+    match state {
+        TextScriptExecutionState::Running(event, ip)
+        | TextScriptExecutionState::Msg(event, ip, _, _)
+        | TextScriptExecutionState::MsgNewLine(event, ip, _, _, _)
+        | TextScriptExecutionState::WaitTicks(event, ip, _)
+        | TextScriptExecutionState::WaitInput(event, ip, _)
+        | TextScriptExecutionState::WaitStanding(event, ip)
+        | TextScriptExecutionState::WaitConfirmation(event, ip, _, _, _)
+        | TextScriptExecutionState::WaitFade(event, ip)
+        | TextScriptExecutionState::FallingIsland(event, ip, _, _, _, _)
+        | TextScriptExecutionState::SaveProfile(event, ip) => Some((event, ip)),
+        TextScriptExecutionState::Ended
+        | TextScriptExecutionState::MapSystem
+        | TextScriptExecutionState::LoadProfile
+        | TextScriptExecutionState::Reset => None,
+    }
+}
+
+fn describe_wait(state: TextScriptExecutionState) -> String {
+    match state {
+        TextScriptExecutionState::Msg(_, _, remaining, _) => format!("printing text ({} chars left)", remaining),
+        TextScriptExecutionState::MsgNewLine(..) => "scrolling text box".to_owned(),
+        TextScriptExecutionState::WaitTicks(_, _, 9999) => "<WAI9999 (forever)".to_owned(),
+        TextScriptExecutionState::WaitTicks(_, _, ticks) => format!("{} ticks", ticks),
+        TextScriptExecutionState::WaitInput(..) => "<NOD (player input)".to_owned(),
+        TextScriptExecutionState::WaitStanding(..) => "<WAS (player on ground)".to_owned(),
+        TextScriptExecutionState::WaitConfirmation(_, _, no_event, _, selection) => {
+            format!("<YNJ (selected {:?}, no jumps to #{:04})", selection, no_event)
+        }
+        TextScriptExecutionState::WaitFade(..) => "fade".to_owned(),
+        TextScriptExecutionState::FallingIsland(_, _, _, _, tick, _) => format!("falling island ({}/900)", tick),
+        TextScriptExecutionState::MapSystem => "map system".to_owned(),
+        _ => "none".to_owned(),
+    }
+}
+
 fn cond_flags(ui: &imgui::Ui, cond: &mut crate::common::Condition) {
     ui.checkbox_flags("Interacted", &mut cond.0, 1);
     ui.checkbox_flags("Hidden", &mut cond.0, 2);
@@ -0,0 +1,482 @@
+use std::collections::HashSet;
+
+use imgui::{CollapsingHeader, Condition, MouseButton, TableFlags, TableSortDirection};
+
+use crate::common::Rect;
+use crate::game::npc::boss::BossNPC;
+use crate::game::npc::list::{NPCAccessToken, NPCList};
+use crate::game::npc::NPC;
+use crate::game::shared_game_state::SharedGameState;
+use crate::scene::game_scene::GameScene;
+
+/// Extra room around hitboxes when picking NPCs with the mouse, NPCs without a hitbox would be unclickable otherwise.
+const PICK_MARGIN: i32 = 2 * 0x200;
+
+const COLUMNS: [&str; 6] = ["ID", "Type", "Event", "Action", "Anim", "Life"];
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum InspectorTarget {
+    /// An NPC from the NPC list, by its slot id.
+    NPC(u16),
+    /// A part of the current boss, by its index in `BossNPC::parts`.
+    BossPart(usize),
+}
+
+/// Debugger state the game scene has to know about: the highlighted NPC and the NPCs excluded from ticking.
+pub struct NPCDebugState {
+    pub selected: Option<InspectorTarget>,
+    frozen: HashSet<InspectorTarget>,
+}
+
+impl NPCDebugState {
+    pub fn new() -> NPCDebugState {
+        NPCDebugState { selected: None, frozen: HashSet::new() }
+    }
+
+    pub fn is_frozen(&self, target: InspectorTarget) -> bool {
+        !self.frozen.is_empty() && self.frozen.contains(&target)
+    }
+
+    pub fn set_frozen(&mut self, target: InspectorTarget, frozen: bool) {
+        if frozen {
+            self.frozen.insert(target);
+        } else {
+            self.frozen.remove(&target);
+        }
+    }
+
+    /// Drops the selection and freeze of a removed NPC, so whatever takes over its slot starts fresh.
+    pub fn forget(&mut self, target: InspectorTarget) {
+        self.frozen.remove(&target);
+        if self.selected == Some(target) {
+            self.selected = None;
+        }
+    }
+
+    /// Forgets NPCs that died since the last tick.
+    pub fn prune(&mut self, npc_list: &NPCList, token: &NPCAccessToken, boss: &BossNPC) {
+        if self.frozen.is_empty() && self.selected.is_none() {
+            return;
+        }
+
+        let alive = |target: &InspectorTarget| match *target {
+            InspectorTarget::NPC(id) => {
+                npc_list.get_npc(id as usize).map_or(false, |npc| npc.borrow(token).cond.alive())
+            }
+            InspectorTarget::BossPart(idx) => boss.parts[idx].cond.alive(),
+        };
+
+        self.frozen.retain(alive);
+        if !self.selected.as_ref().map_or(true, alive) {
+            self.selected = None;
+        }
+    }
+
+    /// Copies the frozen boss parts, the boss AI moves all parts at once so they are restored after it ran instead.
+    pub fn save_frozen_parts(&self, boss: &BossNPC) -> Vec<(usize, NPC)> {
+        self.frozen
+            .iter()
+            .filter_map(|target| match *target {
+                InspectorTarget::BossPart(idx) => Some((idx, boss.parts[idx].clone())),
+                InspectorTarget::NPC(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the hitbox of the selected NPC.
+    pub fn selected_rect(&self, npc_list: &NPCList, token: &NPCAccessToken, boss: &BossNPC) -> Option<Rect<i32>> {
+        match self.selected? {
+            InspectorTarget::NPC(id) => npc_list.get_npc(id as usize).map(|npc| hit_rect(&npc.borrow(token))),
+            InspectorTarget::BossPart(idx) => Some(hit_rect(&boss.parts[idx])),
+        }
+    }
+}
+
+pub fn hit_rect(npc: &NPC) -> Rect<i32> {
+    Rect::new(
+        npc.x - npc.hit_bounds.left as i32,
+        npc.y - npc.hit_bounds.top as i32,
+        npc.x + npc.hit_bounds.right as i32,
+        npc.y + npc.hit_bounds.bottom as i32,
+    )
+}
+
+/// Finds the NPC or boss part under the given point, preferring the one closest to its center.
+pub fn pick(npc_list: &NPCList, token: &NPCAccessToken, boss: &BossNPC, x: i32, y: i32) -> Option<InspectorTarget> {
+    let distance = |npc: &NPC| {
+        let rect = hit_rect(npc);
+        let rect = Rect::new(
+            rect.left - PICK_MARGIN,
+            rect.top - PICK_MARGIN,
+            rect.right + PICK_MARGIN,
+            rect.bottom + PICK_MARGIN,
+        );
+
+        if rect.has_point_excl(x, y) {
+            Some((npc.x - x).abs() as i64 + (npc.y - y).abs() as i64)
+        } else {
+            None
+        }
+    };
+
+    let npcs = npc_list.iter_alive(token).filter_map(|npc| Some((distance(&npc)?, InspectorTarget::NPC(npc.id))));
+    let parts = boss
+        .parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| part.cond.alive())
+        .filter_map(|(idx, part)| Some((distance(part)?, InspectorTarget::BossPart(idx))));
+
+    npcs.chain(parts).min_by_key(|(distance, _)| *distance).map(|(_, target)| target)
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+enum FilterField {
+    Any,
+    Type,
+    Event,
+    Action,
+}
+
+/// Space separated terms like `type:60` or `event:200`, a bare number matches any of the fields.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct NPCFilter {
+    terms: Vec<(FilterField, u16)>,
+}
+
+impl NPCFilter {
+    pub fn parse(filter: &str) -> Option<NPCFilter> {
+        let mut terms = Vec::new();
+
+        for term in filter.split_whitespace() {
+            let (field, value) = match term.split_once(':') {
+                Some((field, value)) => {
+                    let field = match field.to_lowercase().as_str() {
+                        "type" | "t" => FilterField::Type,
+                        "event" | "e" => FilterField::Event,
+                        "action" | "a" => FilterField::Action,
+                        _ => return None,
+                    };
+                    (field, value)
+                }
+                None => (FilterField::Any, term),
+            };
+
+            terms.push((field, value.parse().ok()?));
+        }
+
+        Some(NPCFilter { terms })
+    }
+
+    pub fn matches(&self, npc: &NPC) -> bool {
+        self.terms.iter().all(|&(field, value)| match field {
+            FilterField::Any => npc.npc_type == value || npc.event_num == value || npc.action_num == value,
+            FilterField::Type => npc.npc_type == value,
+            FilterField::Event => npc.event_num == value,
+            FilterField::Action => npc.action_num == value,
+        })
+    }
+}
+
+struct NPCRow {
+    target: InspectorTarget,
+    values: [u16; COLUMNS.len()],
+}
+
+impl NPCRow {
+    fn new(target: InspectorTarget, npc: &NPC) -> NPCRow {
+        let id = match target {
+            InspectorTarget::NPC(id) => id,
+            InspectorTarget::BossPart(idx) => idx as u16,
+        };
+
+        NPCRow { target, values: [id, npc.npc_type, npc.event_num, npc.action_num, npc.anim_num, npc.life] }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct TableSort {
+    column: usize,
+    descending: bool,
+}
+
+pub struct NPCInspector {
+    filter: String,
+    npc_sort: TableSort,
+    boss_sort: TableSort,
+}
+
+impl NPCInspector {
+    pub fn new() -> NPCInspector {
+        NPCInspector {
+            filter: String::new(),
+            npc_sort: TableSort { column: 0, descending: false },
+            boss_sort: TableSort { column: 0, descending: false },
+        }
+    }
+
+    pub fn draw(&mut self, ui: &imgui::Ui, game_scene: &mut GameScene, state: &SharedGameState) {
+        if !ui.io().want_capture_mouse && ui.is_mouse_clicked(MouseButton::Left) {
+            let [mouse_x, mouse_y] = ui.io().mouse_pos;
+            let x = game_scene.frame.x + (mouse_x / state.scale * 512.0) as i32;
+            let y = game_scene.frame.y + (mouse_y / state.scale * 512.0) as i32;
+
+            if let Some(target) = pick(&game_scene.npc_list, &game_scene.npc_token, &game_scene.boss, x, y) {
+                game_scene.npc_debug.selected = Some(target);
+            }
+        }
+
+        ui.window("NPC Inspector")
+            .position([80.0, 80.0], Condition::FirstUseEver)
+            .size([340.0, 480.0], Condition::FirstUseEver)
+            .build(|| {
+                {
+                    let _iw = ui.push_item_width(160.0);
+                    ui.input_text("Filter", &mut self.filter).build();
+                }
+
+                let filter = NPCFilter::parse(&self.filter);
+                if filter.is_none() {
+                    ui.same_line();
+                    ui.text_colored([1.0, 0.5, 0.0, 1.0], "Invalid filter");
+                }
+                let filter = filter.unwrap_or_default();
+
+                ui.text_disabled("e.g. \"type:60\" or \"event:200 action:3\".");
+                ui.text_disabled("Click an NPC in the game view to select it.");
+
+                let selected = game_scene.npc_debug.selected;
+
+                let mut rows: Vec<NPCRow> = game_scene
+                    .npc_list
+                    .iter_alive(&game_scene.npc_token)
+                    .filter(|npc| filter.matches(npc))
+                    .map(|npc| NPCRow::new(InspectorTarget::NPC(npc.id), &npc))
+                    .collect();
+
+                if let Some(target) = npc_table(ui, "##NPCs", &mut rows, &mut self.npc_sort, selected) {
+                    game_scene.npc_debug.selected = Some(target);
+                }
+
+                let header = format!("Boss parts (boss type {})###BossParts", game_scene.boss.boss_type);
+                if CollapsingHeader::new(header).default_open(false).build(ui) {
+                    let mut rows: Vec<NPCRow> = game_scene
+                        .boss
+                        .parts
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, part)| part.cond.alive() && filter.matches(part))
+                        .map(|(idx, part)| NPCRow::new(InspectorTarget::BossPart(idx), part))
+                        .collect();
+
+                    if let Some(target) = npc_table(ui, "##BossParts", &mut rows, &mut self.boss_sort, selected) {
+                        game_scene.npc_debug.selected = Some(target);
+                    }
+                }
+
+                ui.separator();
+                selection_editor(ui, game_scene);
+            });
+    }
+}
+
+fn npc_table(
+    ui: &imgui::Ui,
+    id: &str,
+    rows: &mut [NPCRow],
+    sort: &mut TableSort,
+    selected: Option<InspectorTarget>,
+) -> Option<InspectorTarget> {
+    let flags = TableFlags::SORTABLE
+        | TableFlags::ROW_BG
+        | TableFlags::BORDERS_OUTER
+        | TableFlags::SCROLL_Y
+        | TableFlags::SIZING_STRETCH_SAME;
+    let _table = ui.begin_table_with_sizing(id, COLUMNS.len(), flags, [0.0, 180.0], 0.0)?;
+
+    ui.table_setup_scroll_freeze(0, 1);
+    for name in COLUMNS {
+        ui.table_setup_column(name);
+    }
+    ui.table_headers_row();
+
+    if let Some(specs) = ui.table_sort_specs_mut() {
+        specs.conditional_sort(|specs| {
+            if let Some(spec) = specs.iter().next() {
+                sort.column = spec.column_idx();
+                sort.descending = spec.sort_direction() == Some(TableSortDirection::Descending);
+            }
+        });
+    }
+
+    rows.sort_by_key(|row| row.values[sort.column]);
+    if sort.descending {
+        rows.reverse();
+    }
+
+    let mut clicked = None;
+    for row in rows.iter() {
+        ui.table_next_row();
+        ui.table_next_column();
+
+        let label = format!("{}##{:?}", row.values[0], row.target);
+        if ui.selectable_config(label).selected(selected == Some(row.target)).span_all_columns(true).build() {
+            clicked = Some(row.target);
+        }
+
+        for value in &row.values[1..] {
+            ui.table_next_column();
+            ui.text(value.to_string());
+        }
+    }
+
+    clicked
+}
+
+fn selection_editor(ui: &imgui::Ui, game_scene: &mut GameScene) {
+    let Some(target) = game_scene.npc_debug.selected else {
+        ui.text_disabled("No NPC selected.");
+        return;
+    };
+
+    match target {
+        InspectorTarget::NPC(id) => {
+            let Some(cell) = game_scene.npc_list.get_npc(id as usize) else {
+                game_scene.npc_debug.forget(target);
+                return;
+            };
+
+            let mut npc = cell.borrow_mut(&mut game_scene.npc_token);
+            ui.text(format!("NPC #{}", id));
+            npc_editor(ui, target, &mut npc, &mut game_scene.npc_debug);
+        }
+        InspectorTarget::BossPart(idx) => {
+            ui.text(format!("Boss part #{} (boss type {})", idx, game_scene.boss.boss_type));
+            npc_editor(ui, target, &mut game_scene.boss.parts[idx], &mut game_scene.npc_debug);
+        }
+    }
+}
+
+fn npc_editor(ui: &imgui::Ui, target: InspectorTarget, npc: &mut NPC, debug: &mut NPCDebugState) {
+    let mut frozen = debug.is_frozen(target);
+    if ui.checkbox("Freeze", &mut frozen) {
+        debug.set_frozen(target, frozen);
+    }
+
+    ui.same_line();
+    if ui.button("Delete") {
+        npc.cond.set_alive(false);
+        debug.forget(target);
+        return;
+    }
+
+    ui.same_line();
+    if ui.button("Deselect") {
+        debug.selected = None;
+    }
+
+    let mut position = [npc.x as f32 / 512.0, npc.y as f32 / 512.0];
+    if ui.input_float2("Position", &mut position).build() {
+        npc.x = (position[0] * 512.0) as i32;
+        npc.y = (position[1] * 512.0) as i32;
+    }
+
+    input_u16(ui, "Action", &mut npc.action_num);
+    input_u16(ui, "Action counter", &mut npc.action_counter);
+    input_u16(ui, "Animation", &mut npc.anim_num);
+    input_u16(ui, "Life", &mut npc.life);
+
+    ui.text_wrapped(format!(
+        "\
+            Type: {}, Direction: {:?}\n\
+            Velocity: ({:.1},{:.1})\n\
+            Vel2: ({:.1},{:.1})\n\
+            Counters: anim={}, action2={}, action3={}\n\
+            Experience drop: {}, Damage: {}\n\
+            Event ID: {}, Flag ID: {}\n\
+            Parent: {}, Shock: {}, Size: {}",
+        npc.npc_type,
+        npc.direction,
+        npc.vel_x as f32 / 512.0,
+        npc.vel_y as f32 / 512.0,
+        npc.vel_x2 as f32 / 512.0,
+        npc.vel_y2 as f32 / 512.0,
+        npc.anim_counter,
+        npc.action_counter2,
+        npc.action_counter3,
+        npc.exp,
+        npc.damage,
+        npc.event_num,
+        npc.flag_num,
+        npc.parent_id,
+        npc.shock,
+        npc.size
+    ));
+
+    if CollapsingHeader::new("Condition flags").default_open(false).build(ui) {
+        super::cond_flags(ui, &mut npc.cond);
+    }
+}
+
+fn input_u16(ui: &imgui::Ui, label: &str, value: &mut u16) {
+    let mut input = *value as i32;
+    if ui.input_int(label, &mut input).build() {
+        *value = input.clamp(0, u16::MAX as i32) as u16;
+    }
+}
+
+#[test]
+fn test_npc_filter() {
+    let mut npc = NPC::empty();
+    npc.npc_type = 60;
+    npc.event_num = 200;
+    npc.action_num = 3;
+
+    assert!(NPCFilter::parse("").unwrap().matches(&npc));
+    assert!(NPCFilter::parse("60").unwrap().matches(&npc));
+    assert!(NPCFilter::parse("3").unwrap().matches(&npc));
+    assert!(NPCFilter::parse("type:60 event:200").unwrap().matches(&npc));
+    assert!(NPCFilter::parse("A:3").unwrap().matches(&npc));
+    assert!(!NPCFilter::parse("type:3").unwrap().matches(&npc));
+    assert!(!NPCFilter::parse("type:60 action:4").unwrap().matches(&npc));
+
+    assert_eq!(NPCFilter::parse("flag:1"), None);
+    assert_eq!(NPCFilter::parse("type:"), None);
+    assert_eq!(NPCFilter::parse("sixty"), None);
+}
+
+#[test]
+fn test_npc_pick() -> crate::framework::error::GameResult {
+    let (npc_list, token) = NPCList::new();
+    let mut boss = BossNPC::new();
+    boss.parts[0].cond.set_alive(false);
+
+    let mut npc = NPC::empty();
+    npc.cond.set_alive(true);
+    npc.x = 100 * 0x200;
+    npc.y = 100 * 0x200;
+    npc.hit_bounds.left = 8 * 0x200;
+    npc.hit_bounds.right = 8 * 0x200;
+    npc.hit_bounds.top = 8 * 0x200;
+    npc.hit_bounds.bottom = 8 * 0x200;
+    npc_list.spawn(0, npc.clone())?;
+
+    npc.x = 110 * 0x200;
+    boss.parts[3] = npc.clone();
+
+    assert_eq!(pick(&npc_list, &token, &boss, 98 * 0x200, 100 * 0x200), Some(InspectorTarget::NPC(0)));
+    assert_eq!(pick(&npc_list, &token, &boss, 108 * 0x200, 104 * 0x200), Some(InspectorTarget::BossPart(3)));
+    assert_eq!(pick(&npc_list, &token, &boss, 100 * 0x200, 120 * 0x200), None);
+
+    let mut debug = NPCDebugState::new();
+    debug.set_frozen(InspectorTarget::BossPart(3), true);
+    debug.set_frozen(InspectorTarget::NPC(0), true);
+    assert_eq!(debug.save_frozen_parts(&boss).len(), 1);
+
+    boss.parts[3].cond.set_alive(false);
+    debug.prune(&npc_list, &token, &boss);
+    assert!(debug.is_frozen(InspectorTarget::NPC(0)));
+    assert!(!debug.is_frozen(InspectorTarget::BossPart(3)));
+
+    Ok(())
+}
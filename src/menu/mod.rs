@@ -1,5 +1,7 @@
 use std::cell::Cell;
 
+use chrono::{Local, TimeZone};
+
 use crate::common::{Color, Rect};
 use crate::components::draw_common::{draw_number, Alignment};
 use crate::framework::context::Context;
@@ -59,7 +61,13 @@ impl MenuEntry {
             MenuEntry::DescriptiveOptions(_, _, _, _) => 32.0,
             MenuEntry::OptionsBar(_, _) => 16.0,
             MenuEntry::SaveData(_) => 32.0,
-            MenuEntry::SaveDataSingle(_) => 32.0,
+            MenuEntry::SaveDataSingle(save) => {
+                if save.has_details() {
+                    84.0
+                } else {
+                    32.0
+                }
+            }
             MenuEntry::NewSave => 32.0,
             MenuEntry::PlayerSkin => 24.0,
             MenuEntry::Control(_, _) => 16.0,
@@ -715,6 +723,50 @@ impl<T: std::cmp::PartialEq + std::default::Default + Clone> Menu<T> {
 
                         draw_number(right_edge - 36.0, y, save.life as usize, Alignment::Right, state, ctx)?;
                     }
+
+                    if matches!(entry, MenuEntry::SaveDataSingle(_)) && save.has_details() {
+                        let mut text_x = self.x as f32 + 20.0;
+                        let mut text_y = y + 30.0;
+
+                        if let Some(batch) =
+                            save.thumbnail.as_ref().and_then(|name| state.texture_set.tex_map.get_mut(name))
+                        {
+                            let (width, height) = batch.dimensions();
+                            batch.add_rect(text_x, y + 28.0, &Rect::new_size(0, 0, width as u16, height as u16));
+                            batch.draw(ctx)?;
+
+                            text_x += width as f32 + 8.0;
+                        }
+
+                        let mut details = Vec::new();
+
+                        if let Some(play_time) = save.play_time {
+                            let seconds = play_time / 1000;
+                            let time = format!("{}:{:02}:{:02}", seconds / 3600, (seconds / 60) % 60, seconds % 60);
+                            details.push(state.loc.tt("menus.save_menu.play_time", &[("time", &time)]));
+                        }
+
+                        if let Some(date) = Local.timestamp_opt(save.timestamp as i64, 0).single() {
+                            if save.timestamp != 0 {
+                                let date = date.format("%Y-%m-%d %H:%M").to_string();
+                                details.push(state.loc.tt("menus.save_menu.last_saved", &[("date", &date)]));
+                            }
+                        }
+
+                        if let Some(mod_name) = &save.mod_name {
+                            details.push(state.loc.tt("menus.save_menu.mod_name", &[("name", mod_name)]));
+                        }
+
+                        for line in details {
+                            state.font.builder().with_symbols(symbols).position(text_x, text_y).draw(
+                                &line,
+                                ctx,
+                                &state.constants,
+                                &mut state.texture_set,
+                            )?;
+                            text_y += 12.0;
+                        }
+                    }
                 }
                 MenuEntry::Control(name, data) => {
                     state.font.builder().with_symbols(symbols).position(self.x as f32 + 20.0, y).draw(
@@ -1,14 +1,14 @@
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
-use crate::framework::filesystem;
-use crate::game::profile::GameProfile;
+use crate::framework::{filesystem, graphics};
+use crate::game::profile::{GameProfile, SaveThumbnail};
 use crate::game::shared_game_state::{GameDifficulty, SharedGameState};
 use crate::input::combined_menu_controller::CombinedMenuController;
 use crate::menu::coop_menu::PlayerCountMenu;
 use crate::menu::MenuEntry;
 use crate::menu::{Menu, MenuSelectionResult};
 
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct MenuSaveInfo {
     pub current_map: u32,
     pub max_life: u16,
@@ -16,11 +16,34 @@ pub struct MenuSaveInfo {
     pub weapon_count: usize,
     pub weapon_id: [u32; 8],
     pub difficulty: u8,
+    pub timestamp: u64,
+    /// Play time in milliseconds, unknown for saves made by other engines.
+    pub play_time: Option<u64>,
+    pub mod_name: Option<String>,
+    /// Name of the thumbnail texture registered in the texture set.
+    pub thumbnail: Option<String>,
+}
+
+impl MenuSaveInfo {
+    pub fn has_details(&self) -> bool {
+        self.timestamp != 0 || self.play_time.is_some() || self.mod_name.is_some() || self.thumbnail.is_some()
+    }
 }
 
 impl Default for MenuSaveInfo {
     fn default() -> Self {
-        MenuSaveInfo { current_map: 0, max_life: 0, life: 0, weapon_count: 0, weapon_id: [0; 8], difficulty: 0 }
+        MenuSaveInfo {
+            current_map: 0,
+            max_life: 0,
+            life: 0,
+            weapon_count: 0,
+            weapon_id: [0; 8],
+            difficulty: 0,
+            timestamp: 0,
+            play_time: None,
+            mod_name: None,
+            thumbnail: None,
+        }
     }
 }
 
@@ -89,6 +112,8 @@ impl Default for LoadConfirmMenuEntry {
 
 pub struct SaveSelectMenu {
     pub saves: [MenuSaveInfo; 3],
+    /// Thumbnails waiting to be uploaded to the GPU.
+    pending_thumbnails: [Option<SaveThumbnail>; 3],
     current_menu: CurrentMenu,
     save_menu: Menu<SaveMenuEntry>,
     save_detailed: Menu<usize>,
@@ -102,7 +127,8 @@ pub struct SaveSelectMenu {
 impl SaveSelectMenu {
     pub fn new() -> SaveSelectMenu {
         SaveSelectMenu {
-            saves: [MenuSaveInfo::default(); 3],
+            saves: Default::default(),
+            pending_thumbnails: Default::default(),
             current_menu: CurrentMenu::SaveMenu,
             save_menu: Menu::new(0, 0, 230, 0),
             coop_menu: PlayerCountMenu::new(),
@@ -127,6 +153,11 @@ impl SaveSelectMenu {
         let mut should_mutate_selection = true;
 
         for (iter, save) in self.saves.iter_mut().enumerate() {
+            let thumbnail_name = format!("SaveThumbnail{}", iter);
+            state.texture_set.tex_map.remove(&thumbnail_name);
+            self.pending_thumbnails[iter] = None;
+            *save = MenuSaveInfo::default();
+
             if let Ok(data) = filesystem::user_open(ctx, state.get_save_filename(iter + 1).unwrap_or(String::new())) {
                 let loaded_save = GameProfile::load_from_save(data)?;
 
@@ -136,8 +167,19 @@ impl SaveSelectMenu {
                 save.weapon_count = loaded_save.weapon_data.iter().filter(|weapon| weapon.weapon_id != 0).count();
                 save.weapon_id = loaded_save.weapon_data.map(|weapon| weapon.weapon_id);
                 save.difficulty = loaded_save.difficulty;
+                save.timestamp = loaded_save.timestamp;
+
+                if let Some(metadata) = loaded_save.metadata {
+                    save.play_time = Some(metadata.play_time);
+                    save.mod_name = Some(metadata.mod_name).filter(|name| !name.is_empty());
+
+                    if metadata.thumbnail.is_some() {
+                        save.thumbnail = Some(thumbnail_name);
+                        self.pending_thumbnails[iter] = metadata.thumbnail;
+                    }
+                }
 
-                self.save_menu.push_entry(SaveMenuEntry::Load(iter), MenuEntry::SaveData(*save));
+                self.save_menu.push_entry(SaveMenuEntry::Load(iter), MenuEntry::SaveData(save.clone()));
 
                 if should_mutate_selection {
                     should_mutate_selection = false;
@@ -202,8 +244,8 @@ impl SaveSelectMenu {
 
         self.save_detailed.draw_cursor = false;
 
-        if let (_, MenuEntry::SaveData(save)) = self.save_menu.entries[0] {
-            self.save_detailed.push_entry(0, MenuEntry::SaveDataSingle(save));
+        if let (_, MenuEntry::SaveData(save)) = &self.save_menu.entries[0] {
+            self.save_detailed.push_entry(0, MenuEntry::SaveDataSingle(save.clone()));
         }
 
         self.update_sizes(state);
@@ -240,7 +282,22 @@ impl SaveSelectMenu {
         self.save_detailed.update_width(state);
         self.save_detailed.update_height(state);
         self.save_detailed.x = ((state.canvas_size.0 - self.save_detailed.width as f32) / 2.0).floor() as isize;
-        self.save_detailed.y = -40 + ((state.canvas_size.1 - self.save_detailed.height as f32) / 2.0).floor() as isize;
+        let detailed_y = -40 + ((state.canvas_size.1 - self.save_detailed.height as f32) / 2.0).floor() as isize;
+        // keep the taller detailed view from overlapping the confirmation menus.
+        self.save_detailed.y = detailed_y.min(self.load_confirm.y - self.save_detailed.height as isize - 16);
+    }
+
+    fn upload_thumbnails(&mut self, state: &mut SharedGameState, ctx: &mut Context) {
+        for (slot, thumbnail) in self.pending_thumbnails.iter_mut().enumerate() {
+            if let Some(thumbnail) = thumbnail.take() {
+                let name = format!("SaveThumbnail{}", slot);
+
+                match graphics::create_texture(ctx, thumbnail.width, thumbnail.height, &thumbnail.to_rgba()) {
+                    Ok(texture) => state.texture_set.insert_texture(&name, texture),
+                    Err(e) => log::warn!("Failed to create save thumbnail texture: {}", e),
+                }
+            }
+        }
     }
 
     pub fn tick(
@@ -251,6 +308,8 @@ impl SaveSelectMenu {
         ctx: &mut Context,
     ) -> GameResult {
         self.update_sizes(state);
+        self.upload_thumbnails(state, ctx);
+
         match self.current_menu {
             CurrentMenu::SaveMenu => match self.save_menu.tick(controller, state) {
                 MenuSelectionResult::Selected(SaveMenuEntry::Back, _) | MenuSelectionResult::Canceled => exit_action(),
@@ -270,9 +329,9 @@ impl SaveSelectMenu {
                     if let Ok(_) =
                         filesystem::user_open(ctx, state.get_save_filename(state.save_slot).unwrap_or(String::new()))
                     {
-                        if let (_, MenuEntry::SaveData(save)) = self.save_menu.entries[slot] {
+                        if let (_, MenuEntry::SaveData(save)) = &self.save_menu.entries[slot] {
                             self.save_detailed.entries.clear();
-                            self.save_detailed.push_entry(0, MenuEntry::SaveDataSingle(save));
+                            self.save_detailed.push_entry(0, MenuEntry::SaveDataSingle(save.clone()));
                         }
 
                         self.current_menu = CurrentMenu::LoadConfirm;
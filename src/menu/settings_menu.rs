@@ -75,6 +75,7 @@ impl Default for GraphicsMenuEntry {
 enum SoundMenuEntry {
     MusicVolume,
     EffectsVolume,
+    PositionalEffects,
     BGMInterpolation,
     Soundtrack,
     Back,
@@ -542,6 +543,13 @@ impl SettingsMenu {
                 state.settings.sfx_volume,
             ),
         );
+        self.sound.push_entry(
+            SoundMenuEntry::PositionalEffects,
+            MenuEntry::Toggle(
+                state.loc.t("menus.options_menu.sound_menu.positional_effects").to_owned(),
+                state.settings.positional_sfx,
+            ),
+        );
 
         self.sound.push_entry(
             SoundMenuEntry::BGMInterpolation,
@@ -578,7 +586,7 @@ impl SettingsMenu {
             .soundtracks
             .iter()
             .filter(|s| s.available)
-            .map(|s| state.loc.t(format!("soundtrack.{}", s.id).as_str()).to_owned())
+            .map(|s| s.localized_name(&state.loc))
             .collect_vec();
         soundtrack_entries.push(state.loc.t("soundtrack.organya").to_owned());
 
@@ -915,6 +923,15 @@ impl SettingsMenu {
                         let _ = state.settings.save(ctx);
                     }
                 }
+                MenuSelectionResult::Selected(SoundMenuEntry::PositionalEffects, toggle) => {
+                    if let MenuEntry::Toggle(_, value) = toggle {
+                        state.settings.positional_sfx = !state.settings.positional_sfx;
+                        state.sound_manager.set_positional_sfx(state.settings.positional_sfx);
+                        let _ = state.settings.save(ctx);
+
+                        *value = state.settings.positional_sfx;
+                    }
+                }
                 MenuSelectionResult::Selected(SoundMenuEntry::BGMInterpolation, toggle)
                 | MenuSelectionResult::Right(SoundMenuEntry::BGMInterpolation, toggle, _) => {
                     if let MenuEntry::DescriptiveOptions(_, value, _, _) = toggle {
@@ -1012,7 +1029,7 @@ impl SettingsMenu {
                             .constants
                             .soundtracks
                             .iter()
-                            .find(|s| state.loc.t(format!("soundtrack.{}", s.id).as_str()) == name)
+                            .find(|s| s.available && s.localized_name(&state.loc) == *name)
                             .map_or_else(|| name.to_owned(), |s| s.id.clone());
 
                         if state.settings.soundtrack == "Organya" || state.settings.soundtrack == "オルガーニャ" {
@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader};
 use std::iter::Peekable;
+use std::path::{Path, PathBuf};
 use std::str::Chars;
 
 use crate::engine_constants::EngineConstants;
 use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::framework::filesystem;
+use crate::framework::vfs::{is_zip_path, ZipFS};
 use crate::mod_requirements::ModRequirements;
 
 #[derive(Debug)]
@@ -162,6 +164,12 @@ impl ModList {
                 let mut description: Option<String> = None;
                 let mut save_slot = -1;
 
+                if is_zip_path(Path::new(&path)) {
+                    if let Err(err) = Self::mount_archive(ctx, &path) {
+                        log::warn!("Failed to mount mod archive {}: {}", path, err);
+                    }
+                }
+
                 if let Ok(file) = filesystem::open(ctx, [&path, "/mod.txt"].join("")) {
                     valid = true;
                     let reader = BufReader::new(file);
@@ -189,6 +197,18 @@ impl ModList {
         Ok(ModList { mods })
     }
 
+    /// Mounts a mod shipped as a zip archive, so its contents can be accessed as if `path` was a directory.
+    fn mount_archive(ctx: &mut Context, path: &str) -> GameResult {
+        let source = PathBuf::from(path);
+        filesystem::unmount_vfs(ctx, &source);
+
+        let file = filesystem::open(ctx, path)?;
+        let archive = ZipFS::from_reader(Box::new(file), &source, &source)?;
+        filesystem::mount_vfs(ctx, Box::new(archive));
+
+        Ok(())
+    }
+
     pub fn get_info_from_path(&self, mod_path: String) -> Option<&ModInfo> {
         self.mods.iter().find(|x| x.path == mod_path)
     }
@@ -26,6 +26,22 @@ impl ErrorList {
         Self { errors: Vec::new() }
     }
 
+    fn window(&mut self, ui: &imgui::Ui) {
+        if self.errors.is_empty() {
+            return;
+        }
+
+        ui.window("Errors").size([400.0, 150.0], Condition::FirstUseEver).build(|| {
+            for error in self.errors.iter() {
+                ui.text_wrapped(error);
+            }
+
+            if ui.button("Clear") {
+                self.errors.clear();
+            }
+        });
+    }
+
     fn try_or_push_error(&mut self, func: impl FnOnce() -> GameResult<()>) {
         if let Err(err) = func() {
             self.errors.push(err.to_string());
@@ -76,8 +92,9 @@ impl EditorScene {
 
             if let Some(stage) = state.stages.get(stage_id) {
                 let stage = Stage::load(&state.constants.base_paths, stage, ctx)?;
+                let npc_list = stage.load_npcs(&state.constants.base_paths, ctx).unwrap_or_default();
 
-                let new_instance = EditorInstance::new(stage_id, stage);
+                let new_instance = EditorInstance::new(stage_id, stage, npc_list);
                 self.instances.push(new_instance);
                 self.selected_instance = self.instances.len() - 1;
                 self.switch_tab = true;
@@ -87,6 +104,16 @@ impl EditorScene {
         });
     }
 
+    fn save_stage(&mut self, state: &mut SharedGameState, ctx: &mut Context) {
+        catch(self.error_list.clone(), || {
+            if let Some(instance) = self.instances.get_mut(self.selected_instance) {
+                instance.save(state, ctx)?;
+            }
+
+            Ok(())
+        });
+    }
+
     fn test_stage(&mut self, state: &mut SharedGameState, ctx: &mut Context) {
         catch(self.error_list.clone(), || {
             if let Some(instance) = self.instances.get(self.selected_instance) {
@@ -216,6 +243,10 @@ impl Scene for EditorScene {
             return Ok(());
         }
 
+        if ui.io().key_ctrl && ui.is_key_pressed(imgui::Key::S) {
+            self.save_stage(state, ctx);
+        }
+
         let mut menu_bar_size = (0.0, 0.0);
         if let Some(menu_bar) = ui.begin_main_menu_bar() {
             let [menu_bar_w, menu_bar_h] = ui.window_size();
@@ -226,6 +257,10 @@ impl Scene for EditorScene {
                     self.stage_list.show();
                 }
 
+                if ui.menu_item_config("Save stage").shortcut("Ctrl+S").enabled(!self.instances.is_empty()).build() {
+                    self.save_stage(state, ctx);
+                }
+
                 ui.separator();
 
                 if ui.menu_item("Exit editor") {
@@ -275,6 +310,10 @@ impl Scene for EditorScene {
                             flags |= TabItemFlags::SET_SELECTED;
                         }
 
+                        if inst.modified {
+                            flags |= TabItemFlags::UNSAVED_DOCUMENT;
+                        }
+
                         if let Some(item) = TabItem::new(&inst.stage.data.name).flags(flags).begin(ui) {
                             if !self.switch_tab {
                                 self.selected_instance = idx;
@@ -288,6 +327,7 @@ impl Scene for EditorScene {
             });
 
         self.stage_list.action(state, ctx, ui);
+        self.error_list.borrow_mut().window(ui);
 
         if let Some(instance) = self.instances.get_mut(self.selected_instance) {
             instance.process(state, ctx, ui, self.current_tool);
@@ -1,6 +1,7 @@
 use std::cell::RefCell;
 use std::ops::{ControlFlow, Deref, Range};
 use std::rc::Rc;
+use std::time::Duration;
 
 use log::info;
 
@@ -16,7 +17,8 @@ use crate::components::hud::HUD;
 use crate::components::inventory::InventoryUI;
 use crate::components::map_system::MapSystem;
 use crate::components::nikumaru::NikumaruCounter;
-use crate::components::replay::Replay;
+use crate::components::replay::{Replay, ReplayKeyframe};
+use crate::components::split_timer::draw_split_timer;
 use crate::components::stage_select::StageSelect;
 use crate::components::text_boxes::TextBoxes;
 use crate::components::tilemap::{TileLayer, Tilemap};
@@ -39,16 +41,21 @@ use crate::game::npc::list::{NPCAccessToken, NPCList, NPCTokenProvider};
 use crate::game::npc::{NPCContext, NPCLayer, NPC};
 use crate::game::physics::{PhysicalEntity, OFFSETS};
 use crate::game::player::{ControlMode, Player, TargetPlayer};
+use crate::game::replay_library::GHOST_ALPHA;
+use crate::game::save_state::SaveState;
 use crate::game::scripting::tsc::credit_script::CreditScriptVM;
 use crate::game::scripting::tsc::text_script::{ScriptMode, TextScriptExecutionState, TextScriptVM};
 use crate::game::settings::ControllerType;
-use crate::game::shared_game_state::{CutsceneSkipMode, PlayerCount, ReplayState, SharedGameState, TileSize};
+use crate::game::shared_game_state::{
+    CutsceneSkipMode, PlayerCount, ReplayState, SharedGameState, TileSize, TimingMode,
+};
 use crate::game::stage::{BackgroundType, Stage, StageTexturePaths};
 use crate::game::weapon::bullet::BulletManager;
 use crate::game::weapon::{Weapon, WeaponType};
 use crate::graphics::font::{Font, Symbols};
 use crate::graphics::texture_set::SpriteBatch;
 use crate::input::touch_controls::TouchControlType;
+use crate::live_debugger::npc_inspector::{InspectorTarget, NPCDebugState};
 use crate::menu::pause_menu::PauseMenu;
 use crate::scene::title_scene::TitleScene;
 use crate::scene::Scene;
@@ -89,12 +96,17 @@ pub struct GameScene {
     pub pause_menu: PauseMenu,
     pub stage_textures: Rc<RefCell<StageTexturePaths>>,
     pub replay: Replay,
-    map_name_counter: u16,
+    /// Player drawn from the ghost track of the replay raced against, see `SharedGameState::replay_ghost`.
+    pub ghost_player: Player,
+    pub npc_debug: NPCDebugState,
+    /// Save state to apply once the scene is initialized, set when restoring a snapshot of another stage.
+    pub pending_save_state: Option<Box<SaveState>>,
+    pub(crate) map_name_counter: u16,
     skip_counter: u16,
     inventory_dim: f32,
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, serde::Serialize, serde::Deserialize)]
 pub enum LightingMode {
     None,
     BackgroundOnly,
@@ -187,6 +199,9 @@ impl GameScene {
             skip_counter: 0,
             inventory_dim: 0.0,
             replay: Replay::new(),
+            ghost_player: Player::new(state, ctx),
+            npc_debug: NPCDebugState::new(),
+            pending_save_state: None,
         })
     }
 
@@ -1184,7 +1199,7 @@ impl GameScene {
                     } else {
                         if npc.shock < 14 {
                             if let Some(table_entry) = state.npc_table.get_entry(npc.npc_type) {
-                                state.sound_manager.play_sfx(table_entry.hurt_sound);
+                                state.sound_manager.play_sfx_at(table_entry.hurt_sound, npc.x, npc.y);
                             }
 
                             npc.shock = 16;
@@ -1284,7 +1299,7 @@ impl GameScene {
                             state.control_flags.set_interactions_disabled(true);
                             state.textscript_vm.start_script(npc.event_num);
                         } else {
-                            state.sound_manager.play_sfx(self.boss.death_sound[idx]);
+                            state.sound_manager.play_sfx_at(self.boss.death_sound[idx], npc.x, npc.y);
 
                             let destroy_count = 4usize * (2usize).pow((npc.size as u32).saturating_sub(1));
 
@@ -1303,7 +1318,7 @@ impl GameScene {
                             for _ in 0..3 {
                                 state.create_caret(bullet.x, bullet.y, CaretType::HurtParticles, Direction::Left);
                             }
-                            state.sound_manager.play_sfx(self.boss.hurt_sound[idx]);
+                            state.sound_manager.play_sfx_at(self.boss.hurt_sound[idx], npc.x, npc.y);
                         }
 
                         npc.shock = 8;
@@ -1332,6 +1347,7 @@ impl GameScene {
     }
 
     fn tick_world(&mut self, state: &mut SharedGameState) -> GameResult {
+        state.sound_manager.set_listener(&self.frame, state.canvas_size);
         self.nikumaru.tick(state, &self.player1)?;
         self.background.tick()?;
         self.hud_player1.visible = self.player1.cond.alive();
@@ -1383,7 +1399,13 @@ impl GameScene {
             self.player2.damage = 0;
         }
 
+        self.npc_debug.prune(&self.npc_list, &self.npc_token, &self.boss);
+
         self.npc_list.try_for_each_alive_mut(&mut self.npc_token, |mut npc| {
+            if self.npc_debug.is_frozen(InspectorTarget::NPC(npc.id)) {
+                return ControlFlow::Continue(());
+            }
+
             map_err_to_break(npc.tick(
                 state,
                 NPCContext {
@@ -1399,6 +1421,8 @@ impl GameScene {
             ControlFlow::Continue(())
         })?;
 
+        let boss_alive = self.boss.parts[0].cond.alive();
+        let frozen_parts = self.npc_debug.save_frozen_parts(&self.boss);
         self.boss.tick(
             state,
             BossNPCContext {
@@ -1411,6 +1435,14 @@ impl GameScene {
             },
         )?;
 
+        for (idx, part) in frozen_parts {
+            self.boss.parts[idx] = part;
+        }
+
+        if boss_alive && !self.boss.parts[0].cond.alive() && self.boss.boss_type != 0 {
+            state.split_timer.on_boss_defeated(self.boss.boss_type, state.replay_state);
+        }
+
         //decides if the player is tangible or not
         if !state.settings.noclip {
             self.player1.tick_map_collisions(state, &self.npc_list, &mut self.stage);
@@ -1435,13 +1467,16 @@ impl GameScene {
         }
 
         self.npc_list.for_each_alive_mut(&mut self.npc_token, |mut npc| {
-            if !npc.npc_flags.ignore_solidity() {
+            if !npc.npc_flags.ignore_solidity() && !self.npc_debug.is_frozen(InspectorTarget::NPC(npc.id)) {
                 npc.tick_map_collisions(state, &self.npc_list, &mut self.stage);
             }
         });
 
-        for npc in self.boss.parts.iter_mut() {
-            if npc.cond.alive() && !npc.npc_flags.ignore_solidity() {
+        for (idx, npc) in self.boss.parts.iter_mut().enumerate() {
+            if npc.cond.alive()
+                && !npc.npc_flags.ignore_solidity()
+                && !self.npc_debug.is_frozen(InspectorTarget::BossPart(idx))
+            {
                 npc.tick_map_collisions(state, &self.npc_list, &mut self.stage);
             }
         }
@@ -1654,12 +1689,53 @@ impl GameScene {
 
         Ok(())
     }
+
+    fn draw_debug_selection(&self, state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
+        let Some(hit_rect) = self.npc_debug.selected_rect(&self.npc_list, &self.npc_token, &self.boss) else {
+            return Ok(());
+        };
+
+        let color = match self.npc_debug.selected {
+            Some(target) if self.npc_debug.is_frozen(target) => Color::from_rgb(0, 192, 255),
+            _ => Color::from_rgb(255, 255, 0),
+        };
+
+        let to_screen = |value: i32, frame: i32| ((value - frame) as f32 / 512.0 * state.scale) as isize;
+        let rect = Rect::new(
+            to_screen(hit_rect.left, self.frame.x),
+            to_screen(hit_rect.top, self.frame.y),
+            to_screen(hit_rect.right, self.frame.x),
+            to_screen(hit_rect.bottom, self.frame.y),
+        );
+
+        graphics::draw_outline_rect(ctx, rect, state.scale as usize, color)
+    }
+
+    /// Starts recording a GIF to the `recordings` directory in the user data path, or stops the active recording.
+    fn toggle_recording(&mut self, state: &mut SharedGameState) -> GameResult {
+        if state.frame_recorder.is_some() {
+            state.stop_recording();
+            return Ok(());
+        }
+
+        let Some(fs_container) = &state.fs_container else {
+            return Ok(());
+        };
+
+        let file_name = format!("{}.gif", chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"));
+        let path = fs_container.user_path.join("recordings").join(file_name);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        state.start_recording(&path)
+    }
 }
 
 impl Scene for GameScene {
     fn init(&mut self, state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
         if state.mod_path.is_some() && state.replay_state == ReplayState::Recording {
-            self.replay.initialize_recording(state);
+            self.replay.initialize_recording(state, self.stage_id);
         }
         if state.player_count == PlayerCount::Two {
             self.add_player2(state, ctx);
@@ -1754,6 +1830,10 @@ impl Scene for GameScene {
         self.pause_menu.init(state, ctx)?;
         self.whimsical_star.init(&self.player1);
 
+        if let Some(save_state) = self.pending_save_state.take() {
+            save_state.apply(state, self, ctx)?;
+        }
+
         #[cfg(feature = "discord-rpc")]
         {
             if self.stage.data.map == state.stages[state.constants.game.intro_stage as usize].map {
@@ -1769,7 +1849,54 @@ impl Scene for GameScene {
     }
 
     fn tick(&mut self, state: &mut SharedGameState, ctx: &mut Context) -> GameResult {
-        if !self.pause_menu.is_paused() {
+        // taken before anything of this tick runs, so playback can continue from it like from any other tick
+        if state.replay_state == ReplayState::Recording && self.replay.data.is_keyframe_due() {
+            let mut keyframe = Vec::new();
+            SaveState::capture_keyframe(state, self).write_compressed(&mut keyframe)?;
+
+            let tick = self.replay.data.inputs.len() as u32;
+            self.replay.data.keyframes.push(ReplayKeyframe { tick, state: keyframe });
+        }
+
+        // without a window or while capturing frames, the ticks don't run at real-time speed
+        if ctx.headless || state.frame_recorder.is_some() {
+            state.split_timer.disable_real_time();
+        }
+
+        // runs the ticks skipped by a replay seek, see Replay::seek
+        if let Some(target) = self.replay.take_seek_target() {
+            let current_tick = self.replay.current_tick();
+            state.split_timer.disable_real_time();
+
+            // most of the ticks are skipped by restoring the last keyframe before the target
+            let keyframe = self.replay.data.keyframe_before(target);
+            if let Some(keyframe) = keyframe.filter(|k| target < current_tick || k.tick as usize > current_tick) {
+                let keyframe_tick = keyframe.tick as usize;
+                let save_state = SaveState::read_compressed(keyframe.state.as_slice())?;
+
+                self.replay.resume_at(keyframe_tick);
+                save_state.restore_keyframe(state, self, ctx)?;
+            }
+
+            for _ in self.replay.current_tick()..target {
+                if !matches!(state.replay_state, ReplayState::Playback(_)) || state.next_scene.is_some() {
+                    break;
+                }
+
+                Scene::tick(self, state, ctx)?;
+            }
+
+            // the stage changed while seeking, the next scene continues with the rest
+            if let Some(next_scene) = &mut state.next_scene {
+                if let Ok(game_scene) = next_scene.downcast_mut::<GameScene>() {
+                    game_scene.replay.seek(target);
+                }
+
+                return Ok(());
+            }
+        }
+
+        if !self.pause_menu.is_paused() && !state.flag_watcher.paused {
             if let ReplayState::Playback(_) = state.replay_state {
                 self.replay.tick(state, (ctx, &mut self.player1))?;
             }
@@ -1817,12 +1944,40 @@ impl Scene for GameScene {
         }
 
         if self.pause_menu.is_paused() {
+            if !self.intro_mode {
+                state.split_timer.tick(true);
+            }
+
             self.pause_menu.tick(state, ctx)?;
             return Ok(());
         }
 
+        // a watched flag has changed, wait for the debugger to resume the game.
+        if state.flag_watcher.paused {
+            return Ok(());
+        }
+
+        if !self.intro_mode {
+            // frame synchronized timing has no fixed tick length, assume the display runs at 60Hz.
+            let delta = match state.settings.timing_mode {
+                TimingMode::FrameSynchronized => TimingMode::_60Hz.get_delta(),
+                timing_mode => timing_mode.get_delta(),
+            };
+            state.play_time += Duration::from_nanos(delta as u64);
+            state.split_timer.tick(false);
+
+            if let Err(err) = state.split_timer.save_bests(ctx) {
+                log::warn!("Failed to save best split times: {}", err);
+            }
+        }
+
         if state.replay_state == ReplayState::Recording {
             self.replay.tick(state, (ctx, &mut self.player1))?;
+            self.replay.record_ghost_frame(&self.player1, self.stage_id);
+
+            if let Some(ghost) = &state.replay_ghost {
+                ghost.update(&mut self.ghost_player, self.stage_id, self.replay.data.inputs.len().saturating_sub(1));
+            }
         }
 
         match state.textscript_vm.state {
@@ -2016,6 +2171,9 @@ impl Scene for GameScene {
         self.boss.draw(state, ctx, &self.frame)?;
         self.draw_npc_layer(state, ctx, NPCLayer::Middleground)?;
         self.draw_bullets(state, ctx)?;
+        if state.replay_ghost.is_some() && state.replay_state == ReplayState::Recording {
+            self.ghost_player.draw_ghost(state, ctx, &self.frame, GHOST_ALPHA)?;
+        }
         self.player2.draw(state, ctx, &self.frame)?;
         self.player1.draw(state, ctx, &self.frame)?;
 
@@ -2261,6 +2419,10 @@ impl Scene for GameScene {
             self.draw_debug_outlines(state, ctx)?;
         }
 
+        if state.debugger {
+            self.draw_debug_selection(state, ctx)?;
+        }
+
         if state.settings.god_mode {
             let debug_name = "GOD";
             state
@@ -2306,6 +2468,7 @@ impl Scene for GameScene {
         }
 
         self.replay.draw(state, ctx, &self.frame)?;
+        draw_split_timer(state, ctx)?;
 
         self.pause_menu.draw(state, ctx)?;
 
@@ -2341,7 +2504,23 @@ impl Scene for GameScene {
             return Ok(());
         }
 
+        if key_code == ScanCode::F1 && ctx.keyboard_context.active_mods().ctrl() {
+            match state.quick_save_state(self) {
+                Ok(()) => state.sound_manager.play_sfx(18),
+                Err(err) => log::error!("Failed to save state: {}", err),
+            }
+            return Ok(());
+        }
+
+        if key_code == ScanCode::F2 && ctx.keyboard_context.active_mods().ctrl() {
+            if let Err(err) = state.quick_load_state(self, ctx) {
+                log::error!("Failed to load state: {}", err);
+            }
+            return Ok(());
+        }
+
         match key_code {
+            ScanCode::F2 => self.toggle_recording(state)?,
             ScanCode::F3 => state.settings.god_mode = !state.settings.god_mode,
             ScanCode::F4 => state.settings.infinite_booster = !state.settings.infinite_booster,
             ScanCode::F5 => state.settings.subpixel_coords = !state.settings.subpixel_coords,
@@ -36,7 +36,7 @@ impl JukeboxSoundtrackKind {
     pub fn to_localized_string(&self, state: &mut SharedGameState) -> String {
         match self {
             JukeboxSoundtrackKind::Organya => state.loc.t("soundtrack.organya").to_owned(),
-            JukeboxSoundtrackKind::Extra(s) => state.loc.t(format!("soundtrack.{}", s.id).as_str()).to_owned(),
+            JukeboxSoundtrackKind::Extra(s) => s.localized_name(&state.loc),
             JukeboxSoundtrackKind::Custom(s) => s.clone(),
         }
     }
@@ -7,8 +7,9 @@ use crate::framework::context::Context;
 use crate::framework::error::GameResult;
 use crate::game::frame::Frame;
 use crate::game::map::Map;
+use crate::game::replay_library::{self, ReplayGhost, ReplayLibraryEntry};
 use crate::game::shared_game_state::{
-    GameDifficulty, MenuCharacter, ReplayKind, ReplayState, Season, SharedGameState, TileSize,
+    GameDifficulty, MenuCharacter, PlayerCount, ReplayKind, ReplayState, Season, SharedGameState, TileSize,
 };
 use crate::game::stage::{BackgroundType, NpcType, Stage, StageData, StageTexturePaths, Tileset};
 use crate::graphics::font::Font;
@@ -18,6 +19,7 @@ use crate::menu::coop_menu::PlayerCountMenu;
 use crate::menu::save_select_menu::SaveSelectMenu;
 use crate::menu::settings_menu::SettingsMenu;
 use crate::menu::{Menu, MenuEntry, MenuSelectionResult};
+use crate::scene::game_scene::GameScene;
 use crate::scene::jukebox_scene::JukeboxScene;
 use crate::scene::Scene;
 
@@ -31,12 +33,15 @@ enum CurrentMenu {
     ChallengesMenu,
     ChallengeConfirmMenu,
     PlayerCountMenu,
+    ReplayLibraryMenu,
+    ReplayMenu,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum MainMenuEntry {
     Start,
     Challenges,
+    Replays,
     Options,
     Editor,
     Jukebox,
@@ -76,6 +81,34 @@ impl Default for ConfirmMenuEntry {
     }
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReplayLibraryMenuEntry {
+    Empty,
+    Replay(usize),
+    Back,
+}
+
+impl Default for ReplayLibraryMenuEntry {
+    fn default() -> Self {
+        ReplayLibraryMenuEntry::Back
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReplayMenuEntry {
+    Title,
+    Play,
+    Race,
+    Delete,
+    Back,
+}
+
+impl Default for ReplayMenuEntry {
+    fn default() -> Self {
+        ReplayMenuEntry::Play
+    }
+}
+
 pub struct TitleScene {
     tick: usize,
     controller: CombinedMenuController,
@@ -84,6 +117,10 @@ pub struct TitleScene {
     save_select_menu: SaveSelectMenu,
     challenges_menu: Menu<ChallengesMenuEntry>,
     confirm_menu: Menu<ConfirmMenuEntry>,
+    replay_library_menu: Menu<ReplayLibraryMenuEntry>,
+    replay_menu: Menu<ReplayMenuEntry>,
+    replays: Vec<ReplayLibraryEntry>,
+    selected_replay: usize,
     coop_menu: PlayerCountMenu,
     settings_menu: SettingsMenu,
     background: Background,
@@ -126,6 +163,10 @@ impl TitleScene {
             save_select_menu: SaveSelectMenu::new(),
             challenges_menu: Menu::new(0, 0, 150, 0),
             confirm_menu: Menu::new(0, 0, 150, 0),
+            replay_library_menu: Menu::new(0, 0, 150, 0),
+            replay_menu: Menu::new(0, 0, 150, 0),
+            replays: Vec::new(),
+            selected_replay: 0,
             coop_menu: PlayerCountMenu::new(),
             settings_menu,
             background: Background::new(),
@@ -193,6 +234,154 @@ impl TitleScene {
         self.current_menu = CurrentMenu::OptionMenu;
         Ok(())
     }
+
+    fn replay_label(&self, state: &SharedGameState, replay: &ReplayLibraryEntry) -> String {
+        let mod_name = state
+            .mod_list
+            .mods
+            .iter()
+            .find(|mod_info| mod_info.id == replay.header.mod_id)
+            .and_then(|mod_info| mod_info.name.clone())
+            .unwrap_or_else(|| replay.header.mod_id.clone());
+
+        match replay.recorded_at() {
+            Some(date) => format!("{}  {}  {}", mod_name, replay.duration(), date),
+            None => format!("{}  {}", mod_name, replay.duration()),
+        }
+    }
+
+    fn replay_mod_path(&self, state: &SharedGameState, replay: &ReplayLibraryEntry) -> Option<String> {
+        state
+            .mod_list
+            .mods
+            .iter()
+            .find(|mod_info| mod_info.valid && mod_info.id == replay.header.mod_id)
+            .map(|mod_info| mod_info.path.clone())
+    }
+
+    fn open_replay_library(&mut self, state: &mut SharedGameState, ctx: &mut Context) {
+        self.replays = replay_library::list(ctx);
+        self.replay_library_menu.entries.clear();
+
+        if self.replays.is_empty() {
+            self.replay_library_menu.push_entry(
+                ReplayLibraryMenuEntry::Empty,
+                MenuEntry::Disabled(state.loc.t("menus.replay_library_menu.empty").to_owned()),
+            );
+        }
+
+        for (idx, replay) in self.replays.iter().enumerate() {
+            let label = self.replay_label(state, replay);
+            self.replay_library_menu.push_entry(ReplayLibraryMenuEntry::Replay(idx), MenuEntry::Active(label));
+        }
+
+        self.replay_library_menu
+            .push_entry(ReplayLibraryMenuEntry::Back, MenuEntry::Active(state.loc.t("common.back").to_owned()));
+
+        let selected_exists = self
+            .replay_library_menu
+            .entries
+            .iter()
+            .any(|(id, entry)| *id == self.replay_library_menu.selected && entry.selectable());
+        if !selected_exists {
+            self.replay_library_menu.selected =
+                if self.replays.is_empty() { ReplayLibraryMenuEntry::Back } else { ReplayLibraryMenuEntry::Replay(0) };
+        }
+
+        self.current_menu = CurrentMenu::ReplayLibraryMenu;
+    }
+
+    fn open_replay_menu(&mut self, state: &mut SharedGameState, idx: usize) {
+        let Some(replay) = self.replays.get(idx) else {
+            return;
+        };
+
+        let label = self.replay_label(state, replay);
+        let mod_installed = self.replay_mod_path(state, replay).is_some();
+
+        self.replay_menu.entries.clear();
+        self.replay_menu.width = (state.font.builder().compute_width(&label).max(50.0) + 32.0) as u16;
+        self.replay_menu.push_entry(ReplayMenuEntry::Title, MenuEntry::Disabled(label));
+
+        if mod_installed {
+            self.replay_menu.push_entry(
+                ReplayMenuEntry::Play,
+                MenuEntry::Active(state.loc.t("menus.replay_library_menu.play").to_owned()),
+            );
+
+            if replay.has_ghost {
+                self.replay_menu.push_entry(
+                    ReplayMenuEntry::Race,
+                    MenuEntry::Active(state.loc.t("menus.replay_library_menu.race").to_owned()),
+                );
+            } else {
+                self.replay_menu.push_entry(
+                    ReplayMenuEntry::Race,
+                    MenuEntry::Disabled(state.loc.t("menus.replay_library_menu.race").to_owned()),
+                );
+            }
+        } else {
+            self.replay_menu.push_entry(
+                ReplayMenuEntry::Play,
+                MenuEntry::Disabled(state.loc.t("menus.replay_library_menu.mod_missing").to_owned()),
+            );
+        }
+
+        self.replay_menu.push_entry(
+            ReplayMenuEntry::Delete,
+            MenuEntry::Active(state.loc.t("menus.replay_library_menu.delete").to_owned()),
+        );
+        self.replay_menu.push_entry(ReplayMenuEntry::Back, MenuEntry::Active(state.loc.t("common.back").to_owned()));
+        self.replay_menu.selected = if mod_installed { ReplayMenuEntry::Play } else { ReplayMenuEntry::Back };
+
+        self.selected_replay = idx;
+        self.current_menu = CurrentMenu::ReplayMenu;
+    }
+
+    /// Loads the mod a library replay was recorded with and starts a new game for playing it back or racing it.
+    fn start_library_replay(&mut self, state: &mut SharedGameState, ctx: &mut Context, race: bool) -> GameResult {
+        let Some(replay) = self.replays.get(self.selected_replay) else {
+            return Ok(());
+        };
+        let Some(mod_path) = self.replay_mod_path(state, replay) else {
+            return Ok(());
+        };
+
+        let (data, ghost) = if race {
+            match ReplayGhost::load(ctx, &replay.name) {
+                Ok(ghost) => (None, Some(ghost)),
+                Err(err) => {
+                    log::warn!("Failed to load replay ghost: {}", err);
+                    return Ok(());
+                }
+            }
+        } else {
+            match replay_library::load(ctx, &replay.name) {
+                Ok(data) => (Some(data), None),
+                Err(err) => {
+                    log::warn!("Failed to load replay: {}", err);
+                    return Ok(());
+                }
+            }
+        };
+
+        state.mod_path = Some(mod_path);
+        state.difficulty = GameDifficulty::Normal;
+        state.player_count = PlayerCount::One;
+        state.replay_ghost = ghost;
+        // library replays are preloaded into the game scene, the kind only matters for reading replay files
+        state.replay_state = if race { ReplayState::Recording } else { ReplayState::Playback(ReplayKind::Best) };
+        state.reload_resources(ctx)?;
+        state.start_new_game(ctx)?;
+
+        if let (Some(data), Some(next_scene)) = (data, state.next_scene.as_mut()) {
+            if let Ok(game_scene) = next_scene.downcast_mut::<GameScene>() {
+                game_scene.replay.preload(data);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 static COPYRIGHT_PIXEL: &str = "2004.12  Studio Pixel";
@@ -217,6 +406,10 @@ impl Scene for TitleScene {
                 MainMenuEntry::Challenges,
                 MenuEntry::Active(state.loc.t("menus.main_menu.challenges").to_owned()),
             );
+            self.main_menu.push_entry(
+                MainMenuEntry::Replays,
+                MenuEntry::Active(state.loc.t("menus.main_menu.replays").to_owned()),
+            );
         }
 
         self.main_menu
@@ -295,6 +488,8 @@ impl Scene for TitleScene {
         self.update_menu_cursor(state, ctx)?;
 
         state.replay_state = ReplayState::None;
+        state.replay_ghost = None;
+        state.restore_replay_settings();
         state.textscript_vm.flags.set_cutscene_skip(false);
         state.difficulty = GameDifficulty::Normal;
 
@@ -321,6 +516,13 @@ impl Scene for TitleScene {
         self.challenges_menu.y =
             ((state.canvas_size.1 + 30.0 - self.challenges_menu.height as f32) / 2.0).floor() as isize;
 
+        self.replay_library_menu.update_width(state);
+        self.replay_library_menu.update_height(state);
+        self.replay_library_menu.x =
+            ((state.canvas_size.0 - self.replay_library_menu.width as f32) / 2.0).floor() as isize;
+        self.replay_library_menu.y =
+            ((state.canvas_size.1 + 30.0 - self.replay_library_menu.height as f32) / 2.0).floor() as isize;
+
         if self.controller.trigger_left()
             && self.compact_jukebox.is_shown()
             && self.current_menu == CurrentMenu::MainMenu
@@ -346,6 +548,9 @@ impl Scene for TitleScene {
                 MenuSelectionResult::Selected(MainMenuEntry::Challenges, _) => {
                     self.current_menu = CurrentMenu::ChallengesMenu;
                 }
+                MenuSelectionResult::Selected(MainMenuEntry::Replays, _) => {
+                    self.open_replay_library(state, ctx);
+                }
                 MenuSelectionResult::Selected(MainMenuEntry::Options, _) => {
                     self.current_menu = CurrentMenu::OptionMenu;
                 }
@@ -467,6 +672,35 @@ impl Scene for TitleScene {
                 }
                 _ => (),
             },
+            CurrentMenu::ReplayLibraryMenu => match self.replay_library_menu.tick(&mut self.controller, state) {
+                MenuSelectionResult::Selected(ReplayLibraryMenuEntry::Replay(idx), _) => {
+                    self.open_replay_menu(state, idx);
+                }
+                MenuSelectionResult::Selected(ReplayLibraryMenuEntry::Back, _) | MenuSelectionResult::Canceled => {
+                    self.current_menu = CurrentMenu::MainMenu;
+                }
+                _ => (),
+            },
+            CurrentMenu::ReplayMenu => match self.replay_menu.tick(&mut self.controller, state) {
+                MenuSelectionResult::Selected(ReplayMenuEntry::Play, _) => {
+                    self.start_library_replay(state, ctx, false)?;
+                }
+                MenuSelectionResult::Selected(ReplayMenuEntry::Race, _) => {
+                    self.start_library_replay(state, ctx, true)?;
+                }
+                MenuSelectionResult::Selected(ReplayMenuEntry::Delete, _) => {
+                    if let Some(replay) = self.replays.get(self.selected_replay) {
+                        if let Err(err) = replay_library::delete(ctx, &replay.name) {
+                            log::warn!("Failed to delete replay: {}", err);
+                        }
+                    }
+                    self.open_replay_library(state, ctx);
+                }
+                MenuSelectionResult::Selected(ReplayMenuEntry::Back, _) | MenuSelectionResult::Canceled => {
+                    self.current_menu = CurrentMenu::ReplayLibraryMenu;
+                }
+                _ => (),
+            },
             CurrentMenu::PlayerCountMenu => {
                 let cm = &mut self.current_menu;
                 let rm = CurrentMenu::ChallengeConfirmMenu;
@@ -486,6 +720,11 @@ impl Scene for TitleScene {
         self.confirm_menu.x = ((state.canvas_size.0 - self.confirm_menu.width as f32) / 2.0).floor() as isize;
         self.confirm_menu.y = ((state.canvas_size.1 + 30.0 - self.confirm_menu.height as f32) / 2.0).floor() as isize;
 
+        self.replay_menu.update_width(state);
+        self.replay_menu.update_height(state);
+        self.replay_menu.x = ((state.canvas_size.0 - self.replay_menu.width as f32) / 2.0).floor() as isize;
+        self.replay_menu.y = ((state.canvas_size.1 + 30.0 - self.replay_menu.height as f32) / 2.0).floor() as isize;
+
         self.tick += 1;
 
         Ok(())
@@ -515,6 +754,7 @@ impl Scene for TitleScene {
                 CurrentMenu::OptionMenu => state.loc.t("menus.main_menu.options"),
                 CurrentMenu::MainMenu => unreachable!(),
                 CurrentMenu::PlayerCountMenu => state.loc.t("menus.main_menu.start"),
+                CurrentMenu::ReplayLibraryMenu | CurrentMenu::ReplayMenu => state.loc.t("menus.main_menu.replays"),
             };
             state
                 .font
@@ -546,6 +786,8 @@ impl Scene for TitleScene {
             CurrentMenu::OptionMenu => self.settings_menu.draw(state, ctx)?,
             CurrentMenu::SaveSelectMenu => self.save_select_menu.draw(state, ctx)?,
             CurrentMenu::PlayerCountMenu => self.coop_menu.draw(state, ctx)?,
+            CurrentMenu::ReplayLibraryMenu => self.replay_library_menu.draw(state, ctx)?,
+            CurrentMenu::ReplayMenu => self.replay_menu.draw(state, ctx)?,
         }
 
         Ok(())
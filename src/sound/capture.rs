@@ -0,0 +1,162 @@
+//! In-memory audio output for headless mode, enabled with [crate::framework::context::Context::audio_capture].
+//!
+//! The playback messages are processed and mixed once per game tick instead of on the audio thread,
+//! which makes the output deterministic, so tests can check which sound effects were played on which tick
+//! and what the mixer produced.
+
+use std::sync::mpsc::Receiver;
+
+use crate::sound::wav::{WavFormat, WavSample};
+use crate::sound::wave_bank::SoundBank;
+use crate::sound::{Mixer, PlaybackMessage, SharedSongPosition};
+
+/// Sample rate of the captured audio.
+pub const CAPTURE_SAMPLE_RATE: u32 = 44100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SoundEventKind {
+    PlaySfx(u8),
+    LoopSfx(u8),
+    StopSfx(u8),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SoundEvent {
+    /// Number of the game tick the sound effect was requested on, counted from the start of the capture.
+    pub tick: u64,
+    pub kind: SoundEventKind,
+}
+
+pub struct AudioCapture {
+    mixer: Mixer,
+    rx: Receiver<PlaybackMessage>,
+    tick: u64,
+    /// Sound effect requests, oldest first.
+    pub events: Vec<SoundEvent>,
+    /// Mixed output as interleaved 16-bit stereo at [CAPTURE_SAMPLE_RATE].
+    pub samples: Vec<i16>,
+}
+
+impl AudioCapture {
+    pub(in crate::sound) fn new(
+        bank: SoundBank,
+        rx: Receiver<PlaybackMessage>,
+        song_position: SharedSongPosition,
+    ) -> AudioCapture {
+        AudioCapture {
+            mixer: Mixer::new(bank, CAPTURE_SAMPLE_RATE as f32, song_position),
+            rx,
+            tick: 0,
+            events: Vec::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Number of game ticks mixed so far.
+    pub fn current_tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Processes the messages sent during the current tick and mixes one tick worth of audio.
+    pub fn end_tick(&mut self, tps: usize) {
+        // frame synchronized timing has no fixed tick rate, assume the common refresh rate
+        let tps = if tps == 0 { 60 } else { tps as u64 };
+
+        // rounding is spread over the ticks, so a second of ticks is always exactly a second of audio
+        let rate = CAPTURE_SAMPLE_RATE as u64;
+        let frames = ((self.tick + 1) * rate / tps - self.tick * rate / tps) as usize;
+
+        // same order as the output callback of the audio thread
+        self.mixer.update_fadeout(frames);
+
+        while let Ok(message) = self.rx.try_recv() {
+            let kind = match message {
+                PlaybackMessage::PlaySample(id) | PlaybackMessage::PlaySampleAt(id, _) => {
+                    Some(SoundEventKind::PlaySfx(id))
+                }
+                PlaybackMessage::LoopSample(id) | PlaybackMessage::LoopSampleFreq(id, _) => {
+                    Some(SoundEventKind::LoopSfx(id))
+                }
+                PlaybackMessage::StopSample(id) => Some(SoundEventKind::StopSfx(id)),
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                self.events.push(SoundEvent { tick: self.tick, kind });
+            }
+
+            self.mixer.handle_message(message);
+        }
+
+        let start = self.samples.len();
+        self.samples.resize(start + frames * 2, 0);
+        self.mixer.render(&mut self.samples[start..], 2);
+
+        self.tick += 1;
+    }
+
+    /// Returns the sound effects started on given tick.
+    pub fn sfx_played_at(&self, tick: u64) -> Vec<u8> {
+        self.events
+            .iter()
+            .filter(|event| event.tick == tick)
+            .filter_map(|event| match event.kind {
+                SoundEventKind::PlaySfx(id) | SoundEventKind::LoopSfx(id) => Some(id),
+                SoundEventKind::StopSfx(_) => None,
+            })
+            .collect()
+    }
+
+    /// Returns the highest absolute amplitude of the captured samples, zero means silence.
+    pub fn peak(&self) -> u16 {
+        self.samples.iter().map(|sample| sample.unsigned_abs()).max().unwrap_or(0)
+    }
+
+    /// Returns the captured samples as a WAV sample.
+    pub fn to_wav(&self) -> WavSample {
+        samples_to_wav(&self.samples)
+    }
+
+    /// Discards the captured events and samples, the tick counter keeps counting.
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.samples.clear();
+    }
+}
+
+/// Packs interleaved 16-bit stereo samples captured at [CAPTURE_SAMPLE_RATE] into a WAV sample.
+pub fn samples_to_wav(samples: &[i16]) -> WavSample {
+    let data: Vec<u8> = samples.iter().flat_map(|sample| sample.to_le_bytes()).collect();
+
+    WavSample { format: WavFormat { channels: 2, sample_rate: CAPTURE_SAMPLE_RATE, bit_depth: 16 }, data: data.into() }
+}
+
+#[test]
+fn test_audio_capture() {
+    use std::sync::mpsc;
+
+    let bank = SoundBank::load_from(&include_bytes!("../data/builtin/organya-wavetable-doukutsu.bin")[..]).unwrap();
+    let (tx, rx) = mpsc::channel();
+    let song_position = SharedSongPosition::default();
+    let mut capture = AudioCapture::new(bank, rx, song_position.clone());
+
+    capture.end_tick(50);
+    assert_eq!(capture.samples.len(), 882 * 2);
+    assert_eq!(capture.peak(), 0);
+
+    tx.send(PlaybackMessage::PlaySample(1)).unwrap();
+    capture.end_tick(50);
+    capture.end_tick(50);
+
+    assert_eq!(capture.events, vec![SoundEvent { tick: 1, kind: SoundEventKind::PlaySfx(1) }]);
+    assert_eq!(capture.sfx_played_at(1), vec![1]);
+    assert!(capture.sfx_played_at(2).is_empty());
+    assert!(capture.peak() > 0);
+    assert_eq!(capture.current_tick(), 3);
+    // no song is playing
+    assert_eq!(*song_position.lock().unwrap(), None);
+
+    let wav = capture.to_wav();
+    assert_eq!(wav.format, WavFormat { channels: 2, sample_rate: CAPTURE_SAMPLE_RATE, bit_depth: 16 });
+    assert_eq!(wav.data.len(), capture.samples.len() * 2);
+}
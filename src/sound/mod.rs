@@ -1,43 +1,60 @@
 use std::io;
-use std::io::{BufRead, BufReader, Lines};
-use std::str::FromStr;
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender};
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 #[cfg(feature = "ogg-playback")]
 use lewton::inside_ogg::OggStreamReader;
 use num_traits::clamp;
 
-use crate::engine_constants::EngineConstants;
+use crate::engine_constants::{EngineConstants, SoundtrackSong};
 use crate::framework::context::Context;
 use crate::framework::error::GameError::{AudioError, InvalidValue};
 use crate::framework::error::{GameError, GameResult};
 use crate::framework::filesystem;
 use crate::framework::filesystem::File;
+use crate::game::frame::Frame;
 use crate::game::settings::Settings;
+use crate::sound::capture::AudioCapture;
 #[cfg(feature = "ogg-playback")]
-use crate::sound::ogg_playback::{OggPlaybackEngine, SavedOggPlaybackState};
+use crate::sound::ogg_playback::{LoopPoints, OggPlaybackEngine, SavedOggPlaybackState};
 use crate::sound::org_playback::{OrgPlaybackEngine, SavedOrganyaPlaybackState};
 use crate::sound::organya::Song;
 use crate::sound::pixtone::{PixToneParameters, PixTonePlayback};
+use crate::sound::pxtone_playback::{PxTonePlaybackEngine, SavedPxTonePlaybackState};
 use crate::sound::wave_bank::SoundBank;
 
+pub mod capture;
 mod fir;
+pub mod offline;
 #[cfg(feature = "ogg-playback")]
 mod ogg_playback;
 mod org_playback;
 mod organya;
 pub mod pixtone;
 mod pixtone_sfx;
+mod pxtone;
+mod pxtone_playback;
 mod stuff;
-mod wav;
+pub mod wav;
 mod wave_bank;
 
 /// Number of seconds to fade out the background music completely.
 const FADEOUT_DURATION: f32 = 5.0;
 
+/// Instrument samples used by Organya songs.
+const WAVETABLE_PATH: &str = "/builtin/organya-wavetable-doukutsu.bin";
+
+/// Pan of sound effects at the edges of the screen, so they can still be heard from both speakers.
+const MAX_SFX_PAN: f32 = 0.75;
+
+/// Distance from the edge of the screen in pixels at which sound effects are attenuated the most.
+const SFX_FALLOFF_DISTANCE: f32 = 240.0;
+
+/// Gain of sound effects far away from the screen.
+const MIN_SFX_GAIN: f32 = 0.25;
+
 pub struct SoundManager {
     soundbank: Option<SoundBank>,
     tx: Sender<PlaybackMessage>,
@@ -46,17 +63,99 @@ pub struct SoundManager {
     no_audio: bool,
     load_failed: bool,
     stream: Option<cpal::Stream>,
+    capture: Option<Box<AudioCapture>>,
+    positional_sfx: bool,
+    /// Visible part of the stage and its size in pixels, positional sound effects are placed relative to it.
+    listener: Option<(Frame, (f32, f32))>,
+    song_position: SharedSongPosition,
+}
+
+/// Stereo placement of a sound effect.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SfxPosition {
+    /// From -1.0 (left) to 1.0 (right).
+    pub pan: f32,
+    pub gain: f32,
+}
+
+impl SfxPosition {
+    pub const CENTER: SfxPosition = SfxPosition { pan: 0.0, gain: 1.0 };
+
+    /// Places a sound effect coming from given world position relative to the visible part of the stage.
+    /// Effects on screen are played at full volume and get quieter the further off screen they are.
+    pub fn from_world(x: i32, y: i32, frame: &Frame, view_size: (f32, f32)) -> SfxPosition {
+        let half_width = (view_size.0 / 2.0).max(1.0);
+        let half_height = (view_size.1 / 2.0).max(1.0);
+        let dx = (x - frame.x) as f32 / 512.0 - half_width;
+        let dy = (y - frame.y) as f32 / 512.0 - half_height;
+
+        let pan = (dx / half_width).clamp(-1.0, 1.0) * MAX_SFX_PAN;
+        let distance = (dx.abs() - half_width).max(0.0).hypot((dy.abs() - half_height).max(0.0));
+        let gain = (1.0 - distance / SFX_FALLOFF_DISTANCE).max(MIN_SFX_GAIN);
+
+        SfxPosition { pan, gain }
+    }
+
+    /// Returns the volume of the left and right channel, both are 1.0 for centered effects.
+    pub fn volumes(&self) -> (f32, f32) {
+        (self.gain * (1.0 - self.pan).min(1.0), self.gain * (1.0 + self.pan).min(1.0))
+    }
+}
+
+/// Last known playback position of the current song, published by the mixer after every rendered buffer.
+type SharedSongPosition = Arc<Mutex<Option<(bool, u64)>>>;
+
+/// Currently playing song and the playback position in it, as stored in save states.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct SongPosition {
+    pub song_id: usize,
+    /// Whether the intro part of a multi part Ogg song is playing.
+    pub intro: bool,
+    /// Organya beat, PxTone frame or Ogg granule position, depending on the song format.
+    pub position: u64,
 }
 
 enum SongFormat {
     Organya,
+    PxTone,
+    #[cfg(feature = "ogg-playback")]
+    OggSinglePart(LoopPoints),
     #[cfg(feature = "ogg-playback")]
-    OggSinglePart,
+    OggMultiPart(LoopPoints),
+}
+
+/// Returns the files a song can be played from with given path prefix and file name, in order of preference.
+#[cfg_attr(not(feature = "ogg-playback"), allow(unused_variables))]
+fn song_files(prefix: &str, file_name: &str, song: Option<&SoundtrackSong>) -> Vec<(SongFormat, Vec<String>)> {
     #[cfg(feature = "ogg-playback")]
-    OggMultiPart,
+    let loop_points = song.map(LoopPoints::from).unwrap_or_default();
+
+    vec![
+        #[cfg(feature = "ogg-playback")]
+        (
+            SongFormat::OggMultiPart(loop_points),
+            vec![format!("{}{}_intro.ogg", prefix, file_name), format!("{}{}_loop.ogg", prefix, file_name)],
+        ),
+        #[cfg(feature = "ogg-playback")]
+        (SongFormat::OggSinglePart(loop_points), vec![format!("{}{}.ogg", prefix, file_name)]),
+        (SongFormat::PxTone, vec![format!("{}{}.pttune", prefix, file_name)]),
+        (SongFormat::PxTone, vec![format!("{}{}.ptcop", prefix, file_name)]),
+        (SongFormat::Organya, vec![format!("{}{}.org", prefix, file_name)]),
+    ]
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    strum_macros::Display,
+    strum_macros::EnumString,
+)]
+#[strum(serialize_all = "lowercase")]
 pub enum InterpolationMode {
     Nearest,
     Linear,
@@ -69,6 +168,28 @@ impl SoundManager {
     pub fn new(ctx: &mut Context) -> GameResult<SoundManager> {
         let (tx, rx): (Sender<PlaybackMessage>, Receiver<PlaybackMessage>) = mpsc::channel();
 
+        if ctx.headless && ctx.audio_capture {
+            log::info!("Running in headless mode, capturing audio to memory.");
+
+            let soundbank = SoundBank::load_from(filesystem::open(ctx, WAVETABLE_PATH)?)?;
+            let song_position = SharedSongPosition::default();
+            let capture = AudioCapture::new(soundbank.clone(), rx, song_position.clone());
+
+            return Ok(SoundManager {
+                soundbank: Some(soundbank),
+                tx,
+                prev_song_id: 0,
+                current_song_id: 0,
+                no_audio: false,
+                load_failed: false,
+                stream: None,
+                capture: Some(Box::new(capture)),
+                positional_sfx: false,
+                listener: None,
+                song_position,
+            });
+        }
+
         if ctx.headless {
             log::info!("Running in headless mode, skipping initialization.");
 
@@ -80,10 +201,14 @@ impl SoundManager {
                 no_audio: true,
                 load_failed: false,
                 stream: None,
+                capture: None,
+                positional_sfx: false,
+                listener: None,
+                song_position: SharedSongPosition::default(),
             });
         }
 
-        let bnk = wave_bank::SoundBank::load_from(filesystem::open(ctx, "/builtin/organya-wavetable-doukutsu.bin")?)?;
+        let bnk = wave_bank::SoundBank::load_from(filesystem::open(ctx, WAVETABLE_PATH)?)?;
         Ok(SoundManager::bootstrap(bnk, tx, rx)?)
     }
 
@@ -92,6 +217,7 @@ impl SoundManager {
         tx: Sender<PlaybackMessage>,
         rx: Receiver<PlaybackMessage>,
     ) -> GameResult<SoundManager> {
+        let song_position = SharedSongPosition::default();
         let mut sound_manager = SoundManager {
             soundbank: Some(soundbank.clone()),
             tx,
@@ -100,6 +226,10 @@ impl SoundManager {
             no_audio: false,
             load_failed: false,
             stream: None,
+            capture: None,
+            positional_sfx: false,
+            listener: None,
+            song_position: song_position.clone(),
         };
 
         let host = cpal::default_host();
@@ -126,16 +256,16 @@ impl SoundManager {
         let config = config_result.unwrap();
 
         let res = match config.sample_format() {
-            cpal::SampleFormat::I8 => run::<i8>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::I16 => run::<i16>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::I32 => run::<i32>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::I64 => run::<i64>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::U8 => run::<u8>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::U16 => run::<u16>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::U32 => run::<u32>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::U64 => run::<u64>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::F32 => run::<f32>(rx, soundbank, device, config.into()),
-            cpal::SampleFormat::F64 => run::<f64>(rx, soundbank, device, config.into()),
+            cpal::SampleFormat::I8 => run::<i8>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::I16 => run::<i16>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::I32 => run::<i32>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::I64 => run::<i64>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::U8 => run::<u8>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::U16 => run::<u16>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::U32 => run::<u32>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::U64 => run::<u64>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::F32 => run::<f32>(rx, soundbank, device, config.into(), song_position),
+            cpal::SampleFormat::F64 => run::<f64>(rx, soundbank, device, config.into(), song_position),
             _ => Err(AudioError("Unsupported sample format.".to_owned())),
         };
 
@@ -148,7 +278,7 @@ impl SoundManager {
     }
 
     pub fn reload(&mut self) -> GameResult<()> {
-        if self.no_audio {
+        if self.no_audio || self.capture.is_some() {
             log::info!("Skipping sound manager reload because audio is not enabled.");
             return Ok(());
         }
@@ -157,7 +287,11 @@ impl SoundManager {
 
         let (tx, rx): (Sender<PlaybackMessage>, Receiver<PlaybackMessage>) = mpsc::channel();
         let soundbank = self.soundbank.take().unwrap();
+        let positional_sfx = self.positional_sfx;
+        let listener = self.listener.take();
         *self = SoundManager::bootstrap(soundbank, tx, rx)?;
+        self.positional_sfx = positional_sfx;
+        self.listener = listener;
 
         Ok(())
     }
@@ -177,6 +311,22 @@ impl SoundManager {
         Ok(())
     }
 
+    /// Captured audio, if the game runs in headless mode with audio capture enabled.
+    pub fn capture(&self) -> Option<&AudioCapture> {
+        self.capture.as_deref()
+    }
+
+    pub fn capture_mut(&mut self) -> Option<&mut AudioCapture> {
+        self.capture.as_deref_mut()
+    }
+
+    /// Mixes the audio of a game tick when capturing, does nothing otherwise.
+    pub fn end_tick(&mut self, tps: usize) {
+        if let Some(capture) = &mut self.capture {
+            capture.end_tick(tps);
+        }
+    }
+
     pub fn pause(&mut self) {
         if let Some(stream) = &mut self.stream {
             let _ = stream.pause();
@@ -189,6 +339,9 @@ impl SoundManager {
         }
     }
 
+    /// Plays a centered sound effect. Kept for sounds without a place in the stage: menus, the HUD, the
+    /// inventory, `<SOU` in scripts, and the player's own weapon and damage sounds, since the camera
+    /// follows the player. Everything emitted by NPCs, bosses or terrain collisions uses [`Self::play_sfx_at`].
     pub fn play_sfx(&mut self, id: u8) {
         if self.no_audio {
             return;
@@ -197,6 +350,35 @@ impl SoundManager {
         self.send(PlaybackMessage::PlaySample(id)).unwrap();
     }
 
+    /// Plays a sound effect coming from given world position, panned and attenuated relative to the screen
+    /// if positional sound effects are enabled.
+    pub fn play_sfx_at(&mut self, id: u8, x: i32, y: i32) {
+        if self.no_audio {
+            return;
+        }
+
+        let position = match &self.listener {
+            Some((frame, view_size)) if self.positional_sfx => SfxPosition::from_world(x, y, frame, *view_size),
+            _ => SfxPosition::CENTER,
+        };
+
+        if position == SfxPosition::CENTER {
+            self.send(PlaybackMessage::PlaySample(id)).unwrap();
+        } else {
+            self.send(PlaybackMessage::PlaySampleAt(id, position)).unwrap();
+        }
+    }
+
+    pub fn set_positional_sfx(&mut self, enabled: bool) {
+        self.positional_sfx = enabled;
+    }
+
+    /// Sets the visible part of the stage positional sound effects are placed relative to,
+    /// `view_size` is the size of the screen in pixels.
+    pub fn set_listener(&mut self, frame: &Frame, view_size: (f32, f32)) {
+        self.listener = Some((frame.clone(), view_size));
+    }
+
     pub fn loop_sfx(&self, id: u8) {
         if self.no_audio {
             return;
@@ -286,120 +468,162 @@ impl SoundManager {
                 self.send(PlaybackMessage::Stop).unwrap();
             }
         } else if let Some(song_name) = constants.music_table.get(song_id) {
-            let mut paths = constants.organya_paths.clone();
+            let soundtrack = constants.soundtracks.iter().find(|s| s.available && s.id == settings.soundtrack);
+            let organya_fallback = soundtrack.map_or(true, |s| s.organya_fallback);
+            let mut songs = Vec::new();
+
+            if let Some(soundtrack) = soundtrack {
+                match soundtrack.songs.get(song_name) {
+                    Some(song) if song.organya => {}
+                    #[cfg(feature = "ogg-playback")]
+                    Some(song @ SoundtrackSong { loop_file: Some(loop_file), .. }) => {
+                        let loop_path = format!("{}{}", soundtrack.path, loop_file);
+
+                        songs.push(match &song.intro {
+                            Some(intro) => (
+                                SongFormat::OggMultiPart(LoopPoints::from(song)),
+                                vec![format!("{}{}", soundtrack.path, intro), loop_path],
+                            ),
+                            None => (SongFormat::OggSinglePart(LoopPoints::from(song)), vec![loop_path]),
+                        });
+                    }
+                    song => {
+                        let file_name = soundtrack.song_file_name(song_id, song_name);
+                        songs.extend(song_files(&soundtrack.path, &file_name, song));
+                    }
+                }
+            }
 
-            paths.insert(0, "/Soundtracks/".to_owned() + &settings.soundtrack + "/");
+            songs.extend(song_files(&format!("/Soundtracks/{}/", settings.soundtrack), song_name, None));
 
-            if let Some(soundtrack) = constants.soundtracks.iter().find(|s| s.available && s.id == settings.soundtrack)
-            {
-                paths.insert(0, soundtrack.path.clone());
+            // PxPack data roots keep their PxTone songs in bgm/
+            for root in constants.base_paths.iter() {
+                let prefix = format!("{}bgm/", root);
+                songs.push((SongFormat::PxTone, vec![format!("{}{}.pttune", prefix, song_name)]));
+                songs.push((SongFormat::PxTone, vec![format!("{}{}.ptcop", prefix, song_name)]));
             }
 
-            let songs_paths = paths.iter().map(|prefix| {
-                [
-                    #[cfg(feature = "ogg-playback")]
-                    (
-                        SongFormat::OggMultiPart,
-                        vec![format!("{}{}_intro.ogg", prefix, song_name), format!("{}{}_loop.ogg", prefix, song_name)],
-                    ),
-                    #[cfg(feature = "ogg-playback")]
-                    (SongFormat::OggSinglePart, vec![format!("{}{}.ogg", prefix, song_name)]),
-                    (SongFormat::Organya, vec![format!("{}{}.org", prefix, song_name)]),
-                ]
-            });
+            if organya_fallback {
+                for prefix in constants.organya_paths.iter() {
+                    songs.extend(song_files(prefix, song_name, None));
+                }
+            }
+
+            for (format, paths) in
+                songs.iter().filter(|(_, paths)| paths.iter().all(|path| filesystem::exists(ctx, path)))
+            {
+                match format {
+                    SongFormat::Organya => {
+                        // we're sure that there's one element
+                        let path = unsafe { paths.get_unchecked(0) };
+
+                        match filesystem::open(ctx, path).map(organya::Song::load_from) {
+                            Ok(Ok(org)) => {
+                                log::info!("Playing Organya BGM: {} {}", song_id, path);
+
+                                self.prev_song_id = self.current_song_id;
+                                self.current_song_id = song_id;
+                                let _ = self
+                                    .send(PlaybackMessage::SetOrgInterpolation(settings.organya_interpolation))
+                                    .unwrap();
+                                self.send(PlaybackMessage::SaveState).unwrap();
+                                self.send(PlaybackMessage::PlayOrganyaSong(Box::new(org))).unwrap();
 
-            for songs in songs_paths {
-                for (format, paths) in
-                    songs.iter().filter(|(_, paths)| paths.iter().all(|path| filesystem::exists(ctx, path)))
-                {
-                    match format {
-                        SongFormat::Organya => {
-                            // we're sure that there's one element
-                            let path = unsafe { paths.get_unchecked(0) };
-
-                            match filesystem::open(ctx, path).map(organya::Song::load_from) {
-                                Ok(Ok(org)) => {
-                                    log::info!("Playing Organya BGM: {} {}", song_id, path);
-
-                                    self.prev_song_id = self.current_song_id;
-                                    self.current_song_id = song_id;
-                                    let _ = self
-                                        .send(PlaybackMessage::SetOrgInterpolation(settings.organya_interpolation))
-                                        .unwrap();
-                                    self.send(PlaybackMessage::SaveState).unwrap();
-                                    self.send(PlaybackMessage::PlayOrganyaSong(Box::new(org))).unwrap();
-
-                                    return Ok(());
-                                }
-                                Ok(Err(err)) | Err(err) => {
-                                    log::warn!("Failed to load Organya BGM {}: {}", song_id, err);
-                                }
+                                return Ok(());
+                            }
+                            Ok(Err(err)) | Err(err) => {
+                                log::warn!("Failed to load Organya BGM {}: {}", song_id, err);
                             }
                         }
-                        #[cfg(feature = "ogg-playback")]
-                        SongFormat::OggSinglePart => {
-                            // we're sure that there's one element
-                            let path = unsafe { paths.get_unchecked(0) };
+                    }
+                    SongFormat::PxTone => {
+                        let path = unsafe { paths.get_unchecked(0) };
 
-                            match filesystem::open(ctx, path).map(|f| {
-                                OggStreamReader::new(f).map_err(|e| GameError::ResourceLoadError(e.to_string()))
-                            }) {
-                                Ok(Ok(song)) => {
-                                    log::info!("Playing single part Ogg BGM: {} {}", song_id, path);
-
-                                    self.prev_song_id = self.current_song_id;
-                                    self.current_song_id = song_id;
-                                    self.send(PlaybackMessage::SaveState).unwrap();
-                                    self.send(PlaybackMessage::PlayOggSongSinglePart(Box::new(song))).unwrap();
-
-                                    return Ok(());
-                                }
-                                Ok(Err(err)) | Err(err) => {
-                                    log::warn!("Failed to load single part Ogg BGM {}: {}", song_id, err);
-                                }
+                        match filesystem::open(ctx, path).map(pxtone::Song::load_from) {
+                            Ok(Ok(song)) => {
+                                log::info!("Playing PxTone BGM: {} {}", song_id, path);
+
+                                self.prev_song_id = self.current_song_id;
+                                self.current_song_id = song_id;
+                                self.send(PlaybackMessage::SaveState).unwrap();
+                                self.send(PlaybackMessage::PlayPxToneSong(Box::new(song))).unwrap();
+
+                                return Ok(());
+                            }
+                            Ok(Err(err)) | Err(err) => {
+                                log::warn!("Failed to load PxTone BGM {}: {}", song_id, err);
                             }
                         }
-                        #[cfg(feature = "ogg-playback")]
-                        SongFormat::OggMultiPart => {
-                            // we're sure that there are two elements
-                            let path_intro = unsafe { paths.get_unchecked(0) };
-                            let path_loop = unsafe { paths.get_unchecked(1) };
-
-                            match (
-                                filesystem::open(ctx, path_intro).map(|f| {
-                                    OggStreamReader::new(f).map_err(|e| GameError::ResourceLoadError(e.to_string()))
-                                }),
-                                filesystem::open(ctx, path_loop).map(|f| {
-                                    OggStreamReader::new(f).map_err(|e| GameError::ResourceLoadError(e.to_string()))
-                                }),
-                            ) {
-                                (Ok(Ok(song_intro)), Ok(Ok(song_loop))) => {
-                                    log::info!(
-                                        "Playing multi part Ogg BGM: {} {} + {}",
-                                        song_id,
-                                        path_intro,
-                                        path_loop
-                                    );
-
-                                    self.prev_song_id = self.current_song_id;
-                                    self.current_song_id = song_id;
-                                    self.send(PlaybackMessage::SaveState).unwrap();
-                                    self.send(PlaybackMessage::PlayOggSongMultiPart(
-                                        Box::new(song_intro),
-                                        Box::new(song_loop),
-                                    ))
+                    }
+                    #[cfg(feature = "ogg-playback")]
+                    SongFormat::OggSinglePart(loop_points) => {
+                        // we're sure that there's one element
+                        let path = unsafe { paths.get_unchecked(0) };
+
+                        match filesystem::open(ctx, path)
+                            .map(|f| OggStreamReader::new(f).map_err(|e| GameError::ResourceLoadError(e.to_string())))
+                        {
+                            Ok(Ok(song)) => {
+                                log::info!("Playing single part Ogg BGM: {} {}", song_id, path);
+
+                                self.prev_song_id = self.current_song_id;
+                                self.current_song_id = song_id;
+                                self.send(PlaybackMessage::SaveState).unwrap();
+                                self.send(PlaybackMessage::PlayOggSongSinglePart(Box::new(song), *loop_points))
                                     .unwrap();
 
-                                    return Ok(());
-                                }
-                                (Ok(Err(err)), _) | (Err(err), _) | (_, Ok(Err(err))) | (_, Err(err)) => {
-                                    log::warn!("Failed to load multi part Ogg BGM {}: {}", song_id, err);
-                                }
+                                return Ok(());
+                            }
+                            Ok(Err(err)) | Err(err) => {
+                                log::warn!("Failed to load single part Ogg BGM {}: {}", song_id, err);
+                            }
+                        }
+                    }
+                    #[cfg(feature = "ogg-playback")]
+                    SongFormat::OggMultiPart(loop_points) => {
+                        // we're sure that there are two elements
+                        let path_intro = unsafe { paths.get_unchecked(0) };
+                        let path_loop = unsafe { paths.get_unchecked(1) };
+
+                        match (
+                            filesystem::open(ctx, path_intro).map(|f| {
+                                OggStreamReader::new(f).map_err(|e| GameError::ResourceLoadError(e.to_string()))
+                            }),
+                            filesystem::open(ctx, path_loop).map(|f| {
+                                OggStreamReader::new(f).map_err(|e| GameError::ResourceLoadError(e.to_string()))
+                            }),
+                        ) {
+                            (Ok(Ok(song_intro)), Ok(Ok(song_loop))) => {
+                                log::info!("Playing multi part Ogg BGM: {} {} + {}", song_id, path_intro, path_loop);
+
+                                self.prev_song_id = self.current_song_id;
+                                self.current_song_id = song_id;
+                                self.send(PlaybackMessage::SaveState).unwrap();
+                                self.send(PlaybackMessage::PlayOggSongMultiPart(
+                                    Box::new(song_intro),
+                                    Box::new(song_loop),
+                                    *loop_points,
+                                ))
+                                .unwrap();
+
+                                return Ok(());
+                            }
+                            (Ok(Err(err)), _) | (Err(err), _) | (_, Ok(Err(err))) | (_, Err(err)) => {
+                                log::warn!("Failed to load multi part Ogg BGM {}: {}", song_id, err);
                             }
                         }
                     }
                 }
             }
+
+            if !organya_fallback {
+                log::info!("Soundtrack {} has no BGM {}, stopping BGM", settings.soundtrack, song_id);
+
+                self.prev_song_id = self.current_song_id;
+                self.current_song_id = song_id;
+                self.send(PlaybackMessage::SaveState).unwrap();
+                self.send(PlaybackMessage::Stop).unwrap();
+            }
         }
 
         Ok(())
@@ -445,64 +669,58 @@ impl SoundManager {
         self.current_song_id
     }
 
-    pub fn set_sample_params_from_file<R: io::Read>(&mut self, id: u8, data: R) -> GameResult {
-        if self.no_audio {
-            return Ok(());
-        }
+    /// Returns the playback position of the current song as of the last mixed audio buffer, so it can lag
+    /// behind slightly. Without audio output the song is reported to be at its start.
+    pub fn song_position(&self) -> SongPosition {
+        let mut song_position = SongPosition { song_id: self.current_song_id, ..Default::default() };
 
-        let mut reader = BufReader::new(data).lines();
-        let mut params = PixToneParameters::empty();
-
-        fn next_string<T: FromStr, R: io::Read>(reader: &mut Lines<BufReader<R>>) -> GameResult<T> {
-            while let Some(Ok(str)) = reader.next() {
-                let str = str.trim();
-                if str.is_empty() || str.starts_with('#') {
-                    continue;
-                }
+        if let Ok(shared) = self.song_position.lock() {
+            if let Some((intro, position)) = *shared {
+                song_position.intro = intro;
+                song_position.position = position;
+            }
+        }
 
-                let mut splits = str.split(':');
+        song_position
+    }
 
-                let _ = splits.next();
-                if let Some(str) = splits.next() {
-                    return str.trim().parse::<T>().map_err(|_| {
-                        GameError::ParseError("failed to parse the value as specified type.".to_string())
-                    });
-                } else {
-                    break;
-                }
-            }
+    /// Plays the song from given position, switching songs if necessary.
+    pub fn set_song_position(
+        &mut self,
+        song_position: &SongPosition,
+        constants: &EngineConstants,
+        settings: &Settings,
+        ctx: &mut Context,
+    ) -> GameResult {
+        self.play_song(song_position.song_id, constants, settings, ctx, false)?;
 
-            Err(GameError::ParseError("unexpected end.".to_string()))
+        if self.no_audio || song_position.song_id == 0 {
+            return Ok(());
         }
 
-        for channel in &mut params.channels {
-            channel.enabled = next_string::<u8, R>(&mut reader)? != 0;
-            channel.length = next_string::<u32, R>(&mut reader)?;
+        self.send(PlaybackMessage::SetSongPosition(song_position.intro, song_position.position))
+    }
 
-            channel.carrier.waveform_type = next_string::<u8, R>(&mut reader)?;
-            channel.carrier.pitch = next_string::<f32, R>(&mut reader)?;
-            channel.carrier.level = next_string::<i32, R>(&mut reader)?;
-            channel.carrier.offset = next_string::<i32, R>(&mut reader)?;
+    /// Replaces the built-in sound effects with the `.pxt` files found in given data roots.
+    pub fn load_pixtone_overrides(&mut self, ctx: &Context, roots: &[String]) -> GameResult {
+        if self.no_audio {
+            return Ok(());
+        }
 
-            channel.frequency.waveform_type = next_string::<u8, R>(&mut reader)?;
-            channel.frequency.pitch = next_string::<f32, R>(&mut reader)?;
-            channel.frequency.level = next_string::<i32, R>(&mut reader)?;
-            channel.frequency.offset = next_string::<i32, R>(&mut reader)?;
+        for (id, params) in PixTonePlayback::load_overrides(ctx, roots)? {
+            self.set_sample_params(id, params)?;
+        }
 
-            channel.amplitude.waveform_type = next_string::<u8, R>(&mut reader)?;
-            channel.amplitude.pitch = next_string::<f32, R>(&mut reader)?;
-            channel.amplitude.level = next_string::<i32, R>(&mut reader)?;
-            channel.amplitude.offset = next_string::<i32, R>(&mut reader)?;
+        Ok(())
+    }
 
-            channel.envelope.initial = next_string::<i32, R>(&mut reader)?;
-            channel.envelope.time_a = next_string::<i32, R>(&mut reader)?;
-            channel.envelope.value_a = next_string::<i32, R>(&mut reader)?;
-            channel.envelope.time_b = next_string::<i32, R>(&mut reader)?;
-            channel.envelope.value_b = next_string::<i32, R>(&mut reader)?;
-            channel.envelope.time_c = next_string::<i32, R>(&mut reader)?;
-            channel.envelope.value_c = next_string::<i32, R>(&mut reader)?;
+    pub fn set_sample_params_from_file<R: io::Read>(&mut self, id: u8, data: R) -> GameResult {
+        if self.no_audio {
+            return Ok(());
         }
 
+        let params = PixToneParameters::load_from(data)?;
+
         self.set_sample_params(id, params)
     }
 
@@ -554,11 +772,13 @@ impl SoundManager {
 pub(in crate::sound) enum PlaybackMessage {
     Stop,
     PlayOrganyaSong(Box<Song>),
+    PlayPxToneSong(Box<pxtone::Song>),
     #[cfg(feature = "ogg-playback")]
-    PlayOggSongSinglePart(Box<OggStreamReader<File>>),
+    PlayOggSongSinglePart(Box<OggStreamReader<File>>, LoopPoints),
     #[cfg(feature = "ogg-playback")]
-    PlayOggSongMultiPart(Box<OggStreamReader<File>>, Box<OggStreamReader<File>>),
+    PlayOggSongMultiPart(Box<OggStreamReader<File>>, Box<OggStreamReader<File>>, LoopPoints),
     PlaySample(u8),
+    PlaySampleAt(u8, SfxPosition),
     LoopSample(u8),
     LoopSampleFreq(u8, f32),
     StopSample(u8),
@@ -571,12 +791,14 @@ pub(in crate::sound) enum PlaybackMessage {
     SetSampleParams(u8, PixToneParameters),
     SetOrgInterpolation(InterpolationMode),
     SetSampleData(u8, Vec<i16>),
+    SetSongPosition(bool, u64),
 }
 
 #[derive(PartialEq, Eq)]
 enum PlaybackState {
     Stopped,
     PlayingOrg,
+    PlayingPxTone,
     #[cfg(feature = "ogg-playback")]
     PlayingOgg,
 }
@@ -584,6 +806,7 @@ enum PlaybackState {
 enum PlaybackStateType {
     None,
     Organya(SavedOrganyaPlaybackState),
+    PxTone(SavedPxTonePlaybackState),
     #[cfg(feature = "ogg-playback")]
     Ogg(SavedOggPlaybackState),
 }
@@ -594,316 +817,458 @@ impl Default for PlaybackStateType {
     }
 }
 
-fn run<T>(
-    rx: Receiver<PlaybackMessage>,
+/// Playback state of the audio thread, mixes the background music and sound effects into the output buffer.
+struct Mixer {
     bank: SoundBank,
-    device: cpal::Device,
-    config: cpal::StreamConfig,
-) -> GameResult<cpal::Stream>
-where
-    T: cpal::SizedSample + cpal::FromSample<u16>,
-{
-    let sample_rate = config.sample_rate.0 as f32;
-    let channels = config.channels as usize;
-    let mut state = PlaybackState::Stopped;
-    let mut saved_state: PlaybackStateType = PlaybackStateType::None;
-    let mut speed = 1.0;
-    let mut org_engine = Box::new(OrgPlaybackEngine::new());
+    sample_rate: f32,
+    state: PlaybackState,
+    saved_state: PlaybackStateType,
+    speed: f32,
+    org_engine: Box<OrgPlaybackEngine>,
+    pxtone_engine: Box<PxTonePlaybackEngine>,
     #[cfg(feature = "ogg-playback")]
-    let mut ogg_engine = Box::new(OggPlaybackEngine::new());
-    let mut pixtone = Box::new(PixTonePlayback::new());
-    pixtone.create_samples();
+    ogg_engine: Box<OggPlaybackEngine>,
+    pixtone: Box<PixTonePlayback>,
+    bgm_buf: Vec<u16>,
+    pxt_buf: Vec<u16>,
+    bgm_index: usize,
+    pxt_index: usize,
+    samples: usize,
+    bgm_vol: f32,
+    bgm_vol_saved: f32,
+    sfx_vol: f32,
+    bgm_fadeout: bool,
+    song_position: SharedSongPosition,
+}
 
-    log::info!("Audio format: {} {}", sample_rate, channels);
-    org_engine.set_sample_rate(sample_rate as usize);
-    #[cfg(feature = "ogg-playback")]
-    {
-        org_engine.loops = usize::MAX;
-        ogg_engine.set_sample_rate(sample_rate as usize);
-    }
-
-    let buf_size = sample_rate as usize * 10 / 1000;
-    let mut bgm_buf = vec![0x8080; buf_size * 2];
-    let mut pxt_buf = vec![0x8000; buf_size];
-    let mut bgm_index = 0;
-    let mut pxt_index = 0;
-    let mut samples = 0;
-    let mut bgm_vol = 1.0_f32;
-    let mut bgm_vol_saved = 1.0_f32;
-    let mut sfx_vol = 1.0_f32;
-    let mut bgm_fadeout = false;
-    let mut last_fadeout = Instant::now();
-    pixtone.mix(&mut pxt_buf, sample_rate);
+impl Mixer {
+    fn new(bank: SoundBank, sample_rate: f32, song_position: SharedSongPosition) -> Mixer {
+        let mut org_engine = Box::new(OrgPlaybackEngine::new());
+        let mut pxtone_engine = Box::new(PxTonePlaybackEngine::new());
+        #[cfg(feature = "ogg-playback")]
+        let mut ogg_engine = Box::new(OggPlaybackEngine::new());
+        let mut pixtone = Box::new(PixTonePlayback::new());
+        pixtone.create_samples();
+
+        org_engine.set_sample_rate(sample_rate as usize);
+        pxtone_engine.set_sample_rate(sample_rate as usize);
+        #[cfg(feature = "ogg-playback")]
+        {
+            org_engine.loops = usize::MAX;
+            ogg_engine.set_sample_rate(sample_rate as usize);
+        }
 
-    let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
+        let buf_size = sample_rate as usize * 10 / 1000;
+        let mut pxt_buf = vec![0x8000; buf_size * 2];
+        pixtone.mix(&mut pxt_buf, sample_rate);
+
+        Mixer {
+            bank,
+            sample_rate,
+            state: PlaybackState::Stopped,
+            saved_state: PlaybackStateType::None,
+            speed: 1.0,
+            org_engine,
+            pxtone_engine,
+            #[cfg(feature = "ogg-playback")]
+            ogg_engine,
+            pixtone,
+            bgm_buf: vec![0x8080; buf_size * 2],
+            pxt_buf,
+            bgm_index: 0,
+            pxt_index: 0,
+            samples: 0,
+            bgm_vol: 1.0,
+            bgm_vol_saved: 1.0,
+            sfx_vol: 1.0,
+            bgm_fadeout: false,
+            song_position,
+        }
+    }
 
-    let stream_result = device.build_output_stream(
-        &config,
-        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
-            loop {
-                if bgm_fadeout && bgm_vol > 0.0 {
-                    // Since this callback can be called at different time intervals,
-                    // we try to decrease the volume corresponding to the time elapsed since the last invocation.
-                    let elapsed = last_fadeout.elapsed().as_secs_f32();
-                    last_fadeout = Instant::now();
-
-                    let step = bgm_vol_saved / FADEOUT_DURATION * elapsed;
-                    bgm_vol -= step;
-
-                    if bgm_vol < 0.0 {
-                        bgm_vol = 0.0;
-                    }
+    /// Publishes the position of the current song for `SoundManager::song_position`. Skipped while the game
+    /// is reading it, so the audio thread never waits.
+    fn publish_song_position(&self) {
+        let position = match self.state {
+            PlaybackState::Stopped => None,
+            PlaybackState::PlayingOrg => Some((false, self.org_engine.get_position().max(0) as u64)),
+            PlaybackState::PlayingPxTone => Some((false, self.pxtone_engine.get_position())),
+            #[cfg(feature = "ogg-playback")]
+            PlaybackState::PlayingOgg => Some(self.ogg_engine.get_position()),
+        };
+
+        if let Ok(mut shared) = self.song_position.try_lock() {
+            *shared = position;
+        }
+    }
+
+    /// Lowers the background music volume while fading out, `frames` is the number of frames about to be rendered.
+    fn update_fadeout(&mut self, frames: usize) {
+        if self.bgm_fadeout && self.bgm_vol > 0.0 {
+            // The output callback can be called at different time intervals, so the volume is decreased
+            // according to the length of the rendered audio instead.
+            let elapsed = frames as f32 / self.sample_rate;
+
+            let step = self.bgm_vol_saved / FADEOUT_DURATION * elapsed;
+            self.bgm_vol -= step;
+
+            if self.bgm_vol < 0.0 {
+                self.bgm_vol = 0.0;
+            }
+        }
+    }
+
+    fn clear_bgm_buf(&mut self) {
+        for i in &mut self.bgm_buf[0..self.samples] {
+            *i = 0x8000
+        }
+    }
+
+    fn handle_message(&mut self, message: PlaybackMessage) {
+        match message {
+            PlaybackMessage::PlayOrganyaSong(song) => {
+                if self.state == PlaybackState::Stopped {
+                    self.saved_state = PlaybackStateType::None;
                 }
 
-                match rx.try_recv() {
-                    Ok(PlaybackMessage::PlayOrganyaSong(song)) => {
-                        if state == PlaybackState::Stopped {
-                            saved_state = PlaybackStateType::None;
-                        }
+                if self.bgm_fadeout {
+                    self.bgm_fadeout = false;
+                    self.bgm_vol = self.bgm_vol_saved;
+                }
 
-                        if bgm_fadeout {
-                            bgm_fadeout = false;
-                            bgm_vol = bgm_vol_saved;
-                        }
+                self.org_engine.start_song(*song, &self.bank);
 
-                        org_engine.start_song(*song, &bank);
+                self.clear_bgm_buf();
+                self.samples = self.org_engine.render_to(&mut self.bgm_buf);
+                self.bgm_index = 0;
 
-                        for i in &mut bgm_buf[0..samples] {
-                            *i = 0x8000
-                        }
-                        samples = org_engine.render_to(&mut bgm_buf);
-                        bgm_index = 0;
+                self.state = PlaybackState::PlayingOrg;
+            }
+            PlaybackMessage::PlayPxToneSong(song) => {
+                if self.state == PlaybackState::Stopped {
+                    self.saved_state = PlaybackStateType::None;
+                }
 
-                        state = PlaybackState::PlayingOrg;
-                    }
+                if self.bgm_fadeout {
+                    self.bgm_fadeout = false;
+                    self.bgm_vol = self.bgm_vol_saved;
+                }
+
+                self.pxtone_engine.start_song(*song);
+
+                self.clear_bgm_buf();
+                self.samples = self.pxtone_engine.render_to(&mut self.bgm_buf);
+                self.bgm_index = 0;
+
+                self.state = PlaybackState::PlayingPxTone;
+            }
+            #[cfg(feature = "ogg-playback")]
+            PlaybackMessage::PlayOggSongSinglePart(data, loop_points) => {
+                if self.state == PlaybackState::Stopped {
+                    self.saved_state = PlaybackStateType::None;
+                }
+
+                if self.bgm_fadeout {
+                    self.bgm_fadeout = false;
+                    self.bgm_vol = self.bgm_vol_saved;
+                }
+
+                self.ogg_engine.start_single(data, loop_points);
+
+                self.clear_bgm_buf();
+                self.samples = self.ogg_engine.render_to(&mut self.bgm_buf);
+                self.bgm_index = 0;
+
+                self.state = PlaybackState::PlayingOgg;
+            }
+            #[cfg(feature = "ogg-playback")]
+            PlaybackMessage::PlayOggSongMultiPart(data_intro, data_loop, loop_points) => {
+                if self.state == PlaybackState::Stopped {
+                    self.saved_state = PlaybackStateType::None;
+                }
+
+                if self.bgm_fadeout {
+                    self.bgm_fadeout = false;
+                    self.bgm_vol = self.bgm_vol_saved;
+                }
+
+                self.ogg_engine.start_multi(data_intro, data_loop, loop_points);
+
+                self.clear_bgm_buf();
+                self.samples = self.ogg_engine.render_to(&mut self.bgm_buf);
+                self.bgm_index = 0;
+
+                self.state = PlaybackState::PlayingOgg;
+            }
+            PlaybackMessage::PlaySample(id) => {
+                self.pixtone.play_sfx(id);
+            }
+            PlaybackMessage::PlaySampleAt(id, position) => {
+                self.pixtone.play_sfx_at(id, position);
+            }
+            PlaybackMessage::LoopSample(id) => {
+                self.pixtone.loop_sfx(id);
+            }
+            PlaybackMessage::LoopSampleFreq(id, freq) => {
+                self.pixtone.loop_sfx_freq(id, freq);
+            }
+            PlaybackMessage::StopSample(id) => {
+                self.pixtone.stop_sfx(id);
+            }
+            PlaybackMessage::Stop => {
+                if self.state == PlaybackState::Stopped {
+                    self.saved_state = PlaybackStateType::None;
+                }
+
+                self.state = PlaybackState::Stopped;
+            }
+            PlaybackMessage::SetSpeed(new_speed) => {
+                assert!(new_speed > 0.0);
+                self.speed = new_speed;
+                #[cfg(feature = "ogg-playback")]
+                self.ogg_engine.set_sample_rate((self.sample_rate / new_speed) as usize);
+                self.org_engine.set_sample_rate((self.sample_rate / new_speed) as usize);
+                self.pxtone_engine.set_sample_rate((self.sample_rate / new_speed) as usize);
+            }
+            PlaybackMessage::SetSongVolume(new_volume) => {
+                assert!(self.bgm_vol >= 0.0);
+                if self.bgm_fadeout {
+                    self.bgm_vol_saved = new_volume;
+                } else {
+                    self.bgm_vol = new_volume;
+                }
+            }
+            PlaybackMessage::SetSampleVolume(new_volume) => {
+                assert!(self.sfx_vol >= 0.0);
+                self.sfx_vol = new_volume;
+            }
+            PlaybackMessage::FadeoutSong => {
+                self.bgm_fadeout = true;
+                self.bgm_vol_saved = self.bgm_vol;
+            }
+            PlaybackMessage::SaveState => {
+                self.saved_state = match self.state {
+                    PlaybackState::Stopped => PlaybackStateType::None,
+                    PlaybackState::PlayingOrg => PlaybackStateType::Organya(self.org_engine.get_state()),
+                    PlaybackState::PlayingPxTone => PlaybackStateType::PxTone(self.pxtone_engine.get_state()),
                     #[cfg(feature = "ogg-playback")]
-                    Ok(PlaybackMessage::PlayOggSongSinglePart(data)) => {
-                        if state == PlaybackState::Stopped {
-                            saved_state = PlaybackStateType::None;
-                        }
+                    PlaybackState::PlayingOgg => PlaybackStateType::Ogg(self.ogg_engine.get_state()),
+                };
+            }
+            PlaybackMessage::RestoreState => {
+                let saved_state_loc = std::mem::take(&mut self.saved_state);
 
-                        if bgm_fadeout {
-                            bgm_fadeout = false;
-                            bgm_vol = bgm_vol_saved;
+                match saved_state_loc {
+                    PlaybackStateType::None => {
+                        self.state = PlaybackState::Stopped;
+                    }
+                    PlaybackStateType::Organya(playback_state) => {
+                        self.org_engine.set_state(playback_state, &self.bank);
+
+                        if self.state == PlaybackState::Stopped {
+                            self.org_engine.rewind();
                         }
 
-                        ogg_engine.start_single(data);
+                        self.clear_bgm_buf();
+                        self.samples = self.org_engine.render_to(&mut self.bgm_buf);
+                        self.bgm_index = 0;
 
-                        for i in &mut bgm_buf[0..samples] {
-                            *i = 0x8000
+                        if self.bgm_fadeout {
+                            self.bgm_fadeout = false;
+                            self.bgm_vol = self.bgm_vol_saved;
                         }
-                        samples = ogg_engine.render_to(&mut bgm_buf);
-                        bgm_index = 0;
 
-                        state = PlaybackState::PlayingOgg;
+                        self.state = PlaybackState::PlayingOrg;
                     }
-                    #[cfg(feature = "ogg-playback")]
-                    Ok(PlaybackMessage::PlayOggSongMultiPart(data_intro, data_loop)) => {
-                        if state == PlaybackState::Stopped {
-                            saved_state = PlaybackStateType::None;
-                        }
+                    PlaybackStateType::PxTone(playback_state) => {
+                        self.pxtone_engine.set_state(playback_state);
 
-                        if bgm_fadeout {
-                            bgm_fadeout = false;
-                            bgm_vol = bgm_vol_saved;
+                        if self.state == PlaybackState::Stopped {
+                            self.pxtone_engine.rewind();
                         }
 
-                        ogg_engine.start_multi(data_intro, data_loop);
+                        self.clear_bgm_buf();
+                        self.samples = self.pxtone_engine.render_to(&mut self.bgm_buf);
+                        self.bgm_index = 0;
 
-                        for i in &mut bgm_buf[0..samples] {
-                            *i = 0x8000
+                        if self.bgm_fadeout {
+                            self.bgm_fadeout = false;
+                            self.bgm_vol = self.bgm_vol_saved;
                         }
-                        samples = ogg_engine.render_to(&mut bgm_buf);
-                        bgm_index = 0;
 
-                        state = PlaybackState::PlayingOgg;
-                    }
-                    Ok(PlaybackMessage::PlaySample(id)) => {
-                        pixtone.play_sfx(id);
+                        self.state = PlaybackState::PlayingPxTone;
                     }
+                    #[cfg(feature = "ogg-playback")]
+                    PlaybackStateType::Ogg(playback_state) => {
+                        self.ogg_engine.set_state(playback_state);
 
-                    Ok(PlaybackMessage::LoopSample(id)) => {
-                        pixtone.loop_sfx(id);
-                    }
-                    Ok(PlaybackMessage::LoopSampleFreq(id, freq)) => {
-                        pixtone.loop_sfx_freq(id, freq);
-                    }
-                    Ok(PlaybackMessage::StopSample(id)) => {
-                        pixtone.stop_sfx(id);
-                    }
-                    Ok(PlaybackMessage::Stop) => {
-                        if state == PlaybackState::Stopped {
-                            saved_state = PlaybackStateType::None;
+                        if self.state == PlaybackState::Stopped {
+                            self.ogg_engine.rewind();
                         }
 
-                        state = PlaybackState::Stopped;
-                    }
-                    Ok(PlaybackMessage::SetSpeed(new_speed)) => {
-                        assert!(new_speed > 0.0);
-                        speed = new_speed;
-                        #[cfg(feature = "ogg-playback")]
-                        ogg_engine.set_sample_rate((sample_rate / new_speed) as usize);
-                        org_engine.set_sample_rate((sample_rate / new_speed) as usize);
-                    }
-                    Ok(PlaybackMessage::SetSongVolume(new_volume)) => {
-                        assert!(bgm_vol >= 0.0);
-                        if bgm_fadeout {
-                            bgm_vol_saved = new_volume;
-                        } else {
-                            bgm_vol = new_volume;
+                        self.clear_bgm_buf();
+                        self.samples = self.ogg_engine.render_to(&mut self.bgm_buf);
+                        self.bgm_index = 0;
+
+                        if self.bgm_fadeout {
+                            self.bgm_fadeout = false;
+                            self.bgm_vol = self.bgm_vol_saved;
                         }
+
+                        self.state = PlaybackState::PlayingOgg;
                     }
-                    Ok(PlaybackMessage::SetSampleVolume(new_volume)) => {
-                        assert!(sfx_vol >= 0.0);
-                        sfx_vol = new_volume;
+                }
+            }
+            PlaybackMessage::SetSampleParams(id, params) => {
+                self.pixtone.set_sample_parameters(id, params);
+            }
+            PlaybackMessage::SetOrgInterpolation(interpolation) => {
+                self.org_engine.interpolation = interpolation;
+            }
+            PlaybackMessage::SetSampleData(id, data) => {
+                self.pixtone.set_sample_data(id, data);
+            }
+            PlaybackMessage::SetSongPosition(intro, position) => {
+                match self.state {
+                    PlaybackState::Stopped => return,
+                    PlaybackState::PlayingOrg => {
+                        self.org_engine.set_position(position.min(i32::MAX as u64) as i32);
+                        self.clear_bgm_buf();
+                        self.samples = self.org_engine.render_to(&mut self.bgm_buf);
                     }
-                    Ok(PlaybackMessage::FadeoutSong) => {
-                        bgm_fadeout = true;
-                        bgm_vol_saved = bgm_vol;
-                        last_fadeout = Instant::now();
+                    PlaybackState::PlayingPxTone => {
+                        self.pxtone_engine.set_position(position);
+                        self.clear_bgm_buf();
+                        self.samples = self.pxtone_engine.render_to(&mut self.bgm_buf);
                     }
-                    Ok(PlaybackMessage::SaveState) => {
-                        saved_state = match state {
-                            PlaybackState::Stopped => PlaybackStateType::None,
-                            PlaybackState::PlayingOrg => PlaybackStateType::Organya(org_engine.get_state()),
-                            #[cfg(feature = "ogg-playback")]
-                            PlaybackState::PlayingOgg => PlaybackStateType::Ogg(ogg_engine.get_state()),
-                        };
+                    #[cfg(feature = "ogg-playback")]
+                    PlaybackState::PlayingOgg => {
+                        self.ogg_engine.set_position(intro, position);
+                        self.clear_bgm_buf();
+                        self.samples = self.ogg_engine.render_to(&mut self.bgm_buf);
                     }
-                    Ok(PlaybackMessage::RestoreState) => {
-                        let saved_state_loc = std::mem::take(&mut saved_state);
-
-                        match saved_state_loc {
-                            PlaybackStateType::None => {
-                                state = PlaybackState::Stopped;
-                            }
-                            PlaybackStateType::Organya(playback_state) => {
-                                org_engine.set_state(playback_state, &bank);
+                }
 
-                                if state == PlaybackState::Stopped {
-                                    org_engine.rewind();
-                                }
+                #[cfg(not(feature = "ogg-playback"))]
+                let _ = intro;
 
-                                for i in &mut bgm_buf[0..samples] {
-                                    *i = 0x8000
-                                }
-                                samples = org_engine.render_to(&mut bgm_buf);
-                                bgm_index = 0;
+                self.bgm_index = 0;
+            }
+        }
+    }
 
-                                if bgm_fadeout {
-                                    bgm_fadeout = false;
-                                    bgm_vol = bgm_vol_saved;
-                                }
+    fn render<T>(&mut self, data: &mut [T], channels: usize)
+    where
+        T: cpal::SizedSample + cpal::FromSample<u16>,
+    {
+        for frame in data.chunks_mut(channels) {
+            let (bgm_sample_l, bgm_sample_r): (u16, u16) = {
+                if self.state == PlaybackState::Stopped {
+                    (0x8000, 0x8000)
+                } else if self.bgm_index < self.samples {
+                    let samples = (self.bgm_buf[self.bgm_index], self.bgm_buf[self.bgm_index + 1]);
+                    self.bgm_index += 2;
+                    samples
+                } else {
+                    self.clear_bgm_buf();
 
-                                state = PlaybackState::PlayingOrg;
-                            }
-                            #[cfg(feature = "ogg-playback")]
-                            PlaybackStateType::Ogg(playback_state) => {
-                                ogg_engine.set_state(playback_state);
-
-                                if state == PlaybackState::Stopped {
-                                    ogg_engine.rewind();
-                                }
-
-                                for i in &mut bgm_buf[0..samples] {
-                                    *i = 0x8000
-                                }
-                                samples = ogg_engine.render_to(&mut bgm_buf);
-                                bgm_index = 0;
-
-                                if bgm_fadeout {
-                                    bgm_fadeout = false;
-                                    bgm_vol = bgm_vol_saved;
-                                }
-
-                                state = PlaybackState::PlayingOgg;
-                            }
+                    match self.state {
+                        PlaybackState::PlayingOrg => {
+                            self.samples = self.org_engine.render_to(&mut self.bgm_buf);
                         }
+                        PlaybackState::PlayingPxTone => {
+                            self.samples = self.pxtone_engine.render_to(&mut self.bgm_buf);
+                        }
+                        #[cfg(feature = "ogg-playback")]
+                        PlaybackState::PlayingOgg => {
+                            self.samples = self.ogg_engine.render_to(&mut self.bgm_buf);
+                        }
+                        _ => unreachable!(),
                     }
-                    Ok(PlaybackMessage::SetSampleParams(id, params)) => {
-                        pixtone.set_sample_parameters(id, params);
-                    }
-                    Ok(PlaybackMessage::SetOrgInterpolation(interpolation)) => {
-                        org_engine.interpolation = interpolation;
-                    }
-                    Ok(PlaybackMessage::SetSampleData(id, data)) => {
-                        pixtone.set_sample_data(id, data);
-                    }
-                    Err(_) => {
-                        break;
-                    }
+                    self.bgm_index = 2;
+                    (self.bgm_buf[0], self.bgm_buf[1])
                 }
+            };
+
+            let (pxt_sample_l, pxt_sample_r) = (self.pxt_buf[self.pxt_index], self.pxt_buf[self.pxt_index + 1]);
+
+            if self.pxt_index < (self.pxt_buf.len() - 2) {
+                self.pxt_index += 2;
+            } else {
+                self.pxt_index = 0;
+                self.pxt_buf.fill(0x8000);
+                self.pixtone.mix(&mut self.pxt_buf, self.sample_rate / self.speed);
             }
 
-            for frame in data.chunks_mut(channels) {
-                let (bgm_sample_l, bgm_sample_r): (u16, u16) = {
-                    if state == PlaybackState::Stopped {
-                        (0x8000, 0x8000)
-                    } else if bgm_index < samples {
-                        let samples = (bgm_buf[bgm_index], bgm_buf[bgm_index + 1]);
-                        bgm_index += 2;
-                        samples
-                    } else {
-                        for i in &mut bgm_buf[0..samples] {
-                            *i = 0x8000
-                        }
+            let bgm_vol = self.bgm_vol;
+            let sfx_vol = self.sfx_vol;
+
+            if frame.len() >= 2 {
+                let sample_l = clamp(
+                    (((bgm_sample_l ^ 0x8000) as i16) as f32 * bgm_vol) as isize
+                        + (((pxt_sample_l ^ 0x8000) as i16) as f32 * sfx_vol) as isize,
+                    -0x7fff,
+                    0x7fff,
+                ) as u16
+                    ^ 0x8000;
+                let sample_r = clamp(
+                    (((bgm_sample_r ^ 0x8000) as i16) as f32 * bgm_vol) as isize
+                        + (((pxt_sample_r ^ 0x8000) as i16) as f32 * sfx_vol) as isize,
+                    -0x7fff,
+                    0x7fff,
+                ) as u16
+                    ^ 0x8000;
+
+                frame[0] = T::from_sample(sample_l);
+                frame[1] = T::from_sample(sample_r);
+            } else {
+                let sample = clamp(
+                    ((((bgm_sample_l ^ 0x8000) as i16) + ((bgm_sample_r ^ 0x8000) as i16)) as f32 * bgm_vol / 2.0)
+                        as isize
+                        + ((((pxt_sample_l ^ 0x8000) as i16) as f32 + ((pxt_sample_r ^ 0x8000) as i16) as f32)
+                            * sfx_vol
+                            / 2.0) as isize,
+                    -0x7fff,
+                    0x7fff,
+                ) as u16
+                    ^ 0x8000;
+
+                frame[0] = T::from_sample(sample);
+            }
+        }
 
-                        match state {
-                            PlaybackState::PlayingOrg => {
-                                samples = org_engine.render_to(&mut bgm_buf);
-                            }
-                            #[cfg(feature = "ogg-playback")]
-                            PlaybackState::PlayingOgg => {
-                                samples = ogg_engine.render_to(&mut bgm_buf);
-                            }
-                            _ => unreachable!(),
-                        }
-                        bgm_index = 2;
-                        (bgm_buf[0], bgm_buf[1])
-                    }
-                };
+        self.publish_song_position();
+    }
+}
+
+fn run<T>(
+    rx: Receiver<PlaybackMessage>,
+    bank: SoundBank,
+    device: cpal::Device,
+    config: cpal::StreamConfig,
+    song_position: SharedSongPosition,
+) -> GameResult<cpal::Stream>
+where
+    T: cpal::SizedSample + cpal::FromSample<u16>,
+{
+    let sample_rate = config.sample_rate.0 as f32;
+    let channels = config.channels as usize;
+    let mut mixer = Mixer::new(bank, sample_rate, song_position);
 
-                let pxt_sample: u16 = pxt_buf[pxt_index];
+    log::info!("Audio format: {} {}", sample_rate, channels);
 
-                if pxt_index < (pxt_buf.len() - 1) {
-                    pxt_index += 1;
-                } else {
-                    pxt_index = 0;
-                    pxt_buf.fill(0x8000);
-                    pixtone.mix(&mut pxt_buf, sample_rate / speed);
-                }
+    let err_fn = |err| eprintln!("an error occurred on stream: {}", err);
 
-                if frame.len() >= 2 {
-                    let sample_l = clamp(
-                        (((bgm_sample_l ^ 0x8000) as i16) as f32 * bgm_vol) as isize
-                            + (((pxt_sample ^ 0x8000) as i16) as f32 * sfx_vol) as isize,
-                        -0x7fff,
-                        0x7fff,
-                    ) as u16
-                        ^ 0x8000;
-                    let sample_r = clamp(
-                        (((bgm_sample_r ^ 0x8000) as i16) as f32 * bgm_vol) as isize
-                            + (((pxt_sample ^ 0x8000) as i16) as f32 * sfx_vol) as isize,
-                        -0x7fff,
-                        0x7fff,
-                    ) as u16
-                        ^ 0x8000;
-
-                    frame[0] = T::from_sample(sample_l);
-                    frame[1] = T::from_sample(sample_r);
-                } else {
-                    let sample = clamp(
-                        ((((bgm_sample_l ^ 0x8000) as i16) + ((bgm_sample_r ^ 0x8000) as i16)) as f32 * bgm_vol / 2.0)
-                            as isize
-                            + (((pxt_sample ^ 0x8000) as i16) as f32 * sfx_vol) as isize,
-                        -0x7fff,
-                        0x7fff,
-                    ) as u16
-                        ^ 0x8000;
-
-                    frame[0] = T::from_sample(sample);
-                }
+    let stream_result = device.build_output_stream(
+        &config,
+        move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+            mixer.update_fadeout(data.len() / channels);
+
+            while let Ok(message) = rx.try_recv() {
+                mixer.handle_message(message);
             }
+
+            mixer.render(data, channels);
         },
         err_fn,
         None,
@@ -918,3 +1283,27 @@ where
 
     Ok(stream)
 }
+
+#[test]
+fn test_sfx_position() {
+    let mut frame = Frame::new();
+    frame.x = 100 * 0x200;
+    frame.y = 50 * 0x200;
+    let view_size = (320.0, 240.0);
+
+    // the center of the screen, same as non-positional effects
+    let center = SfxPosition::from_world(260 * 0x200, 170 * 0x200, &frame, view_size);
+    assert_eq!(center, SfxPosition::CENTER);
+    assert_eq!(center.volumes(), (1.0, 1.0));
+
+    // the right edge of the screen is panned but not attenuated
+    let right = SfxPosition::from_world(420 * 0x200, 170 * 0x200, &frame, view_size);
+    assert_eq!(right.pan, MAX_SFX_PAN);
+    assert_eq!(right.gain, 1.0);
+    assert!(right.volumes().0 < right.volumes().1);
+
+    // far away effects are quiet, but still audible
+    let far = SfxPosition::from_world(-5000 * 0x200, 170 * 0x200, &frame, view_size);
+    assert_eq!(far.pan, -MAX_SFX_PAN);
+    assert_eq!(far.gain, MIN_SFX_GAIN);
+}
@@ -0,0 +1,253 @@
+//! Offline rendering of Organya songs and PixTone sound effects to WAV files, used by the `--render-org`
+//! and `--render-pixtone` launch options.
+
+use std::fs;
+use std::io;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::data::builtin_fs::BuiltinFS;
+use crate::framework::context::Context;
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::filesystem;
+use crate::game::init_headless;
+use crate::sound::org_playback::OrgPlaybackEngine;
+use crate::sound::organya::Song;
+use crate::sound::pixtone::PixTonePlayback;
+use crate::sound::wav::{WavFormat, WavSample};
+use crate::sound::wave_bank::SoundBank;
+use crate::sound::{InterpolationMode, FADEOUT_DURATION, WAVETABLE_PATH};
+
+/// Exit code returned when everything was rendered.
+pub const EXIT_OK: i32 = 0;
+/// Exit code returned when the input couldn't be loaded or the output couldn't be written.
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Sample rate of the PixTone synthesizer.
+const PIXTONE_SAMPLE_RATE: u64 = 22050;
+
+/// Longest song that will be rendered, in seconds.
+const MAX_SONG_DURATION: u64 = 60 * 60;
+
+#[derive(Copy, Clone)]
+pub struct RenderOptions {
+    pub sample_rate: u32,
+    /// Interpolation of Organya instruments, PixTone effects are always resampled the same way as in game.
+    pub interpolation: InterpolationMode,
+    /// Number of times the looped part of a song is repeated after the first playthrough.
+    pub loops: usize,
+    /// Length of the fade-out after the last loop in seconds, the song ends right at the loop point if zero.
+    pub fadeout: f32,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            sample_rate: 44100,
+            interpolation: InterpolationMode::Linear,
+            loops: 1,
+            fadeout: FADEOUT_DURATION,
+        }
+    }
+}
+
+pub struct OfflineRenderer {
+    soundbank: SoundBank,
+    pixtone: PixTonePlayback,
+    pub options: RenderOptions,
+}
+
+impl OfflineRenderer {
+    pub fn new(ctx: &mut Context, options: RenderOptions) -> GameResult<OfflineRenderer> {
+        let soundbank = SoundBank::load_from(filesystem::open(ctx, WAVETABLE_PATH)?)?;
+
+        Ok(OfflineRenderer::with_soundbank(soundbank, options))
+    }
+
+    fn with_soundbank(soundbank: SoundBank, options: RenderOptions) -> OfflineRenderer {
+        let mut pixtone = PixTonePlayback::new();
+        pixtone.create_samples();
+
+        OfflineRenderer { soundbank, pixtone, options }
+    }
+
+    /// Replaces the built-in sound effects with the `.pxt` files found in given data roots,
+    /// the same way the game does.
+    pub fn load_pixtone_overrides(&mut self, ctx: &mut Context, roots: &[String]) -> GameResult {
+        for (id, params) in PixTonePlayback::load_overrides(ctx, roots)? {
+            self.pixtone.set_sample_parameters(id, params);
+        }
+
+        Ok(())
+    }
+
+    /// Renders an Organya song to 16-bit stereo PCM.
+    pub fn render_organya<R: io::Read>(&self, data: R) -> GameResult<WavSample> {
+        let song = Song::load_from(data)?;
+        let loop_range = song.time.loop_range;
+        if loop_range.start < 0 || loop_range.end <= loop_range.start {
+            return Err(GameError::ResourceLoadError(format!(
+                "Invalid loop range {}..{}.",
+                loop_range.start, loop_range.end
+            )));
+        }
+
+        let sample_rate = self.options.sample_rate;
+        let mut engine = Box::new(OrgPlaybackEngine::new());
+        engine.interpolation = self.options.interpolation;
+        engine.set_sample_rate(sample_rate as usize);
+        engine.start_song(song, &self.soundbank);
+        engine.loops = self.options.loops;
+
+        let max_frames = MAX_SONG_DURATION * sample_rate as u64;
+        let song_frames = (engine.get_total_samples() as u64).min(max_frames) as usize;
+        let fadeout_frames = (self.options.fadeout.max(0.0) * sample_rate as f32) as usize;
+
+        // the fade-out continues into the next loop, so the engine must not stop at the loop point
+        engine.loops = usize::MAX;
+
+        let mut buf = vec![0x8000u16; (song_frames + fadeout_frames) * 2];
+        let mut position = 0;
+        while position < buf.len() {
+            let rendered = engine.render_to(&mut buf[position..]);
+            if rendered == 0 {
+                break;
+            }
+
+            position += rendered;
+        }
+
+        let mut data = Vec::with_capacity(buf.len() * 2);
+        for (i, frame) in buf.chunks_exact(2).enumerate() {
+            let volume = if i < song_frames { 1.0 } else { 1.0 - (i - song_frames) as f32 / fadeout_frames as f32 };
+
+            for &sample in frame {
+                let sample = ((sample ^ 0x8000) as i16 as f32 * volume) as i16;
+                data.extend_from_slice(&sample.to_le_bytes());
+            }
+        }
+
+        Ok(WavSample { format: WavFormat { channels: 2, sample_rate, bit_depth: 16 }, data: data.into() })
+    }
+
+    /// Renders a PixTone sound effect to 16-bit mono PCM, returns `None` if the effect is empty.
+    pub fn render_pixtone(&mut self, id: u8) -> Option<WavSample> {
+        let length = self.pixtone.samples.get(&id).map_or(0, |sample| sample.len()) as u64;
+        if length == 0 {
+            return None;
+        }
+
+        let sample_rate = self.options.sample_rate;
+        let frames = (length * sample_rate as u64).div_ceil(PIXTONE_SAMPLE_RATE);
+        let mut buf = vec![0x8000u16; frames as usize * 2];
+
+        self.pixtone.playback_state.clear();
+        self.pixtone.play_sfx(id);
+        self.pixtone.mix(&mut buf, sample_rate as f32);
+
+        // centered effects are mixed equally into both channels
+        let data: Vec<u8> =
+            buf.iter().step_by(2).flat_map(|&sample| ((sample ^ 0x8000) as i16).to_le_bytes()).collect();
+
+        Some(WavSample { format: WavFormat { channels: 1, sample_rate, bit_depth: 16 }, data: data.into() })
+    }
+
+    /// Renders every non-empty sound effect to `<dir>/<id>.wav`, returns the number of written files.
+    pub fn render_pixtone_table(&mut self, dir: &Path) -> GameResult<usize> {
+        fs::create_dir_all(dir)?;
+
+        let mut count = 0;
+        for id in 0..=0xffu8 {
+            if let Some(sample) = self.render_pixtone(id) {
+                sample.write_to(BufWriter::new(fs::File::create(dir.join(format!("{:03}.wav", id)))?))?;
+                count += 1;
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+/// Renders an Organya file to a WAV file and returns the process exit code.
+pub fn run_organya(input: &Path, output: &Path, options: RenderOptions) -> i32 {
+    match render_organya_file(input, output, options) {
+        Ok(sample) => {
+            log::info!("Rendered {:?} to {:?} ({}).", input, output, sample);
+            EXIT_OK
+        }
+        Err(err) => {
+            log::error!("Failed to render {:?}: {}", input, err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+/// Renders the sound effects of the active data root to a directory and returns the process exit code.
+pub fn run_pixtone(output: &Path, options: RenderOptions) -> i32 {
+    match render_pixtone_files(output, options) {
+        Ok(count) => {
+            log::info!("Rendered {} sound effects to {:?}.", count, output);
+            EXIT_OK
+        }
+        Err(err) => {
+            log::error!("Failed to render sound effects: {}", err);
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn render_organya_file(input: &Path, output: &Path, options: RenderOptions) -> GameResult<WavSample> {
+    // the song is read from a path outside of the game data, only the built-in wavetable is needed
+    let mut context = Context::new();
+    filesystem::mount_vfs(&mut context, Box::new(BuiltinFS::new()));
+
+    let renderer = OfflineRenderer::new(&mut context, options)?;
+    let sample = renderer.render_organya(io::BufReader::new(fs::File::open(input)?))?;
+    sample.write_to(BufWriter::new(fs::File::create(output)?))?;
+
+    Ok(sample)
+}
+
+fn render_pixtone_files(output: &Path, options: RenderOptions) -> GameResult<usize> {
+    let (mut context, state) = init_headless(false, false)?;
+    let ctx = &mut *context;
+
+    let mut renderer = OfflineRenderer::new(ctx, options)?;
+    renderer.load_pixtone_overrides(ctx, &state.constants.base_paths)?;
+    renderer.render_pixtone_table(output)
+}
+
+#[test]
+fn test_offline_render() {
+    let soundbank =
+        SoundBank::load_from(&include_bytes!("../data/builtin/organya-wavetable-doukutsu.bin")[..]).unwrap();
+    let options =
+        RenderOptions { sample_rate: 10000, interpolation: InterpolationMode::Linear, loops: 1, fadeout: 0.5 };
+    let mut renderer = OfflineRenderer::with_soundbank(soundbank, options);
+
+    // 100ms per tick, 4 ticks of intro and 4 looped ticks
+    let mut song = Vec::new();
+    song.extend_from_slice(b"Org-02");
+    song.extend_from_slice(&100u16.to_le_bytes());
+    song.extend_from_slice(&[4, 4]);
+    song.extend_from_slice(&4i32.to_le_bytes());
+    song.extend_from_slice(&8i32.to_le_bytes());
+    for _ in 0..16 {
+        song.extend_from_slice(&[0xe8, 0x03, 0, 0, 0, 0]);
+    }
+
+    // intro + 2 loops + fade-out
+    let sample = renderer.render_organya(&song[..]).unwrap();
+    assert_eq!(sample.format, WavFormat { channels: 2, sample_rate: 10000, bit_depth: 16 });
+    assert_eq!(sample.data.len(), (4 + 4 * 2) * 1000 * 4 + 5000 * 4);
+
+    let mut wav = Vec::new();
+    sample.write_to(&mut wav).unwrap();
+    let read_back = WavSample::read_from(&wav[..]).unwrap();
+    assert_eq!(read_back.format, sample.format);
+    assert_eq!(read_back.data, sample.data);
+
+    let effect = renderer.render_pixtone(1).unwrap();
+    assert_eq!(effect.format.channels, 1);
+    assert!(effect.data.iter().any(|&b| b != 0));
+}
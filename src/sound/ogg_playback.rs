@@ -3,24 +3,44 @@ use std::sync::{Arc, RwLock};
 use lewton::inside_ogg::OggStreamReader;
 use num_traits::clamp;
 
+use crate::engine_constants::SoundtrackSong;
 use crate::framework::filesystem::File;
 use crate::sound::stuff::cubic_interp;
 use crate::sound::wav::WavFormat;
 
+/// Looped range of the looped stream, in sample frames at the sample rate of the stream.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) struct LoopPoints {
+    pub start: u64,
+    /// The end of the stream if not set.
+    pub end: Option<u64>,
+}
+
+impl From<&SoundtrackSong> for LoopPoints {
+    fn from(song: &SoundtrackSong) -> Self {
+        LoopPoints { start: song.loop_start, end: song.loop_end }
+    }
+}
+
 pub(crate) struct OggPlaybackEngine {
     intro_music: Option<Arc<RwLock<Box<OggStreamReader<File>>>>>,
     loop_music: Option<Arc<RwLock<Box<OggStreamReader<File>>>>>,
     output_format: WavFormat,
+    loop_points: LoopPoints,
     playing_intro: bool,
     position: u64,
+    /// Set after seeking to the loop start, the frames decoded before it are discarded.
+    skip_until: Option<u64>,
     buffer: Vec<i16>,
 }
 
 pub struct SavedOggPlaybackState {
     intro_music: Option<Arc<RwLock<Box<OggStreamReader<File>>>>>,
     loop_music: Option<Arc<RwLock<Box<OggStreamReader<File>>>>>,
+    loop_points: LoopPoints,
     playing_intro: bool,
     position: u64,
+    skip_until: Option<u64>,
 }
 
 impl OggPlaybackEngine {
@@ -29,8 +49,10 @@ impl OggPlaybackEngine {
             intro_music: None,
             loop_music: None,
             output_format: WavFormat { channels: 2, sample_rate: 44100, bit_depth: 16 },
+            loop_points: LoopPoints::default(),
             playing_intro: false,
             position: 0,
+            skip_until: None,
             buffer: Vec::with_capacity(4096),
         }
     }
@@ -43,33 +65,48 @@ impl OggPlaybackEngine {
         SavedOggPlaybackState {
             intro_music: self.intro_music.clone(),
             loop_music: self.loop_music.clone(),
+            loop_points: self.loop_points,
             playing_intro: self.playing_intro,
             position: self.position,
+            skip_until: self.skip_until,
         }
     }
 
     pub fn set_state(&mut self, state: SavedOggPlaybackState) {
         self.intro_music = state.intro_music;
         self.loop_music = state.loop_music;
+        self.loop_points = state.loop_points;
         self.playing_intro = state.playing_intro;
         self.position = state.position;
+        self.skip_until = state.skip_until;
     }
 
-    pub fn start_single(&mut self, loop_music: Box<OggStreamReader<File>>) {
+    pub fn start_single(&mut self, loop_music: Box<OggStreamReader<File>>, loop_points: LoopPoints) {
         self.intro_music = None;
         self.loop_music = Some(Arc::new(RwLock::new(loop_music)));
+        self.loop_points = loop_points;
         self.playing_intro = false;
         self.position = 0;
+        self.skip_until = None;
     }
 
-    pub fn start_multi(&mut self, intro_music: Box<OggStreamReader<File>>, loop_music: Box<OggStreamReader<File>>) {
+    pub fn start_multi(
+        &mut self,
+        intro_music: Box<OggStreamReader<File>>,
+        loop_music: Box<OggStreamReader<File>>,
+        loop_points: LoopPoints,
+    ) {
         self.intro_music = Some(Arc::new(RwLock::new(intro_music)));
         self.loop_music = Some(Arc::new(RwLock::new(loop_music)));
+        self.loop_points = loop_points;
         self.playing_intro = true;
         self.position = 0;
+        self.skip_until = None;
     }
 
     pub fn rewind(&mut self) {
+        self.skip_until = None;
+
         if let Some(music) = &self.intro_music {
             let _ = music.write().unwrap().seek_absgp_pg(0);
             self.position = 0;
@@ -84,6 +121,34 @@ impl OggPlaybackEngine {
         }
     }
 
+    /// Returns whether the intro is playing and the position in the currently playing stream.
+    pub fn get_position(&self) -> (bool, u64) {
+        (self.playing_intro, self.position)
+    }
+
+    pub fn set_position(&mut self, intro: bool, position: u64) {
+        self.buffer.clear();
+
+        match &self.intro_music {
+            Some(music) if intro => {
+                let _ = music.write().unwrap().seek_absgp_pg(position);
+                self.playing_intro = true;
+                self.skip_until = None;
+            }
+            _ => {
+                if let Some(music) = &self.loop_music {
+                    if music.write().unwrap().seek_absgp_pg(position).is_ok() {
+                        self.skip_until = Some(position).filter(|&position| position > 0);
+                    }
+                }
+
+                self.playing_intro = false;
+            }
+        }
+
+        self.position = position;
+    }
+
     fn decode(&mut self) {
         if self.playing_intro {
             if let Some(music) = &self.intro_music {
@@ -93,6 +158,7 @@ impl OggPlaybackEngine {
                     Ok(Some(buf)) => buf,
                     Ok(None) => {
                         self.playing_intro = false;
+                        self.position = 0;
                         return;
                     }
                     Err(e) => {
@@ -110,11 +176,14 @@ impl OggPlaybackEngine {
             }
         } else if let Some(music) = &self.loop_music {
             let mut music = music.write().unwrap();
+            let channels = music.ident_hdr.audio_channels.max(1) as usize;
 
             let mut buf = match music.read_dec_packet_itl() {
                 Ok(Some(buf)) => buf,
                 Ok(None) => {
-                    if music.seek_absgp_pg(0).is_ok() {
+                    if music.seek_absgp_pg(self.loop_points.start).is_ok() {
+                        self.skip_until = Some(self.loop_points.start).filter(|&start| start > 0);
+                        self.position = self.loop_points.start;
                         return;
                     }
 
@@ -125,7 +194,46 @@ impl OggPlaybackEngine {
                 }
             };
 
-            self.position = music.get_last_absgp().unwrap_or(0);
+            // the position of a packet is only known at the end of a page, in between it's counted from the
+            // previous packets
+            let mut start = match music.get_last_absgp() {
+                Some(end) => end.saturating_sub((buf.len() / channels) as u64),
+                None => self.position,
+            };
+
+            if let Some(skip_until) = self.skip_until {
+                // the seek has page granularity, so packets are collected until it's known how many frames to drop
+                while music.get_last_absgp().is_none() {
+                    match music.read_dec_packet_itl() {
+                        Ok(Some(mut next)) => buf.append(&mut next),
+                        _ => break,
+                    }
+                }
+
+                let end = music.get_last_absgp().unwrap_or(0);
+                start = end.saturating_sub((buf.len() / channels) as u64);
+                let skipped = (skip_until.saturating_sub(start) as usize * channels).min(buf.len());
+                buf.drain(..skipped);
+                start += (skipped / channels) as u64;
+
+                if end >= skip_until {
+                    self.skip_until = None;
+                }
+            }
+
+            self.position = start + (buf.len() / channels) as u64;
+
+            if let Some(loop_end) = self.loop_points.end {
+                if self.position >= loop_end && self.skip_until.is_none() {
+                    buf.truncate(loop_end.saturating_sub(start) as usize * channels);
+
+                    if music.seek_absgp_pg(self.loop_points.start).is_ok() {
+                        self.skip_until = Some(self.loop_points.start);
+                        self.position = self.loop_points.start;
+                    }
+                }
+            }
+
             buf = self.resample_buffer(buf, music.ident_hdr.audio_sample_rate, music.ident_hdr.audio_channels);
             self.buffer.append(&mut buf);
         } else {
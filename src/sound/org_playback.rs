@@ -145,6 +145,10 @@ impl OrgPlaybackEngine {
         self.keys.fill(255);
     }
 
+    pub fn get_position(&self) -> i32 {
+        self.play_pos
+    }
+
     pub fn set_position(&mut self, position: i32) {
         self.play_pos = position;
     }
@@ -153,7 +157,6 @@ impl OrgPlaybackEngine {
         self.set_position(0);
     }
 
-    #[allow(unused)]
     pub fn get_total_samples(&self) -> u32 {
         let ticks_intro = self.song.time.loop_range.start;
         let ticks_loop = self.song.time.loop_range.end - self.song.time.loop_range.start;
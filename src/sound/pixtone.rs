@@ -1,10 +1,17 @@
 use std::collections::HashMap;
+use std::io;
+use std::io::{BufRead, BufReader, Lines};
+use std::str::FromStr;
 
 use lazy_static::lazy_static;
 use vec_mut_scan::VecMutScan;
 
+use crate::framework::context::Context;
+use crate::framework::error::{GameError, GameResult};
+use crate::framework::filesystem;
 use crate::sound::pixtone_sfx::DEFAULT_PIXTONE_TABLE;
 use crate::sound::stuff::cubic_interp;
+use crate::sound::SfxPosition;
 
 lazy_static! {
     static ref WAVEFORMS: [[i8; 0x100]; 6] = {
@@ -129,6 +136,64 @@ impl PixToneParameters {
         }
     }
 
+    /// Reads the parameters from a PixTone `.pxt` file.
+    pub fn load_from<R: io::Read>(data: R) -> GameResult<PixToneParameters> {
+        let mut reader = BufReader::new(data).lines();
+        let mut params = PixToneParameters::empty();
+
+        fn next_string<T: FromStr, R: io::Read>(reader: &mut Lines<BufReader<R>>) -> GameResult<T> {
+            while let Some(Ok(str)) = reader.next() {
+                let str = str.trim();
+                if str.is_empty() || str.starts_with('#') {
+                    continue;
+                }
+
+                let mut splits = str.split(':');
+
+                let _ = splits.next();
+                if let Some(str) = splits.next() {
+                    return str.trim().parse::<T>().map_err(|_| {
+                        GameError::ParseError("failed to parse the value as specified type.".to_string())
+                    });
+                } else {
+                    break;
+                }
+            }
+
+            Err(GameError::ParseError("unexpected end.".to_string()))
+        }
+
+        for channel in &mut params.channels {
+            channel.enabled = next_string::<u8, R>(&mut reader)? != 0;
+            channel.length = next_string::<u32, R>(&mut reader)?;
+
+            channel.carrier.waveform_type = next_string::<u8, R>(&mut reader)?;
+            channel.carrier.pitch = next_string::<f32, R>(&mut reader)?;
+            channel.carrier.level = next_string::<i32, R>(&mut reader)?;
+            channel.carrier.offset = next_string::<i32, R>(&mut reader)?;
+
+            channel.frequency.waveform_type = next_string::<u8, R>(&mut reader)?;
+            channel.frequency.pitch = next_string::<f32, R>(&mut reader)?;
+            channel.frequency.level = next_string::<i32, R>(&mut reader)?;
+            channel.frequency.offset = next_string::<i32, R>(&mut reader)?;
+
+            channel.amplitude.waveform_type = next_string::<u8, R>(&mut reader)?;
+            channel.amplitude.pitch = next_string::<f32, R>(&mut reader)?;
+            channel.amplitude.level = next_string::<i32, R>(&mut reader)?;
+            channel.amplitude.offset = next_string::<i32, R>(&mut reader)?;
+
+            channel.envelope.initial = next_string::<i32, R>(&mut reader)?;
+            channel.envelope.time_a = next_string::<i32, R>(&mut reader)?;
+            channel.envelope.value_a = next_string::<i32, R>(&mut reader)?;
+            channel.envelope.time_b = next_string::<i32, R>(&mut reader)?;
+            channel.envelope.value_b = next_string::<i32, R>(&mut reader)?;
+            channel.envelope.time_c = next_string::<i32, R>(&mut reader)?;
+            channel.envelope.value_c = next_string::<i32, R>(&mut reader)?;
+        }
+
+        Ok(params)
+    }
+
     pub fn synth(&self) -> Vec<i16> {
         let length = self.channels.iter().map(|c| c.length as usize).max().unwrap_or(0);
         if length == 0 {
@@ -183,6 +248,7 @@ pub struct PlaybackState {
     pos: f32,
     tag: u32,
     freq: f32,
+    position: SfxPosition,
 }
 
 pub struct PixTonePlayback {
@@ -209,6 +275,26 @@ impl PixTonePlayback {
         }
     }
 
+    /// Loads the `.pxt` files found in given data roots, which replace the built-in sound effects with the same ID.
+    pub fn load_overrides(ctx: &Context, roots: &[String]) -> GameResult<Vec<(u8, PixToneParameters)>> {
+        let mut overrides = Vec::new();
+
+        for i in 0..0xffu8 {
+            let path = format!("pxt/fx{:02x}.pxt", i);
+            if let Ok(file) = filesystem::open_find(ctx, roots, path) {
+                overrides.push((i, PixToneParameters::load_from(file)?));
+                continue;
+            }
+
+            let path = format!("PixTone/{:03}.pxt", i);
+            if let Ok(file) = filesystem::open_find(ctx, roots, path) {
+                overrides.push((i, PixToneParameters::load_from(file)?));
+            }
+        }
+
+        Ok(overrides)
+    }
+
     pub fn set_sample_parameters(&mut self, id: u8, params: PixToneParameters) {
         self.table[id as usize] = params;
         self.samples.insert(id, params.synth());
@@ -219,15 +305,20 @@ impl PixTonePlayback {
     }
 
     pub fn play_sfx(&mut self, id: u8) {
+        self.play_sfx_at(id, SfxPosition::CENTER);
+    }
+
+    pub fn play_sfx_at(&mut self, id: u8, position: SfxPosition) {
         for state in &mut self.playback_state {
             if state.id == id && state.tag == 0 {
                 state.pos = 0.0;
                 state.looping = false;
+                state.position = position;
                 return;
             }
         }
 
-        self.playback_state.push(PlaybackState { id, pos: 0.0, tag: 0, looping: false, freq: 1.0 });
+        self.playback_state.push(PlaybackState { id, pos: 0.0, tag: 0, looping: false, freq: 1.0, position });
     }
 
     pub fn loop_sfx(&mut self, id: u8) {
@@ -238,7 +329,14 @@ impl PixTonePlayback {
             }
         }
 
-        self.playback_state.push(PlaybackState { id, pos: 0.0, tag: 0, looping: true, freq: 1.0 });
+        self.playback_state.push(PlaybackState {
+            id,
+            pos: 0.0,
+            tag: 0,
+            looping: true,
+            freq: 1.0,
+            position: SfxPosition::CENTER,
+        });
     }
 
     pub fn loop_sfx_freq(&mut self, id: u8, freq: f32) {
@@ -249,7 +347,14 @@ impl PixTonePlayback {
             }
         }
 
-        self.playback_state.push(PlaybackState { id, pos: 0.0, tag: 0, looping: true, freq });
+        self.playback_state.push(PlaybackState {
+            id,
+            pos: 0.0,
+            tag: 0,
+            looping: true,
+            freq,
+            position: SfxPosition::CENTER,
+        });
     }
 
     pub fn stop_sfx(&mut self, id: u8) {
@@ -259,9 +364,17 @@ impl PixTonePlayback {
     }
 
     pub fn play_concurrent(&mut self, id: u8, tag: u32) {
-        self.playback_state.push(PlaybackState { id, pos: 0.0, tag, looping: false, freq: 1.0 });
+        self.playback_state.push(PlaybackState {
+            id,
+            pos: 0.0,
+            tag,
+            looping: false,
+            freq: 1.0,
+            position: SfxPosition::CENTER,
+        });
     }
 
+    /// Mixes the playing sound effects into interleaved stereo samples.
     pub fn mix(&mut self, dst: &mut [u16], sample_rate: f32) {
         let mut scan = VecMutScan::new(&mut self.playback_state);
         let delta = 22050.0 / sample_rate;
@@ -276,7 +389,9 @@ impl PixTonePlayback {
                     continue;
                 };
 
-                for result in dst.iter_mut() {
+                let (volume_l, volume_r) = state.position.volumes();
+
+                for result in dst.chunks_exact_mut(2) {
                     if state.pos >= sample.len() as f32 {
                         if state.looping {
                             state.pos = 0.0;
@@ -294,8 +409,10 @@ impl PixTonePlayback {
 
                     let s = cubic_interp(s1, s2, s4, s3, state.pos.fract()) * 32768.0;
                     // let s = sample[pos] as f32;
-                    let sam = (*result ^ 0x8000) as i16;
-                    *result = sam.saturating_add(s as i16) as u16 ^ 0x8000;
+                    for (result, volume) in result.iter_mut().zip([volume_l, volume_r]) {
+                        let sam = (*result ^ 0x8000) as i16;
+                        *result = sam.saturating_add((s * volume) as i16) as u16 ^ 0x8000;
+                    }
 
                     state.pos += delta * state.freq;
                 }
@@ -0,0 +1,1096 @@
+//! Loader of PxTone Collage projects (.ptcop) and tunes (.pttune), the music format of PxPack based games.
+//!
+//! Woice samples are built when the song is loaded, as 16-bit stereo at [BASIC_SAMPLE_RATE], the same way
+//! the PxTone library does it. Envelopes and effects depend on the output sample rate and are prepared
+//! by the playback engine.
+
+use std::f64::consts::PI;
+use std::io;
+use std::io::{Cursor, Read};
+
+use byteorder::{ReadBytesExt, LE};
+
+use crate::framework::error::{GameError, GameResult};
+
+/// Sample rate of the woice samples.
+pub const BASIC_SAMPLE_RATE: u32 = 44100;
+
+/// Number of effect groups the units are mixed into.
+pub const MAX_GROUPS: usize = 7;
+
+/// Key the woices are tuned to, notes played at this key are played at the original pitch of the woice.
+pub const BASIC_KEY: i32 = 0x4500;
+
+/// Initial key of the units.
+pub const DEFAULT_KEY: i32 = 0x6000;
+
+pub const VOICE_FLAG_WAVE_LOOP: u32 = 0x1;
+pub const VOICE_FLAG_SMOOTH: u32 = 0x2;
+pub const VOICE_FLAG_BEAT_FIT: u32 = 0x4;
+
+const CODE_TUNE_V5: &[u8; 16] = b"PTTUNE--20071119";
+const CODE_PROJECT_V5: &[u8; 16] = b"PTCOLLAGE-071119";
+
+/// Chunks larger than that are treated as a corrupted file.
+const MAX_CHUNK_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Number of sample frames of a synthesized waveform cycle.
+const PTV_WAVE_FRAMES: usize = 400;
+
+const PTV_DATA_FLAG_WAVE: u32 = 0x1;
+const PTV_DATA_FLAG_ENVELOPE: u32 = 0x2;
+const PTV_MAX_VERSION: u32 = 20060111;
+
+const PTN_MAX_VERSION: u32 = 20120418;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    /// Starts a note, the value is its length in clocks.
+    On,
+    Key,
+    PanVolume,
+    Velocity,
+    Volume,
+    /// Length of the slide between keys in clocks.
+    Portament,
+    VoiceNo,
+    GroupNo,
+    /// Bits of a float pitch multiplier.
+    Tuning,
+    PanTime,
+}
+
+impl EventKind {
+    fn from_u8(kind: u8) -> Option<EventKind> {
+        match kind {
+            1 => Some(EventKind::On),
+            2 => Some(EventKind::Key),
+            3 => Some(EventKind::PanVolume),
+            4 => Some(EventKind::Velocity),
+            5 => Some(EventKind::Volume),
+            6 => Some(EventKind::Portament),
+            12 => Some(EventKind::VoiceNo),
+            13 => Some(EventKind::GroupNo),
+            14 => Some(EventKind::Tuning),
+            15 => Some(EventKind::PanTime),
+            // beat and loop events are only used by the editor, the master chunk has the actual values
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Event {
+    pub clock: u32,
+    pub unit: u8,
+    pub kind: EventKind,
+    pub value: i32,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Master {
+    /// Clocks per beat.
+    pub beat_clock: u32,
+    /// Beats per measure.
+    pub beat_num: u32,
+    /// Beats per minute.
+    pub beat_tempo: f32,
+    pub repeat_clock: u32,
+    /// End of the song, the end of the last measure with events if zero.
+    pub last_clock: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Envelope {
+    /// Time units of the points per second.
+    pub fps: u32,
+    /// Attack and decay points, the volume stays at the last one while the note is held.
+    pub points: Vec<(u32, u8)>,
+    /// Length of the release after the note ends, in the time units of the points.
+    pub release: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Voice {
+    pub basic_key: i32,
+    pub tuning: f32,
+    pub flags: u32,
+    /// Interleaved 16-bit stereo samples at [BASIC_SAMPLE_RATE].
+    pub samples: Vec<i16>,
+    pub envelope: Option<Envelope>,
+}
+
+impl Voice {
+    pub fn frames(&self) -> usize {
+        self.samples.len() / 2
+    }
+}
+
+/// Instrument of the song, made of one or more voices played together.
+#[derive(Debug, Clone)]
+pub struct Woice {
+    pub voices: Vec<Voice>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DelayUnit {
+    Beat,
+    Measure,
+    Second,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Delay {
+    pub unit: DelayUnit,
+    pub group: usize,
+    /// Volume of the echo in percent.
+    pub rate: f32,
+    /// Number of echoes per unit.
+    pub freq: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct Overdrive {
+    pub group: usize,
+    /// Part of the amplitude that is cut off, in percent.
+    pub cut: f32,
+    pub amp: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Song {
+    pub master: Master,
+    pub unit_count: usize,
+    /// Events sorted by clock, events on the same clock are kept in the file order.
+    pub events: Vec<Event>,
+    pub woices: Vec<Woice>,
+    pub delays: Vec<Delay>,
+    pub overdrives: Vec<Overdrive>,
+}
+
+impl Song {
+    pub fn load_from<R: io::Read>(mut f: R) -> GameResult<Song> {
+        let mut code = [0u8; 16];
+        f.read_exact(&mut code)?;
+
+        if &code != CODE_TUNE_V5 && &code != CODE_PROJECT_V5 {
+            return Err(GameError::ResourceLoadError("Unsupported PxTone version.".to_owned()));
+        }
+
+        let _exe_version = f.read_u16::<LE>()?;
+        let _dummy = f.read_u16::<LE>()?;
+
+        let mut song = Song {
+            master: Master { beat_clock: 480, beat_num: 4, beat_tempo: 120.0, repeat_clock: 0, last_clock: 0 },
+            unit_count: 0,
+            events: Vec::new(),
+            woices: Vec::new(),
+            delays: Vec::new(),
+            overdrives: Vec::new(),
+        };
+
+        loop {
+            let mut tag = [0u8; 8];
+            f.read_exact(&mut tag)?;
+
+            if &tag == b"pxtoneND" {
+                break;
+            }
+
+            let size = f.read_u32::<LE>()?;
+            if size > MAX_CHUNK_SIZE {
+                return Err(GameError::ResourceLoadError(format!("PxTone chunk is too large: {} bytes.", size)));
+            }
+
+            let mut chunk = Cursor::new(read_bytes(&mut f, size)?);
+
+            match &tag {
+                b"antiOPER" => {
+                    return Err(GameError::ResourceLoadError("PxTone file is marked as invalid.".to_owned()));
+                }
+                b"MasterV5" => song.master = read_master(&mut chunk)?,
+                b"Event V5" => song.events = read_events(&mut chunk)?,
+                b"num UNIT" => song.unit_count = chunk.read_u16::<LE>()? as usize,
+                b"matePCM " => song.woices.push(read_pcm_woice(&mut chunk)?),
+                b"matePTV " => song.woices.push(read_ptv_woice(&mut chunk)?),
+                b"matePTN " => song.woices.push(read_ptn_woice(&mut chunk)?),
+                b"mateOGGV" => song.woices.push(read_ogg_woice(&mut chunk)?),
+                b"effeDELA" => song.delays.push(read_delay(&mut chunk)?),
+                b"effeOVER" => song.overdrives.push(read_overdrive(&mut chunk)?),
+                // names, comments and editor settings
+                _ => {}
+            }
+        }
+
+        song.events.sort_by_key(|event| event.clock);
+
+        Ok(song)
+    }
+
+    /// Number of clocks in a measure.
+    pub fn measure_clocks(&self) -> u32 {
+        (self.master.beat_num * self.master.beat_clock).max(1)
+    }
+
+    /// Returns the clocks the song loops from and to, rounded to whole measures.
+    pub fn loop_clocks(&self) -> (u32, u32) {
+        let measure = self.measure_clocks();
+        let last_event = self
+            .events
+            .iter()
+            .map(|event| match event.kind {
+                EventKind::On => event.clock.saturating_add(event.value.max(0) as u32),
+                _ => event.clock,
+            })
+            .max()
+            .unwrap_or(0);
+
+        let end_measure = if self.master.last_clock > 0 {
+            self.master.last_clock / measure
+        } else {
+            last_event.div_ceil(measure).max(1)
+        };
+        let repeat_measure = (self.master.repeat_clock / measure).min(end_measure.saturating_sub(1));
+
+        (repeat_measure * measure, end_measure * measure)
+    }
+}
+
+/// Reads `size` bytes without trusting the size for the allocation, fails if the data is shorter.
+fn read_bytes<R: io::Read>(f: &mut R, size: u32) -> GameResult<Vec<u8>> {
+    let mut data = Vec::new();
+    f.take(size as u64).read_to_end(&mut data)?;
+
+    if data.len() != size as usize {
+        return Err(GameError::ResourceLoadError(format!(
+            "PxTone data is truncated: expected {} bytes, got {}.",
+            size,
+            data.len()
+        )));
+    }
+
+    Ok(data)
+}
+
+/// Reads a little-endian base-128 integer, used by most of the PxTone structures.
+fn read_varint<R: io::Read>(f: &mut R) -> GameResult<u32> {
+    let mut value = 0u32;
+
+    for i in 0..5 {
+        let byte = f.read_u8()?;
+        value |= ((byte & 0x7f) as u32) << (i * 7);
+
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+
+    Ok(value)
+}
+
+fn read_master<R: io::Read>(f: &mut R) -> GameResult<Master> {
+    let beat_clock = f.read_i16::<LE>()?.max(1) as u32;
+    let beat_num = f.read_i8()?.max(1) as u32;
+    let beat_tempo = f.read_f32::<LE>()?;
+    let repeat_clock = f.read_i32::<LE>()?.max(0) as u32;
+    let last_clock = f.read_i32::<LE>()?.max(0) as u32;
+
+    if beat_tempo.is_nan() || beat_tempo <= 0.0 {
+        return Err(GameError::ResourceLoadError(format!("Invalid PxTone tempo: {}.", beat_tempo)));
+    }
+
+    Ok(Master { beat_clock, beat_num, beat_tempo, repeat_clock, last_clock })
+}
+
+fn read_events<R: io::Read>(f: &mut R) -> GameResult<Vec<Event>> {
+    let count = f.read_u32::<LE>()?;
+    let mut events = Vec::new();
+    let mut clock = 0u32;
+
+    for _ in 0..count {
+        clock = clock.wrapping_add(read_varint(f)?);
+        let unit = f.read_u8()?;
+        let kind = f.read_u8()?;
+        let value = read_varint(f)? as i32;
+
+        if let Some(kind) = EventKind::from_u8(kind) {
+            events.push(Event { clock, unit, kind, value });
+        }
+    }
+
+    Ok(events)
+}
+
+fn read_delay<R: io::Read>(f: &mut R) -> GameResult<Delay> {
+    let unit = match f.read_u16::<LE>()? {
+        0 => DelayUnit::Beat,
+        1 => DelayUnit::Measure,
+        _ => DelayUnit::Second,
+    };
+    let group = f.read_u16::<LE>()? as usize;
+    let rate = f.read_f32::<LE>()?;
+    let freq = f.read_f32::<LE>()?;
+
+    Ok(Delay { unit, group: group.min(MAX_GROUPS - 1), rate, freq })
+}
+
+fn read_overdrive<R: io::Read>(f: &mut R) -> GameResult<Overdrive> {
+    let _reserved = f.read_u16::<LE>()?;
+    let group = f.read_u16::<LE>()? as usize;
+    let cut = f.read_f32::<LE>()?;
+    let amp = f.read_f32::<LE>()?;
+
+    Ok(Overdrive { group: group.min(MAX_GROUPS - 1), cut, amp })
+}
+
+fn read_pcm_woice<R: io::Read>(f: &mut R) -> GameResult<Woice> {
+    let _unit = f.read_u16::<LE>()?;
+    let basic_key = f.read_u16::<LE>()? as i32;
+    let flags = f.read_u32::<LE>()?;
+    let channels = f.read_u16::<LE>()?;
+    let bits = f.read_u16::<LE>()?;
+    let sample_rate = f.read_u32::<LE>()?;
+    let tuning = f.read_f32::<LE>()?;
+    let size = f.read_u32::<LE>()?;
+
+    let data = read_bytes(f, size)?;
+
+    let samples = match (channels, bits) {
+        (1 | 2, 8) => data.iter().map(|&sample| ((sample as i16) - 0x80) << 8).collect(),
+        (1 | 2, 16) => data.chunks_exact(2).map(|sample| i16::from_le_bytes([sample[0], sample[1]])).collect(),
+        _ => {
+            return Err(GameError::ResourceLoadError(format!(
+                "Unsupported PxTone PCM format: {} channels, {} bits.",
+                channels, bits
+            )))
+        }
+    };
+
+    let samples = to_basic_format(samples, channels as usize, sample_rate);
+
+    Ok(Woice { voices: vec![Voice { basic_key, tuning, flags, samples, envelope: None }] })
+}
+
+#[cfg(feature = "ogg-playback")]
+fn decode_ogg(data: Vec<u8>) -> GameResult<Vec<i16>> {
+    use lewton::inside_ogg::OggStreamReader;
+
+    let error =
+        |e: lewton::VorbisError| GameError::ResourceLoadError(format!("Failed to decode PxTone Ogg woice: {}", e));
+
+    let mut stream = OggStreamReader::new(Cursor::new(data)).map_err(error)?;
+    let mut samples = Vec::new();
+    while let Some(mut packet) = stream.read_dec_packet_itl().map_err(error)? {
+        samples.append(&mut packet);
+    }
+
+    Ok(to_basic_format(samples, stream.ident_hdr.audio_channels as usize, stream.ident_hdr.audio_sample_rate))
+}
+
+#[cfg(not(feature = "ogg-playback"))]
+fn decode_ogg(_data: Vec<u8>) -> GameResult<Vec<i16>> {
+    log::warn!("Ogg playback is disabled, Ogg woices of PxTone songs will be silent.");
+    Ok(Vec::new())
+}
+
+fn read_ogg_woice<R: io::Read>(f: &mut R) -> GameResult<Woice> {
+    let _reserved = f.read_u16::<LE>()?;
+    let basic_key = f.read_u16::<LE>()? as i32;
+    let flags = f.read_u32::<LE>()?;
+    let tuning = f.read_f32::<LE>()?;
+
+    let _channels = f.read_i32::<LE>()?;
+    let _sample_rate = f.read_i32::<LE>()?;
+    let _frames = f.read_i32::<LE>()?;
+    let size = f.read_u32::<LE>()?;
+
+    let data = read_bytes(f, size)?;
+
+    let samples = decode_ogg(data)?;
+
+    Ok(Woice { voices: vec![Voice { basic_key, tuning, flags, samples, envelope: None }] })
+}
+
+/// Converts interleaved samples to stereo at [BASIC_SAMPLE_RATE], with the nearest neighbour resampling of PxTone.
+fn to_basic_format(samples: Vec<i16>, channels: usize, sample_rate: u32) -> Vec<i16> {
+    let channels = channels.max(1);
+    let frames = samples.len() / channels;
+    let stereo: Vec<i16> = if channels == 2 {
+        samples
+    } else {
+        samples.chunks_exact(channels).flat_map(|frame| [frame[0], frame[0]]).collect()
+    };
+
+    if sample_rate == BASIC_SAMPLE_RATE || sample_rate == 0 {
+        return stereo;
+    }
+
+    let new_frames = (frames as u64 * BASIC_SAMPLE_RATE as u64 / sample_rate as u64) as usize;
+    let mut resampled = Vec::with_capacity(new_frames * 2);
+    for i in 0..new_frames {
+        let index = (i as u64 * sample_rate as u64 / BASIC_SAMPLE_RATE as u64) as usize;
+        resampled.extend_from_slice(&stereo[index * 2..index * 2 + 2]);
+    }
+
+    resampled
+}
+
+fn read_ptv_woice<R: io::Read>(f: &mut R) -> GameResult<Woice> {
+    let _unit = f.read_u16::<LE>()?;
+    let _reserved = f.read_u16::<LE>()?;
+    let tuning = f.read_f32::<LE>()?;
+    let _size = f.read_i32::<LE>()?;
+
+    let mut voices = read_ptv(f)?;
+
+    // tuning of files from old versions of the editor
+    if tuning != 1.0 {
+        if let Some(voice) = voices.first_mut() {
+            voice.tuning = tuning;
+        }
+    }
+
+    Ok(Woice { voices })
+}
+
+enum PtvWave {
+    Coordinate { resolution: u32, points: Vec<(i32, i32)> },
+    Overtone { points: Vec<(i32, i32)> },
+}
+
+fn read_ptv<R: io::Read>(f: &mut R) -> GameResult<Vec<Voice>> {
+    let mut code = [0u8; 8];
+    f.read_exact(&mut code)?;
+    let version = f.read_u32::<LE>()?;
+
+    if &code != b"PTVOICE-" || version > PTV_MAX_VERSION {
+        return Err(GameError::ResourceLoadError("Unsupported PxTone voice format.".to_owned()));
+    }
+
+    let _total = f.read_u32::<LE>()?;
+    if read_varint(f)? != 0 || read_varint(f)? != 0 {
+        return Err(GameError::ResourceLoadError("Unsupported PxTone voice format.".to_owned()));
+    }
+
+    let count = read_varint(f)?;
+    let mut voices = Vec::new();
+
+    for _ in 0..count {
+        let basic_key = read_varint(f)? as i32;
+        let volume = read_varint(f)? as i32;
+        let pan = read_varint(f)? as i32;
+        let tuning = f32::from_bits(read_varint(f)?);
+        let flags = read_varint(f)?;
+        let data_flags = read_varint(f)?;
+
+        let mut wave = None;
+        if data_flags & PTV_DATA_FLAG_WAVE != 0 {
+            wave = Some(match read_varint(f)? {
+                0 => {
+                    let count = read_varint(f)?;
+                    let resolution = read_varint(f)?;
+                    let mut points = Vec::new();
+                    for _ in 0..count {
+                        let x = f.read_u8()? as i32;
+                        let y = f.read_i8()? as i32;
+                        points.push((x, y));
+                    }
+
+                    PtvWave::Coordinate { resolution, points }
+                }
+                1 => {
+                    let count = read_varint(f)?;
+                    let mut points = Vec::new();
+                    for _ in 0..count {
+                        let x = read_varint(f)? as i32;
+                        let y = read_varint(f)? as i32;
+                        points.push((x, y));
+                    }
+
+                    PtvWave::Overtone { points }
+                }
+                kind => {
+                    return Err(GameError::ResourceLoadError(format!("Unsupported PxTone voice type: {}.", kind)));
+                }
+            });
+        }
+
+        let mut envelope = None;
+        if data_flags & PTV_DATA_FLAG_ENVELOPE != 0 {
+            let fps = read_varint(f)?;
+            let head = read_varint(f)?;
+            let body = read_varint(f)?;
+            let tail = read_varint(f)?;
+
+            if body != 0 || tail != 1 {
+                return Err(GameError::ResourceLoadError("Unsupported PxTone envelope.".to_owned()));
+            }
+
+            let mut points = Vec::new();
+            for _ in 0..head {
+                let x = read_varint(f)?;
+                let y = read_varint(f)?.min(128) as u8;
+                points.push((x, y));
+            }
+
+            let release = read_varint(f)?;
+            let _release_volume = read_varint(f)?;
+
+            envelope = Some(Envelope { fps: fps.max(1), points, release });
+        }
+
+        let samples = match &wave {
+            Some(wave) => build_ptv_wave(wave, volume, pan),
+            None => vec![0; PTV_WAVE_FRAMES * 2],
+        };
+
+        voices.push(Voice { basic_key, tuning, flags, samples, envelope });
+    }
+
+    Ok(voices)
+}
+
+/// Returns a sample of an additive waveform, `points` are pairs of harmonic numbers and their amplitudes.
+fn overtone_sample(points: &[(i32, i32)], volume: i32, frames: usize, index: usize) -> f64 {
+    let sum: f64 = points
+        .iter()
+        .filter(|(x, _)| *x != 0)
+        .map(|&(x, y)| (2.0 * PI * x as f64 * index as f64 / frames as f64).sin() * y as f64 / x as f64 / 128.0)
+        .sum();
+
+    sum * volume as f64 / 128.0
+}
+
+/// Returns a sample of a waveform drawn as a line through `points`, which is closed back to the first point.
+fn coordinate_sample(points: &[(i32, i32)], resolution: i32, volume: i32, frames: usize, index: usize) -> f64 {
+    if points.is_empty() {
+        return 0.0;
+    }
+
+    let i = (resolution as i64 * index as i64 / frames as i64) as i32;
+    let c = points.iter().position(|&(x, _)| x > i).unwrap_or(points.len());
+
+    let ((x1, y1), (x2, y2)) = if c == points.len() {
+        (points[c - 1], (resolution, points[0].1))
+    } else if c > 0 {
+        (points[c - 1], points[c])
+    } else {
+        (points[0], points[0])
+    };
+
+    let offset = i - x1;
+    let value = if offset != 0 { y1 as f64 + (y2 - y1) as f64 * offset as f64 / (x2 - x1) as f64 } else { y1 as f64 };
+
+    value * volume as f64 / 128.0 / 128.0
+}
+
+fn build_ptv_wave(wave: &PtvWave, volume: i32, pan: i32) -> Vec<i16> {
+    let mut pan_volume = [64, 64];
+    if pan > 64 {
+        pan_volume[0] = 128 - pan;
+    }
+    if pan < 64 {
+        pan_volume[1] = pan;
+    }
+
+    let mut samples = Vec::with_capacity(PTV_WAVE_FRAMES * 2);
+    for s in 0..PTV_WAVE_FRAMES {
+        let osc = match wave {
+            PtvWave::Coordinate { resolution, points } => {
+                coordinate_sample(points, *resolution as i32, volume, PTV_WAVE_FRAMES, s)
+            }
+            PtvWave::Overtone { points } => overtone_sample(points, volume, PTV_WAVE_FRAMES, s),
+        };
+
+        for pan_volume in pan_volume {
+            let work = (osc * pan_volume as f64 / 64.0).clamp(-1.0, 1.0);
+            samples.push((work * 32767.0) as i16);
+        }
+    }
+
+    samples
+}
+
+fn read_ptn_woice<R: io::Read>(f: &mut R) -> GameResult<Woice> {
+    let _unit = f.read_u16::<LE>()?;
+    let basic_key = f.read_u16::<LE>()? as i32;
+    let flags = f.read_u32::<LE>()?;
+    let tuning = f.read_f32::<LE>()?;
+    let _reserved = f.read_i32::<LE>()?;
+
+    let samples = build_noise(&read_ptn(f)?);
+
+    Ok(Woice { voices: vec![Voice { basic_key, tuning, flags, samples, envelope: None }] })
+}
+
+const PTN_FLAG_ENVELOPE: u32 = 0x4;
+const PTN_FLAG_PAN: u32 = 0x8;
+const PTN_FLAG_OSC_MAIN: u32 = 0x10;
+const PTN_FLAG_OSC_FREQ: u32 = 0x20;
+const PTN_FLAG_OSC_VOLUME: u32 = 0x40;
+const PTN_FLAG_UNCOVERED: u32 = 0xffffff83;
+
+const PTN_MAX_FRAMES: u32 = 48000 * 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NoiseWave {
+    None,
+    Sine,
+    Saw,
+    Rect,
+    Random,
+    Saw2,
+    Rect2,
+    Triangle,
+    Random2,
+    Rect3,
+    Rect4,
+    Rect8,
+    Rect16,
+    Saw3,
+    Saw4,
+    Saw6,
+    Saw8,
+}
+
+impl NoiseWave {
+    const ALL: [NoiseWave; 17] = [
+        NoiseWave::None,
+        NoiseWave::Sine,
+        NoiseWave::Saw,
+        NoiseWave::Rect,
+        NoiseWave::Random,
+        NoiseWave::Saw2,
+        NoiseWave::Rect2,
+        NoiseWave::Triangle,
+        NoiseWave::Random2,
+        NoiseWave::Rect3,
+        NoiseWave::Rect4,
+        NoiseWave::Rect8,
+        NoiseWave::Rect16,
+        NoiseWave::Saw3,
+        NoiseWave::Saw4,
+        NoiseWave::Saw6,
+        NoiseWave::Saw8,
+    ];
+}
+
+#[derive(Debug, Copy, Clone)]
+struct NoiseOscillator {
+    wave: NoiseWave,
+    reverse: bool,
+    freq: f64,
+    /// In percent.
+    volume: f64,
+    /// In percent of a cycle.
+    offset: f64,
+}
+
+impl Default for NoiseOscillator {
+    fn default() -> Self {
+        NoiseOscillator { wave: NoiseWave::None, reverse: false, freq: 0.0, volume: 0.0, offset: 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct NoiseUnit {
+    /// Pairs of milliseconds and volumes in percent.
+    envelope: Vec<(u32, u32)>,
+    pan: i8,
+    main: NoiseOscillator,
+    freq: NoiseOscillator,
+    volume: NoiseOscillator,
+}
+
+struct Noise {
+    frames: u32,
+    units: Vec<NoiseUnit>,
+}
+
+fn read_noise_oscillator<R: io::Read>(f: &mut R) -> GameResult<NoiseOscillator> {
+    let wave = *NoiseWave::ALL
+        .get(read_varint(f)? as usize)
+        .ok_or_else(|| GameError::ResourceLoadError("Invalid PxTone noise waveform.".to_owned()))?;
+    let reverse = read_varint(f)? != 0;
+    let freq = (read_varint(f)? as f64 / 10.0).min(44100.0);
+    let volume = (read_varint(f)? as f64 / 10.0).min(200.0);
+    let offset = (read_varint(f)? as f64 / 10.0).min(100.0);
+
+    Ok(NoiseOscillator { wave, reverse, freq, volume, offset })
+}
+
+fn read_ptn<R: io::Read>(f: &mut R) -> GameResult<Noise> {
+    let mut code = [0u8; 8];
+    f.read_exact(&mut code)?;
+    let version = f.read_u32::<LE>()?;
+
+    if &code != b"PTNOISE-" || version > PTN_MAX_VERSION {
+        return Err(GameError::ResourceLoadError("Unsupported PxTone noise format.".to_owned()));
+    }
+
+    let frames = read_varint(f)?.min(PTN_MAX_FRAMES);
+    let count = f.read_u8()?;
+    let mut units = Vec::new();
+
+    for _ in 0..count {
+        let flags = read_varint(f)?;
+        if flags & PTN_FLAG_UNCOVERED != 0 {
+            return Err(GameError::ResourceLoadError("Unsupported PxTone noise format.".to_owned()));
+        }
+
+        let mut unit = NoiseUnit::default();
+
+        if flags & PTN_FLAG_ENVELOPE != 0 {
+            let points = read_varint(f)?;
+            for _ in 0..points {
+                let x = read_varint(f)?.min(10000);
+                let y = read_varint(f)?.min(100);
+                unit.envelope.push((x, y));
+            }
+        }
+
+        if flags & PTN_FLAG_PAN != 0 {
+            unit.pan = f.read_i8()?.clamp(-100, 100);
+        }
+
+        if flags & PTN_FLAG_OSC_MAIN != 0 {
+            unit.main = read_noise_oscillator(f)?;
+        }
+
+        if flags & PTN_FLAG_OSC_FREQ != 0 {
+            unit.freq = read_noise_oscillator(f)?;
+        }
+
+        if flags & PTN_FLAG_OSC_VOLUME != 0 {
+            unit.volume = read_noise_oscillator(f)?;
+        }
+
+        units.push(unit);
+    }
+
+    Ok(Noise { frames, units })
+}
+
+/// Length of a waveform table cycle, one cycle at 100Hz.
+const NOISE_TABLE_FRAMES: usize = 441;
+const NOISE_RANDOM_FRAMES: usize = 44100;
+const NOISE_BASIC_FREQUENCY: f64 = 100.0;
+const NOISE_SAMPLE_TOP: f64 = 32767.0;
+/// Range of the frequency modulation in keys.
+const NOISE_KEY_TOP: f64 = 0x3200 as f64;
+
+fn noise_table(wave: NoiseWave) -> Vec<i16> {
+    let n = NOISE_TABLE_FRAMES;
+    let top = NOISE_SAMPLE_TOP;
+
+    let steps = |levels: &[f64]| -> Vec<i16> { (0..n).map(|s| (levels[s * levels.len() / n] * top) as i16).collect() };
+    let duty = |fraction: usize| -> Vec<i16> {
+        (0..n).map(|s| if s < n / fraction { top as i16 } else { -top as i16 }).collect()
+    };
+    let overtones = |points: &[(i32, i32)]| -> Vec<i16> {
+        (0..n).map(|s| (overtone_sample(points, 128, n, s).clamp(-1.0, 1.0) * top) as i16).collect()
+    };
+
+    match wave {
+        NoiseWave::None | NoiseWave::Random | NoiseWave::Random2 => vec![0; n],
+        NoiseWave::Sine => overtones(&[(1, 128)]),
+        NoiseWave::Saw => (0..n).map(|s| (top - 2.0 * top * s as f64 / n as f64) as i16).collect(),
+        NoiseWave::Rect => duty(2),
+        NoiseWave::Saw2 => overtones(&(1..=16).map(|x| (x, 128)).collect::<Vec<_>>()),
+        NoiseWave::Rect2 => overtones(&(0..8).map(|x| (x * 2 + 1, 128)).collect::<Vec<_>>()),
+        NoiseWave::Triangle => {
+            let points = [(0, 0), (n as i32 / 4, 128), (n as i32 * 3 / 4, -128), (n as i32, 0)];
+            (0..n).map(|s| (coordinate_sample(&points, n as i32, 128, n, s) * top) as i16).collect()
+        }
+        NoiseWave::Rect3 => duty(3),
+        NoiseWave::Rect4 => duty(4),
+        NoiseWave::Rect8 => duty(8),
+        NoiseWave::Rect16 => duty(16),
+        NoiseWave::Saw3 => steps(&[1.0, 0.0, -1.0]),
+        NoiseWave::Saw4 => steps(&[1.0, 1.0 / 3.0, -1.0 / 3.0, -1.0]),
+        NoiseWave::Saw6 => steps(&[1.0, 0.6, 0.2, -0.2, -0.6, -1.0]),
+        NoiseWave::Saw8 => steps(&[1.0, 5.0 / 7.0, 3.0 / 7.0, 1.0 / 7.0, -1.0 / 7.0, -3.0 / 7.0, -5.0 / 7.0, -1.0]),
+    }
+}
+
+fn noise_random_table() -> Vec<i16> {
+    let mut state = 0x4444_8888u32;
+
+    (0..NOISE_RANDOM_FRAMES)
+        .map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) as i16
+        })
+        .collect()
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum RandomMode {
+    None,
+    /// Slides between random values.
+    Saw,
+    /// Holds a random value for a cycle.
+    Rect,
+}
+
+struct OscillatorState {
+    table: Vec<i16>,
+    mode: RandomMode,
+    reverse: bool,
+    increment: f64,
+    offset: f64,
+    volume: f64,
+    random_start: f64,
+    random_margin: f64,
+    random_index: usize,
+}
+
+impl OscillatorState {
+    fn new(osc: &NoiseOscillator, sample_rate: f64, random: &[i16]) -> OscillatorState {
+        let mode = match osc.wave {
+            NoiseWave::Random => RandomMode::Saw,
+            NoiseWave::Random2 => RandomMode::Rect,
+            _ => RandomMode::None,
+        };
+        let random_index = ((NOISE_RANDOM_FRAMES as f64 * osc.offset / 100.0) as usize).min(NOISE_RANDOM_FRAMES - 1);
+
+        OscillatorState {
+            table: noise_table(osc.wave),
+            mode,
+            reverse: osc.reverse,
+            increment: (BASIC_SAMPLE_RATE as f64 / sample_rate) * (osc.freq / NOISE_BASIC_FREQUENCY),
+            offset: if mode == RandomMode::None { NOISE_TABLE_FRAMES as f64 * osc.offset / 100.0 } else { 0.0 },
+            volume: osc.volume / 100.0,
+            random_start: 0.0,
+            random_margin: random[random_index] as f64,
+            random_index,
+        }
+    }
+
+    fn sample(&self) -> f64 {
+        let value = match self.mode {
+            RandomMode::None => self.table[(self.offset as usize).min(NOISE_TABLE_FRAMES - 1)] as f64,
+            RandomMode::Saw => {
+                self.random_start + self.random_margin * (self.offset as usize) as f64 / NOISE_TABLE_FRAMES as f64
+            }
+            RandomMode::Rect => self.random_start,
+        };
+
+        if self.reverse {
+            -value * self.volume
+        } else {
+            value * self.volume
+        }
+    }
+
+    fn increment(&mut self, increment: f64, random: &[i16]) {
+        self.offset += increment;
+
+        if self.offset > NOISE_TABLE_FRAMES as f64 {
+            self.offset -= NOISE_TABLE_FRAMES as f64;
+            if self.offset >= NOISE_TABLE_FRAMES as f64 {
+                self.offset = 0.0;
+            }
+
+            if self.mode != RandomMode::None {
+                self.random_start = random[self.random_index] as f64;
+                self.random_index = (self.random_index + 1) % NOISE_RANDOM_FRAMES;
+                self.random_margin = random[self.random_index] as f64 - self.random_start;
+            }
+        }
+    }
+}
+
+struct NoiseUnitState {
+    pan: [f64; 2],
+    /// Pairs of sample frames and volumes.
+    envelope: Vec<(u32, f64)>,
+    envelope_index: usize,
+    envelope_start: f64,
+    envelope_margin: f64,
+    envelope_count: u32,
+    main: OscillatorState,
+    freq: OscillatorState,
+    volume: OscillatorState,
+}
+
+impl NoiseUnitState {
+    /// Skips the envelope points with zero length.
+    fn next_envelope_point(&mut self) {
+        while let Some(&(frames, volume)) = self.envelope.get(self.envelope_index) {
+            self.envelope_margin = volume - self.envelope_start;
+            if frames != 0 {
+                break;
+            }
+
+            self.envelope_start = volume;
+            self.envelope_index += 1;
+        }
+    }
+
+    fn envelope_volume(&self) -> f64 {
+        match self.envelope.get(self.envelope_index) {
+            Some(&(frames, _)) => {
+                self.envelope_start + self.envelope_margin * self.envelope_count as f64 / frames as f64
+            }
+            None => self.envelope_start,
+        }
+    }
+}
+
+fn build_noise(noise: &Noise) -> Vec<i16> {
+    let sample_rate = BASIC_SAMPLE_RATE as f64;
+    let random = noise_random_table();
+
+    let mut units: Vec<NoiseUnitState> = noise
+        .units
+        .iter()
+        .map(|unit| {
+            let pan = match unit.pan {
+                pan if pan < 0 => [1.0, (100.0 + pan as f64) / 100.0],
+                pan if pan > 0 => [(100.0 - pan as f64) / 100.0, 1.0],
+                _ => [1.0, 1.0],
+            };
+
+            let mut state = NoiseUnitState {
+                pan,
+                envelope: unit
+                    .envelope
+                    .iter()
+                    .map(|&(x, y)| ((sample_rate as u32 * x / 1000), y as f64 / 100.0))
+                    .collect(),
+                envelope_index: 0,
+                envelope_start: 0.0,
+                envelope_margin: 0.0,
+                envelope_count: 0,
+                main: OscillatorState::new(&unit.main, sample_rate, &random),
+                freq: OscillatorState::new(&unit.freq, sample_rate, &random),
+                volume: OscillatorState::new(&unit.volume, sample_rate, &random),
+            };
+            state.next_envelope_point();
+
+            state
+        })
+        .collect();
+
+    let mut samples = Vec::with_capacity(noise.frames as usize * 2);
+    for _ in 0..noise.frames {
+        for channel in 0..2 {
+            let mut store = 0.0;
+
+            for unit in units.iter() {
+                let volume = unit.volume.sample();
+                let work = unit.main.sample() * (volume + NOISE_SAMPLE_TOP) / (NOISE_SAMPLE_TOP * 2.0);
+
+                store += work * unit.pan[channel] * unit.envelope_volume();
+            }
+
+            samples.push(store.clamp(-NOISE_SAMPLE_TOP, NOISE_SAMPLE_TOP) as i16);
+        }
+
+        for unit in units.iter_mut() {
+            let key = NOISE_KEY_TOP * unit.freq.sample() / NOISE_SAMPLE_TOP;
+            let main_increment = unit.main.increment * key_to_frequency(key as i32);
+            let volume_increment = unit.volume.increment;
+            let freq_increment = unit.freq.increment;
+
+            unit.main.increment(main_increment, &random);
+            unit.volume.increment(volume_increment, &random);
+            unit.freq.increment(freq_increment, &random);
+
+            if let Some(&(frames, volume)) = unit.envelope.get(unit.envelope_index) {
+                unit.envelope_count += 1;
+
+                if unit.envelope_count >= frames {
+                    unit.envelope_count = 0;
+                    unit.envelope_start = volume;
+                    unit.envelope_margin = 0.0;
+                    unit.envelope_index += 1;
+                    unit.next_envelope_point();
+                }
+            }
+        }
+    }
+
+    samples
+}
+
+/// Returns the frequency ratio of a key offset, 0x100 units per semitone, over a range of 8 octaves up and down.
+pub fn key_to_frequency(key: i32) -> f32 {
+    let key = key.clamp(-0x6000, 0x5fff);
+
+    2.0f32.powf(key as f32 / (12.0 * 256.0))
+}
+
+#[test]
+fn test_pxtone_load() {
+    let mut data = Vec::new();
+    data.extend_from_slice(CODE_PROJECT_V5);
+    data.extend_from_slice(&[0, 0, 0, 0]);
+
+    let mut chunk = |tag: &[u8; 8], payload: &[u8]| {
+        data.extend_from_slice(tag);
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+    };
+
+    let mut master = Vec::new();
+    master.extend_from_slice(&480i16.to_le_bytes());
+    master.push(4);
+    master.extend_from_slice(&120.0f32.to_le_bytes());
+    master.extend_from_slice(&1920i32.to_le_bytes());
+    master.extend_from_slice(&0i32.to_le_bytes());
+    chunk(b"MasterV5", &master);
+    chunk(b"num UNIT", &[1, 0, 0, 0]);
+
+    // a single sine voice with a varint encoded tuning of 1.0
+    let mut ptv = Vec::new();
+    ptv.extend_from_slice(&[0, 0, 0, 0]);
+    ptv.extend_from_slice(&1.0f32.to_le_bytes());
+    ptv.extend_from_slice(&0i32.to_le_bytes());
+    ptv.extend_from_slice(b"PTVOICE-");
+    ptv.extend_from_slice(&PTV_MAX_VERSION.to_le_bytes());
+    ptv.extend_from_slice(&0u32.to_le_bytes());
+    ptv.extend_from_slice(&[0, 0, 1]);
+    ptv.extend_from_slice(&[0x80, 0x8a, 0x01, 0x80, 0x01, 0x40]);
+    ptv.extend_from_slice(&[0x80, 0x80, 0x80, 0xfc, 0x03]);
+    ptv.extend_from_slice(&[0x01, 0x01, 0x01, 0x01, 0x01, 0x80, 0x01]);
+    chunk(b"matePTV ", &ptv);
+
+    // key change, note on for a measure and a note on a later measure
+    let mut events = Vec::new();
+    events.extend_from_slice(&3u32.to_le_bytes());
+    events.extend_from_slice(&[0, 0, 2, 0x80, 0xc0, 0x01]);
+    events.extend_from_slice(&[0, 0, 1, 0x80, 0x0f]);
+    events.extend_from_slice(&[0x80, 0x1e, 0, 1, 0xe0, 0x03]);
+    chunk(b"Event V5", &events);
+
+    data.extend_from_slice(b"pxtoneND");
+
+    let song = Song::load_from(&data[..]).unwrap();
+    assert_eq!(song.unit_count, 1);
+    assert_eq!(song.master.beat_tempo, 120.0);
+    assert_eq!(song.events.len(), 3);
+    assert_eq!(song.events[0].kind, EventKind::Key);
+    assert_eq!(song.events[0].value, DEFAULT_KEY);
+    assert_eq!(song.events[2].clock, 3840);
+    assert_eq!(song.loop_clocks(), (1920, 3 * 1920));
+
+    let voice = &song.woices[0].voices[0];
+    assert_eq!(voice.basic_key, BASIC_KEY);
+    assert_eq!(voice.tuning, 1.0);
+    assert_eq!(voice.frames(), PTV_WAVE_FRAMES);
+    assert_eq!(voice.samples[0], 0);
+    assert!(voice.samples[PTV_WAVE_FRAMES / 2] > 30000);
+
+    assert!(Song::load_from(&b"PTCOLLAGE-050227\0\0\0\0"[..]).is_err());
+
+    // a chunk claiming more data than the file has
+    let mut truncated = Vec::new();
+    truncated.extend_from_slice(CODE_PROJECT_V5);
+    truncated.extend_from_slice(&[0, 0, 0, 0]);
+    truncated.extend_from_slice(b"MasterV5");
+    truncated.extend_from_slice(&0x10000u32.to_le_bytes());
+    truncated.extend_from_slice(&master);
+    assert!(Song::load_from(&truncated[..]).is_err());
+}
@@ -0,0 +1,704 @@
+use std::sync::Arc;
+
+use crate::sound::pxtone::{
+    key_to_frequency, DelayUnit, EventKind, Song, Voice, BASIC_KEY, BASIC_SAMPLE_RATE, DEFAULT_KEY, MAX_GROUPS,
+    VOICE_FLAG_BEAT_FIT, VOICE_FLAG_SMOOTH, VOICE_FLAG_WAVE_LOOP,
+};
+use crate::sound::wav::WavFormat;
+
+/// Length of the buffer used to delay the channels of panned units, must be a power of two.
+const TIME_PAN_BUFFER: usize = 0x40;
+
+/// Longest delay of a delay effect in seconds, songs can set arbitrarily low echo frequencies.
+const MAX_DELAY_SECONDS: f64 = 4.0;
+
+const DEFAULT_VOLUME: i32 = 104;
+const DEFAULT_VELOCITY: i32 = 104;
+
+/// Playback state of a voice of a unit.
+#[derive(Clone, Default)]
+struct Tone {
+    life_count: i32,
+    on_count: i32,
+    position: f64,
+    env_volume: i32,
+    env_start: i32,
+    env_pos: i32,
+    /// Length of the release in clocks, used to find out whether the next note cuts it off.
+    env_release_clock: i32,
+    offset_freq: f32,
+}
+
+/// Envelope of a voice, resampled to the output sample rate.
+#[derive(Clone, Default)]
+struct VoiceEnvelope {
+    volumes: Vec<u8>,
+    release: i32,
+}
+
+/// Track of the song, plays notes with one woice at a time.
+#[derive(Clone)]
+struct Unit {
+    woice: Option<usize>,
+    tones: Vec<Tone>,
+    key_now: i32,
+    key_start: i32,
+    key_margin: i32,
+    portament_frames: i32,
+    portament_pos: i32,
+    pan_volumes: [i32; 2],
+    pan_times: [usize; 2],
+    pan_time_bufs: [[i32; TIME_PAN_BUFFER]; 2],
+    velocity: i32,
+    volume: i32,
+    group: usize,
+    tuning: f32,
+}
+
+impl Unit {
+    fn new() -> Unit {
+        Unit {
+            woice: None,
+            tones: Vec::new(),
+            key_now: DEFAULT_KEY,
+            key_start: DEFAULT_KEY,
+            key_margin: 0,
+            portament_frames: 0,
+            portament_pos: 0,
+            pan_volumes: [64; 2],
+            pan_times: [0; 2],
+            pan_time_bufs: [[0; TIME_PAN_BUFFER]; 2],
+            velocity: DEFAULT_VELOCITY,
+            volume: DEFAULT_VOLUME,
+            group: 0,
+            tuning: 1.0,
+        }
+    }
+
+    /// Resets the playback parameters, the delayed samples are kept so the loop point doesn't click.
+    fn reset(&mut self) {
+        let pan_time_bufs = self.pan_time_bufs;
+        *self = Unit::new();
+        self.pan_time_bufs = pan_time_bufs;
+    }
+
+    fn increment_key(&mut self) -> i32 {
+        if self.portament_frames > 0 && self.key_margin != 0 {
+            if self.portament_pos < self.portament_frames {
+                self.portament_pos += 1;
+                self.key_now = (self.key_start as f64
+                    + self.key_margin as f64 * self.portament_pos as f64 / self.portament_frames as f64)
+                    as i32;
+            } else {
+                self.key_now = self.key_start + self.key_margin;
+                self.key_start = self.key_now;
+                self.key_margin = 0;
+            }
+        } else {
+            self.key_now = self.key_start + self.key_margin;
+        }
+
+        self.key_now
+    }
+}
+
+struct DelayState {
+    group: usize,
+    rate: i32,
+    offset: usize,
+    buffers: [Vec<i32>; 2],
+}
+
+#[derive(Clone)]
+pub struct SavedPxTonePlaybackState {
+    song: Arc<Song>,
+    frame: u64,
+}
+
+pub(crate) struct PxTonePlaybackEngine {
+    song: Arc<Song>,
+    output_format: WavFormat,
+    envelopes: Vec<Vec<VoiceEnvelope>>,
+    units: Vec<Unit>,
+    delays: Vec<DelayState>,
+    /// Clipping level of the overdrive effects.
+    overdrive_tops: Vec<i32>,
+    next_event: usize,
+    frame: u64,
+    /// Output frames per clock.
+    clock_rate: f64,
+    repeat_frame: u64,
+    end_frame: u64,
+    smooth_frames: i32,
+    time_pan_index: usize,
+}
+
+impl PxTonePlaybackEngine {
+    pub fn new() -> PxTonePlaybackEngine {
+        let song = Song {
+            master: crate::sound::pxtone::Master {
+                beat_clock: 480,
+                beat_num: 4,
+                beat_tempo: 120.0,
+                repeat_clock: 0,
+                last_clock: 0,
+            },
+            unit_count: 0,
+            events: Vec::new(),
+            woices: Vec::new(),
+            delays: Vec::new(),
+            overdrives: Vec::new(),
+        };
+
+        let mut engine = PxTonePlaybackEngine {
+            song: Arc::new(song),
+            output_format: WavFormat { channels: 2, sample_rate: 44100, bit_depth: 16 },
+            envelopes: Vec::new(),
+            units: Vec::new(),
+            delays: Vec::new(),
+            overdrive_tops: Vec::new(),
+            next_event: 0,
+            frame: 0,
+            clock_rate: 1.0,
+            repeat_frame: 0,
+            end_frame: 0,
+            smooth_frames: 1,
+            time_pan_index: 0,
+        };
+        engine.prepare();
+
+        engine
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: usize) {
+        let old_rate = self.output_format.sample_rate as u64;
+        self.output_format.sample_rate = sample_rate.max(1) as u32;
+        self.frame = self.frame * self.output_format.sample_rate as u64 / old_rate.max(1);
+
+        let frame = self.frame;
+        let next_event = self.next_event;
+        let units = std::mem::take(&mut self.units);
+
+        self.prepare();
+
+        // the playing notes keep going, only the timing dependent parts are recalculated
+        self.frame = frame;
+        self.next_event = next_event;
+        self.units = units;
+    }
+
+    pub fn get_state(&self) -> SavedPxTonePlaybackState {
+        SavedPxTonePlaybackState { song: self.song.clone(), frame: self.frame }
+    }
+
+    pub fn set_state(&mut self, state: SavedPxTonePlaybackState) {
+        self.song = state.song;
+        self.prepare();
+        self.seek(state.frame);
+    }
+
+    pub fn start_song(&mut self, song: Song) {
+        self.song = Arc::new(song);
+        self.prepare();
+    }
+
+    pub fn rewind(&mut self) {
+        self.seek(0);
+    }
+
+    /// Returns the current output frame.
+    pub fn get_position(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn set_position(&mut self, frame: u64) {
+        self.seek(frame);
+    }
+
+    /// Moves the playback to given output frame, the events before it are applied on the next frame.
+    fn seek(&mut self, frame: u64) {
+        self.frame = if frame < self.end_frame { frame } else { self.repeat_frame };
+        self.next_event = 0;
+        self.reset_units();
+    }
+
+    /// Recalculates everything that depends on the song and the output sample rate and rewinds the song.
+    fn prepare(&mut self) {
+        let sample_rate = self.output_format.sample_rate as f64;
+        let master = self.song.master;
+
+        self.clock_rate = 60.0 * sample_rate / (master.beat_tempo as f64 * master.beat_clock as f64);
+        self.smooth_frames = (sample_rate / 250.0).max(1.0) as i32;
+
+        let (repeat_clock, end_clock) = self.song.loop_clocks();
+        self.repeat_frame = (repeat_clock as f64 * self.clock_rate) as u64;
+        self.end_frame = (end_clock as f64 * self.clock_rate) as u64;
+
+        self.envelopes = self
+            .song
+            .woices
+            .iter()
+            .map(|woice| woice.voices.iter().map(|voice| prepare_envelope(voice, sample_rate)).collect())
+            .collect();
+
+        self.delays = self
+            .song
+            .delays
+            .iter()
+            .filter(|delay| delay.freq > 0.0 && delay.rate != 0.0)
+            .map(|delay| {
+                let frames = match delay.unit {
+                    DelayUnit::Beat => sample_rate * 60.0 / master.beat_tempo as f64 / delay.freq as f64,
+                    DelayUnit::Measure => {
+                        sample_rate * 60.0 * master.beat_num as f64 / master.beat_tempo as f64 / delay.freq as f64
+                    }
+                    DelayUnit::Second => sample_rate / delay.freq as f64,
+                };
+                let frames = (frames as usize).clamp(1, (sample_rate * MAX_DELAY_SECONDS) as usize);
+
+                DelayState {
+                    group: delay.group,
+                    rate: delay.rate as i32,
+                    offset: 0,
+                    buffers: [vec![0; frames], vec![0; frames]],
+                }
+            })
+            .collect();
+
+        self.overdrive_tops =
+            self.song.overdrives.iter().map(|overdrive| (32767.0 * (100.0 - overdrive.cut) / 100.0) as i32).collect();
+
+        self.units = vec![Unit::new(); self.song.unit_count];
+        self.time_pan_index = 0;
+        self.frame = 0;
+        self.next_event = 0;
+        self.reset_units();
+    }
+
+    fn reset_units(&mut self) {
+        for unit in 0..self.units.len() {
+            self.units[unit].reset();
+            self.set_woice(unit, 0);
+        }
+    }
+
+    fn set_woice(&mut self, unit: usize, woice_index: usize) {
+        let Some(woice) = self.song.woices.get(woice_index) else {
+            return;
+        };
+
+        let tones = woice
+            .voices
+            .iter()
+            .zip(self.envelopes[woice_index].iter())
+            .map(|(voice, envelope)| {
+                let offset_freq = if voice.flags & VOICE_FLAG_BEAT_FIT != 0 {
+                    (voice.frames() as f32 * self.song.master.beat_tempo)
+                        / (BASIC_SAMPLE_RATE as f32 * 60.0 * voice.tuning)
+                } else {
+                    key_to_frequency(BASIC_KEY - voice.basic_key) * voice.tuning
+                };
+
+                Tone {
+                    env_release_clock: (envelope.release as f64 / self.clock_rate) as i32,
+                    offset_freq,
+                    ..Tone::default()
+                }
+            })
+            .collect();
+
+        let unit = &mut self.units[unit];
+        unit.woice = Some(woice_index);
+        unit.tones = tones;
+    }
+
+    fn process_events(&mut self) {
+        let clock = (self.frame as f64 / self.clock_rate) as i64;
+        let sample_rate = self.output_format.sample_rate as i32;
+
+        while let Some(&event) = self.song.events.get(self.next_event) {
+            if event.clock as i64 > clock {
+                break;
+            }
+
+            self.next_event += 1;
+
+            let unit_index = event.unit as usize;
+            if unit_index >= self.units.len() {
+                continue;
+            }
+
+            match event.kind {
+                EventKind::On => self.note_on(unit_index, event.clock as i64, event.value as i64, clock),
+                EventKind::Key => {
+                    let unit = &mut self.units[unit_index];
+                    unit.key_start = unit.key_now;
+                    unit.key_margin = event.value - unit.key_start;
+                    unit.portament_pos = 0;
+                }
+                EventKind::PanVolume => {
+                    let pan = event.value;
+                    let unit = &mut self.units[unit_index];
+                    unit.pan_volumes = [64, 64];
+                    if pan >= 64 {
+                        unit.pan_volumes[0] = 128 - pan;
+                    } else {
+                        unit.pan_volumes[1] = pan;
+                    }
+                }
+                EventKind::PanTime => {
+                    let pan = event.value;
+                    let unit = &mut self.units[unit_index];
+                    unit.pan_times = [0, 0];
+                    let (channel, delay) = if pan >= 64 { (0, pan - 64) } else { (1, 64 - pan) };
+                    unit.pan_times[channel] = (delay.min(63) * 44100 / sample_rate.max(1)) as usize;
+                }
+                EventKind::Velocity => self.units[unit_index].velocity = event.value,
+                EventKind::Volume => self.units[unit_index].volume = event.value,
+                EventKind::Portament => {
+                    self.units[unit_index].portament_frames = (event.value as f64 * self.clock_rate) as i32;
+                }
+                EventKind::VoiceNo => self.set_woice(unit_index, event.value as usize),
+                EventKind::GroupNo => self.units[unit_index].group = (event.value as usize).min(MAX_GROUPS - 1),
+                EventKind::Tuning => self.units[unit_index].tuning = f32::from_bits(event.value as u32),
+            }
+        }
+    }
+
+    fn note_on(&mut self, unit_index: usize, event_clock: i64, length: i64, clock: i64) {
+        let clock_rate = self.clock_rate;
+        let on_count = ((event_clock + length - clock) as f64 * clock_rate) as i32;
+        let unit = &mut self.units[unit_index];
+
+        if on_count <= 0 {
+            for tone in unit.tones.iter_mut() {
+                tone.life_count = 0;
+            }
+
+            return;
+        }
+
+        unit.key_now = unit.key_start + unit.key_margin;
+        unit.key_start = unit.key_now;
+        unit.key_margin = 0;
+
+        let Some(woice_index) = unit.woice else {
+            return;
+        };
+
+        for (tone, envelope) in unit.tones.iter_mut().zip(self.envelopes[woice_index].iter()) {
+            let remaining = ((length - (clock - event_clock)) as f64 * clock_rate) as i32;
+
+            tone.life_count = if envelope.release > 0 {
+                // the release is cut off by the next note of the unit
+                let release_end = event_clock + length + tone.env_release_clock as i64;
+                let next_note = self.song.events[self.next_event..]
+                    .iter()
+                    .take_while(|event| event.clock as i64 <= release_end)
+                    .find(|event| event.unit as usize == unit_index && event.kind == EventKind::On);
+
+                let until_next = match next_note {
+                    Some(next) => ((next.clock as i64 - clock) as f64 * clock_rate) as i32,
+                    None => (self.end_frame as f64 - clock as f64 * clock_rate) as i32,
+                };
+
+                (remaining + envelope.release).min(until_next)
+            } else {
+                remaining
+            };
+
+            if tone.life_count > 0 {
+                tone.on_count = on_count;
+                tone.position = 0.0;
+                tone.env_pos = 0;
+                tone.env_volume = if envelope.volumes.is_empty() { 128 } else { 0 };
+                tone.env_start = tone.env_volume;
+            }
+        }
+    }
+
+    fn update_envelopes(&mut self) {
+        for unit in self.units.iter_mut() {
+            let Some(woice_index) = unit.woice else {
+                continue;
+            };
+
+            for (tone, envelope) in unit.tones.iter_mut().zip(self.envelopes[woice_index].iter()) {
+                if tone.life_count <= 0 || envelope.volumes.is_empty() {
+                    continue;
+                }
+
+                if tone.on_count > 0 {
+                    if let Some(&volume) = envelope.volumes.get(tone.env_pos as usize) {
+                        tone.env_volume = volume as i32;
+                        tone.env_pos += 1;
+                    }
+                } else {
+                    tone.env_volume = tone.env_start - tone.env_start * tone.env_pos / envelope.release.max(1);
+                    tone.env_pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Renders the voices of the units into their time panning buffers.
+    fn sample_units(&mut self) {
+        let time_pan_index = self.time_pan_index;
+        let smooth_frames = self.smooth_frames;
+
+        for unit in self.units.iter_mut() {
+            let Some(woice_index) = unit.woice else {
+                continue;
+            };
+            let voices = &self.song.woices[woice_index].voices;
+            let envelopes = &self.envelopes[woice_index];
+
+            for channel in 0..2 {
+                let mut sum = 0;
+
+                for ((tone, voice), envelope) in unit.tones.iter().zip(voices.iter()).zip(envelopes.iter()) {
+                    if tone.life_count <= 0 {
+                        continue;
+                    }
+
+                    let index = tone.position as usize * 2 + channel;
+                    let mut work = voice.samples.get(index).copied().unwrap_or(0) as i32;
+                    work = work * unit.velocity / 128;
+                    work = work * unit.volume / 128;
+                    work = work * unit.pan_volumes[channel] / 64;
+
+                    if !envelope.volumes.is_empty() {
+                        work = work * tone.env_volume / 128;
+                    }
+
+                    if voice.flags & VOICE_FLAG_SMOOTH != 0 && tone.life_count < smooth_frames {
+                        work = work * tone.life_count / smooth_frames;
+                    }
+
+                    sum += work;
+                }
+
+                unit.pan_time_bufs[channel][time_pan_index] = sum;
+            }
+        }
+    }
+
+    fn increment(&mut self) {
+        let stride = BASIC_SAMPLE_RATE as f64 / self.output_format.sample_rate as f64;
+
+        for unit in self.units.iter_mut() {
+            let freq = key_to_frequency(unit.increment_key() - DEFAULT_KEY) as f64 * stride;
+
+            let Some(woice_index) = unit.woice else {
+                continue;
+            };
+            let voices = &self.song.woices[woice_index].voices;
+            let envelopes = &self.envelopes[woice_index];
+
+            for ((tone, voice), envelope) in unit.tones.iter_mut().zip(voices.iter()).zip(envelopes.iter()) {
+                increment_tone(tone, voice, envelope, unit.tuning, freq);
+            }
+        }
+
+        for delay in self.delays.iter_mut() {
+            delay.offset = (delay.offset + 1) % delay.buffers[0].len();
+        }
+
+        self.time_pan_index = (self.time_pan_index + 1) & (TIME_PAN_BUFFER - 1);
+        self.frame += 1;
+
+        if self.frame >= self.end_frame {
+            self.frame = self.repeat_frame;
+            self.next_event = 0;
+            self.reset_units();
+        }
+    }
+
+    fn render_frame(&mut self) -> [i16; 2] {
+        self.update_envelopes();
+        self.process_events();
+        self.sample_units();
+
+        let mut output = [0i16; 2];
+        for (channel, output) in output.iter_mut().enumerate() {
+            let mut groups = [0i32; MAX_GROUPS];
+
+            for unit in self.units.iter() {
+                let index = (self.time_pan_index.wrapping_sub(unit.pan_times[channel])) & (TIME_PAN_BUFFER - 1);
+                groups[unit.group] += unit.pan_time_bufs[channel][index];
+            }
+
+            for (overdrive, &top) in self.song.overdrives.iter().zip(self.overdrive_tops.iter()) {
+                let work = groups[overdrive.group].clamp(-top.abs(), top.abs());
+                groups[overdrive.group] = (work as f32 * overdrive.amp) as i32;
+            }
+
+            for delay in self.delays.iter_mut() {
+                let buffer = &mut delay.buffers[channel];
+                groups[delay.group] += buffer[delay.offset] * delay.rate / 100;
+                buffer[delay.offset] = groups[delay.group];
+            }
+
+            *output = groups.iter().sum::<i32>().clamp(-0x7fff, 0x7fff) as i16;
+        }
+
+        self.increment();
+
+        output
+    }
+
+    pub fn render_to(&mut self, buf: &mut [u16]) -> usize {
+        for frame in buf.chunks_exact_mut(2) {
+            if self.end_frame == 0 {
+                frame.fill(0x8000);
+                continue;
+            }
+
+            let [left, right] = self.render_frame();
+            frame[0] = left as u16 ^ 0x8000;
+            frame[1] = right as u16 ^ 0x8000;
+        }
+
+        buf.len()
+    }
+}
+
+fn increment_tone(tone: &mut Tone, voice: &Voice, envelope: &VoiceEnvelope, tuning: f32, freq: f64) {
+    if tone.life_count > 0 {
+        tone.life_count -= 1;
+    }
+
+    if tone.life_count <= 0 {
+        return;
+    }
+
+    tone.on_count -= 1;
+    tone.position += tone.offset_freq as f64 * tuning as f64 * freq;
+
+    let frames = voice.frames() as f64;
+    if tone.position >= frames {
+        if voice.flags & VOICE_FLAG_WAVE_LOOP != 0 && frames > 0.0 {
+            tone.position -= frames;
+            if tone.position >= frames {
+                tone.position = 0.0;
+            }
+        } else {
+            tone.life_count = 0;
+        }
+    }
+
+    // the note has ended, start the release from the current volume
+    if tone.on_count == 0 && !envelope.volumes.is_empty() {
+        tone.env_start = tone.env_volume;
+        tone.env_pos = 0;
+    }
+}
+
+fn prepare_envelope(voice: &Voice, sample_rate: f64) -> VoiceEnvelope {
+    let Some(envelope) = &voice.envelope else {
+        return VoiceEnvelope::default();
+    };
+
+    let fps = envelope.fps as f64;
+    let release = (envelope.release as f64 * sample_rate / fps) as i32;
+
+    if envelope.points.is_empty() {
+        return VoiceEnvelope { volumes: Vec::new(), release };
+    }
+
+    let length: u32 = envelope.points.iter().map(|&(x, _)| x).sum();
+    let size = ((length as f64 * sample_rate / fps) as usize).max(1);
+
+    // points are stored as lengths of the segments, convert them to absolute positions
+    let mut points = Vec::with_capacity(envelope.points.len());
+    let mut offset = 0;
+    for (i, &(x, y)) in envelope.points.iter().enumerate() {
+        if i == 0 || x != 0 || y != 0 {
+            offset += (x as f64 * sample_rate / fps) as i64;
+            points.push((offset, y as i64));
+        }
+    }
+
+    let mut volumes = Vec::with_capacity(size);
+    let mut start = (0i64, 0i64);
+    let mut next = 0;
+    for s in 0..size as i64 {
+        while next < points.len() && s >= points[next].0 {
+            start = points[next];
+            next += 1;
+        }
+
+        let volume = match points.get(next) {
+            Some(&(x, y)) => start.1 + (y - start.1) * (s - start.0) / (x - start.0),
+            None => start.1,
+        };
+        volumes.push(volume as u8);
+    }
+
+    VoiceEnvelope { volumes, release }
+}
+
+#[test]
+fn test_pxtone_playback() {
+    use crate::sound::pxtone::{Event, Master, Woice};
+
+    // one cycle of a square wave per 100 samples, looped
+    let mut samples = Vec::new();
+    for i in 0..100 {
+        let value = if i < 50 { 10000 } else { -10000 };
+        samples.extend_from_slice(&[value, value]);
+    }
+
+    let voice = Voice { basic_key: BASIC_KEY, tuning: 1.0, flags: VOICE_FLAG_WAVE_LOOP, samples, envelope: None };
+    let song = Song {
+        master: Master { beat_clock: 480, beat_num: 4, beat_tempo: 120.0, repeat_clock: 0, last_clock: 0 },
+        unit_count: 1,
+        events: vec![Event { clock: 0, unit: 0, kind: EventKind::On, value: 480 }],
+        woices: vec![Woice { voices: vec![voice] }],
+        delays: Vec::new(),
+        overdrives: Vec::new(),
+    };
+
+    let mut engine = PxTonePlaybackEngine::new();
+    engine.set_sample_rate(44100);
+    engine.start_song(song);
+
+    // a measure of 4 beats at 120 BPM is 2 seconds
+    assert_eq!(engine.end_frame, 88200);
+
+    let mut buf = vec![0u16; 88200 * 2];
+    engine.render_to(&mut buf);
+
+    let to_i16 = |sample: u16| (sample ^ 0x8000) as i16;
+
+    // the note lasts a beat, half a second
+    assert!(buf[..22050 * 2].iter().any(|&s| to_i16(s) > 5000));
+    assert!(buf[22100 * 2..].iter().all(|&s| to_i16(s) == 0));
+
+    // the song loops back to the note
+    engine.render_to(&mut buf[..1000]);
+    assert!(buf[..1000].iter().any(|&s| to_i16(s) > 5000));
+}
+
+#[test]
+fn test_pxtone_delay_length() {
+    use crate::sound::pxtone::{Delay, Master};
+
+    let delay = |unit, freq| Delay { unit, group: 0, rate: 50.0, freq };
+    let song = Song {
+        master: Master { beat_clock: 480, beat_num: 4, beat_tempo: 120.0, repeat_clock: 0, last_clock: 0 },
+        unit_count: 0,
+        events: Vec::new(),
+        woices: Vec::new(),
+        delays: vec![delay(DelayUnit::Beat, 1.0), delay(DelayUnit::Second, 0.0001), delay(DelayUnit::Measure, 1e-30)],
+        overdrives: Vec::new(),
+    };
+
+    let mut engine = PxTonePlaybackEngine::new();
+    engine.set_sample_rate(44100);
+    engine.start_song(song);
+
+    // a beat at 120 BPM, then the echoes that would be hours apart are limited to a few seconds
+    let lengths: Vec<_> = engine.delays.iter().map(|delay| delay.buffers[0].len()).collect();
+    assert_eq!(lengths, [22050, 176400, 176400]);
+}
@@ -6,7 +6,7 @@ use std::io;
 use std::io::ErrorKind;
 use std::sync::Arc;
 
-use byteorder::{LE, ReadBytesExt};
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub struct RiffChunk {
@@ -122,4 +122,29 @@ impl WavSample {
 
         Ok(WavSample { format: WavFormat { channels, sample_rate: samples, bit_depth: bits }, data: buf.into() })
     }
+
+    /// Writes the sample as a PCM WAV file.
+    pub fn write_to<W: io::Write>(&self, mut f: W) -> io::Result<()> {
+        let block_align = self.format.channels * (self.format.bit_depth / 8);
+        let data_len = self.data.len() as u32;
+
+        f.write_all(b"RIFF")?;
+        f.write_u32::<LE>(4 + 8 + 16 + 8 + data_len)?;
+        f.write_all(b"WAVE")?;
+
+        f.write_all(b"fmt ")?;
+        f.write_u32::<LE>(16)?;
+        f.write_u16::<LE>(1)?;
+        f.write_u16::<LE>(self.format.channels)?;
+        f.write_u32::<LE>(self.format.sample_rate)?;
+        f.write_u32::<LE>(self.format.sample_rate * block_align as u32)?;
+        f.write_u16::<LE>(block_align)?;
+        f.write_u16::<LE>(self.format.bit_depth)?;
+
+        f.write_all(b"data")?;
+        f.write_u32::<LE>(data_len)?;
+        f.write_all(&self.data)?;
+
+        Ok(())
+    }
 }
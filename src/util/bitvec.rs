@@ -1,3 +1,4 @@
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct BitVec {
     bits: Vec<u8>,
     len: usize,